@@ -0,0 +1,278 @@
+use std::path::{Path, PathBuf};
+use quote::quote;
+use syn::__private::TokenStream2;
+use crate::function_mock::create_generic_implementation::instantiation_suffix;
+use crate::function_mock::mock_args::{find_mock_function_attr, parse_mock_function_args};
+use crate::method_mock::parse_method_names;
+use crate::naming_utils::snake_case;
+use crate::struct_mock::struct_args::MockStructArgs;
+use crate::struct_mock::{is_selective, method_mock_args};
+
+/// Double found in the source files of the crate
+///
+/// # Fields
+///
+/// - `module_path` - the module path of the function
+/// - `function` - the name of the function, or `Type::method` for methods
+/// - `module` - the path of the generated module relative to `module_path`
+/// - `kind` - the name of the `fnmock::inventory::DoubleKind` variant
+struct FoundDouble {
+    module_path: String,
+    function: String,
+    module: String,
+    kind: &'static str,
+}
+
+/// Generates a slice of `fnmock::inventory::GeneratedDouble` listing every double of the crate.
+///
+/// Proc macros can't see the expansions of other items, so the source files of the crate are parsed instead,
+/// starting at the crate root and following `mod` declarations, including their `#[path]` attributes.
+/// The doubles of functions, `extern` blocks, traits and impl blocks are listed, including the fakes of
+/// `#[mock_function(fake)]` and the mocks of every instantiation of `#[mock_function(instantiate(..))]`.
+/// The `#[cfg]` attributes aren't evaluated, so doubles compiled out are listed as well,
+/// and modules whose file isn't found, e.g. generated by a build script, are missed.
+///
+/// # Returns
+///
+/// - `Ok(TokenStream2)` - An expression of type `&'static [fnmock::inventory::GeneratedDouble]`
+/// - `Err(syn::Error)` - If a source file can't be read or parsed
+pub(crate) fn process_mocks_in_crate() -> syn::Result<TokenStream2> {
+    let crate_root = find_crate_root()?;
+    let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "crate".to_string());
+
+    let mut doubles = Vec::new();
+    scan_file(&crate_root, &crate_name, true, &mut doubles)?;
+
+    let entries = doubles.iter().map(|double| {
+        let module_path = &double.module_path;
+        let function = &double.function;
+        let module = &double.module;
+        let kind = syn::Ident::new(double.kind, proc_macro2::Span::call_site());
+        quote! {
+            fnmock::inventory::GeneratedDouble {
+                module_path: #module_path,
+                function: #function,
+                module: #module,
+                kind: fnmock::inventory::DoubleKind::#kind,
+            }
+        }
+    });
+
+    Ok(quote! {
+        {
+            const DOUBLES: &[fnmock::inventory::GeneratedDouble] = &[#(#entries),*];
+            DOUBLES
+        }
+    })
+}
+
+/// Returns the root file of the crate being compiled, `src/lib.rs` or the file of the binary.
+fn find_crate_root() -> syn::Result<PathBuf> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| error("mocks_in_crate!() requires CARGO_MANIFEST_DIR, build the crate with cargo"))?;
+    let src = Path::new(&manifest_dir).join("src");
+
+    let root = match (std::env::var("CARGO_BIN_NAME"), std::env::var("CARGO_PKG_NAME")) {
+        (Ok(bin_name), Ok(package_name)) if bin_name != package_name => src.join("bin").join(format!("{}.rs", bin_name)),
+        (Ok(_), _) => src.join("main.rs"),
+        (Err(_), _) => src.join("lib.rs"),
+    };
+    Ok(root)
+}
+
+/// Collects the doubles of a source file and the modules it declares.
+///
+/// # Arguments
+///
+/// * `path` - The source file
+/// * `module_path` - The module path of the file
+/// * `owns_directory` - Whether the file is a crate root or `mod.rs`, whose submodules are next to it
+/// * `doubles` - The doubles found so far
+fn scan_file(path: &Path, module_path: &str, owns_directory: bool, doubles: &mut Vec<FoundDouble>) -> syn::Result<()> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| error(&format!("mocks_in_crate!() can't read {}: {}", path.display(), e)))?;
+    let file = syn::parse_file(&source)
+        .map_err(|e| error(&format!("mocks_in_crate!() can't parse {}: {}", path.display(), e)))?;
+
+    let directory = match owns_directory {
+        true => path.parent().unwrap().to_path_buf(),
+        false => path.with_extension(""),
+    };
+    scan_items(&file.items, &directory, path.parent().unwrap(), module_path, doubles)
+}
+
+/// Collects the doubles of the items of a module and its submodules.
+///
+/// # Arguments
+///
+/// * `items` - The items of the module
+/// * `directory` - The directory of the files of the submodules
+/// * `path_directory` - The directory the `#[path]` attributes of the submodules are relative to
+/// * `module_path` - The module path of the module
+/// * `doubles` - The doubles found so far
+fn scan_items(
+    items: &[syn::Item],
+    directory: &Path,
+    path_directory: &Path,
+    module_path: &str,
+    doubles: &mut Vec<FoundDouble>,
+) -> syn::Result<()> {
+    let found = |function: &dyn ToString, module: String, kind| FoundDouble {
+        module_path: module_path.to_string(),
+        function: function.to_string(),
+        module,
+        kind,
+    };
+
+    for item in items {
+        match item {
+            syn::Item::Fn(item_fn) => {
+                let name = &item_fn.sig.ident;
+                for attr in &item_fn.attrs {
+                    match double_kind(attr.path()) {
+                        Some("Mock") => {
+                            let args = parse_mock_function_args(attr)?;
+                            // A generic function has a mock per instantiation instead of a single one
+                            if args.instantiations.is_empty() {
+                                doubles.push(found(name, format!("{}_mock", name), "Mock"));
+                            }
+                            for instantiation in &args.instantiations {
+                                let suffix = item_fn.sig.generics.type_params()
+                                    .filter_map(|param| instantiation.iter().find(|(name, _)| *name == param.ident))
+                                    .map(|(_, ty)| instantiation_suffix(ty))
+                                    .collect::<Vec<_>>()
+                                    .join("_");
+                                doubles.push(found(name, format!("{}_{}_mock", name, suffix), "Mock"));
+                            }
+                            if args.fake {
+                                doubles.push(found(name, format!("{}_fake", name), "Fake"));
+                            }
+                        }
+                        Some(kind) => doubles.push(found(name, format!("{}_{}", name, kind.to_lowercase()), kind)),
+                        None => {}
+                    }
+                }
+            }
+            // A mocked `extern` block generates a mock per declared function
+            syn::Item::ForeignMod(foreign_mod) if find_mock_function_attr(&foreign_mod.attrs).is_some() => {
+                for foreign_item in &foreign_mod.items {
+                    if let syn::ForeignItem::Fn(foreign_fn) = foreign_item {
+                        let name = &foreign_fn.sig.ident;
+                        doubles.push(found(name, format!("{}_mock", name), "Mock"));
+                    }
+                }
+            }
+            // The mocks of methods are in a module named after the trait or the type, e.g. `user_service_mock::find_user`
+            syn::Item::Trait(item_trait) if find_attr(&item_trait.attrs, "mock_trait").is_some() => {
+                for trait_item in &item_trait.items {
+                    if let syn::TraitItem::Fn(method) = trait_item {
+                        doubles.push(method_double(&found, &item_trait.ident, &method.sig.ident));
+                    }
+                }
+            }
+            syn::Item::Impl(item_impl) => {
+                let syn::Type::Path(type_path) = &*item_impl.self_ty else {
+                    continue;
+                };
+                let Some(type_name) = type_path.path.segments.last().map(|segment| &segment.ident) else {
+                    continue;
+                };
+                for method in mocked_methods(item_impl)? {
+                    doubles.push(method_double(&found, type_name, method));
+                }
+            }
+            syn::Item::Mod(item_mod) => {
+                let name = item_mod.ident.to_string();
+                let child_path = format!("{}::{}", module_path, name);
+                let child_directory = directory.join(&name);
+                match &item_mod.content {
+                    Some((_, items)) => scan_items(items, &child_directory, &child_directory, &child_path, doubles)?,
+                    None => {
+                        if let Some((file, owns_directory)) = module_file(item_mod, directory, path_directory)? {
+                            scan_file(&file, &child_path, owns_directory, doubles)?;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the file of a module declared without a body and whether it owns its directory,
+/// or None if the file doesn't exist, e.g. for a module generated by a build script.
+///
+/// A file loaded with `#[path]` owns its directory like `mod.rs`.
+fn module_file(item_mod: &syn::ItemMod, directory: &Path, path_directory: &Path) -> syn::Result<Option<(PathBuf, bool)>> {
+    if let Some(attr) = find_attr(&item_mod.attrs, "path") {
+        let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(path), .. }) = &attr.meta.require_name_value()?.value else {
+            return Err(syn::Error::new_spanned(attr, "expected #[path = \"..\"]"));
+        };
+        let file = path_directory.join(path.value());
+        return Ok(file.exists().then_some((file, true)));
+    }
+
+    let name = item_mod.ident.to_string();
+    let candidates = [(directory.join(format!("{}.rs", name)), false), (directory.join(&name).join("mod.rs"), true)];
+    Ok(candidates.into_iter().find(|(file, _)| file.exists()))
+}
+
+/// Creates the double of a method, whose mock is in the module of the trait or the type.
+fn method_double(
+    found: &impl Fn(&dyn ToString, String, &'static str) -> FoundDouble,
+    type_name: &syn::Ident,
+    method: &syn::Ident,
+) -> FoundDouble {
+    let module = format!("{}_mock::{}", snake_case(&type_name.to_string()), method);
+    found(&format!("{}::{}", type_name, method), module, "Mock")
+}
+
+/// Returns the methods of an impl block mocked by `#[mock_struct]` or shimmed by `#[mock_method]`.
+fn mocked_methods(item_impl: &syn::ItemImpl) -> syn::Result<Vec<&syn::Ident>> {
+    let methods = item_impl.items.iter().filter_map(|item| match item {
+        syn::ImplItem::Fn(method) => Some(method),
+        _ => None,
+    });
+
+    if let Some(attr) = find_attr(&item_impl.attrs, "mock_struct") {
+        let skip = match &attr.meta {
+            syn::Meta::List(_) => attr.parse_args::<MockStructArgs>()?.skip,
+            _ => Vec::new(),
+        };
+        // The methods are selected like in `process_mock_struct`
+        let is_selective = is_selective(&item_impl.items);
+        let mut mocked = Vec::new();
+        for method in methods {
+            if method_mock_args(method, &skip, is_selective)?.is_some() {
+                mocked.push(&method.sig.ident);
+            }
+        }
+        return Ok(mocked);
+    }
+    if let Some(attr) = find_attr(&item_impl.attrs, "mock_method") {
+        let names = parse_method_names(attr.meta.require_list()?.tokens.clone())?;
+        return Ok(methods.map(|method| &method.sig.ident).filter(|method| names.contains(method)).collect());
+    }
+    Ok(Vec::new())
+}
+
+/// Finds the attribute whose path ends with the name, e.g. `#[mock_struct]` or `#[fnmock::derive::mock_struct]`.
+fn find_attr<'a>(attrs: &'a [syn::Attribute], name: &str) -> Option<&'a syn::Attribute> {
+    attrs.iter().find(|attr| attr.path().segments.last().is_some_and(|segment| segment.ident == name))
+}
+
+/// Returns the kind of double an attribute generates, matching the last segment of its path.
+fn double_kind(path: &syn::Path) -> Option<&'static str> {
+    let segment = path.segments.last()?;
+    match segment.ident.to_string().as_str() {
+        "mock_function" => Some("Mock"),
+        "fake_function" => Some("Fake"),
+        "stub_function" => Some("Stub"),
+        _ => None,
+    }
+}
+
+fn error(message: &str) -> syn::Error {
+    syn::Error::new(proc_macro2::Span::call_site(), message)
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Environment variable enabling the dump of the expansions, read while the macros expand
+const DEBUG_ENV_VAR: &str = "FNMOCK_DEBUG";
+
+/// Writes the expansion of a macro to `target/fnmock-expansions/<name>.rs`, if `FNMOCK_DEBUG` is set.
+///
+/// `FNMOCK_DEBUG=1` dumps every expansion, any other value is a comma separated list of the
+/// names to dump, e.g. `FNMOCK_DEBUG=fetch_user,send_email`. An expansion of the same name replaces
+/// the previous dump. The file is formatted with `rustfmt` if it is installed, and its path is printed
+/// as a note. Dumping is best effort, so a failure is printed instead of failing the build.
+///
+/// # Arguments
+///
+/// * `macro_name` - The name of the expanded macro, e.g. `mock_function`
+/// * `name` - The name of the annotated item, which names the file
+/// * `expansion` - The generated code
+pub(crate) fn dump_expansion(macro_name: &str, name: &syn::Ident, expansion: &proc_macro2::TokenStream) {
+    let Ok(filter) = std::env::var(DEBUG_ENV_VAR) else {
+        return;
+    };
+    let name = name.to_string();
+    let name = name.trim_start_matches("r#");
+    if !matches!(filter.trim(), "" | "1" | "true") && !filter.split(',').any(|filtered| filtered.trim() == name) {
+        return;
+    }
+
+    let dir = expansion_dir();
+    let path = dir.join(format!("{}.rs", name));
+    let content = format!("// Expansion of #[{}] on `{}`\n\n{}\n", macro_name, name, expansion);
+    if let Err(error) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, content)) {
+        eprintln!("warning: fnmock failed to write the expansion of `{}` to {}: {}", name, path.display(), error);
+        return;
+    }
+
+    // The raw token stream is a single line, rustfmt's output is ignored, since the dump is kept unformatted on errors
+    let _ = Command::new("rustfmt").args(["--edition", "2021"]).arg(&path).output();
+    eprintln!("note: fnmock wrote the expansion of `{}` to {}", name, path.display());
+}
+
+/// Returns the directory of the dumps in the target directory of the crate being compiled.
+///
+/// Cargo doesn't pass the target directory to the compiler, so it is `CARGO_TARGET_DIR` if set,
+/// or the `target` directory of the closest ancestor of the crate having one, e.g. the workspace root.
+fn expansion_dir() -> PathBuf {
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR").map(PathBuf::from).unwrap_or_else(|| {
+        let manifest_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default());
+        manifest_dir
+            .ancestors()
+            .find(|dir| dir.join("target").is_dir())
+            .unwrap_or(&manifest_dir)
+            .join("target")
+    });
+    target_dir.join("fnmock-expansions")
+}
@@ -0,0 +1,9 @@
+/// Checks whether the generated proxy functions get rich documentation.
+///
+/// This is the case when the `rich-docs` feature of `fnmock` (and therefore `fnmock-derive`) is enabled,
+/// which it is by default. Generating the docs costs expansion time for every double,
+/// so large codebases that never read the generated docs can disable it for faster builds.
+/// The proxy functions are undocumented without it.
+pub(crate) fn is_rich_docs_enabled() -> bool {
+    cfg!(feature = "rich-docs")
+}
@@ -0,0 +1,70 @@
+//! Expansion benchmarks for the double macros.
+//!
+//! Crates with hundreds of annotated functions pay the expansion time on every build,
+//! so these measure the `process_*_function` entry points on representative functions.
+//! They are ignored by default, run them in release mode to catch macro-side regressions:
+//!
+//! ```text
+//! cargo test -p fnmock-derive --release -- --ignored --nocapture expansion_bench
+//! ```
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use crate::function_fake::process_fake_function;
+use crate::function_mock::process_mock_function;
+use crate::function_stub::process_stub_function;
+
+const ITERATIONS: u32 = 1000;
+
+// Generous upper bound per expansion, only meant to catch accidental slowdowns by orders of magnitude
+const MAX_EXPANSION_TIME: Duration = Duration::from_millis(5);
+
+fn representative_function() -> syn::ItemFn {
+    syn::parse_quote! {
+        pub fn send_email(user: String, subject: String, body: Vec<u8>, retries: u32) -> Result<u64, String> {
+            let message_id = body.len() as u64 + retries as u64;
+            println!("Sending {} to {}", subject, user);
+            Ok(message_id)
+        }
+    }
+}
+
+fn bench(name: &str, expand: impl Fn(syn::ItemFn) -> syn::Result<proc_macro2::TokenStream>) {
+    let item = representative_function();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(expand(black_box(item.clone())).unwrap());
+    }
+    let per_expansion = start.elapsed() / ITERATIONS;
+
+    println!("{}: {:?} per expansion", name, per_expansion);
+    assert!(
+        per_expansion < MAX_EXPANSION_TIME,
+        "{} expansion took {:?}, expected less than {:?}", name, per_expansion, MAX_EXPANSION_TIME
+    );
+}
+
+#[test]
+#[ignore]
+fn expansion_bench_mock_function() {
+    bench("mock_function", |item| process_mock_function(item, Vec::new(), None, false, false, false, false, Vec::new()));
+}
+
+#[test]
+#[ignore]
+fn expansion_bench_mock_function_with_ignored_params() {
+    bench("mock_function(ignore = [body])", |item| process_mock_function(item, vec!["body".to_string()], None, false, false, false, false, Vec::new()));
+}
+
+#[test]
+#[ignore]
+fn expansion_bench_fake_function() {
+    bench("fake_function", process_fake_function);
+}
+
+#[test]
+#[ignore]
+fn expansion_bench_stub_function() {
+    bench("stub_function", |item| process_stub_function(item, false));
+}
@@ -0,0 +1,83 @@
+use quote::quote;
+
+/// Checks whether generated doubles should be exported to downstream crates.
+///
+/// This is the case when the `mocks` feature of `fnmock` (and therefore `fnmock-derive`) is enabled.
+/// Library crates forward their own `mocks` feature to `fnmock/mocks`, so downstream crates can
+/// enable it through a dev-dependency and configure the library's doubles in their own tests.
+pub(crate) fn is_export_enabled() -> bool {
+    cfg!(feature = "mocks")
+}
+
+/// Creates the cfg attribute under which the generated doubles are compiled.
+///
+/// # Returns
+///
+/// - Without the `mocks` feature: `#[cfg(test)]`
+/// - With the `mocks` feature: nothing, since the doubles have to exist in non-test builds
+///   of the library for downstream tests to use them
+pub(crate) fn double_cfg() -> proc_macro2::TokenStream {
+    if is_export_enabled() {
+        quote! {}
+    } else {
+        quote! { #[cfg(test)] }
+    }
+}
+
+/// Creates the cfg attribute under which the original function is compiled without the doubles.
+///
+/// Only needed for functions generated twice, like the ones returning `impl Iterator`.
+///
+/// # Returns
+///
+/// - Without the `mocks` feature: `Some(#[cfg(not(test))])`
+/// - With the `mocks` feature: None, since the doubles are compiled in every build
+pub(crate) fn original_cfg() -> Option<proc_macro2::TokenStream> {
+    (!is_export_enabled()).then(|| quote! { #[cfg(not(test))] })
+}
+
+/// Collects the `#[cfg(..)]` attributes of the doubled function.
+///
+/// Every generated item carries them, so the function and its double modules are compiled out together,
+/// e.g. for a function behind `#[cfg(feature = "postgres")]` placed below the attribute macro.
+/// They are combined with `double_cfg()` / `original_cfg()`, which only adds further conditions.
+///
+/// # Arguments
+///
+/// * `attrs` - The attributes of the doubled function
+pub(crate) fn item_cfg(attrs: &[syn::Attribute]) -> proc_macro2::TokenStream {
+    let cfg_attrs = attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+    quote! { #(#cfg_attrs)* }
+}
+
+/// Creates the visibility of the generated double module and its proxy functions.
+///
+/// # Arguments
+///
+/// * `fn_visibility` - The visibility of the original function
+///
+/// # Returns
+///
+/// - `pub` if the `mocks` feature is enabled and the original function is `pub`
+/// - `pub(crate)` otherwise
+pub(crate) fn double_visibility(fn_visibility: &syn::Visibility) -> proc_macro2::TokenStream {
+    if is_export_enabled() && matches!(fn_visibility, syn::Visibility::Public(_)) {
+        quote! { pub }
+    } else {
+        quote! { pub(crate) }
+    }
+}
+
+/// Creates the expression evaluating to the location of the doubled function, e.g. `"src/db.rs:12"`.
+///
+/// The tokens are spanned at the name of the double module, which has the span of the function name,
+/// so `file!()` and `line!()` point at the function instead of the attribute.
+///
+/// # Arguments
+///
+/// * `double_mod_name` - The name of the generated double module
+pub(crate) fn definition_location(double_mod_name: &syn::Ident) -> proc_macro2::TokenStream {
+    quote::quote_spanned! { double_mod_name.span()=>
+        concat!(file!(), ":", line!())
+    }
+}
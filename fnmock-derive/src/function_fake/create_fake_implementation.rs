@@ -1,4 +1,6 @@
 use quote::quote;
+use crate::doc_utils::is_rich_docs_enabled;
+use crate::export_utils::{definition_location, double_cfg};
 use syn::token::Async;
 use crate::param_utils::get_param_names;
 use crate::function_fake::proxy_docs::FakeProxyDocs;
@@ -14,6 +16,8 @@ use crate::function_fake::proxy_docs::FakeProxyDocs;
 /// * `fn_name` - The name of the original function
 /// * `fn_visibility` - The visibility modifier of the function (pub, pub(crate), etc.)
 /// * `fn_asyncness` - Optional async keyword if the function is async
+/// * `fn_unsafety` - Optional unsafe keyword if the function is unsafe
+/// * `fn_abi` - Optional ABI of the function, e.g. `extern "C"`
 /// * `fn_inputs` - The function parameters
 /// * `fn_output` - The return type
 /// * `fn_block` - The original function body to execute when fake is not set
@@ -22,22 +26,26 @@ use crate::function_fake::proxy_docs::FakeProxyDocs;
 /// # Returns
 ///
 /// Generated token stream for the function with injected fake checking logic
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_fake_function(
     fn_name: syn::Ident,
     fn_visibility: syn::Visibility,
     fn_asyncness: Option<Async>,
-    fn_inputs: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+    fn_unsafety: Option<syn::token::Unsafe>,
+    fn_abi: Option<syn::Abi>,
+    fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
     fn_output: syn::ReturnType,
-    fn_block: Box<syn::Block>,
+    fn_block: &syn::Block,
     fake_mod_name: syn::Ident,
 ) -> proc_macro2::TokenStream {
-    let param_names = get_param_names(&fn_inputs);
+    let param_names = get_param_names(fn_inputs);
     let original_fn_stmts = &fn_block.stmts;
+    let fake_cfg = double_cfg();
     
     quote! {
-        #fn_visibility #fn_asyncness fn #fn_name(#fn_inputs) #fn_output {
-            // Call the fake implementation if set (only in test mode)
-            #[cfg(test)]
+        #fn_visibility #fn_asyncness #fn_unsafety #fn_abi fn #fn_name(#fn_inputs) #fn_output {
+            // Call the fake implementation if set (only in test mode or with the `mocks` feature)
+            #fake_cfg
             if #fake_mod_name::is_set() {
                 return #fake_mod_name::get_implementation()(#(#param_names),*);
             }
@@ -51,55 +59,82 @@ pub(crate) fn create_fake_function(
 ///
 /// Creates a module with the same name as the fake function that contains:
 /// - Type alias for the function type
-/// - Thread-local storage for the FunctionFake instance
+/// - Thread-local storage for the FunctionFake instance, registered in `fnmock::registry` once configured
 /// - Proxy functions for fake operations
 ///
 /// # Arguments
 ///
 /// * `fake_fn_name` - The name of the fake module (same as fake function name)
+/// * `mod_visibility` - The visibility of the fake module and its proxy functions
 /// * `params_type` - The type representing the function parameters (single type or tuple)
 /// * `return_type` - The return type of the function
 /// * `fn_inputs` - The original function parameters (for documentation)
 pub(crate) fn create_fake_module(
     fake_fn_name: syn::Ident,
+    mod_visibility: proc_macro2::TokenStream,
     params_type: syn::Type,
     return_type: syn::Type,
     fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
     fn_asyncness: Option<syn::token::Async>,
 ) -> proc_macro2::TokenStream {
     // Generate documentation using the proxy_docs module
-    let docs = FakeProxyDocs::new(&fake_fn_name, fn_inputs, &return_type, fn_asyncness);
-    let setup_docs = docs.setup_docs();
-    let clear_docs = docs.clear_docs();
-    let is_set_docs = docs.is_set_docs();
-    let get_implementation_docs = docs.get_implementation_docs();
+    // Without the `rich-docs` feature the docs are None and generate nothing
+    let docs = is_rich_docs_enabled().then(|| FakeProxyDocs::new(&fake_fn_name, fn_inputs, &return_type, fn_asyncness));
+    let setup_docs = docs.as_ref().map(FakeProxyDocs::setup_docs);
+    let clear_docs = docs.as_ref().map(FakeProxyDocs::clear_docs);
+    let is_set_docs = docs.as_ref().map(FakeProxyDocs::is_set_docs);
+    let get_implementation_docs = docs.as_ref().map(FakeProxyDocs::get_implementation_docs);
+    let definition = definition_location(&fake_fn_name);
     
     quote! {
-        pub(crate) mod #fake_fn_name {
+        #mod_visibility mod #fake_fn_name {
             use super::*;
 
+            // Location of the faked function, which is appended to the panic messages of the fake
+            const DEFINITION: &str = #definition;
+
+            // The const initializer doesn't allocate or register anything,
+            // so tests that never configure the fake don't pay for it.
             thread_local! {
-                static FAKE: std::cell::RefCell<fnmock::function_fake::FunctionFake<fn(#params_type) -> #return_type>> =
-                    std::cell::RefCell::new(fnmock::function_fake::FunctionFake::new(stringify!(#fake_fn_name)));
+                static FAKE: std::cell::RefCell<fnmock::function_fake::FunctionFake<fn(#params_type) -> #return_type>> = const {
+                    std::cell::RefCell::new(fnmock::function_fake::FunctionFake::new(stringify!(#fake_fn_name)).with_definition(DEFINITION))
+                };
+            }
+
+            // Registers the fake for `fnmock::clear_all_mocks!()` once it is configured
+            fn register() {
+                fnmock::registry::register(module_path!(), clear, || FAKE.with(|fake| fake.borrow().to_string()));
             }
 
             #setup_docs
-            pub(crate) fn setup(new_f: fn(#params_type) -> #return_type) {
-                FAKE.with(|fake| { fake.borrow_mut().setup(new_f) })
+            #[track_caller]
+            #mod_visibility fn setup(new_f: fn(#params_type) -> #return_type) {
+                // Remembers where the fake is set up for the repeated setup check of `fnmock::strict`
+                let location = std::panic::Location::caller();
+                register();
+                FAKE.with(|fake| {
+                    let mut fake = fake.borrow_mut();
+                    fake.record_setup(location);
+                    fake.setup(new_f)
+                })
             }
 
             #clear_docs
-            pub(crate) fn clear() {
+            #mod_visibility fn clear() {
                 FAKE.with(|fake| { fake.borrow_mut().clear() })
             }
 
             #is_set_docs
-            pub(crate) fn is_set() -> bool {
+
+            #[inline]
+            #mod_visibility fn is_set() -> bool {
                 FAKE.with(|fake| { fake.borrow().is_set() })
             }
 
             #get_implementation_docs
-            pub(crate) fn get_implementation() -> fn(#params_type) -> #return_type {
+
+            #[inline]
+            #mod_visibility fn get_implementation() -> fn(#params_type) -> #return_type {
                 FAKE.with(|fake| { fake.borrow().get_implementation() })
             }
         }
@@ -1,10 +1,11 @@
 use quote::quote;
 use syn::__private::TokenStream2;
 use crate::function_fake::create_fake_implementation::{create_fake_function, create_fake_module};
-use crate::param_utils::{create_param_type, get_param_names};
+use crate::param_utils::create_param_type;
+use crate::export_utils::{double_cfg, double_visibility, item_cfg};
 use crate::return_utils::extract_return_type;
 
-mod create_fake_implementation;
+pub(crate) mod create_fake_implementation;
 mod proxy_docs;
 
 /// Processes a function and generates the complete fake infrastructure.
@@ -25,42 +26,53 @@ mod proxy_docs;
 /// - `Ok(TokenStream2)` - The complete generated code including original and fake infrastructure
 /// - `Err(syn::Error)` - If validation fails or the function cannot be faked
 pub(crate) fn process_fake_function(fake_function: syn::ItemFn) -> syn::Result<TokenStream2> {
-    // Extract function details
-    let fn_visibility = fake_function.vis.clone();
-    let fn_asyncness = fake_function.sig.asyncness;
-    let fn_name = fake_function.sig.ident.clone();
-    let fn_inputs = fake_function.sig.inputs.clone();
-    let fn_output = fake_function.sig.output.clone();
-    let fn_block = fake_function.block.clone();
+    // Extract function details, moving them out of the item instead of cloning them
+    let item_cfg = item_cfg(&fake_function.attrs);
+    let syn::ItemFn { attrs: fn_attrs, vis: fn_visibility, sig, block: fn_block } = fake_function;
+    let fn_asyncness = sig.asyncness;
+    let fn_unsafety = sig.unsafety;
+    let fn_abi = sig.abi;
+    let fn_name = sig.ident;
+    let fn_inputs = sig.inputs;
+    let fn_output = sig.output;
 
     // Generate fake function name
     let fake_mod_name = syn::Ident::new(&format!("{}_fake", &fn_name), fn_name.span());
+    let fake_mod_visibility = double_visibility(&fn_visibility);
 
     let params_type = create_param_type(&fn_inputs, &[]);
-    let return_type = extract_return_type(&fake_function.sig.output);
+    let return_type = extract_return_type(&fn_output);
 
     let fake_function = create_fake_function(
         fn_name,
         fn_visibility,
         fn_asyncness,
-        fn_inputs.clone(),
+        fn_unsafety,
+        fn_abi,
+        &fn_inputs,
         fn_output,
-        fn_block,
+        &fn_block,
         fake_mod_name.clone(),
     );
 
     let fake_module = create_fake_module(
         fake_mod_name,
+        fake_mod_visibility,
         params_type,
         return_type,
         &fn_inputs,
         fn_asyncness
     );
 
+    let fake_cfg = double_cfg();
+
     Ok(quote! {
+        // The original attributes include the cfg attributes
+        #(#fn_attrs)*
         #fake_function
 
-        #[cfg(test)]
+        #item_cfg
+        #fake_cfg
         #fake_module
     })
 }
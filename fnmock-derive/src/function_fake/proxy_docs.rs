@@ -1,4 +1,4 @@
-/// Generates documentation strings for fake proxy functions based on actual function parameters.
+//! Generates documentation strings for fake proxy functions based on actual function parameters.
 
 use quote::quote;
 
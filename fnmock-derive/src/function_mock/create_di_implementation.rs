@@ -0,0 +1,108 @@
+use quote::quote;
+use crate::export_utils::double_cfg;
+use crate::naming_utils::upper_camel_case;
+use crate::param_utils::create_tuple_from_param_names;
+
+/// Generates the dependency injection seam of a function mocked with `#[mock_function(di)]`.
+///
+/// Creates, next to the unchanged original function:
+/// - A trait with a single method named like the function, e.g. `FetchUser::fetch_user(&self, id)`
+/// - A struct holding the function as an injectable function pointer, which defaults to the original function
+/// - A test double implementing the trait, which calls the mock module (test-only)
+///
+/// # Arguments
+///
+/// * `fn_name` - The name of the original function
+/// * `fn_visibility` - The visibility of the original function, which the trait and the struct share
+/// * `fn_inputs` - The function parameters
+/// * `fn_output` - The return type
+/// * `mock_mod_name` - The name of the mock module the test double calls
+/// * `mock_visibility` - The visibility of the mock module, which the test double shares
+/// * `ignore_indices` - Indices of the parameters that the mock doesn't record
+/// * `item_cfg` - The cfg attributes of the original function, which every generated item carries
+///
+/// # Returns
+///
+/// - `Ok(TokenStream)` - The trait, the struct and the test double
+/// - `Err(syn::Error)` - If a parameter isn't a plain identifier, which the trait method can't declare
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_di_seam(
+    fn_name: &syn::Ident,
+    fn_visibility: &syn::Visibility,
+    fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+    fn_output: &syn::ReturnType,
+    mock_mod_name: &syn::Ident,
+    mock_visibility: &proc_macro2::TokenStream,
+    ignore_indices: &[usize],
+    item_cfg: &proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // Methods without a body can't declare patterns, so `mut` is dropped and other patterns are rejected
+    let mut fn_inputs = fn_inputs.clone();
+    let mut param_names = Vec::new();
+    let mut param_types = Vec::new();
+    for input in fn_inputs.iter_mut() {
+        let syn::FnArg::Typed(pat_type) = input else {
+            return Err(syn::Error::new_spanned(input, "`di` requires a standalone function without `self`"));
+        };
+        let syn::Pat::Ident(pat_ident) = &mut *pat_type.pat else {
+            return Err(syn::Error::new_spanned(&pat_type.pat, "`di` requires named parameters, destructure them in the function body instead"));
+        };
+        pat_ident.mutability = None;
+        param_names.push(pat_ident.ident.clone());
+        param_types.push(pat_type.ty.clone());
+    }
+    let params_to_tuple = create_tuple_from_param_names(&fn_inputs, ignore_indices);
+
+    let trait_name = syn::Ident::new(&upper_camel_case(&fn_name.to_string()), fn_name.span());
+    let struct_name = syn::Ident::new(&format!("{}Fn", trait_name), fn_name.span());
+    let double_name = syn::Ident::new(&format!("Mock{}", trait_name), fn_name.span());
+    let mock_cfg = double_cfg();
+
+    let trait_doc = format!("Dependency injection seam of [`{fn_name}`], generated by `#[mock_function(di)]`");
+    let struct_doc = format!("Implements [`{trait_name}`] with an injectable function pointer, which defaults to [`{fn_name}`]");
+    let double_doc = format!("Implements [`{trait_name}`] with the `{mock_mod_name}` module, so the calls are configured and asserted like the mock");
+
+    Ok(quote! {
+        #item_cfg
+        #[doc = #trait_doc]
+        #fn_visibility trait #trait_name {
+            fn #fn_name(&self, #(#param_names: #param_types),*) #fn_output;
+        }
+
+        #item_cfg
+        #[doc = #struct_doc]
+        #[derive(Clone, Copy)]
+        #fn_visibility struct #struct_name {
+            pub #fn_name: fn(#(#param_types),*) #fn_output,
+        }
+
+        #item_cfg
+        impl Default for #struct_name {
+            fn default() -> Self {
+                Self { #fn_name }
+            }
+        }
+
+        #item_cfg
+        impl #trait_name for #struct_name {
+            fn #fn_name(&self, #(#param_names: #param_types),*) #fn_output {
+                (self.#fn_name)(#(#param_names),*)
+            }
+        }
+
+        #item_cfg
+        #mock_cfg
+        #[doc = #double_doc]
+        #[derive(Clone, Copy, Default)]
+        #mock_visibility struct #double_name;
+
+        #item_cfg
+        #mock_cfg
+        impl #trait_name for #double_name {
+            #[allow(unused_variables)]
+            fn #fn_name(&self, #(#param_names: #param_types),*) #fn_output {
+                #mock_mod_name::call(#params_to_tuple)
+            }
+        }
+    })
+}
@@ -0,0 +1,136 @@
+use quote::{quote, ToTokens};
+use crate::export_utils::{double_cfg, double_visibility, item_cfg, original_cfg};
+use crate::function_mock::create_mock_implementation::{create_mock_function, create_mock_module};
+use crate::param_utils::{create_param_type, create_tuple_from_param_names, elide_ref_params, filter_params, get_param_names, owned_ref_indices, record_fn_pointers, record_owned_refs, validate_static_params};
+use crate::return_utils::extract_return_type;
+use crate::type_utils::static_lifetimes;
+
+/// Generates the mocks of the functions declared in an `extern` block, e.g. the bindings of a C library.
+///
+/// The declarations have no body, so in test builds every function is replaced by an `unsafe` function
+/// with the ABI of the block, which calls its mock module and panics if the mock isn't configured.
+/// The block itself is only compiled in the other builds, so tests don't link the foreign library.
+/// With the `mocks` feature the replacements exist in every build and call the foreign function instead of panicking.
+///
+/// # Arguments
+///
+/// * `foreign_mod` - The `extern` block, which may only declare functions
+/// * `group` - The name of the group the mocks belong to, if any
+/// * `type_state` - Whether the assertions are only available on the `MockHandle` returned by the setups
+/// * `own_refs` - Whether reference parameters are recorded as owned values instead of being rejected
+///
+/// # Returns
+///
+/// - `Ok(TokenStream)` - The block, the replacements and the mock modules
+/// - `Err(syn::Error)` - If the block declares other items, or a function is variadic or has unnamed or non 'static parameters
+pub(crate) fn create_foreign_mocks(
+    foreign_mod: syn::ItemForeignMod,
+    group: Option<String>,
+    type_state: bool,
+    own_refs: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let block_cfg = item_cfg(&foreign_mod.attrs);
+    let mock_cfg = double_cfg();
+
+    let mut mocks = Vec::new();
+    for item in &foreign_mod.items {
+        let syn::ForeignItem::Fn(foreign_fn) = item else {
+            return Err(syn::Error::new_spanned(item, "mock_function only supports functions in `extern` blocks"));
+        };
+        let sig = &foreign_fn.sig;
+        if let Some(variadic) = &sig.variadic {
+            return Err(syn::Error::new_spanned(variadic, "variadic functions can't be mocked"));
+        }
+        // The parameters are passed to the mock by name
+        if let Some(param) = sig.inputs.iter().find(|param| !matches!(param, syn::FnArg::Typed(pat_type) if matches!(*pat_type.pat, syn::Pat::Ident(_)))) {
+            return Err(syn::Error::new_spanned(param, "mock_function requires named parameters in `extern` blocks"));
+        }
+        let unchecked_indices = match own_refs {
+            true => owned_ref_indices(&sig.inputs),
+            false => Vec::new(),
+        };
+        validate_static_params(&sig.inputs, &unchecked_indices)?;
+
+        let fn_name = &sig.ident;
+        let mock_mod_name = syn::Ident::new(&format!("{}_mock", fn_name), fn_name.span());
+        let mock_mod_visibility = double_visibility(&foreign_fn.vis);
+        let params_type = create_param_type(&record_owned_refs(&record_fn_pointers(&sig.inputs)), &[]);
+        let params_to_tuple = create_tuple_from_param_names(&sig.inputs, &[]);
+        let return_type = static_lifetimes(&extract_return_type(&sig.output));
+
+        // Without the foreign library, a call the mock doesn't handle has nothing to run
+        let fallback = match original_cfg() {
+            Some(_) => {
+                let message = format!("`{}` is declared in an `extern` block, which isn't linked in tests, set up `{}` before calling it", fn_name, mock_mod_name);
+                quote! { panic!(#message) }
+            }
+            None => {
+                let unsafety = &foreign_mod.unsafety;
+                let abi = &foreign_mod.abi;
+                let param_names = get_param_names(&sig.inputs);
+                quote! {
+                    #unsafety #abi {
+                        #foreign_fn
+                    }
+                    unsafe { #fn_name(#(#param_names),*) }
+                }
+            }
+        };
+        let fn_block: syn::Block = syn::parse_quote! {{ #fallback }};
+
+        // `link_name` only applies to foreign functions, the other attributes like doc comments are kept
+        let fn_attrs = foreign_fn.attrs.iter().filter(|attr| !attr.path().is_ident("link_name"));
+        let fn_cfg = item_cfg(&foreign_fn.attrs);
+        let mock_function = create_mock_function(
+            fn_name.clone(),
+            foreign_fn.vis.clone(),
+            None,
+            Some(Default::default()),
+            Some(foreign_mod.abi.clone()),
+            &syn::Generics::default(),
+            &sig.inputs,
+            sig.output.clone(),
+            &fn_block,
+            mock_mod_name.to_token_stream(),
+            params_to_tuple.clone(),
+            None,
+            None,
+            &quote! { #block_cfg #(#fn_attrs)* #mock_cfg },
+        );
+
+        let mock_module = create_mock_module(
+            mock_mod_name,
+            mock_mod_visibility,
+            params_type,
+            return_type,
+            &sig.inputs,
+            &[],
+            None,
+            params_to_tuple,
+            elide_ref_params(&filter_params(&sig.inputs, &[])),
+            None,
+            group.clone(),
+            type_state,
+        );
+
+        mocks.push(quote! {
+            #mock_function
+
+            #block_cfg
+            #fn_cfg
+            #mock_cfg
+            #mock_module
+        });
+    }
+
+    let original_block = original_cfg().map(|original_cfg| quote! {
+        #original_cfg
+        #foreign_mod
+    });
+
+    Ok(quote! {
+        #original_block
+
+        #(#mocks)*
+    })
+}
@@ -0,0 +1,164 @@
+use quote::{quote, ToTokens};
+use crate::export_utils::{double_cfg, original_cfg};
+use crate::function_mock::create_mock_implementation::create_mock_module;
+use crate::function_mock::mock_args::Instantiation;
+use crate::param_utils::{create_param_type, create_tuple_from_param_names, elide_ref_params, filter_params, record_fn_pointers, record_owned_refs};
+use crate::return_utils::extract_return_type;
+use crate::type_utils::substitute_types;
+
+/// Generates the mocks of a generic function, one mock module per instantiation.
+///
+/// The mocked function compares its type parameters with the ones of every instantiation
+/// and calls the mock of the matching one, converting the parameters and the result with `fnmock::instantiation`.
+/// The comparison requires `'static` type parameters, so the bound is added in test builds, where the function
+/// is generated a second time like the functions returning `impl Iterator`.
+/// The mock modules are named after the function and the concrete types, e.g. `parse_u32_mock` for `T = u32`.
+///
+/// # Arguments
+///
+/// * `fn_name` - The name of the generic function
+/// * `fn_visibility` - The visibility of the function
+/// * `fn_generics` - The generics of the function, including the where clause
+/// * `fn_inputs` - The function parameters
+/// * `fn_output` - The return type
+/// * `fn_block` - The original function body
+/// * `mod_visibility` - The visibility of the mock modules
+/// * `ignore_indices` - Indices of the parameters that the mocks don't record
+/// * `group` - The name of the group the mocks belong to, if any
+/// * `type_state` - Whether the assertions are only available on the `MockHandle` returned by the setups
+/// * `instantiations` - The concrete types of the type parameters of every mocked instantiation
+/// * `fn_attrs` - The attributes of the original function, including its cfg attributes, which the generated functions carry
+/// * `item_cfg` - The cfg attributes of the original function, which every generated item carries
+///
+/// # Returns
+///
+/// - `Ok(TokenStream)` - The function and the mock modules
+/// - `Err(syn::Error)` - If the function has lifetime or const parameters, or an instantiation doesn't name every type parameter
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_instantiated_mocks(
+    fn_name: &syn::Ident,
+    fn_visibility: &syn::Visibility,
+    fn_generics: &syn::Generics,
+    fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+    fn_output: &syn::ReturnType,
+    fn_block: &syn::Block,
+    mod_visibility: proc_macro2::TokenStream,
+    ignore_indices: &[usize],
+    group: Option<String>,
+    type_state: bool,
+    instantiations: Vec<Instantiation>,
+    fn_attrs: &proc_macro2::TokenStream,
+    item_cfg: &proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if let Some(param) = fn_generics.params.iter().find(|param| !matches!(param, syn::GenericParam::Type(_))) {
+        return Err(syn::Error::new_spanned(param, "instantiate only supports functions with type parameters"));
+    }
+    let type_params: Vec<&syn::Ident> = fn_generics.type_params().map(|param| &param.ident).collect();
+    if type_params.is_empty() {
+        return Err(syn::Error::new_spanned(fn_name, "instantiate requires a generic function"));
+    }
+
+    let generic_return_type = extract_return_type(fn_output);
+    let params_to_tuple = create_tuple_from_param_names(fn_inputs, ignore_indices);
+    let mock_cfg = double_cfg();
+
+    let mut dispatches = Vec::new();
+    let mut mock_modules = Vec::new();
+    for instantiation in instantiations {
+        // Every type parameter needs a concrete type, in the order of the generics
+        let concrete_types = type_params
+            .iter()
+            .map(|type_param| {
+                instantiation.iter().find(|(name, _)| name == *type_param).map(|(_, ty)| ty.clone()).ok_or_else(|| {
+                    syn::Error::new_spanned(type_param, format!("instantiate requires a type for `{}` in every instantiation", type_param))
+                })
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+        if let Some((name, _)) = instantiation.iter().find(|(name, _)| !type_params.contains(&name)) {
+            return Err(syn::Error::new_spanned(name, format!("`{}` isn't a type parameter of the function", name)));
+        }
+
+        let substitutions: Vec<_> = type_params.iter().map(|param| (*param).clone()).zip(concrete_types.iter().cloned()).collect();
+        let mut concrete_inputs = fn_inputs.clone();
+        for input in concrete_inputs.iter_mut() {
+            if let syn::FnArg::Typed(pat_type) = input {
+                *pat_type.ty = substitute_types(&pat_type.ty, &substitutions);
+            }
+        }
+        let params_type = create_param_type(&record_owned_refs(&record_fn_pointers(&concrete_inputs)), ignore_indices);
+        let return_type = substitute_types(&generic_return_type, &substitutions);
+
+        let suffix = concrete_types.iter().map(instantiation_suffix).collect::<Vec<_>>().join("_");
+        let mock_mod_name = syn::Ident::new(&format!("{}_{}_mock", fn_name, suffix), fn_name.span());
+
+        dispatches.push(quote! {
+            if fnmock::instantiation::is_instantiation::<(#(#type_params,)*), (#(#concrete_types,)*)>() {
+                if #mock_mod_name::is_set() {
+                    let params = fnmock::instantiation::cast::<_, #params_type>(#params_to_tuple);
+                    return fnmock::instantiation::cast::<#return_type, #generic_return_type>(#mock_mod_name::call(params));
+                }
+                #mock_mod_name::check_real_call();
+            }
+        });
+
+        let mock_module = create_mock_module(
+            mock_mod_name,
+            mod_visibility.clone(),
+            params_type,
+            return_type,
+            &concrete_inputs,
+            ignore_indices,
+            None,
+            params_to_tuple.clone(),
+            elide_ref_params(&filter_params(&concrete_inputs, ignore_indices)),
+            None,
+            group.clone(),
+            type_state,
+        );
+        mock_modules.push(quote! {
+            #item_cfg
+            #mock_cfg
+            #mock_module
+        });
+    }
+
+    let mut static_generics = fn_generics.clone();
+    for type_param in static_generics.type_params_mut() {
+        type_param.bounds.push(syn::parse_quote! { 'static });
+    }
+    let where_clause = &fn_generics.where_clause;
+    let original_fn_stmts = &fn_block.stmts;
+    let original_fn = original_cfg().map(|original_cfg| quote! {
+        #fn_attrs
+        #original_cfg
+        #fn_visibility fn #fn_name #fn_generics(#fn_inputs) #fn_output #where_clause {
+            #(#original_fn_stmts)*
+        }
+    });
+
+    Ok(quote! {
+        #fn_attrs
+        #mock_cfg
+        #[allow(unused_variables)]
+        #fn_visibility fn #fn_name #static_generics(#fn_inputs) #fn_output #where_clause {
+            #(#dispatches)*
+
+            #(#original_fn_stmts)*
+        }
+
+        #original_fn
+
+        #(#mock_modules)*
+    })
+}
+
+/// Converts a concrete type to the part of the mock module name, e.g. `u32` to `u32` and `Vec<u8>` to `vec_u8`.
+pub(crate) fn instantiation_suffix(ty: &syn::Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .to_lowercase()
+        .split(|char: char| !char.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
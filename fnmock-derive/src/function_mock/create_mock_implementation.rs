@@ -1,5 +1,9 @@
-use quote::quote;
+use quote::{quote, ToTokens};
+use crate::doc_utils::is_rich_docs_enabled;
+use crate::export_utils::{definition_location, double_cfg, original_cfg};
 use crate::function_mock::proxy_docs::MockProxyDocs;
+use crate::param_utils::get_param_names;
+use crate::return_utils::{extract_result_types, extract_return_type, extract_vec_item, is_impl_future};
 
 /// Generates the original function with mock checking logic injected.
 ///
@@ -12,59 +16,189 @@ use crate::function_mock::proxy_docs::MockProxyDocs;
 /// * `fn_name` - The name of the original function
 /// * `fn_visibility` - The visibility modifier of the function (pub, pub(crate), etc.)
 /// * `fn_asyncness` - Optional async keyword if the function is async
+/// * `fn_unsafety` - Optional unsafe keyword if the function is unsafe
+/// * `fn_abi` - Optional ABI of the function, e.g. `extern "C"`
+/// * `fn_generics` - The generics of the function, including the where clause, which the mock doesn't depend on
 /// * `fn_inputs` - The function parameters
 /// * `fn_output` - The return type
 /// * `fn_block` - The original function body to execute when mock is not set
-/// * `mock_mod_name` - The name of the mock module containing the mock infrastructure
+/// * `mock_mod_path` - The path of the mock module containing the mock infrastructure, e.g. `fetch_user_mock`
 /// * `params_to_tuple` - Token stream that converts parameters into a tuple for the mock
+/// * `boxed_return_type` - The boxed trait object returned by the mock, if the function returns `impl Trait`
+/// * `fake_mod_name` - The name of the fake module, if a fake is generated as well (never with a boxed return type)
+/// * `fn_attrs` - The attributes of the original function, including its cfg attributes, which every generated function carries
 ///
 /// # Returns
 ///
 /// Generated token stream for the function with injected mock checking logic
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_mock_function(
     fn_name: syn::Ident,
     fn_visibility: syn::Visibility,
     fn_asyncness: Option<syn::token::Async>,
-    fn_inputs: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+    fn_unsafety: Option<syn::token::Unsafe>,
+    fn_abi: Option<syn::Abi>,
+    fn_generics: &syn::Generics,
+    fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
     fn_output: syn::ReturnType,
-    fn_block: Box<syn::Block>,
-    mock_mod_name: syn::Ident,
+    fn_block: &syn::Block,
+    mock_mod_path: proc_macro2::TokenStream,
     params_to_tuple: proc_macro2::TokenStream,
+    boxed_return_type: Option<syn::Type>,
+    fake_mod_name: Option<syn::Ident>,
+    fn_attrs: &proc_macro2::TokenStream,
 ) -> proc_macro2::TokenStream {
     let original_fn_stmts = &fn_block.stmts;
+    let where_clause = &fn_generics.where_clause;
+
+    // The mock returns a boxed trait object, so the original value is boxed as well in test builds.
+    // The function is generated twice, so non-test builds still return the unboxed original.
+    if let Some(boxed_return_type) = boxed_return_type {
+        let mock_cfg = double_cfg();
+        let original_args = get_param_names(fn_inputs).into_iter().map(|name| match name {
+            syn::Pat::Ident(pat_ident) => pat_ident.ident.to_token_stream(),
+            name => name.to_token_stream(),
+        });
+        // The type parameters can't always be inferred from the arguments, e.g. if they are only used in bounds
+        let type_params: Vec<_> = fn_generics.params.iter().filter_map(|param| match param {
+            syn::GenericParam::Type(type_param) => Some(type_param.ident.to_token_stream()),
+            syn::GenericParam::Const(const_param) => Some(const_param.ident.to_token_stream()),
+            syn::GenericParam::Lifetime(_) => None,
+        }).collect();
+        let original_turbofish = (!type_params.is_empty()).then(|| quote! { ::<#(#type_params),*> });
+        // A boxed future is pinned, see `box_impl_trait`
+        let box_fn = match is_impl_future(&extract_return_type(&fn_output)) {
+            true => quote! { Box::pin },
+            false => quote! { Box::new },
+        };
+        let original_call = quote! { original #original_turbofish(#(#original_args),*) };
+        // The caller of the unsafe function upholds the contract of the original body
+        let original_call = match fn_unsafety {
+            Some(_) => quote! { unsafe { #original_call } },
+            None => original_call,
+        };
+        let original_fn = original_cfg().map(|original_cfg| quote! {
+            #fn_attrs
+            #original_cfg
+            #fn_visibility #fn_unsafety #fn_abi fn #fn_name #fn_generics(#fn_inputs) #fn_output #where_clause {
+                #(#original_fn_stmts)*
+            }
+        });
+
+        return quote! {
+            #fn_attrs
+            #mock_cfg
+            #[allow(unused_variables)]
+            #fn_visibility #fn_unsafety #fn_abi fn #fn_name #fn_generics(#fn_inputs) #fn_output #where_clause {
+                if #mock_mod_path::is_set() {
+                    return #mock_mod_path::call(#params_to_tuple);
+                }
+                #mock_mod_path::check_real_call();
+
+                // A nested function keeps the declared return type, which infers the types of a returned closure
+                #fn_unsafety fn original #fn_generics(#fn_inputs) #fn_output #where_clause {
+                    #(#original_fn_stmts)*
+                }
+                #box_fn(#original_call) as #boxed_return_type
+            }
+
+            #original_fn
+        };
+    }
+    let mock_cfg = double_cfg();
+
+    let mock_call = create_mock_call(&mock_mod_path, &params_to_tuple, fn_asyncness);
+
+    // The fake is used if the mock isn't configured, or before it with `fnmock::resolution::Order::FakeFirst`
+    let fake_call = fake_mod_name.map(|fake_mod_name| {
+        let param_names = get_param_names(fn_inputs);
+        quote! {
+            #mock_cfg
+            if fnmock::resolution::uses_fake(#mock_mod_path::is_set(), #fake_mod_name::is_set()) {
+                return #fake_mod_name::get_implementation()(#(#param_names),*);
+            }
+        }
+    });
     
     quote! {
+        #fn_attrs
         #[allow(unused_variables)]
-        #fn_visibility #fn_asyncness fn #fn_name(#fn_inputs) #fn_output {
-            // Call the mock implementation if set (only in test mode)
-            #[cfg(test)]
-            if #mock_mod_name::is_set() {
-                return #mock_mod_name::call(#params_to_tuple);
+        #fn_visibility #fn_asyncness #fn_unsafety #fn_abi fn #fn_name #fn_generics(#fn_inputs) #fn_output #where_clause {
+            #fake_call
+
+            // Call the mock implementation if set (only in test mode or with the `mocks` feature)
+            #mock_cfg
+            if #mock_mod_path::is_set() {
+                return #mock_call;
             }
+            #mock_cfg
+            #mock_mod_path::check_real_call();
 
             #(#original_fn_stmts)*
         }
     }
 }
 
+/// Generates the call of a configured mock, which the intercepted function returns.
+///
+/// Async functions await a never completing future instead, if the mock is set up with `setup_pending`,
+/// are delayed, if the mock is set up with `with_latency` / `with_jitter`,
+/// and yield once before returning, if the mock is set up with `yield_before_returning`.
+///
+/// # Arguments
+///
+/// * `mock_mod_path` - The path of the mock module, e.g. `fetch_user_mock`
+/// * `params_to_tuple` - Token stream that converts parameters into a tuple for the mock
+/// * `fn_asyncness` - Optional async keyword if the function is async
+pub(crate) fn create_mock_call(
+    mock_mod_path: &proc_macro2::TokenStream,
+    params_to_tuple: &proc_macro2::TokenStream,
+    fn_asyncness: Option<syn::token::Async>,
+) -> proc_macro2::TokenStream {
+    match fn_asyncness {
+        Some(_) => quote! {
+            {
+                let result = match #mock_mod_path::try_call(#params_to_tuple) {
+                    Some(result) => result,
+                    None => std::future::pending().await,
+                };
+                if let Some(delay) = #mock_mod_path::next_delay() {
+                    fnmock::latency::sleep(delay).await;
+                }
+                if #mock_mod_path::yields_before_returning() {
+                    fnmock::cancellation::cancellation_point(#mock_mod_path::record_cancelled).await;
+                }
+                result
+            }
+        },
+        None => quote! { #mock_mod_path::call(#params_to_tuple) },
+    }
+}
+
 /// Generates a mock module containing the mock infrastructure.
 ///
 /// Creates a module with the same name as the mock function that contains:
 /// - Type aliases for parameters and return type
-/// - Thread-local storage for the FunctionMock instance
+/// - Thread-local storage for the FunctionMock instance, registered in `fnmock::registry` once configured
 /// - Proxy functions for all mock operations
 ///
 /// # Arguments
 ///
 /// * `mock_fn_name` - The name of the mock module (same as mock function name)
+/// * `mod_visibility` - The visibility of the mock module and its proxy functions
 /// * `params_type` - The type representing the function parameters (single type or tuple)
 /// * `return_type` - The return type of the function
 /// * `fn_inputs` - The original function parameters (for documentation)
 /// * `ignore_indices` - Indices of parameters to ignore (for documentation)
 /// * `params_to_tuple` - Token stream that converts parameters into a tuple
 /// * `filtered_fn_inputs` - Function parameters excluding ignored ones
+/// * `iterator_item` - The item type, if the function returns `impl Iterator`
+/// * `group` - The name of the group the mock is registered in, if any
+/// * `type_state` - Whether the assertions are only available on the `MockHandle` returned by the setups
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_mock_module(
     mock_fn_name: syn::Ident,
+    mod_visibility: proc_macro2::TokenStream,
     params_type: syn::Type,
     return_type: syn::Type,
     fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
@@ -72,68 +206,974 @@ pub(crate) fn create_mock_module(
     fn_asyncness: Option<syn::token::Async>,
     params_to_tuple: proc_macro2::TokenStream,
     filtered_fn_inputs: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+    iterator_item: Option<syn::Type>,
+    group: Option<String>,
+    type_state: bool,
 ) -> proc_macro2::TokenStream {
+    // With `type_state` only the setups can create a handle, and the assertions are private,
+    // so asserting on a mock that wasn't set up in the test doesn't compile.
+    // The private assertions allow dead code, since the handle doesn't forward to all of them.
+    let (handle_struct, handle, assert_visibility) = if type_state {
+        (
+            quote! { #mod_visibility struct MockHandle(()); },
+            quote! { MockHandle(()) },
+            quote! { #[allow(dead_code)] },
+        )
+    } else {
+        (
+            quote! { #mod_visibility struct MockHandle; },
+            quote! { MockHandle },
+            mod_visibility.clone(),
+        )
+    };
+    // The modifiers don't install an implementation, so with `type_state` they don't return a handle
+    let (modifier_output, modifier_handle) = match type_state {
+        true => (quote! {}, quote! {}),
+        false => (quote! { -> MockHandle }, handle.clone()),
+    };
+
     // Generate documentation using the proxy_docs module
-    let docs = MockProxyDocs::new(&mock_fn_name, fn_inputs, ignore_indices, &return_type, fn_asyncness);
-    let call_docs = docs.call_docs();
-    let setup_docs = docs.setup_docs();
-    let clear_docs = docs.clear_docs();
-    let is_set_docs = docs.is_set_docs();
-    let assert_times_docs = docs.assert_times_docs();
-    let assert_with_docs = docs.assert_with_docs();
+    // Without the `rich-docs` feature the docs are None and generate nothing
+    let docs = is_rich_docs_enabled().then(|| MockProxyDocs::new(&mock_fn_name, fn_inputs, ignore_indices, &return_type, fn_asyncness));
+    let call_docs = docs.as_ref().map(MockProxyDocs::call_docs);
+    let setup_docs = docs.as_ref().map(MockProxyDocs::setup_docs);
+    let setup_ref_docs = docs.as_ref().map(MockProxyDocs::setup_ref_docs);
+    let setup_returning_docs = docs.as_ref().map(MockProxyDocs::setup_returning_docs);
+    let returning_indexed_docs = docs.as_ref().map(MockProxyDocs::returning_indexed_docs);
+    let setup_panic_docs = docs.as_ref().map(MockProxyDocs::setup_panic_docs);
+    let forbid_docs = docs.as_ref().map(MockProxyDocs::forbid_docs);
+    let set_call_budget_docs = docs.as_ref().map(MockProxyDocs::set_call_budget_docs);
+    let require_docs = docs.as_ref().map(MockProxyDocs::require_docs);
+    let add_precondition_docs = docs.as_ref().map(MockProxyDocs::add_precondition_docs);
+    let add_postcondition_docs = docs.as_ref().map(MockProxyDocs::add_postcondition_docs);
+    let with_docs = docs.as_ref().map(MockProxyDocs::with_docs);
+    let clear_docs = docs.as_ref().map(MockProxyDocs::clear_docs);
+    let clear_implementation_docs = docs.as_ref().map(MockProxyDocs::clear_implementation_docs);
+    let clear_history_docs = docs.as_ref().map(MockProxyDocs::clear_history_docs);
+    let dedup_consecutive_calls_docs = docs.as_ref().map(MockProxyDocs::dedup_consecutive_calls_docs);
+    let index_calls_docs = docs.as_ref().map(MockProxyDocs::index_calls_docs);
+    let record_timestamps_docs = docs.as_ref().map(MockProxyDocs::record_timestamps_docs);
+    let call_timestamps_docs = docs.as_ref().map(MockProxyDocs::call_timestamps_docs);
+    let snapshot_docs = docs.as_ref().map(MockProxyDocs::snapshot_docs);
+    let restore_docs = docs.as_ref().map(MockProxyDocs::restore_docs);
+    let export_state_docs = docs.as_ref().map(MockProxyDocs::export_state_docs);
+    let import_state_docs = docs.as_ref().map(MockProxyDocs::import_state_docs);
+    let durations_between_calls_docs = docs.as_ref().map(MockProxyDocs::durations_between_calls_docs);
+    let is_set_docs = docs.as_ref().map(MockProxyDocs::is_set_docs);
+    let assert_times_docs = docs.as_ref().map(MockProxyDocs::assert_times_docs);
+    let assert_with_docs = docs.as_ref().map(MockProxyDocs::assert_with_docs);
+    let assert_with_tuple_docs = docs.as_ref().map(MockProxyDocs::assert_with_tuple_docs);
+    let assert_with_ref_docs = docs.as_ref().map(MockProxyDocs::assert_with_ref_docs);
+    let assert_times_msg_docs = docs.as_ref().map(MockProxyDocs::assert_times_msg_docs);
+    let assert_with_msg_docs = docs.as_ref().map(MockProxyDocs::assert_with_msg_docs);
+    let assert_calls_exact_docs = docs.as_ref().map(MockProxyDocs::assert_calls_exact_docs);
+    let assert_with_matchers_docs = docs.as_ref().map(MockProxyDocs::assert_with_matchers_docs);
+    let assert_times_in_context_docs = docs.as_ref().map(MockProxyDocs::assert_times_in_context_docs);
+    let assert_called_at_most_per_docs = docs.as_ref().map(MockProxyDocs::assert_called_at_most_per_docs);
+    let verify_no_more_interactions_docs = docs.as_ref().map(MockProxyDocs::verify_no_more_interactions_docs);
+    let was_called_docs = docs.as_ref().map(MockProxyDocs::was_called_docs);
+    let calls_docs = docs.as_ref().map(MockProxyDocs::calls_docs);
+    let handle_docs = docs.as_ref().map(MockProxyDocs::handle_docs);
+    let was_called_with_docs = docs.as_ref().map(MockProxyDocs::was_called_with_docs);
+    let format_calls_docs = docs.as_ref().map(MockProxyDocs::format_calls_docs);
+    let print_calls_docs = docs.as_ref().map(MockProxyDocs::print_calls_docs);
+    let configure_docs = docs.as_ref().map(MockProxyDocs::configure_docs);
+    let config_docs = docs.as_ref().map(MockProxyDocs::config_docs);
+    let verify_docs = docs.as_ref().map(MockProxyDocs::verify_docs);
+    let checkpoint_docs = docs.as_ref().map(MockProxyDocs::checkpoint_docs);
+
+    // Column headers and accessors of the recorded parameters for the call table
+    let recorded_param_names: Vec<String> = get_param_names(fn_inputs)
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !ignore_indices.contains(idx))
+        .map(|(_, name)| match name {
+            syn::Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
+            name => quote!(#name).to_string(),
+        })
+        .collect();
+    let recorded_param_accessors: Vec<proc_macro2::TokenStream> = match recorded_param_names.len() {
+        1 => vec![quote! { call }],
+        len => (0..len).map(|idx| {
+            let idx = syn::Index::from(idx);
+            quote! { &call.#idx }
+        }).collect(),
+    };
+    let definition = definition_location(&mock_fn_name);
+
+    // Suggested by the panic of a call to the unconfigured mock
+    let setup_example = format!(
+        "{}::setup(|{}| -> {} {{ todo!() }});",
+        mock_fn_name,
+        match recorded_param_names.len() {
+            0 => "()".to_string(),
+            1 => recorded_param_names[0].clone(),
+            _ => format!("({})", recorded_param_names.join(", ")),
+        },
+        display_type(&return_type),
+    );
+    // Without recorded parameters the call itself is unused
+    let call_pattern = if recorded_param_names.is_empty() { quote! { _ } } else { quote! { call } };
+
+    // Only generate the Result shorthands if the function returns a Result<T, E>
+    let (result_setups, result_config) = match extract_result_types(&return_type) {
+        Some((ok_type, err_type)) => {
+            let setup_ok_docs = docs.as_ref().map(|docs| docs.setup_ok_docs(&ok_type));
+            let setup_err_docs = docs.as_ref().map(|docs| docs.setup_err_docs(&err_type));
+
+            let result_setups = quote! {
+                #setup_ok_docs
+                #[track_caller]
+                #mod_visibility fn setup_ok(value: #ok_type) -> MockHandle
+                where
+                    for<'a> #return_type: Clone,
+                {
+                    setup_returning(Ok(value))
+                }
+
+                #setup_err_docs
+                #[track_caller]
+                #mod_visibility fn setup_err(error: #err_type) -> MockHandle
+                where
+                    for<'a> #return_type: Clone,
+                {
+                    setup_returning(Err(error))
+                }
+            };
+
+            let result_config = quote! {
+                /// Returns `Ok(value)` on every call, see `setup_ok()`.
+                #[track_caller]
+                #mod_visibility fn ok(&mut self, value: #ok_type) -> &mut Self
+                where
+                    for<'a> #return_type: Clone,
+                {
+                    setup_ok(value);
+                    self
+                }
+
+                /// Returns `Err(error)` on every call, see `setup_err()`.
+                #[track_caller]
+                #mod_visibility fn err(&mut self, error: #err_type) -> &mut Self
+                where
+                    for<'a> #return_type: Clone,
+                {
+                    setup_err(error);
+                    self
+                }
+            };
+
+            (result_setups, result_config)
+        }
+        None => (quote! {}, quote! {}),
+    };
+
+    // Only generate the canned iterator setup if the function returns `impl Iterator<Item = T>`
+    let iterator_setups = iterator_item.map(|item_type| {
+        let setup_iter_docs = docs.as_ref().map(MockProxyDocs::setup_iter_docs);
+
+        quote! {
+            #setup_iter_docs
+            #[track_caller]
+            #mod_visibility fn setup_iter(items: impl IntoIterator<Item = #item_type>) -> MockHandle
+            where
+                for<'a> #item_type: Clone,
+            {
+                register();
+                record_setup();
+                let items: Vec<#item_type> = items.into_iter().collect();
+                MOCK.with(|mock| {
+                    // Every call iterates over a new clone of the items
+                    mock.borrow_mut().setup_producing(Box::new(items), |items| {
+                        let items: &Vec<#item_type> = items.downcast_ref().unwrap();
+                        Box::new(items.clone().into_iter())
+                    })
+                });
+                #handle
+            }
+        }
+    });
+
+    // Only generate the batch helpers if the function takes a single `Vec<T>`,
+    // and the per-item setup only if it also returns a `Vec<R>`
+    let batch_item = extract_vec_item(&params_type);
+    let batch_setups = batch_item.clone().zip(extract_vec_item(&return_type)).map(|(item_type, result_type)| {
+        let setup_batch_docs = docs.as_ref().map(MockProxyDocs::setup_batch_docs);
+
+        quote! {
+            #setup_batch_docs
+            #[track_caller]
+            #mod_visibility fn setup_batch(f: fn(#item_type) -> #result_type) -> MockHandle {
+                register();
+                record_setup();
+                MOCK.with(|mock| {
+                    // The per-item function is stored in the implementation, so snapshots and other threads get it as well
+                    mock.borrow_mut().setup_parameterized(std::sync::Arc::new(f), |f, items| {
+                        let f: &fn(#item_type) -> #result_type = f.downcast_ref().unwrap();
+                        items.into_iter().map(f).collect()
+                    })
+                });
+                #handle
+            }
+        }
+    });
+    let batch_assertions = batch_item.map(|item_type| {
+        let assert_item_passed_docs = docs.as_ref().map(MockProxyDocs::assert_item_passed_docs);
+
+        quote! {
+            impl MockHandle {
+                /// Asserts that the item was passed in any call, see `assert_item_passed()`.
+                #[track_caller]
+                #mod_visibility fn assert_item_passed(&self, item: #item_type)
+                where
+                    for<'a> #item_type: PartialEq + std::fmt::Debug,
+                {
+                    assert_item_passed(item)
+                }
+            }
+
+            #assert_item_passed_docs
+            #[track_caller]
+            #assert_visibility fn assert_item_passed(item: #item_type)
+            where
+                for<'a> #item_type: PartialEq + std::fmt::Debug,
+            {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_item_passed(&item)) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+        }
+    });
+
+    // Grouped mocks are registered with their group, so `fnmock::group(..)` can verify them
+    let group = match group {
+        Some(group) => quote! { Some(#group) },
+        None => quote! { None },
+    };
+
+    // Only async functions can be pending or cancelled
+    let async_setups = fn_asyncness.map(|_| {
+        let setup_pending_docs = docs.as_ref().map(MockProxyDocs::setup_pending_docs);
+        let yield_before_returning_docs = docs.as_ref().map(MockProxyDocs::yield_before_returning_docs);
+        let was_cancelled_docs = docs.as_ref().map(MockProxyDocs::was_cancelled_docs);
+        let with_latency_docs = docs.as_ref().map(MockProxyDocs::with_latency_docs);
+        let with_jitter_docs = docs.as_ref().map(MockProxyDocs::with_jitter_docs);
+
+        quote! {
+            #setup_pending_docs
+            #[track_caller]
+            #mod_visibility fn setup_pending() -> MockHandle {
+                register();
+                record_setup();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().setup_pending()
+                });
+                #handle
+            }
+
+            #yield_before_returning_docs
+            #mod_visibility fn yield_before_returning() #modifier_output {
+                register();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().yield_before_returning()
+                });
+                #modifier_handle
+            }
+
+            #with_latency_docs
+            #mod_visibility fn with_latency(latency: std::time::Duration) #modifier_output {
+                register();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().with_latency(latency)
+                });
+                #modifier_handle
+            }
+
+            #with_jitter_docs
+            #mod_visibility fn with_jitter(jitter: std::ops::Range<std::time::Duration>) #modifier_output {
+                register();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().with_jitter(jitter)
+                });
+                #modifier_handle
+            }
+
+            #was_cancelled_docs
+            #mod_visibility fn was_cancelled() -> bool {
+                cancelled_calls() > 0
+            }
+
+            /// Returns the number of calls dropped before they returned, see `yield_before_returning()`.
+            #mod_visibility fn cancelled_calls() -> usize {
+                MOCK.with(|mock| {
+                    mock.borrow().cancelled_calls()
+                })
+            }
+
+            /// Calls the mock implementation like `call()`, but returns None if the call is pending.
+            #[doc(hidden)]
+            #[inline]
+            #mod_visibility fn try_call(params: #params_type) -> Option<#return_type> {
+                MOCK.with(|mock| {
+                    mock.borrow_mut().try_call(params)
+                })
+            }
+
+            #[doc(hidden)]
+            #[inline]
+            #mod_visibility fn next_delay() -> Option<std::time::Duration> {
+                MOCK.with(|mock| {
+                    mock.borrow_mut().next_delay()
+                })
+            }
+
+            #[doc(hidden)]
+            #[inline]
+            #mod_visibility fn yields_before_returning() -> bool {
+                MOCK.with(|mock| {
+                    mock.borrow().yields_before_returning()
+                })
+            }
+
+            // Called when a future is dropped at its cancellation point,
+            // which may happen while the thread-local is destroyed at the end of the thread
+            #[doc(hidden)]
+            #mod_visibility fn record_cancelled() {
+                let _ = MOCK.try_with(|mock| {
+                    mock.borrow_mut().record_cancelled()
+                });
+            }
+        }
+    });
 
     quote! {
-        pub(crate) mod #mock_fn_name {
+        #mod_visibility mod #mock_fn_name {
             use super::*;
 
+            #handle_docs
+            #[derive(Clone, Copy, Debug)]
+            #handle_struct
+
+            impl MockHandle {
+                /// Returns the recorded calls, see `calls()`.
+                #mod_visibility fn calls(&self) -> Vec<#params_type> {
+                    calls()
+                }
+
+                /// Asserts the number of calls, see `assert_times()`.
+                #[track_caller]
+                #mod_visibility fn assert_times(&self, expected_num_of_calls: u32) {
+                    assert_times(expected_num_of_calls)
+                }
+
+                /// Asserts the number of calls, labeling a failure with `message`, see `assert_times_msg()`.
+                #[track_caller]
+                #mod_visibility fn assert_times_msg(&self, expected_num_of_calls: u32, message: &str) {
+                    assert_times_msg(expected_num_of_calls, message)
+                }
+
+                /// Asserts the number of calls in a `fnmock::with_context` context, see `assert_times_in_context()`.
+                #[track_caller]
+                #mod_visibility fn assert_times_in_context(&self, context: &str, expected_num_of_calls: u32) {
+                    assert_times_in_context(context, expected_num_of_calls)
+                }
+
+                /// Asserts that no time window contains more than `max_calls` calls, see `assert_called_at_most_per()`.
+                #[track_caller]
+                #mod_visibility fn assert_called_at_most_per(&self, max_calls: u32, window: std::time::Duration) {
+                    assert_called_at_most_per(max_calls, window)
+                }
+
+                /// Asserts the expectations set with `configure()`, see `verify()`.
+                #[track_caller]
+                #mod_visibility fn verify(&self) {
+                    verify()
+                }
+
+                /// Asserts that the mock was called with the parameters, see `assert_with()`.
+                #[track_caller]
+                #mod_visibility fn assert_with(&self, #filtered_fn_inputs) {
+                    assert_with_ref(&#params_to_tuple)
+                }
+
+                /// Asserts that the mock was called with the recorded parameters, see `assert_with_tuple()`.
+                #[track_caller]
+                #mod_visibility fn assert_with_tuple(&self, params: #params_type) {
+                    assert_with_tuple(params)
+                }
+
+                /// Asserts that the mock was called with the parameters, see `assert_with_ref()`.
+                #[track_caller]
+                #mod_visibility fn assert_with_ref(&self, params: &#params_type) {
+                    assert_with_ref(params)
+                }
+
+                /// Asserts that the mock was called with the parameters, labeling a failure with `message`, see `assert_with_msg()`.
+                #[track_caller]
+                #mod_visibility fn assert_with_msg(&self, params: #params_type, message: &str) {
+                    assert_with_msg(params, message)
+                }
+
+                /// Asserts that a call matches the matchers, see `assert_with_matchers()`.
+                #[track_caller]
+                #mod_visibility fn assert_with_matchers(&self, matcher: impl fnmock::matchers::Matcher<#params_type>) {
+                    assert_with_matchers(matcher)
+                }
+
+                /// Asserts the complete, ordered call history, see `assert_calls_exact()`.
+                #[track_caller]
+                #mod_visibility fn assert_calls_exact(&self, expected: &[#params_type]) {
+                    assert_calls_exact(expected)
+                }
+
+                /// Asserts that every recorded call was covered by an assertion, see `verify_no_more_interactions()`.
+                #[track_caller]
+                #mod_visibility fn verify_no_more_interactions(&self) {
+                    verify_no_more_interactions()
+                }
+            }
+
+            impl fnmock::MockControl for MockHandle {
+                fn name(&self) -> &'static str {
+                    stringify!(#mock_fn_name)
+                }
+
+                fn clear(&self) {
+                    clear()
+                }
+
+                #[track_caller]
+                fn assert_times(&self, expected_num_of_calls: u32) {
+                    assert_times(expected_num_of_calls)
+                }
+
+                fn check_no_more_interactions(&self) -> Result<(), String> {
+                    MOCK.with(|mock| mock.borrow().check_no_more_interactions())
+                }
+            }
+
+            // Location of the mocked function, which is appended to the messages of the mock
+            const DEFINITION: &str = #definition;
+
+            // The const initializer doesn't allocate or register anything,
+            // so tests that never configure the mock don't pay for it.
             thread_local! {
                 static MOCK: std::cell::RefCell<fnmock::function_mock::FunctionMock<
                     #params_type,
                     #return_type,
-                >> = std::cell::RefCell::new(fnmock::function_mock::FunctionMock::new(stringify!(#mock_fn_name)));
+                >> = const {
+                    std::cell::RefCell::new(
+                        fnmock::function_mock::FunctionMock::new(stringify!(#mock_fn_name))
+                            .with_module_path(module_path!())
+                            .with_setup_example(#setup_example)
+                            .with_definition(DEFINITION)
+                    )
+                };
+            }
+
+            // Registers the mock for `fnmock::clear_all_mocks!()` once it is configured
+            fn register() {
+                fnmock::registry::register_mock(module_path!(), clear, describe, check_expectations, status, export, #group);
+            }
+
+            fn describe() -> String {
+                MOCK.with(|mock| mock.borrow().to_string())
+            }
+
+            fn check_expectations() -> Result<(), String> {
+                MOCK.with(|mock| mock.borrow().check_expectations())
+            }
+
+            fn status() -> fnmock::verify::MockStatus {
+                MOCK.with(|mock| mock.borrow().status())
+            }
+
+            // Exports the configuration for `fnmock::spawn`, unless it can't be sent to another thread or holds canned items
+            fn export() -> Option<fnmock::propagation::ExportedMock> {
+                use fnmock::propagation::{ExportLocal, ExportProbe, ExportSend};
+                let export_state = || MOCK.with(|mock| mock.borrow().try_export());
+                (&ExportProbe::<#params_type, #return_type>::new()).export_mock(export_state, |state| {
+                    import_state(state);
+                })
+            }
+
+            // Remembers where the mock is set up for the repeated setup check of `fnmock::strict`
+            #[track_caller]
+            fn record_setup() {
+                let location = std::panic::Location::caller();
+                MOCK.with(|mock| mock.borrow_mut().record_setup(location))
             }
 
             #call_docs
-            pub(crate) fn call(params: #params_type) -> #return_type {
+
+            #[inline]
+            #mod_visibility fn call(params: #params_type) -> #return_type {
                 MOCK.with(|mock| {
                     mock.borrow_mut().call(params)
                 })
             }
 
             #setup_docs
-            pub(crate) fn setup(new_f: fn(#params_type) -> #return_type) {
+            #[track_caller]
+            #mod_visibility fn setup(new_f: fn(#params_type) -> #return_type) -> MockHandle {
+                register();
+                record_setup();
                 MOCK.with(|mock| {
                     mock.borrow_mut().setup(new_f)
+                });
+                #handle
+            }
+
+            #setup_ref_docs
+            #[track_caller]
+            #mod_visibility fn setup_ref(new_f: fn(&#params_type) -> #return_type) -> MockHandle {
+                register();
+                record_setup();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().setup_ref(new_f)
+                });
+                #handle
+            }
+
+            #setup_returning_docs
+            // The higher-ranked bound defers the `Clone` check to the call site,
+            // so functions with a non-`Clone` return type can still be mocked.
+            #[track_caller]
+            #mod_visibility fn setup_returning(return_value: #return_type) -> MockHandle
+            where
+                for<'a> #return_type: Clone,
+            {
+                register();
+                record_setup();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().setup_returning(return_value)
+                });
+                #handle
+            }
+
+            #returning_indexed_docs
+            #[track_caller]
+            #mod_visibility fn returning_indexed(new_f: fn(usize, #params_type) -> #return_type) -> MockHandle {
+                register();
+                record_setup();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().returning_indexed(new_f)
+                });
+                #handle
+            }
+
+            #result_setups
+
+            #iterator_setups
+
+            #batch_setups
+
+            #async_setups
+
+            #setup_panic_docs
+            #[track_caller]
+            #mod_visibility fn setup_panic(message: &'static str) -> MockHandle {
+                register();
+                record_setup();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().setup_panic(message)
+                });
+                #handle
+            }
+
+            #forbid_docs
+            #[track_caller]
+            #mod_visibility fn forbid() -> MockHandle {
+                register();
+                record_setup();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().forbid()
+                });
+                #handle
+            }
+
+            #set_call_budget_docs
+            #mod_visibility fn set_call_budget(max_calls: u32) {
+                register();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().set_call_budget(max_calls)
+                })
+            }
+
+            #require_docs
+            #mod_visibility fn require() #modifier_output {
+                register();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().require()
+                });
+                #modifier_handle
+            }
+
+            // Called by the mocked function before it runs the real implementation
+            #[doc(hidden)]
+            #[inline]
+            #mod_visibility fn check_real_call() {
+                MOCK.with(|mock| {
+                    mock.borrow().check_real_call()
+                })
+            }
+
+            #add_precondition_docs
+            #[track_caller]
+            #mod_visibility fn add_precondition(check: fn(&#params_type) -> bool) {
+                register();
+                let location = std::panic::Location::caller();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().add_precondition(check, location)
                 })
             }
 
+            #add_postcondition_docs
+            #[track_caller]
+            #mod_visibility fn add_postcondition(check: fn(&#params_type, &#return_type) -> bool)
+            where
+                for<'a> #return_type: std::fmt::Debug,
+            {
+                register();
+                let location = std::panic::Location::caller();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().add_postcondition(check, location)
+                })
+            }
+
+            #config_docs
+            #[derive(Debug)]
+            #mod_visibility struct MockConfig;
+
+            impl MockConfig {
+                /// Sets the mock behavior, see `setup()`.
+                #[track_caller]
+                #mod_visibility fn implementation(&mut self, new_f: fn(#params_type) -> #return_type) -> &mut Self {
+                    setup(new_f);
+                    self
+                }
+
+                /// Returns a clone of the value on every call, see `setup_returning()`.
+                #[track_caller]
+                #mod_visibility fn returning(&mut self, return_value: #return_type) -> &mut Self
+                where
+                    for<'a> #return_type: Clone,
+                {
+                    setup_returning(return_value);
+                    self
+                }
+
+                #result_config
+
+                /// Makes every call panic immediately, see `forbid()`.
+                #[track_caller]
+                #mod_visibility fn forbid(&mut self) -> &mut Self {
+                    forbid();
+                    self
+                }
+
+                /// Makes every call after the first `max_calls` calls panic, see `set_call_budget()`.
+                #mod_visibility fn call_budget(&mut self, max_calls: u32) -> &mut Self {
+                    set_call_budget(max_calls);
+                    self
+                }
+
+                /// Records identical consecutive calls once with a count, see `dedup_consecutive_calls()`.
+                #mod_visibility fn dedup_consecutive_calls(&mut self) -> &mut Self {
+                    dedup_consecutive_calls();
+                    self
+                }
+
+                /// Maintains a hash index over the recorded calls, see `index_calls()`.
+                #mod_visibility fn index_calls(&mut self) -> &mut Self
+                where
+                    for<'a> #params_type: std::hash::Hash,
+                {
+                    index_calls();
+                    self
+                }
+
+                /// Records the time of every call, see `record_timestamps()`.
+                #mod_visibility fn record_timestamps(&mut self) -> &mut Self {
+                    record_timestamps();
+                    self
+                }
+
+                /// Expects the mock to be called exactly `expected_num_of_calls` times, checked by `verify()`.
+                #mod_visibility fn expect_times(&mut self, expected_num_of_calls: u32) -> &mut Self {
+                    register();
+                    MOCK.with(|mock| {
+                        mock.borrow_mut().expect_times(expected_num_of_calls)
+                    });
+                    self
+                }
+            }
+
+            #configure_docs
+            #mod_visibility fn configure(f: impl FnOnce(&mut MockConfig)) #modifier_output {
+                f(&mut MockConfig);
+                #modifier_handle
+            }
+
+            #verify_docs
+            #[track_caller]
+            #assert_visibility fn verify() {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_expectations()) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #checkpoint_docs
+            #[track_caller]
+            #mod_visibility fn checkpoint() {
+                if let Err(message) = MOCK.with(|mock| mock.borrow_mut().checkpoint()) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #with_docs
+            #[track_caller]
+            #mod_visibility fn with<T>(new_f: fn(#params_type) -> #return_type, body: impl FnOnce() -> T) -> T {
+                setup(new_f);
+                fnmock::scoped::run_scoped(clear, body)
+            }
+
             #clear_docs
-            pub(crate) fn clear() {
+            #mod_visibility fn clear() {
                 MOCK.with(|mock|{
                     mock.borrow_mut().clear()
                 })
             }
 
+            #clear_implementation_docs
+            #mod_visibility fn clear_implementation() {
+                MOCK.with(|mock|{
+                    mock.borrow_mut().clear_implementation()
+                })
+            }
+
+            #clear_history_docs
+            #mod_visibility fn clear_history() {
+                MOCK.with(|mock|{
+                    mock.borrow_mut().clear_history()
+                })
+            }
+
+            #dedup_consecutive_calls_docs
+            #mod_visibility fn dedup_consecutive_calls() {
+                MOCK.with(|mock|{
+                    mock.borrow_mut().dedup_consecutive_calls()
+                })
+            }
+
+            #index_calls_docs
+            // The higher-ranked bound defers the `Hash` check to the call site like in `setup_returning`
+            #mod_visibility fn index_calls()
+            where
+                for<'a> #params_type: std::hash::Hash,
+            {
+                MOCK.with(|mock|{
+                    mock.borrow_mut().index_calls()
+                })
+            }
+
+            #record_timestamps_docs
+            #mod_visibility fn record_timestamps() {
+                MOCK.with(|mock|{
+                    mock.borrow_mut().record_timestamps()
+                })
+            }
+
+            #snapshot_docs
+            #mod_visibility fn snapshot() -> fnmock::function_mock::MockSnapshot<#params_type, #return_type> {
+                MOCK.with(|mock| {
+                    mock.borrow().snapshot()
+                })
+            }
+
+            #restore_docs
+            #mod_visibility fn restore(snapshot: &fnmock::function_mock::MockSnapshot<#params_type, #return_type>) #modifier_output {
+                register();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().restore(snapshot)
+                });
+                #modifier_handle
+            }
+
+            #export_state_docs
+            #mod_visibility fn export_state() -> fnmock::function_mock::MockExport<#params_type, #return_type> {
+                MOCK.with(|mock| {
+                    mock.borrow().export()
+                })
+            }
+
+            #import_state_docs
+            #mod_visibility fn import_state(state: fnmock::function_mock::MockExport<#params_type, #return_type>) #modifier_output {
+                register();
+                MOCK.with(|mock| {
+                    mock.borrow_mut().import(state)
+                });
+                #modifier_handle
+            }
+
             #is_set_docs
-            pub(crate) fn is_set() -> bool {
+
+            #[inline]
+            #mod_visibility fn is_set() -> bool {
                 MOCK.with(|mock| {
                     mock.borrow().is_set()
                 })
             }
 
-            #assert_times_docs
-            pub(crate) fn assert_times(expected_num_of_calls: u32) {
+            #calls_docs
+            #mod_visibility fn calls() -> Vec<#params_type> {
                 MOCK.with(|mock| {
-                    mock.borrow().assert_times(expected_num_of_calls)
+                    mock.borrow().expanded_calls().cloned().collect()
                 })
             }
 
-            #assert_with_docs
-            pub(crate) fn assert_with(#filtered_fn_inputs) {
+            #format_calls_docs
+            #mod_visibility fn format_calls() -> String {
+                let rows: Vec<Vec<String>> = MOCK.with(|mock| {
+                    mock.borrow().expanded_calls().map(|#call_pattern| {
+                        vec![#(format!("{:?}", #recorded_param_accessors)),*]
+                    }).collect()
+                });
+                fnmock::call_table::format_call_table(stringify!(#mock_fn_name), &[#(#recorded_param_names),*], &rows)
+            }
+
+            #print_calls_docs
+            #mod_visibility fn print_calls() {
+                println!("{}", format_calls())
+            }
+
+            #call_timestamps_docs
+            #mod_visibility fn call_timestamps() -> Vec<std::time::Instant> {
+                MOCK.with(|mock| {
+                    mock.borrow().call_timestamps().to_vec()
+                })
+            }
+
+            #durations_between_calls_docs
+            #mod_visibility fn durations_between_calls() -> Vec<std::time::Duration> {
+                MOCK.with(|mock| {
+                    mock.borrow().durations_between_calls()
+                })
+            }
+
+            #was_called_docs
+            #mod_visibility fn was_called() -> bool {
                 MOCK.with(|mock| {
-                    mock.borrow().assert_with(#params_to_tuple)
+                    mock.borrow().was_called()
                 })
             }
+
+            #was_called_with_docs
+            #mod_visibility fn was_called_with(#filtered_fn_inputs) -> bool {
+                MOCK.with(|mock| {
+                    mock.borrow().was_called_with(&#params_to_tuple)
+                })
+            }
+
+            // The assertions panic outside of `MOCK.with`, because closures can't be
+            // `#[track_caller]` and the panic would point into this module otherwise.
+            #assert_times_docs
+            #[track_caller]
+            #assert_visibility fn assert_times(expected_num_of_calls: u32) {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_times(expected_num_of_calls)) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #assert_times_msg_docs
+            #[track_caller]
+            #assert_visibility fn assert_times_msg(expected_num_of_calls: u32, message: &str) {
+                if let Err(failure) = MOCK.with(|mock| mock.borrow().check_times(expected_num_of_calls)) {
+                    fnmock::verify::report_failure(fnmock::verify::with_context_message(message, failure))
+                }
+            }
+
+            #assert_with_matchers_docs
+            #[track_caller]
+            #assert_visibility fn assert_with_matchers(matcher: impl fnmock::matchers::Matcher<#params_type>) {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_with_matcher(&matcher)) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #assert_times_in_context_docs
+            #[track_caller]
+            #assert_visibility fn assert_times_in_context(context: &str, expected_num_of_calls: u32) {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_times_in_context(context, expected_num_of_calls)) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #assert_called_at_most_per_docs
+            #[track_caller]
+            #assert_visibility fn assert_called_at_most_per(max_calls: u32, window: std::time::Duration) {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_called_at_most_per(max_calls, window)) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #assert_with_docs
+            #[track_caller]
+            #assert_visibility fn assert_with(#filtered_fn_inputs) {
+                assert_with_ref(&#params_to_tuple)
+            }
+
+            #assert_with_tuple_docs
+            #[track_caller]
+            #assert_visibility fn assert_with_tuple(params: #params_type) {
+                assert_with_ref(&params)
+            }
+
+            #assert_with_ref_docs
+            #[track_caller]
+            #assert_visibility fn assert_with_ref(params: &#params_type) {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_with_ref(params)) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #assert_with_msg_docs
+            #[track_caller]
+            #assert_visibility fn assert_with_msg(params: #params_type, message: &str) {
+                if let Err(failure) = MOCK.with(|mock| mock.borrow().check_with_ref(&params)) {
+                    fnmock::verify::report_failure(fnmock::verify::with_context_message(message, failure))
+                }
+            }
+
+            #assert_calls_exact_docs
+            #[track_caller]
+            #assert_visibility fn assert_calls_exact(expected: &[#params_type]) {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_calls_exact(expected)) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #verify_no_more_interactions_docs
+            #[track_caller]
+            #assert_visibility fn verify_no_more_interactions() {
+                if let Err(message) = MOCK.with(|mock| mock.borrow().check_no_more_interactions()) {
+                    fnmock::verify::report_failure(message)
+                }
+            }
+
+            #batch_assertions
         }
     }
-}
\ No newline at end of file
+}
+
+/// Renders a type like it is written in code, without the spaces of the token stream.
+fn display_type(ty: &syn::Type) -> String {
+    quote!(#ty)
+        .to_string()
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace(" :: ", "::")
+        .replace("& ", "&")
+}
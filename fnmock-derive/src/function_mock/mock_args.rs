@@ -3,19 +3,35 @@ use syn::punctuated::Punctuated;
 use syn::Token;
 
 /// Structure to parse the mock_function attribute arguments
+#[derive(Default)]
 pub(crate) struct MockFunctionArgs {
     pub(crate) ignore: Vec<String>,
+    pub(crate) group: Option<String>,
+    pub(crate) fake: bool,
+    pub(crate) type_state: bool,
+    pub(crate) di: bool,
+    pub(crate) own_refs: bool,
+    pub(crate) instantiations: Vec<Instantiation>,
 }
 
+/// Concrete types of the type parameters of a generic function, e.g. `T = u32` or `(K = u32, V = String)`
+pub(crate) type Instantiation = Vec<(syn::Ident, syn::Type)>;
+
 impl Parse for MockFunctionArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut ignore = Vec::new();
+        let mut group = None;
+        let mut fake = false;
+        let mut type_state = false;
+        let mut di = false;
+        let mut own_refs = false;
+        let mut instantiations = Vec::new();
 
         if input.is_empty() {
-            return Ok(MockFunctionArgs { ignore });
+            return Ok(MockFunctionArgs { ignore, group, fake, type_state, di, own_refs, instantiations });
         }
 
-        // Parse "ignore = [...]" syntax
+        // Parse "ignore = [...]", "group = \"...\"", "in_place", "fake", "type_state", "di", "own_refs" and "instantiate(...)" syntax
         while !input.is_empty() {
             let key: syn::Ident = input.parse()?;
             if key == "ignore" {
@@ -24,6 +40,33 @@ impl Parse for MockFunctionArgs {
                 syn::bracketed!(content in input);
                 let names: Punctuated<syn::Ident, Token![,]> = content.parse_terminated(syn::Ident::parse, Token![,])?;
                 ignore = names.into_iter().map(|id| id.to_string()).collect();
+            } else if key == "group" {
+                input.parse::<Token![=]>()?;
+                let name: syn::LitStr = input.parse()?;
+                group = Some(name.value());
+            } else if key == "fake" {
+                fake = true;
+            } else if key == "type_state" {
+                type_state = true;
+            } else if key == "di" {
+                di = true;
+            } else if key == "own_refs" {
+                own_refs = true;
+            } else if key == "instantiate" {
+                let content;
+                syn::parenthesized!(content in input);
+                while !content.is_empty() {
+                    instantiations.push(parse_instantiation(&content)?);
+                    if content.peek(Token![,]) {
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+            } else if key == "in_place" {
+                // The interception is always injected into the original function,
+                // the flag only makes that explicit at the definition
+            } else {
+                // A typo like `type_sate` would otherwise silently change the generated mock
+                return Err(syn::Error::new_spanned(key, "unknown mock_function argument"));
             }
 
             // Allow trailing comma or end of input
@@ -32,6 +75,42 @@ impl Parse for MockFunctionArgs {
             }
         }
 
-        Ok(MockFunctionArgs { ignore })
+        Ok(MockFunctionArgs { ignore, group, fake, type_state, di, own_refs, instantiations })
     }
-}
\ No newline at end of file
+}
+
+/// Checks whether the attribute is a `#[mock_function]` attribute, e.g. `#[fnmock::derive::mock_function]`.
+pub(crate) fn is_mock_function_attr(attr: &syn::Attribute) -> bool {
+    attr.path().segments.last().is_some_and(|segment| segment.ident == "mock_function")
+}
+
+/// Finds the `#[mock_function]` attribute of an item, e.g. `#[mock_function(ignore = [timestamp])]` on a method.
+pub(crate) fn find_mock_function_attr(attrs: &[syn::Attribute]) -> Option<&syn::Attribute> {
+    attrs.iter().find(|attr| is_mock_function_attr(attr))
+}
+
+/// Parses the arguments of a `#[mock_function]` attribute, which has none without parentheses.
+pub(crate) fn parse_mock_function_args(attr: &syn::Attribute) -> syn::Result<MockFunctionArgs> {
+    match &attr.meta {
+        syn::Meta::List(_) => attr.parse_args(),
+        _ => Ok(MockFunctionArgs::default()),
+    }
+}
+
+/// Parses an instantiation, either `T = u32` for a single type parameter or `(K = u32, V = String)` for multiple.
+fn parse_instantiation(input: ParseStream) -> syn::Result<Instantiation> {
+    let parse_substitution = |input: ParseStream| -> syn::Result<(syn::Ident, syn::Type)> {
+        let name: syn::Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok((name, input.parse()?))
+    };
+
+    if input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in input);
+        let substitutions = content.parse_terminated(parse_substitution, Token![,])?;
+        Ok(substitutions.into_iter().collect())
+    } else {
+        Ok(vec![parse_substitution(input)?])
+    }
+}
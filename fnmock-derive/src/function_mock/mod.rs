@@ -1,11 +1,21 @@
-use quote::quote;
+use quote::{quote, ToTokens};
 use syn::__private::TokenStream2;
 use crate::function_mock::create_mock_implementation::{create_mock_function, create_mock_module};
+use crate::function_mock::create_di_implementation::create_di_seam;
+use crate::function_mock::create_foreign_implementation::create_foreign_mocks;
+use crate::function_mock::create_generic_implementation::create_instantiated_mocks;
+use crate::function_mock::mock_args::{Instantiation, MockFunctionArgs};
 use crate::function_mock::validate_function::validate_function_mockable;
-use crate::param_utils::{create_param_type, create_tuple_from_param_names, get_param_names};
-use crate::return_utils::extract_return_type;
+use crate::function_fake::create_fake_implementation::create_fake_module;
+use crate::export_utils::{double_cfg, double_visibility, item_cfg};
+use crate::param_utils::{create_param_type, create_tuple_from_param_names, elide_ref_params, get_param_names, owned_ref_indices, record_fn_pointers, record_owned_refs, validate_generic_params};
+use crate::return_utils::{box_impl_trait, extract_iterator_item, extract_return_type};
+use crate::type_utils::{mentions_names, static_lifetimes};
 
-mod create_mock_implementation;
+pub(crate) mod create_mock_implementation;
+mod create_di_implementation;
+mod create_foreign_implementation;
+pub(crate) mod create_generic_implementation;
 mod validate_function;
 mod proxy_docs;
 pub(crate) mod mock_args;
@@ -22,6 +32,13 @@ pub(crate) mod mock_args;
 /// # Arguments
 ///
 /// * `mock_function` - The function item to create mocks for
+/// * `ignore_params` - The names of the parameters that aren't recorded
+/// * `group` - The name of the group the mock belongs to, see `fnmock::group`
+/// * `fake` - Whether a fake is generated as well, which is used if the mock isn't configured
+/// * `type_state` - Whether the assertions are only available on the `MockHandle` returned by the setups
+/// * `di` - Whether a dependency injection seam is generated instead of intercepting the original function
+/// * `own_refs` - Whether reference parameters are recorded as owned values instead of being rejected
+/// * `instantiations` - The concrete types of the type parameters, a mock module is generated per instantiation of a generic function
 ///
 /// # Returns
 ///
@@ -31,69 +48,268 @@ pub(crate) mod mock_args;
 /// # Validation
 ///
 /// The function validates that:
-/// - All parameters are 'static (no references)
+/// - All parameters are 'static (no references, unless `own_refs` is set)
 /// - Parameters can be cloned, compared, and debugged
-pub(crate) fn process_mock_function(mock_function: syn::ItemFn, ignore_params: Vec<String>) -> syn::Result<TokenStream2> {
-    // Extract function details
-    let fn_visibility = mock_function.vis.clone();
-    let fn_asyncness = mock_function.sig.asyncness;
-    let fn_name = mock_function.sig.ident.clone();
-    let fn_inputs = mock_function.sig.inputs.clone();
-    let fn_output = mock_function.sig.output.clone();
-    let fn_block = mock_function.block.clone();
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_mock_function(
+    mock_function: syn::ItemFn,
+    ignore_params: Vec<String>,
+    group: Option<String>,
+    fake: bool,
+    type_state: bool,
+    di: bool,
+    own_refs: bool,
+    instantiations: Vec<Instantiation>,
+) -> syn::Result<TokenStream2> {
+    // Methods need the type to store the mocks in, which `#[mock_struct]` on the impl block generates
+    if let Some(receiver) = mock_function.sig.receiver() {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            "methods can't be mocked with a module next to them, put #[mock_struct] on the impl block",
+        ));
+    }
+
+    // Convert ignore param names to indices
+    let ignore_indices = get_ignore_indices(&mock_function.sig.inputs, &ignore_params)?;
+
+    // Validate function is suitable for mocking (only non-ignored params).
+    // With `own_refs` the references are recorded as owned values, so they don't need to be 'static
+    let unchecked_indices = match own_refs {
+        true => [ignore_indices.clone(), owned_ref_indices(&mock_function.sig.inputs)].concat(),
+        false => ignore_indices.clone(),
+    };
+    validate_function_mockable(&mock_function, &unchecked_indices)?;
+
+    // Extract function details, moving them out of the item instead of cloning them
+    let item_cfg = item_cfg(&mock_function.attrs);
+    let syn::ItemFn { attrs: fn_attrs, vis: fn_visibility, sig, block: fn_block } = mock_function;
+    let fn_attrs = quote! { #(#fn_attrs)* };
+    let fn_asyncness = sig.asyncness;
+    let fn_unsafety = sig.unsafety;
+    let fn_abi = sig.abi;
+    let fn_name = sig.ident;
+    let fn_generics = sig.generics;
+    let fn_inputs = sig.inputs;
+    let fn_output = sig.output;
 
     // Generate mock module name
     let mock_mod_name = syn::Ident::new(&format!("{}_mock", &fn_name), fn_name.span());
+    let mock_mod_visibility = double_visibility(&fn_visibility);
 
-    // Convert ignore param names to indices
-    let ignore_indices = get_ignore_indices(&fn_inputs, &ignore_params)?;
+    // A generic function gets a mock module per instantiation instead of a single one
+    if !instantiations.is_empty() {
+        if fn_asyncness.is_some() || contains_impl_trait(&fn_output) {
+            return Err(syn::Error::new_spanned(
+                &fn_output,
+                "async functions and functions returning `impl Trait` can't use `instantiate`",
+            ));
+        }
+        if fake || di {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`fake` and `di` can't be combined with `instantiate`",
+            ));
+        }
+        if let Some(unsafety) = fn_unsafety {
+            return Err(syn::Error::new_spanned(unsafety, "unsafe functions can't use `instantiate`"));
+        }
+        if let Some(abi) = &fn_abi {
+            return Err(syn::Error::new_spanned(abi, "`extern` functions can't use `instantiate`"));
+        }
+        return create_instantiated_mocks(
+            &fn_name,
+            &fn_visibility,
+            &fn_generics,
+            &fn_inputs,
+            &fn_output,
+            &fn_block,
+            mock_mod_visibility,
+            &ignore_indices,
+            group,
+            type_state,
+            instantiations,
+            &fn_attrs,
+            &item_cfg,
+        );
+    }
 
-    // Validate function is suitable for mocking (only non-ignored params)
-    validate_function_mockable(&mock_function, &ignore_indices)?;
+    // The mock module isn't generic, the generics are only kept on the function.
+    // The fake and the seam of `di` take all parameters, including the ignored ones
+    let recorded_ignore_indices = if fake || di { &[][..] } else { &ignore_indices[..] };
+    validate_generic_params(&fn_generics, &fn_inputs, &fn_output, recorded_ignore_indices)?;
 
     // Only add the not ignored parameters to the param_types / params_to_tuple
-    let params_type = create_param_type(&fn_inputs, &ignore_indices);
+    let params_type = create_param_type(&record_owned_refs(&record_fn_pointers(&fn_inputs)), &ignore_indices);
     let params_to_tuple = create_tuple_from_param_names(&fn_inputs, &ignore_indices);
 
-    let return_type = extract_return_type(&mock_function.sig.output);
+    // A returned reference is stored as a 'static one, see `static_lifetimes`
+    let return_type = static_lifetimes(&extract_return_type(&fn_output));
 
-    let filtered_fn_inputs = crate::param_utils::filter_params(&fn_inputs, &ignore_indices);
+    // An `impl Trait` can't be named in the mock, so the mock returns a boxed trait object instead.
+    // A closure is built by the implementation of the mock, so `setup` is a factory of a fresh closure per call
+    let iterator_item = extract_iterator_item(&return_type);
+    let boxed_return_type = box_impl_trait(&return_type);
+    if boxed_return_type.is_some() && fn_asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            &fn_output,
+            "async functions returning `impl Trait` can't be mocked, return a `Vec` or a `Box<dyn Trait>` instead",
+        ));
+    }
+    let return_type = boxed_return_type.clone().unwrap_or(return_type);
 
-    let mock_function = create_mock_function(
-        fn_name,
-        fn_visibility,
-        fn_asyncness,
-        fn_inputs.clone(),
-        fn_output,
-        fn_block,
-        mock_mod_name.clone(),
-        params_to_tuple.clone()
-    );
+    // The fake stores the implementation with the declared return type, which can't be an `impl Trait`
+    if fake && boxed_return_type.is_some() {
+        return Err(syn::Error::new_spanned(
+            &fn_output,
+            "functions returning `impl Trait` can't have a fake, remove `fake` from the arguments",
+        ));
+    }
+    // The seam calls the function through a function pointer, and the fake relies on the interception
+    if di && (fn_asyncness.is_some() || boxed_return_type.is_some()) {
+        return Err(syn::Error::new_spanned(
+            &fn_output,
+            "async functions and functions returning `impl Trait` can't use `di`, remove `di` from the arguments",
+        ));
+    }
+    // The seam is a safe trait method, which can't call the unsafe function without an unsafe block
+    if let (true, Some(unsafety)) = (di, fn_unsafety) {
+        return Err(syn::Error::new_spanned(unsafety, "unsafe functions can't use `di`, remove `di` from the arguments"));
+    }
+    // The function pointer of the seam has the Rust ABI
+    if let (true, Some(abi)) = (di, &fn_abi) {
+        return Err(syn::Error::new_spanned(abi, "`extern` functions can't use `di`, remove `di` from the arguments"));
+    }
+    if di && fake {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`fake` can't be combined with `di`, inject a different implementation of the trait instead",
+        ));
+    }
+
+    let fake_mod_name = fake.then(|| syn::Ident::new(&format!("{}_fake", &fn_name), fn_name.span()));
+    let fake_module = fake_mod_name.clone().map(|fake_mod_name| {
+        let fake_cfg = double_cfg();
+        let fake_module = create_fake_module(
+            fake_mod_name,
+            mock_mod_visibility.clone(),
+            create_param_type(&fn_inputs, &[]),
+            return_type.clone(),
+            &fn_inputs,
+            fn_asyncness,
+        );
+        quote! {
+            #item_cfg
+            #fake_cfg
+            #fake_module
+        }
+    });
+
+    let filtered_fn_inputs = elide_ref_params(&crate::param_utils::filter_params(&fn_inputs, &ignore_indices));
+
+    // With `di` the original function stays unchanged, the code under test receives the trait instead
+    let where_clause = &fn_generics.where_clause;
+    let mock_function = if di {
+        let di_seam = create_di_seam(
+            &fn_name,
+            &fn_visibility,
+            &fn_inputs,
+            &fn_output,
+            &mock_mod_name,
+            &mock_mod_visibility,
+            &ignore_indices,
+            &item_cfg,
+        )?;
+        quote! {
+            #fn_attrs
+            #fn_visibility fn #fn_name #fn_generics(#fn_inputs) #fn_output #where_clause #fn_block
+
+            #di_seam
+        }
+    } else {
+        create_mock_function(
+            fn_name,
+            fn_visibility,
+            fn_asyncness,
+            fn_unsafety,
+            fn_abi,
+            &fn_generics,
+            &fn_inputs,
+            fn_output,
+            &fn_block,
+            mock_mod_name.to_token_stream(),
+            params_to_tuple.clone(),
+            boxed_return_type,
+            fake_mod_name,
+            &fn_attrs,
+        )
+    };
 
     let mock_module = create_mock_module(
         mock_mod_name,
+        mock_mod_visibility,
         params_type,
         return_type,
         &fn_inputs,
         &ignore_indices,
         fn_asyncness,
         params_to_tuple,
-        filtered_fn_inputs
+        filtered_fn_inputs,
+        iterator_item,
+        group,
+        type_state,
     );
 
+    let mock_cfg = double_cfg();
+
     // Generate the original function and the mock module
     Ok(quote! {
         #mock_function
 
-        #[cfg(test)]
+        #item_cfg
+        #mock_cfg
         #mock_module
+
+        #fake_module
     })
 }
 
+/// Processes an `extern` block and generates the mocks of the functions it declares.
+///
+/// In test builds the declarations are replaced by `unsafe` functions with the ABI of the block,
+/// which call their mock modules, so the tests don't link the foreign library, see `create_foreign_mocks`.
+///
+/// # Arguments
+///
+/// * `foreign_mod` - The `extern` block to create mocks for
+/// * `args` - The arguments of the attribute, only `group`, `type_state` and `own_refs` apply to every declared function
+///
+/// # Returns
+///
+/// - `Ok(TokenStream2)` - The block, the replacements of its functions and their mock modules
+/// - `Err(syn::Error)` - If an argument doesn't apply to an `extern` block or a function can't be mocked
+pub(crate) fn process_mock_foreign_mod(foreign_mod: syn::ItemForeignMod, args: MockFunctionArgs) -> syn::Result<TokenStream2> {
+    if !args.ignore.is_empty() || args.fake || args.di || !args.instantiations.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &foreign_mod.abi,
+            "`ignore`, `fake`, `di` and `instantiate` aren't supported for `extern` blocks",
+        ));
+    }
+    create_foreign_mocks(foreign_mod, args.group, args.type_state, args.own_refs)
+}
+
+/// Checks whether the return type contains an `impl Trait`, which the generic function can't convert to a concrete type.
+fn contains_impl_trait(fn_output: &syn::ReturnType) -> bool {
+    let impl_keyword = syn::Ident::new("impl", proc_macro2::Span::call_site());
+    match fn_output {
+        syn::ReturnType::Default => false,
+        syn::ReturnType::Type(_, return_type) => mentions_names(return_type, &[&impl_keyword]),
+    }
+}
+
 /// Converts parameter names to their indices.
 ///
 /// Maps each ignored parameter name to its position in the function signature.
-fn get_ignore_indices(
+pub(crate) fn get_ignore_indices(
     fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
     ignore_params: &[String]
 ) -> syn::Result<Vec<usize>> {
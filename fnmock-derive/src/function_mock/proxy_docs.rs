@@ -1,4 +1,4 @@
-/// Generates documentation strings for mock proxy functions based on actual function parameters.
+//! Generates documentation strings for mock proxy functions based on actual function parameters.
 
 use quote::quote;
 
@@ -10,6 +10,8 @@ pub(crate) struct MockProxyDocs {
     param_docs: Vec<String>,
     ignored_param_docs: Vec<String>,
     setup_example: Vec<String>,
+    mock_fn_name: String,
+    return_type_str: String,
     is_async: bool,
 }
 
@@ -62,11 +64,14 @@ impl MockProxyDocs {
             })
             .collect();
         
+        // Stringified once, the type is used in several docs
+        let return_type_str = quote::quote!(#return_type).to_string();
+
         let setup_example = if all_params.is_empty() {
             vec![
                 format!("{}::setup(|| {{", mock_fn_name),
                 "    // Custom logic here".to_string(),
-                format!("    {}", quote::quote!(#return_type)),
+                format!("    {}", return_type_str),
                 "});".to_string(),
             ]
         } else {
@@ -85,7 +90,7 @@ impl MockProxyDocs {
             vec![
                 format!("{}::setup(|{}| {{", mock_fn_name, quote::quote!(#params_pattern)),
                 "    // Custom logic here".to_string(),
-                format!("    {}", quote::quote!(#return_type)),
+                format!("    {}", return_type_str),
                 "});".to_string(),
             ]
         };
@@ -94,6 +99,8 @@ impl MockProxyDocs {
             param_docs,
             ignored_param_docs,
             setup_example,
+            mock_fn_name: mock_fn_name.to_string(),
+            return_type_str,
             is_async: fn_asyncness.is_some(),
         }
     }
@@ -150,6 +157,8 @@ impl MockProxyDocs {
             quote! { #[doc = "Configures the function that will be called when the mock is invoked."] },
             quote! { #[doc = "The provided function receives the parameters (excluding ignored ones) and"] },
             quote! { #[doc = "must return the expected return type."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Returns a `MockHandle` that can be used for assertions later on."] },
         ];
         
         if self.is_async {
@@ -201,95 +210,1075 @@ impl MockProxyDocs {
         quote! { #(#docs)* }
     }
 
-    /// Generates documentation attributes for the `clear` function.
-    pub(crate) fn clear_docs(&self) -> proc_macro2::TokenStream {
-        quote! {
-            #[doc = "Clears the mock state."]
-            #[doc = ""]
-            #[doc = "Resets the mock by clearing:"]
-            #[doc = "- The configured behavior (set via `setup()`)"]
-            #[doc = "- The call history"]
-            #[doc = ""]
-            #[doc = "After calling `clear()`, the mock will panic if invoked before"]
-            #[doc = "calling `setup()` again."]
-        }
-    }
+    /// Generates documentation attributes for the `setup_ref` function.
+    pub(crate) fn setup_ref_docs(&self) -> proc_macro2::TokenStream {
+        let setup_ref_example = format!("{}::setup_ref(|params| {{ /* borrow params */ }});", self.mock_fn_name);
 
-    /// Generates documentation attributes for the `is_set` function.
-    pub(crate) fn is_set_docs(&self) -> proc_macro2::TokenStream {
         quote! {
-            #[doc = "Checks if the mock has been configured."]
+            #[doc = "Sets up the mock behavior with an implementation borrowing the parameters."]
             #[doc = ""]
-            #[doc = "Returns `true` if `setup()` has been called and the mock is ready to use,"]
-            #[doc = "or `false` if the mock has not been set up or has been cleared."]
+            #[doc = "Works like `setup()`, but the parameters don't need to be cloned for the call history,"]
+            #[doc = "since they are recorded first and then passed to the implementation by reference."]
+            #[doc = "Prefer this for large parameter types in call-heavy tests."]
             #[doc = ""]
-            #[doc = "# Returns"]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on."]
             #[doc = ""]
-            #[doc = "`bool` - `true` if configured, `false` otherwise"]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_ref_example]
+            #[doc = "```"]
         }
     }
 
-    /// Generates documentation attributes for the `assert_times` function.
-    pub(crate) fn assert_times_docs(&self) -> proc_macro2::TokenStream {
+    /// Generates documentation attributes for the `returning_indexed` function.
+    pub(crate) fn returning_indexed_docs(&self) -> proc_macro2::TokenStream {
+        let returning_indexed_example = format!(
+            "{}::returning_indexed(|call_index, params| {{ /* vary by call_index */ }});",
+            self.mock_fn_name
+        );
+
         quote! {
-            #[doc = "Asserts that the mock was called exactly the expected number of times."]
-            #[doc = ""]
-            #[doc = "# Parameters"]
+            #[doc = "Sets up the mock behavior with an implementation receiving the index of the call."]
             #[doc = ""]
-            #[doc = "* `expected_num_of_calls` - The expected number of times the mock should have been called"]
-            #[doc = ""]
-            #[doc = "# Panics"]
+            #[doc = "The zero-based `call_index` counts the calls since the history was last cleared,"]
+            #[doc = "so pagination or polling can be simulated without a hand-rolled counter."]
             #[doc = ""]
-            #[doc = "Panics if the actual number of calls does not match the expected number"]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on."]
             #[doc = ""]
             #[doc = "# Examples"]
             #[doc = ""]
             #[doc = "```ignore"]
-            #[doc = "my_function_mock::assert_times(3); // Expects exactly 3 calls"]
+            #[doc = #returning_indexed_example]
             #[doc = "```"]
         }
     }
 
-    /// Generates documentation attributes for the `assert_with` function.
-    pub(crate) fn assert_with_docs(&self) -> proc_macro2::TokenStream {
+    /// Generates documentation attributes for the `setup_returning` function.
+    pub(crate) fn setup_returning_docs(&self) -> proc_macro2::TokenStream {
+        let setup_returning_example = format!(
+            "{}::setup_returning(/* value of type {} */);",
+            self.mock_fn_name, self.return_type_str
+        );
+
         let mut docs = vec![
-            quote! { #[doc = "Asserts that the mock was called at least once with the specified parameters."] },
-            quote! { #[doc = ""] },
-            quote! { #[doc = "Checks the call history to verify that at least one call was made with"] },
-            quote! { #[doc = "parameters matching the provided values. Only non-ignored parameters need"] },
-            quote! { #[doc = "to be provided."] },
-            quote! { #[doc = ""] },
-            quote! { #[doc = "# Parameters"] },
+            quote! { #[doc = "Sets up the mock to return a fixed value."] },
             quote! { #[doc = ""] },
+            quote! { #[doc = "Shorthand for `setup(|_| value)`: the parameters are ignored and a clone of"] },
+            quote! { #[doc = "`return_value` is returned on every call. The calls are still recorded for assertions."] },
+            quote! { #[doc = "The return type must implement `Clone`."] },
         ];
-        
-        if self.param_docs.is_empty() {
-            docs.push(quote! { #[doc = "No parameters"] });
-        } else {
-            for param in &self.param_docs {
-                docs.push(quote! { #[doc = #param] });
-            }
-        }
-        
-        if !self.ignored_param_docs.is_empty() {
-            docs.push(quote! { #[doc = ""] });
-            docs.push(quote! { #[doc = "# Ignored Parameters (not required)"] });
-            docs.push(quote! { #[doc = ""] });
-            docs.push(quote! { #[doc = "The following parameters are ignored and do not need to be provided:"] });
-            docs.push(quote! { #[doc = ""] });
-            for param in &self.ignored_param_docs {
-                docs.push(quote! { #[doc = #param] });
-            }
+
+        if self.is_async {
+            docs.extend(vec![
+                quote! { #[doc = ""] },
+                quote! { #[doc = "# Note"] },
+                quote! { #[doc = ""] },
+                quote! { #[doc = "This function is async, but the value is the **non-future** return type."] },
+            ]);
         }
-        
+
         docs.extend(vec![
             quote! { #[doc = ""] },
-            quote! { #[doc = "# Panics"] },
-            quote! { #[doc = ""] },
-            quote! { #[doc = "Panics if no call with matching parameters is found in the call history"] },
+            quote! { #[doc = "# Examples"] },
             quote! { #[doc = ""] },
+            quote! { #[doc = "```ignore"] },
+            quote! { #[doc = #setup_returning_example] },
+            quote! { #[doc = "```"] },
         ]);
-        
+
         quote! { #(#docs)* }
     }
+
+    /// Generates documentation attributes for the `setup_iter` function.
+    pub(crate) fn setup_iter_docs(&self) -> proc_macro2::TokenStream {
+        let setup_iter_example = format!("{}::setup_iter([/* items */]);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Sets up the mock to return an iterator over the items on every call."]
+            #[doc = ""]
+            #[doc = "Every call returns a new iterator over a clone of the items, so the items must implement `Clone`."]
+            #[doc = "Custom implementations passed to `setup()` return a `Box<dyn Iterator>` instead."]
+            #[doc = "Only generated for functions returning `impl Iterator<Item = T>`."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_iter_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `setup_batch` function.
+    pub(crate) fn setup_batch_docs(&self) -> proc_macro2::TokenStream {
+        let setup_batch_example = format!("{}::setup_batch(|item| /* result for the item */);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Sets up the mock to compute the result of every item of the `Vec` separately."]
+            #[doc = ""]
+            #[doc = "Every call returns a `Vec` with the results of `f` for the items, in the order of the items,"]
+            #[doc = "so per-item outcomes of a batch API don't depend on how the items are split into calls."]
+            #[doc = "Only generated for functions taking a single `Vec<T>` and returning a `Vec<R>`."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_batch_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `setup_pending` function.
+    pub(crate) fn setup_pending_docs(&self) -> proc_macro2::TokenStream {
+        let setup_pending_example = format!("{}::setup_pending();", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Makes every call return a future that never completes."]
+            #[doc = ""]
+            #[doc = "The calls are still recorded. Exercises the timeout branches of the caller deterministically,"]
+            #[doc = "e.g. `tokio::time::timeout` with paused time. Only generated for async functions."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_pending_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `yield_before_returning` function.
+    pub(crate) fn yield_before_returning_docs(&self) -> proc_macro2::TokenStream {
+        let yield_before_returning_example = format!("{}::yield_before_returning();", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Makes every call yield once to the async runtime before returning, until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "The call is in flight at the yield point, so callers cancelling it, e.g. in `select!`,"]
+            #[doc = "can be verified with `was_cancelled()` / `cancelled_calls()`. Only generated for async functions."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on, or nothing with `type_state`,"]
+            #[doc = "since it doesn't set up an implementation."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #yield_before_returning_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `with_latency` function.
+    pub(crate) fn with_latency_docs(&self) -> proc_macro2::TokenStream {
+        let with_latency_example = format!("{}::with_latency(std::time::Duration::from_millis(100));", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Delays the result of every call by the latency, until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "Simulates a slow dependency to reproduce race conditions and ordering bugs. With the `tokio`"]
+            #[doc = "feature of fnmock the delay follows tokio's paused clock. Only generated for async functions."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on, or nothing with `type_state`,"]
+            #[doc = "since it doesn't set up an implementation."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #with_latency_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `with_jitter` function.
+    pub(crate) fn with_jitter_docs(&self) -> proc_macro2::TokenStream {
+        let with_jitter_example = format!(
+            "{}::with_jitter(Duration::from_millis(5)..Duration::from_millis(50));",
+            self.mock_fn_name
+        );
+
+        quote! {
+            #[doc = "Delays the result of every call by an additional duration in the range, until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "The durations are pseudo-random with a fixed seed, so a test sees the same delays on every run."]
+            #[doc = "Combines with `with_latency()`. Only generated for async functions."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on, or nothing with `type_state`,"]
+            #[doc = "since it doesn't set up an implementation."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #with_jitter_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `was_cancelled` function.
+    pub(crate) fn was_cancelled_docs(&self) -> proc_macro2::TokenStream {
+        let was_cancelled_example = format!("assert!({}::was_cancelled());", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Checks if a call was dropped before it returned."]
+            #[doc = ""]
+            #[doc = "Calls can only be cancelled at the yield point enabled with `yield_before_returning()`."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #was_cancelled_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `setup_panic` function.
+    pub(crate) fn setup_panic_docs(&self) -> proc_macro2::TokenStream {
+        let setup_panic_example = format!("{}::setup_panic(\"db connection lost\");", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Makes every call panic with the message, simulating a crashing dependency."]
+            #[doc = ""]
+            #[doc = "Unlike `forbid()`, the calls are recorded before panicking, so `catch_unwind` or supervisor"]
+            #[doc = "logic can be tested and the calls asserted afterward."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_panic_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `forbid` function.
+    pub(crate) fn forbid_docs(&self) -> proc_macro2::TokenStream {
+        let forbid_example = format!("{}::forbid();", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Forbids any call to the function."]
+            #[doc = ""]
+            #[doc = "Every call panics immediately with the parameters it was called with. Unlike `assert_times(0)`"]
+            #[doc = "at the end of the test, this fails at the moment the function is called, so the backtrace"]
+            #[doc = "points to the code calling it."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #forbid_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `set_call_budget` function.
+    pub(crate) fn set_call_budget_docs(&self) -> proc_macro2::TokenStream {
+        let set_call_budget_example = format!("{}::set_call_budget(3);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Limits the number of calls to the mock until it is cleared."]
+            #[doc = ""]
+            #[doc = "Every call after the first `max_calls` calls panics immediately with its parameters, so a runaway"]
+            #[doc = "loop, e.g. retries hammering the mocked dependency, fails at the excess call instead of hanging the test."]
+            #[doc = "The calls are counted since the call history was last cleared."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #set_call_budget_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `require` function.
+    pub(crate) fn require_docs(&self) -> proc_macro2::TokenStream {
+        let require_example = format!("{}::require();", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Declares the mock required, so the real implementation of the function panics when it runs."]
+            #[doc = ""]
+            #[doc = "Catches calls silently falling through to real I/O, e.g. after a test helper cleared the mock."]
+            #[doc = "The requirement holds until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #require_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `add_precondition` function.
+    pub(crate) fn add_precondition_docs(&self) -> proc_macro2::TokenStream {
+        let add_precondition_example = format!("{}::add_precondition(|params| /* condition on the params */);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Checks the parameters of every call until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "A call violating the check panics immediately with the location the check was added at"]
+            #[doc = "and the parameters, turning the mock into a contract checker for the calls of the code under test."]
+            #[doc = "The parameters are passed in the form they are recorded in, see `calls()`."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #add_precondition_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `add_postcondition` function.
+    pub(crate) fn add_postcondition_docs(&self) -> proc_macro2::TokenStream {
+        let add_postcondition_example = format!("{}::add_postcondition(|params, result| /* condition on the params and the result */);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Checks the parameters and the result of every call until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "A call violating the check panics with the location the check was added at, the parameters and the result,"]
+            #[doc = "which has to implement `Debug`. Calls of a pending or panicking mock have no result and aren't checked."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #add_postcondition_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `with` function.
+    pub(crate) fn with_docs(&self) -> proc_macro2::TokenStream {
+        let with_example = format!("let result = {}::with(/* implementation */, || {{", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Sets up the mock behavior for the duration of `body`."]
+            #[doc = ""]
+            #[doc = "Configures the mock like `setup()`, runs `body` and clears the mock afterward,"]
+            #[doc = "even if `body` returns early or panics. Assertions have to be made inside `body`,"]
+            #[doc = "since the call history is cleared as well."]
+            #[doc = ""]
+            #[doc = "# Returns"]
+            #[doc = ""]
+            #[doc = "The return value of `body`"]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #with_example]
+            #[doc = "    // Code using the mock"]
+            #[doc = "});"]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `configure` function.
+    pub(crate) fn configure_docs(&self) -> proc_macro2::TokenStream {
+        let configure_example = format!("{}::configure(|m| {{", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Configures the mock in a single closure."]
+            #[doc = ""]
+            #[doc = "The closure receives a `MockConfig` giving access to all configuration of the mock,"]
+            #[doc = "e.g. the implementation, the return value and the expected number of calls."]
+            #[doc = "Expectations are checked with `verify()`."]
+            #[doc = ""]
+            #[doc = "# Returns"]
+            #[doc = ""]
+            #[doc = "A `MockHandle` that can be used for assertions later on, or nothing with `type_state`,"]
+            #[doc = "since it doesn't set up an implementation"]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #configure_example]
+            #[doc = "    m.returning(/* return value */);"]
+            #[doc = "    m.expect_times(2);"]
+            #[doc = "});"]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `MockConfig` struct.
+    pub(crate) fn config_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Configuration of the mock passed to the closure of `configure()`."]
+            #[doc = ""]
+            #[doc = "Every method forwards to the corresponding function of this module"]
+            #[doc = "and returns the config again, so calls can be chained."]
+        }
+    }
+
+    /// Generates documentation attributes for the `verify` function.
+    pub(crate) fn verify_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Asserts the expectations set with `configure()`."]
+            #[doc = ""]
+            #[doc = "Passes if no expectations were set."]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if the mock wasn't called the expected number of times"]
+        }
+    }
+
+    /// Generates documentation attributes for the `checkpoint` function.
+    pub(crate) fn checkpoint_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Asserts the expectations set with `configure()` and starts a new phase of the test."]
+            #[doc = ""]
+            #[doc = "The expectations are discarded, so the mock can be configured again for the next phase"]
+            #[doc = "without tripping the checks of `fnmock::strict`. The behavior and the call history are kept."]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if the mock wasn't called the expected number of times since the last checkpoint"]
+        }
+    }
+
+    /// Generates documentation attributes for the `setup_ok` function.
+    ///
+    /// # Arguments
+    ///
+    /// * `ok_type` - The `T` of the `Result<T, E>` return type
+    pub(crate) fn setup_ok_docs(&self, ok_type: &syn::Type) -> proc_macro2::TokenStream {
+        let setup_ok_example = format!(
+            "{}::setup_ok(/* value of type {} */);",
+            self.mock_fn_name, quote::quote!(#ok_type)
+        );
+
+        quote! {
+            #[doc = "Sets up the mock to return `Ok(value)`."]
+            #[doc = ""]
+            #[doc = "Shorthand for `setup_returning(Ok(value))`: the parameters are ignored and a clone of"]
+            #[doc = "`Ok(value)` is returned on every call. The calls are still recorded for assertions."]
+            #[doc = "Both the `Ok` and the `Err` type must implement `Clone`."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_ok_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `setup_err` function.
+    ///
+    /// # Arguments
+    ///
+    /// * `err_type` - The `E` of the `Result<T, E>` return type
+    pub(crate) fn setup_err_docs(&self, err_type: &syn::Type) -> proc_macro2::TokenStream {
+        let setup_err_example = format!(
+            "{}::setup_err(/* value of type {} */);",
+            self.mock_fn_name, quote::quote!(#err_type)
+        );
+
+        quote! {
+            #[doc = "Sets up the mock to return `Err(error)`."]
+            #[doc = ""]
+            #[doc = "Shorthand for `setup_returning(Err(error))`: the parameters are ignored and a clone of"]
+            #[doc = "`Err(error)` is returned on every call. The calls are still recorded for assertions."]
+            #[doc = "Both the `Ok` and the `Err` type must implement `Clone`."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_err_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `clear` function.
+    pub(crate) fn clear_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Clears the mock state."]
+            #[doc = ""]
+            #[doc = "Resets the mock by clearing:"]
+            #[doc = "- The configured behavior (set via `setup()`)"]
+            #[doc = "- The call history"]
+            #[doc = ""]
+            #[doc = "After calling `clear()`, the mock will panic if invoked before"]
+            #[doc = "calling `setup()` again."]
+        }
+    }
+
+    /// Generates documentation attributes for the `clear_implementation` function.
+    pub(crate) fn clear_implementation_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Clears the configured behavior (set via `setup()`), but keeps the call history."]
+            #[doc = ""]
+            #[doc = "After calling `clear_implementation()`, the original function is executed again,"]
+            #[doc = "while the calls made so far can still be asserted."]
+        }
+    }
+
+    /// Generates documentation attributes for the `clear_history` function.
+    pub(crate) fn clear_history_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Clears the call history, but keeps the configured behavior."]
+            #[doc = ""]
+            #[doc = "Useful for multi-stage tests, that want to make assertions about each stage separately."]
+        }
+    }
+
+    /// Generates documentation attributes for the `dedup_consecutive_calls` function.
+    pub(crate) fn dedup_consecutive_calls_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Records identical consecutive calls as a single entry with a count until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "Saves memory for mocks called thousands of times with the same parameters in a loop."]
+            #[doc = "The assertions, `calls()` and `format_calls()` still see every single call."]
+        }
+    }
+
+    /// Generates documentation attributes for the `index_calls` function.
+    pub(crate) fn index_calls_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Maintains a hash index over the call history until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "Makes `was_called_with()` and the `assert_with*()` assertions O(1) instead of a scan"]
+            #[doc = "over all calls, which matters for mocks recording tens of thousands of calls."]
+            #[doc = "Requires the recorded parameters to implement `Hash`."]
+        }
+    }
+
+    /// Generates documentation attributes for the `record_timestamps` function.
+    pub(crate) fn record_timestamps_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Records the time of every call until the mock is cleared."]
+            #[doc = ""]
+            #[doc = "Enables `call_timestamps()` and `durations_between_calls()`, e.g. to test debounce or throttle logic."]
+            #[doc = "With the `tokio` feature of fnmock the times follow tokio's paused clock."]
+        }
+    }
+
+    /// Generates documentation attributes for the `snapshot` function.
+    pub(crate) fn snapshot_docs(&self) -> proc_macro2::TokenStream {
+        let snapshot_example = format!(
+            "let arranged = {0}::snapshot();\n// act and assert ...\n{0}::restore(&arranged);\n// act and assert again ...",
+            self.mock_fn_name
+        );
+
+        quote! {
+            #[doc = "Captures the configuration of the mock, so it can be restored with `restore()` later on."]
+            #[doc = ""]
+            #[doc = "Lets a complex arrange phase be captured once and restored between multiple act / assert phases of a test."]
+            #[doc = "The snapshot contains the implementation, the expectations and the enabled recording options, but not the calls."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #snapshot_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `restore` function.
+    pub(crate) fn restore_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Replaces the configuration of the mock with the snapshot taken by `snapshot()` and clears the recorded calls."]
+            #[doc = ""]
+            #[doc = "The snapshot can be restored any number of times. `index_calls()` isn't part of it and has to be called again."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on, or nothing with `type_state`,"]
+            #[doc = "since it doesn't set up an implementation."]
+        }
+    }
+
+    /// Generates documentation attributes for the `export_state` function.
+    pub(crate) fn export_state_docs(&self) -> proc_macro2::TokenStream {
+        let export_state_example = format!(
+            "let state = {0}::export_state();\nstd::thread::spawn(move || {{\n    {0}::import_state(state);\n    // ...\n}});",
+            self.mock_fn_name
+        );
+
+        quote! {
+            #[doc = "Copies the configuration of the mock, so it can be imported on another thread with `import_state()`."]
+            #[doc = ""]
+            #[doc = "The mocks are thread-local, so code running on a spawned thread doesn't see the configuration of the test."]
+            #[doc = "The copy contains the implementation, the expectations and the enabled recording options, but not the calls."]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if the mock is set up with `setup_iter()`, whose items can't be copied."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #export_state_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `import_state` function.
+    pub(crate) fn import_state_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Replaces the configuration of the mock on the current thread with one copied by `export_state()`."]
+            #[doc = ""]
+            #[doc = "Clears the mock first, so the calls are recorded on this thread from scratch."]
+            #[doc = "`index_calls()` isn't part of the copy and has to be called again."]
+            #[doc = ""]
+            #[doc = "Returns a `MockHandle` that can be used for assertions later on, or nothing with `type_state`,"]
+            #[doc = "since it doesn't set up an implementation."]
+        }
+    }
+
+    /// Generates documentation attributes for the `call_timestamps` function.
+    pub(crate) fn call_timestamps_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Returns the time of every call since `record_timestamps()` was called."]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if `record_timestamps()` wasn't called."]
+        }
+    }
+
+    /// Generates documentation attributes for the `durations_between_calls` function.
+    pub(crate) fn durations_between_calls_docs(&self) -> proc_macro2::TokenStream {
+        let durations_between_calls_example = format!(
+            "assert!({}::durations_between_calls().iter().all(|duration| *duration >= Duration::from_millis(100)));",
+            self.mock_fn_name
+        );
+
+        quote! {
+            #[doc = "Returns the time between each pair of consecutive calls since `record_timestamps()` was called."]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if `record_timestamps()` wasn't called."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #durations_between_calls_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `is_set` function.
+    pub(crate) fn is_set_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Checks if the mock has been configured."]
+            #[doc = ""]
+            #[doc = "Returns `true` if `setup()` has been called and the mock is ready to use,"]
+            #[doc = "or `false` if the mock has not been set up or has been cleared."]
+            #[doc = ""]
+            #[doc = "# Returns"]
+            #[doc = ""]
+            #[doc = "`bool` - `true` if configured, `false` otherwise"]
+        }
+    }
+
+    /// Generates documentation attributes for the `MockHandle` struct.
+    pub(crate) fn handle_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Handle to the mock returned by the `setup*()` functions."]
+            #[doc = ""]
+            #[doc = "It allows test helpers that configure the mock to hand back something the test"]
+            #[doc = "can make assertions on, without the test needing to know the module path of the mock."]
+            #[doc = "The handle doesn't hold any state, it only forwards to the functions of this module."]
+            #[doc = ""]
+            #[doc = "It implements `fnmock::MockControl`, so helpers can accept handles of different mocks"]
+            #[doc = "as `&[&dyn fnmock::MockControl]`."]
+            #[doc = ""]
+            #[doc = "With `#[mock_function(type_state)]` the handle can only be created by the functions setting up"]
+            #[doc = "an implementation, like the `setup*()` functions and `forbid()`, and is the only way to make assertions on the mock."]
+        }
+    }
+
+    /// Generates documentation attributes for the `calls` function.
+    pub(crate) fn calls_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Returns a copy of the call history."]
+            #[doc = ""]
+            #[doc = "Each call is represented by its non-ignored parameters in the form they are recorded in:"]
+            #[doc = "as a tuple for multiple parameters, as the value itself for a single parameter and as `()`"]
+            #[doc = "for no parameters."]
+            #[doc = ""]
+            #[doc = "# Returns"]
+            #[doc = ""]
+            #[doc = "The recorded parameters of all calls in the order the calls were made,"]
+            #[doc = "with deduplicated calls expanded again (see `dedup_consecutive_calls()`)"]
+        }
+    }
+
+    /// Generates documentation attributes for the `format_calls` function.
+    pub(crate) fn format_calls_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Renders the call history as an aligned table."]
+            #[doc = ""]
+            #[doc = "The table has a row for every call with the call number and the `Debug` representation"]
+            #[doc = "of every non-ignored parameter. Return values aren't recorded, so they aren't included."]
+            #[doc = ""]
+            #[doc = "# Returns"]
+            #[doc = ""]
+            #[doc = "The table as a `String`, or a note that the mock was not called"]
+        }
+    }
+
+    /// Generates documentation attributes for the `print_calls` function.
+    pub(crate) fn print_calls_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Prints the call history as an aligned table, see `format_calls()`."]
+            #[doc = ""]
+            #[doc = "Useful for debugging failing interaction tests, as the output is shown for failed tests."]
+        }
+    }
+
+    /// Generates documentation attributes for the `was_called` function.
+    pub(crate) fn was_called_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Checks if the mock was called at least once."]
+            #[doc = ""]
+            #[doc = "Unlike the `assert_*` functions this doesn't panic, so it can be combined"]
+            #[doc = "with `assert!` and a custom message or used in conditions."]
+            #[doc = ""]
+            #[doc = "# Returns"]
+            #[doc = ""]
+            #[doc = "`bool` - `true` if the call history is not empty, `false` otherwise"]
+        }
+    }
+
+    /// Generates documentation attributes for the `was_called_with` function.
+    pub(crate) fn was_called_with_docs(&self) -> proc_macro2::TokenStream {
+        let mut docs = vec![
+            quote! { #[doc = "Checks if the mock was called at least once with the specified parameters."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Non-panicking counterpart of `assert_with()`. Only non-ignored parameters need"] },
+            quote! { #[doc = "to be provided."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Parameters"] },
+            quote! { #[doc = ""] },
+        ];
+
+        if self.param_docs.is_empty() {
+            docs.push(quote! { #[doc = "No parameters"] });
+        } else {
+            for param in &self.param_docs {
+                docs.push(quote! { #[doc = #param] });
+            }
+        }
+
+        docs.extend(vec![
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Returns"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "`bool` - `true` if a call with matching parameters is found in the call history, `false` otherwise"] },
+        ]);
+
+        quote! { #(#docs)* }
+    }
+
+    /// Generates documentation attributes for the `assert_times` function.
+    pub(crate) fn assert_times_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Asserts that the mock was called exactly the expected number of times."]
+            #[doc = ""]
+            #[doc = "# Parameters"]
+            #[doc = ""]
+            #[doc = "* `expected_num_of_calls` - The expected number of times the mock should have been called"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if the actual number of calls does not match the expected number"]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = "my_function_mock::assert_times(3); // Expects exactly 3 calls"]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `assert_times_msg` function.
+    pub(crate) fn assert_times_msg_docs(&self) -> proc_macro2::TokenStream {
+        let example = format!("{}::assert_times_msg(2, \"after retry loop\");", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Asserts the number of calls like `assert_times()`, labeling a failure with the message."]
+            #[doc = ""]
+            #[doc = "Useful for tests performing the same assertion at several points, to tell which one failed."]
+            #[doc = ""]
+            #[doc = "# Parameters"]
+            #[doc = ""]
+            #[doc = "* `expected_num_of_calls` - The expected number of times the mock should have been called"]
+            #[doc = "* `message` - The label prepended to the failure message"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics with the labeled message if the actual number of calls does not match the expected number"]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `assert_with_msg` function.
+    pub(crate) fn assert_with_msg_docs(&self) -> proc_macro2::TokenStream {
+        let mut docs = vec![
+            quote! { #[doc = "Asserts a call with the parameters like `assert_with_tuple()`, labeling a failure with the message."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Useful for tests performing the same assertion at several points, to tell which one failed."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Parameters"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "* `params` - The non-ignored parameters in the form they are recorded in:"] },
+            quote! { #[doc = ""] },
+        ];
+
+        if self.param_docs.is_empty() {
+            docs.push(quote! { #[doc = "No parameters"] });
+        } else {
+            for param in &self.param_docs {
+                docs.push(quote! { #[doc = #param] });
+            }
+        }
+
+        docs.extend(vec![
+            quote! { #[doc = ""] },
+            quote! { #[doc = "* `message` - The label prepended to the failure message"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Panics"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Panics with the labeled message if no call with matching parameters is found in the call history"] },
+        ]);
+
+        quote! { #(#docs)* }
+    }
+
+    /// Generates documentation attributes for the `assert_with` function.
+    pub(crate) fn assert_with_docs(&self) -> proc_macro2::TokenStream {
+        let mut docs = vec![
+            quote! { #[doc = "Asserts that the mock was called at least once with the specified parameters."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Checks the call history to verify that at least one call was made with"] },
+            quote! { #[doc = "parameters matching the provided values. Only non-ignored parameters need"] },
+            quote! { #[doc = "to be provided."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Parameters"] },
+            quote! { #[doc = ""] },
+        ];
+        
+        if self.param_docs.is_empty() {
+            docs.push(quote! { #[doc = "No parameters"] });
+        } else {
+            for param in &self.param_docs {
+                docs.push(quote! { #[doc = #param] });
+            }
+        }
+        
+        if !self.ignored_param_docs.is_empty() {
+            docs.push(quote! { #[doc = ""] });
+            docs.push(quote! { #[doc = "# Ignored Parameters (not required)"] });
+            docs.push(quote! { #[doc = ""] });
+            docs.push(quote! { #[doc = "The following parameters are ignored and do not need to be provided:"] });
+            docs.push(quote! { #[doc = ""] });
+            for param in &self.ignored_param_docs {
+                docs.push(quote! { #[doc = #param] });
+            }
+        }
+        
+        docs.extend(vec![
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Panics"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Panics if no call with matching parameters is found in the call history"] },
+            quote! { #[doc = ""] },
+        ]);
+        
+        quote! { #(#docs)* }
+    }
+
+    /// Generates documentation attributes for the `assert_with_tuple` function.
+    pub(crate) fn assert_with_tuple_docs(&self) -> proc_macro2::TokenStream {
+        let mut docs = vec![
+            quote! { #[doc = "Asserts that the mock was called at least once with the specified parameters."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Same as `assert_with()`, but the parameters are supplied in the form they are recorded in:"] },
+            quote! { #[doc = "as a tuple for multiple parameters, as the value itself for a single parameter and as `()`"] },
+            quote! { #[doc = "for no parameters. This is useful for test helpers that already hold the parameters."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Parameters"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "* `params` - The non-ignored parameters in the following order:"] },
+            quote! { #[doc = ""] },
+        ];
+
+        if self.param_docs.is_empty() {
+            docs.push(quote! { #[doc = "No parameters"] });
+        } else {
+            for param in &self.param_docs {
+                docs.push(quote! { #[doc = #param] });
+            }
+        }
+
+        docs.extend(vec![
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Panics"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Panics if no call with matching parameters is found in the call history"] },
+        ]);
+
+        quote! { #(#docs)* }
+    }
+
+    /// Generates documentation attributes for the `verify_no_more_interactions` function.
+    pub(crate) fn verify_no_more_interactions_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Asserts that every recorded call was covered by an assertion."]
+            #[doc = ""]
+            #[doc = "A call is covered once an `assert_with*()` assertion matched it or `assert_calls_exact()` succeeded."]
+            #[doc = "Use `fnmock::verify::verify_no_more_interactions` to check several mocks at once."]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics with the uncovered calls if there are any"]
+        }
+    }
+
+    /// Generates documentation attributes for the `assert_with_matchers` function.
+    pub(crate) fn assert_with_matchers_docs(&self) -> proc_macro2::TokenStream {
+        let mut docs = vec![
+            quote! { #[doc = "Asserts that the mock was called at least once with parameters matching the matchers."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Like `assert_with()`, but the parameters are described by the matchers in `fnmock::matchers`"] },
+            quote! { #[doc = "instead of their exact values: a single matcher for a single parameter, a tuple of matchers"] },
+            quote! { #[doc = "for multiple parameters."] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Parameters"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "* `matcher` - The matchers for the non-ignored parameters in the following order:"] },
+            quote! { #[doc = ""] },
+        ];
+
+        if self.param_docs.is_empty() {
+            docs.push(quote! { #[doc = "No parameters"] });
+        } else {
+            for param in &self.param_docs {
+                docs.push(quote! { #[doc = #param] });
+            }
+        }
+
+        docs.extend(vec![
+            quote! { #[doc = ""] },
+            quote! { #[doc = "# Panics"] },
+            quote! { #[doc = ""] },
+            quote! { #[doc = "Panics with the description of the matchers if no call matches"] },
+        ]);
+
+        quote! { #(#docs)* }
+    }
+
+    /// Generates documentation attributes for the `assert_times_in_context` function.
+    pub(crate) fn assert_times_in_context_docs(&self) -> proc_macro2::TokenStream {
+        let assert_times_in_context_example = format!("{}::assert_times_in_context(\"request-1\", 1);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Asserts the number of calls made in a `fnmock::with_context` context."]
+            #[doc = ""]
+            #[doc = "Useful when a test drives multiple logical requests through the same mocks."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = "fnmock::with_context(\"request-1\", || handle_request());"]
+            #[doc = #assert_times_in_context_example]
+            #[doc = "```"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if the number of calls in the context doesn't match `expected_num_of_calls`"]
+        }
+    }
+
+    /// Generates documentation attributes for the `assert_called_at_most_per` function.
+    pub(crate) fn assert_called_at_most_per_docs(&self) -> proc_macro2::TokenStream {
+        let record_timestamps_example = format!("{}::record_timestamps();", self.mock_fn_name);
+        let assert_called_at_most_per_example = format!(
+            "{}::assert_called_at_most_per(10, Duration::from_secs(1));",
+            self.mock_fn_name
+        );
+
+        quote! {
+            #[doc = "Asserts that no time window of length `window` contains more than `max_calls` calls."]
+            #[doc = ""]
+            #[doc = "Verifies rate limiters around the mocked function directly, instead of inferring it from the total"]
+            #[doc = "number of calls. Requires `record_timestamps()` to be called before the calls."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #record_timestamps_example]
+            #[doc = "// ..."]
+            #[doc = #assert_called_at_most_per_example]
+            #[doc = "```"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if a window contains more calls or the timestamps aren't recorded"]
+        }
+    }
+
+    /// Generates documentation attributes for the `assert_calls_exact` function.
+    pub(crate) fn assert_calls_exact_docs(&self) -> proc_macro2::TokenStream {
+        let assert_calls_exact_example = format!("{}::assert_calls_exact(&[/* params of every call */]);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Asserts that the complete, ordered call history equals the expected calls."]
+            #[doc = ""]
+            #[doc = "Every call is supplied in the form it is recorded in, like in `assert_with_tuple()`."]
+            #[doc = "This is the strongest interaction assertion: missing, unexpected and reordered calls all fail it."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #assert_calls_exact_example]
+            #[doc = "```"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics with a diff of the expected (`-`) and the actual (`+`) calls if they differ"]
+        }
+    }
+
+    /// Generates documentation attributes for the `assert_item_passed` function.
+    pub(crate) fn assert_item_passed_docs(&self) -> proc_macro2::TokenStream {
+        let assert_item_passed_example = format!("{}::assert_item_passed(/* item */);", self.mock_fn_name);
+
+        quote! {
+            #[doc = "Asserts that the item was passed in the `Vec` of any call."]
+            #[doc = ""]
+            #[doc = "Checks the union of the items of all calls, so the assertion holds however the caller"]
+            #[doc = "split the items into batches. Only generated for functions taking a single `Vec<T>`."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #assert_item_passed_example]
+            #[doc = "```"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if none of the calls was passed the item"]
+        }
+    }
+
+    /// Generates documentation attributes for the `assert_with_ref` function.
+    pub(crate) fn assert_with_ref_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Asserts that the mock was called at least once with the specified parameters."]
+            #[doc = ""]
+            #[doc = "Same as `assert_with_tuple()`, but borrows the expected parameters instead of taking"]
+            #[doc = "ownership, so large expected values don't have to be cloned for every assertion."]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if no call with matching parameters is found in the call history"]
+        }
+    }
 }
@@ -1,5 +1,8 @@
 use quote::quote;
+use crate::doc_utils::is_rich_docs_enabled;
+use crate::export_utils::{definition_location, double_cfg, original_cfg};
 use crate::function_stub::proxy_docs::StubProxyDocs;
+use crate::return_utils::is_copy_type;
 
 /// Generates the original function with stub checking logic injected.
 ///
@@ -12,33 +15,73 @@ use crate::function_stub::proxy_docs::StubProxyDocs;
 /// * `fn_name` - The name of the original function
 /// * `fn_visibility` - The visibility modifier of the function (pub, pub(crate), etc.)
 /// * `fn_asyncness` - Optional async keyword if the function is async
+/// * `fn_unsafety` - Optional unsafe keyword if the function is unsafe
+/// * `fn_abi` - Optional ABI of the function, e.g. `extern "C"`
 /// * `fn_inputs` - The function parameters
 /// * `fn_output` - The return type
 /// * `fn_block` - The original function body to execute when stub is not set
 /// * `stub_mod_name` - The name of the stub module containing the stub infrastructure
+/// * `args_fields` - The parameters borrowed by the `Args` struct of the stub module, if `setup_when` is supported
+/// * `track_caller` - Whether the function passes its caller to the stub for `setup_for_caller`
 ///
 /// # Returns
 ///
 /// Generated token stream for the function with injected stub checking logic
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_stub_function(
     fn_name: syn::Ident,
     fn_visibility: syn::Visibility,
     fn_asyncness: Option<syn::token::Async>,
-    fn_inputs: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
+    fn_unsafety: Option<syn::token::Unsafe>,
+    fn_abi: Option<syn::Abi>,
+    fn_inputs: &syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma>,
     fn_output: syn::ReturnType,
-    fn_block: Box<syn::Block>,
+    fn_block: &syn::Block,
     stub_mod_name: syn::Ident,
+    args_fields: Option<&[(syn::Ident, syn::Type)]>,
+    track_caller: bool,
 ) -> proc_macro2::TokenStream {
     let original_fn_stmts = &fn_block.stmts;
-    
+    let stub_cfg = double_cfg();
+
+    // With `setup_when` and `setup_for_caller`, the calls not matching a condition run the real implementation
+    // unless the stub returns a value for every call
+    let args = args_fields.map(|args_fields| {
+        let names = args_fields.iter().map(|(name, _)| name);
+        quote! { &#stub_mod_name::Args { #(#names: &#names),* } }
+    });
+    let stub_call = match (args, track_caller) {
+        (Some(args), false) => quote! {
+            if let Some(return_value) = #stub_mod_name::get_return_value_when(#args) {
+                return return_value;
+            }
+        },
+        (args, true) => quote! {
+            if let Some(return_value) = #stub_mod_name::get_return_value_for_caller(std::panic::Location::caller(), #args) {
+                return return_value;
+            }
+        },
+        (None, false) => quote! { return #stub_mod_name::get_return_value(); },
+    };
+
+    // The caller is only visible to the check if the function tracks it, which is limited to
+    // the doubles' builds since it changes the location of the panics in the real implementation
+    let track_caller_attr = track_caller.then(|| match original_cfg() {
+        Some(_) => quote! { #[cfg_attr(test, track_caller)] },
+        None => quote! { #[track_caller] },
+    });
+
     quote! {
         #[allow(unused_variables)]
-        #fn_visibility #fn_asyncness fn #fn_name(#fn_inputs) #fn_output {
-            // Call the stub implementation if set (only in test mode)
-            #[cfg(test)]
+        #track_caller_attr
+        #fn_visibility #fn_asyncness #fn_unsafety #fn_abi fn #fn_name(#fn_inputs) #fn_output {
+            // Call the stub implementation if set (only in test mode or with the `mocks` feature)
+            #stub_cfg
             if #stub_mod_name::is_set() {
-                return #stub_mod_name::get_return_value();
+                #stub_call
             }
+            #stub_cfg
+            #stub_mod_name::check_real_call();
 
             #(#original_fn_stmts)*
         }
@@ -48,49 +91,214 @@ pub(crate) fn create_stub_function(
 /// Generates a stub module containing the stub infrastructure.
 ///
 /// Creates a module with the same name as the stub function that contains:
-/// - Thread-local storage for the FunctionStub instance
+/// - Thread-local storage for the FunctionStub instance, registered in `fnmock::registry` once configured
+///   (a `CopyFunctionStub` without `RefCell` if the return type is known to be `Copy`)
 /// - Proxy functions for stub operations
 ///
 /// # Arguments
 ///
 /// * `stub_fn_name` - The name of the stub module (same as stub function name)
+/// * `mod_visibility` - The visibility of the stub module and its proxy functions
 /// * `return_type` - The return type of the function
-pub(crate) fn create_stub_module(stub_fn_name: syn::Ident, return_type: syn::Type) -> proc_macro2::TokenStream {
+/// * `args_fields` - The parameters borrowed by the `Args` struct, if `setup_when` is supported
+/// * `track_caller` - Whether the stubbed function passes its caller, so `setup_for_caller` is supported
+pub(crate) fn create_stub_module(
+    stub_fn_name: syn::Ident,
+    mod_visibility: proc_macro2::TokenStream,
+    return_type: syn::Type,
+    args_fields: Option<&[(syn::Ident, syn::Type)]>,
+    track_caller: bool,
+) -> proc_macro2::TokenStream {
     // Generate documentation using the proxy_docs module
-    let docs = StubProxyDocs::new(&stub_fn_name, &return_type);
-    let setup_docs = docs.setup_docs();
-    let clear_docs = docs.clear_docs();
-    let is_set_docs = docs.is_set_docs();
-    let get_return_value_docs = docs.get_return_value_docs();
+    // Without the `rich-docs` feature the docs are None and generate nothing
+    let docs = is_rich_docs_enabled().then(|| StubProxyDocs::new(&stub_fn_name, &return_type));
+    let setup_docs = docs.as_ref().map(StubProxyDocs::setup_docs);
+    let setup_from_iter_docs = docs.as_ref().map(StubProxyDocs::setup_from_iter_docs);
+    let setup_panic_docs = docs.as_ref().map(StubProxyDocs::setup_panic_docs);
+    let snapshot_docs = docs.as_ref().map(StubProxyDocs::snapshot_docs);
+    let restore_docs = docs.as_ref().map(StubProxyDocs::restore_docs);
+    let clear_docs = docs.as_ref().map(StubProxyDocs::clear_docs);
+    let is_set_docs = docs.as_ref().map(StubProxyDocs::is_set_docs);
+    let get_return_value_docs = docs.as_ref().map(StubProxyDocs::get_return_value_docs);
+    let setup_when_docs = docs.as_ref().map(StubProxyDocs::setup_when_docs);
+    let setup_for_caller_docs = docs.as_ref().map(StubProxyDocs::setup_for_caller_docs);
+    let require_docs = docs.as_ref().map(StubProxyDocs::require_docs);
+
+    let definition = definition_location(&stub_fn_name);
+
+    // Copy return values are stored in a Cell, which needs neither borrow checks nor clones
+    let (stub_type, stub_new, borrow, borrow_mut) = if is_copy_type(&return_type) {
+        (
+            quote! { fnmock::copy_function_stub::CopyFunctionStub<#return_type> },
+            quote! { fnmock::copy_function_stub::CopyFunctionStub::new(stringify!(#stub_fn_name)).with_definition(DEFINITION) },
+            quote! {},
+            quote! {},
+        )
+    } else {
+        (
+            quote! { std::cell::RefCell<fnmock::function_stub::FunctionStub<#return_type>> },
+            quote! { std::cell::RefCell::new(fnmock::function_stub::FunctionStub::new(stringify!(#stub_fn_name)).with_definition(DEFINITION)) },
+            quote! { .borrow() },
+            quote! { .borrow_mut() },
+        )
+    };
     
+    // Only generate the conditional setup if the parameters can be borrowed by the `Args` struct
+    let conditional_setups = args_fields.map(|args_fields| {
+        let names = args_fields.iter().map(|(name, _)| name);
+        let types = args_fields.iter().map(|(_, ty)| ty);
+
+        quote! {
+            /// Arguments of a call, borrowed by the predicates of `setup_when()`
+            #mod_visibility struct Args<'a> {
+                #(#mod_visibility #names: &'a #types,)*
+            }
+
+            #setup_when_docs
+            #mod_visibility fn setup_when(predicate: fn(&Args<'_>) -> bool, return_value: impl Into<#return_type>) {
+                register();
+                STUB.with(|stub| { stub #borrow_mut .setup_when(Box::new(predicate), return_value.into()) })
+            }
+
+            /// Returns the value of the first matching condition of `setup_when()`,
+            /// else the value returned for every call, or None to run the real implementation.
+            #[doc(hidden)]
+            #[inline]
+            #mod_visibility fn get_return_value_when(args: &Args<'_>) -> Option<#return_type> {
+                STUB.with(|stub| {
+                    stub #borrow .get_return_value_when(|predicate: &fn(&Args<'_>) -> bool| predicate(args))
+                })
+            }
+        }
+    });
+
+    // Only generate the caller setup if the stubbed function tracks its caller
+    let caller_setups = track_caller.then(|| {
+        // Functions without `Args` pass no arguments, so only the caller conditions can match
+        let (args_param, matches) = match args_fields {
+            Some(_) => (
+                quote! { , args: &Args<'_> },
+                quote! { |predicate: &fn(&Args<'_>) -> bool| predicate(args) },
+            ),
+            None => (quote! {}, quote! { |_: &()| false }),
+        };
+
+        quote! {
+            #setup_for_caller_docs
+            #mod_visibility fn setup_for_caller(path: &'static str, return_value: impl Into<#return_type>) {
+                register();
+                STUB.with(|stub| { stub #borrow_mut .setup_for_caller(path, return_value.into()) })
+            }
+
+            /// Returns the value of the first condition matching the call or its caller,
+            /// else the value returned for every call, or None to run the real implementation.
+            #[doc(hidden)]
+            #[inline]
+            #mod_visibility fn get_return_value_for_caller(caller: &std::panic::Location<'_> #args_param) -> Option<#return_type> {
+                STUB.with(|stub| { stub #borrow .get_return_value_for_caller(caller, #matches) })
+            }
+        }
+    });
+
     quote! {
-        pub(crate) mod #stub_fn_name {
+        #mod_visibility mod #stub_fn_name {
             use super::*;
 
+            // Location of the stubbed function, which is appended to the panic messages of the stub
+            const DEFINITION: &str = #definition;
+
+            // The const initializer doesn't allocate or register anything,
+            // so tests that never configure the stub don't pay for it.
             thread_local! {
-                static STUB: std::cell::RefCell<fnmock::function_stub::FunctionStub<#return_type>> =
-                    std::cell::RefCell::new(fnmock::function_stub::FunctionStub::new(stringify!(#stub_fn_name)));
+                static STUB: #stub_type = const { #stub_new };
+            }
+
+            // Registers the stub for `fnmock::clear_all_mocks!()` once it is configured
+            fn register() {
+                fnmock::registry::register(module_path!(), clear, || STUB.with(|stub| stub #borrow .to_string()));
+            }
+
+            // Remembers where the stub is set up for the repeated setup check of `fnmock::strict`
+            #[track_caller]
+            fn record_setup() {
+                let location = std::panic::Location::caller();
+                STUB.with(|stub| { stub #borrow_mut .record_setup(location) })
             }
 
             #setup_docs
-            pub(crate) fn setup(return_value: #return_type) {
-                STUB.with(|stub| { stub.borrow_mut().setup(return_value) })
+            #[track_caller]
+            #mod_visibility fn setup(return_value: impl Into<#return_type>) {
+                register();
+                record_setup();
+                STUB.with(|stub| { stub #borrow_mut .setup(return_value.into()) })
+            }
+
+            #setup_from_iter_docs
+            #[track_caller]
+            #mod_visibility fn setup_from_iter<I>(values: I)
+            where
+                I: IntoIterator<Item = #return_type>,
+                I::IntoIter: 'static,
+            {
+                register();
+                record_setup();
+                STUB.with(|stub| { stub #borrow_mut .setup_from_iter(values) })
+            }
+
+            #setup_panic_docs
+            #[track_caller]
+            #mod_visibility fn setup_panic(message: &'static str) {
+                register();
+                record_setup();
+                STUB.with(|stub| { stub #borrow_mut .setup_panic(message) })
+            }
+
+            #require_docs
+            #mod_visibility fn require() {
+                register();
+                STUB.with(|stub| { stub #borrow_mut .require() })
+            }
+
+            // Called by the stubbed function before it runs the real implementation
+            #[doc(hidden)]
+            #[inline]
+            #mod_visibility fn check_real_call() {
+                STUB.with(|stub| { stub #borrow .check_real_call() })
+            }
+
+            #snapshot_docs
+            #mod_visibility fn snapshot() -> fnmock::function_stub::StubSnapshot<#return_type> {
+                STUB.with(|stub| { stub #borrow .snapshot() })
+            }
+
+            #restore_docs
+            #mod_visibility fn restore(snapshot: &fnmock::function_stub::StubSnapshot<#return_type>) {
+                register();
+                STUB.with(|stub| { stub #borrow_mut .restore(snapshot) })
             }
 
             #clear_docs
-            pub(crate) fn clear() {
-                STUB.with(|stub| { stub.borrow_mut().clear() })
+            #mod_visibility fn clear() {
+                STUB.with(|stub| { stub #borrow_mut .clear() })
             }
 
             #is_set_docs
-            pub(crate) fn is_set() -> bool {
-                STUB.with(|stub| { stub.borrow().is_set() })
+
+            #[inline]
+            #mod_visibility fn is_set() -> bool {
+                STUB.with(|stub| { stub #borrow .is_set() })
             }
 
             #get_return_value_docs
-            pub(crate) fn get_return_value() -> #return_type {
-                STUB.with(|stub| { stub.borrow().get_return_value() })
+
+            #[inline]
+            #mod_visibility fn get_return_value() -> #return_type {
+                STUB.with(|stub| { stub #borrow .get_return_value() })
             }
+
+            #conditional_setups
+
+            #caller_setups
         }
     }
 }
@@ -2,9 +2,13 @@ use quote::quote;
 use syn::__private::TokenStream2;
 use crate::function_stub::create_stub_implementation::{create_stub_function, create_stub_module};
 use crate::return_utils::extract_return_type;
+use crate::type_utils::static_lifetimes;
+use crate::export_utils::{double_cfg, double_visibility, item_cfg};
+use crate::param_utils::get_args_fields;
 
 mod create_stub_implementation;
 mod proxy_docs;
+pub(crate) mod stub_args;
 
 /// Processes a function and generates the complete stub infrastructure.
 ///
@@ -13,50 +17,79 @@ mod proxy_docs;
 /// 1. The original function with stub checking logic injected (in test mode, checks if a stub
 ///    is configured and calls it; otherwise executes the original implementation)
 /// 2. A stub module with control methods (test-only) containing `setup()`, `clear()`,
-///    `is_set()`, and `get_return_value()` functions
+///    `is_set()`, and `get_return_value()` functions, as well as `setup_when()` if the parameters can be borrowed
+///    and `setup_for_caller()` if the function tracks its caller
 ///
 /// # Arguments
 ///
 /// * `stub_function` - The function item to create stubs for
+/// * `track_caller` - Whether the function is `#[track_caller]` in test mode, so stubs can match its caller
 ///
 /// # Returns
 ///
 /// - `Ok(TokenStream2)` - The complete generated code including original and stub infrastructure
 /// - `Err(syn::Error)` - If validation fails or the function cannot be stubbed
-pub(crate) fn process_stub_function(stub_function: syn::ItemFn) -> syn::Result<TokenStream2> {
-    // Extract function details
-    let fn_visibility = stub_function.vis.clone();
-    let fn_asyncness = stub_function.sig.asyncness;
-    let fn_name = stub_function.sig.ident.clone();
-    let fn_inputs = stub_function.sig.inputs.clone();
-    let fn_output = stub_function.sig.output.clone();
-    let fn_block = stub_function.block.clone();
+pub(crate) fn process_stub_function(stub_function: syn::ItemFn, track_caller: bool) -> syn::Result<TokenStream2> {
+    // `#[track_caller]` isn't supported on async functions
+    if track_caller && stub_function.sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            stub_function.sig.asyncness,
+            "#[stub_function(track_caller)] can't be used on async functions",
+        ));
+    }
+
+    // Extract function details, moving them out of the item instead of cloning them
+    let item_cfg = item_cfg(&stub_function.attrs);
+    let syn::ItemFn { attrs: fn_attrs, vis: fn_visibility, sig, block: fn_block } = stub_function;
+    let fn_asyncness = sig.asyncness;
+    let fn_unsafety = sig.unsafety;
+    let fn_abi = sig.abi;
+    let fn_name = sig.ident;
+    let fn_inputs = sig.inputs;
+    let fn_output = sig.output;
 
     // Generate stub module name
     let stub_mod_name = syn::Ident::new(&format!("{}_stub", &fn_name), fn_name.span());
+    let stub_mod_visibility = double_visibility(&fn_visibility);
+
+    // A returned reference is stored as a 'static one, see `static_lifetimes`
+    let return_type = static_lifetimes(&extract_return_type(&fn_output));
 
-    let return_type = extract_return_type(&stub_function.sig.output);
+    // Only functions whose parameters can be borrowed by an `Args` struct support `setup_when`
+    let args_fields = get_args_fields(&fn_inputs);
 
     let stub_function = create_stub_function(
         fn_name,
         fn_visibility,
         fn_asyncness,
-        fn_inputs,
+        fn_unsafety,
+        fn_abi,
+        &fn_inputs,
         fn_output,
-        fn_block,
+        &fn_block,
         stub_mod_name.clone(),
+        args_fields.as_deref(),
+        track_caller,
     );
 
     let stub_module = create_stub_module(
         stub_mod_name,
-        return_type
+        stub_mod_visibility,
+        return_type,
+        args_fields.as_deref(),
+        track_caller,
     );
 
+    let stub_cfg = double_cfg();
+
     // Generate the original function and the stub module
     Ok(quote! {
+        // The original attributes include the cfg attributes
+        #(#fn_attrs)*
         #stub_function
 
-        #[cfg(test)]
+        #item_cfg
+        #stub_cfg
         #stub_module
     })
 }
@@ -1,4 +1,4 @@
-/// Generates documentation strings for stub proxy functions based on actual return type.
+//! Generates documentation strings for stub proxy functions based on actual return type.
 
 use quote::quote;
 
@@ -8,6 +8,12 @@ use quote::quote;
 pub(crate) struct StubProxyDocs {
     return_type_str: String,
     setup_example: String,
+    setup_from_iter_example: String,
+    setup_panic_example: String,
+    snapshot_example: String,
+    setup_when_example: String,
+    setup_for_caller_example: String,
+    require_example: String,
 }
 
 impl StubProxyDocs {
@@ -23,10 +29,22 @@ impl StubProxyDocs {
     ) -> Self {
         let return_type_str = quote::quote!(#return_type).to_string();
         let setup_example = format!("{}::setup(/* value of type {} */);", stub_fn_name, return_type_str);
+        let setup_from_iter_example = format!("{}::setup_from_iter(/* values of type {} */);", stub_fn_name, return_type_str);
+        let setup_panic_example = format!("{}::setup_panic(\"db connection lost\");", stub_fn_name);
+        let snapshot_example = format!("let snapshot = {0}::snapshot();\n// ...\n{0}::restore(&snapshot);", stub_fn_name);
+        let setup_when_example = format!("{}::setup_when(|args| /* condition on the fields of args */, /* value of type {} */);", stub_fn_name, return_type_str);
+        let setup_for_caller_example = format!("{}::setup_for_caller(\"src/handlers/user.rs\", /* value of type {} */);", stub_fn_name, return_type_str);
+        let require_example = format!("{}::require();", stub_fn_name);
         
         Self {
             return_type_str,
             setup_example,
+            setup_from_iter_example,
+            setup_panic_example,
+            snapshot_example,
+            setup_when_example,
+            setup_for_caller_example,
+            require_example,
         }
     }
 
@@ -40,6 +58,8 @@ impl StubProxyDocs {
             #[doc = ""]
             #[doc = "Configures the value that will be returned every time the stub function is called."]
             #[doc = "The value must implement `Clone` since it may be returned multiple times."]
+            #[doc = "Any value that can be converted into the return type with `Into` is accepted,"]
+            #[doc = "e.g. a `&str` for a `String` return type."]
             #[doc = ""]
             #[doc = "# Returns"]
             #[doc = ""]
@@ -53,6 +73,94 @@ impl StubProxyDocs {
         }
     }
 
+    /// Generates documentation attributes for the `setup_from_iter` function.
+    pub(crate) fn setup_from_iter_docs(&self) -> proc_macro2::TokenStream {
+        let setup_from_iter_example = &self.setup_from_iter_example;
+
+        quote! {
+            #[doc = "Sets up the stub to return the next value of the iterator on every call."]
+            #[doc = ""]
+            #[doc = "Stubs sequences like ID generators without a mock capturing state, e.g. with `1..`."]
+            #[doc = "The values don't need to implement `Clone`."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_from_iter_example]
+            #[doc = "```"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "The stub function panics once the iterator is exhausted"]
+        }
+    }
+
+    /// Generates documentation attributes for the `setup_panic` function.
+    pub(crate) fn setup_panic_docs(&self) -> proc_macro2::TokenStream {
+        let setup_panic_example = &self.setup_panic_example;
+
+        quote! {
+            #[doc = "Makes the stub panic with the message on every call."]
+            #[doc = ""]
+            #[doc = "Simulates a crashing dependency, e.g. to test `catch_unwind` or supervisor logic."]
+            #[doc = "Calling `setup()` replaces the panic with a return value again."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_panic_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `require` function.
+    pub(crate) fn require_docs(&self) -> proc_macro2::TokenStream {
+        let require_example = &self.require_example;
+
+        quote! {
+            #[doc = "Declares the stub required, so the real implementation of the function panics when it runs."]
+            #[doc = ""]
+            #[doc = "Catches calls silently falling through to real I/O, e.g. calls not matching any `setup_when()` condition."]
+            #[doc = "The requirement holds until the stub is cleared."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #require_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `snapshot` function.
+    pub(crate) fn snapshot_docs(&self) -> proc_macro2::TokenStream {
+        let snapshot_example = &self.snapshot_example;
+
+        quote! {
+            #[doc = "Captures the configuration of the stub, so it can be restored with `restore()` later on."]
+            #[doc = ""]
+            #[doc = "Lets a complex arrange phase be captured once and restored between multiple phases of a test."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #snapshot_example]
+            #[doc = "```"]
+            #[doc = ""]
+            #[doc = "# Panics"]
+            #[doc = ""]
+            #[doc = "Panics if the stub is set up with `setup_from_iter()`, whose iterator can't be copied"]
+        }
+    }
+
+    /// Generates documentation attributes for the `restore` function.
+    pub(crate) fn restore_docs(&self) -> proc_macro2::TokenStream {
+        quote! {
+            #[doc = "Replaces the configuration of the stub with the snapshot taken by `snapshot()`."]
+            #[doc = ""]
+            #[doc = "The snapshot can be restored any number of times."]
+        }
+    }
+
     /// Generates documentation attributes for the `clear` function.
     pub(crate) fn clear_docs(&self) -> proc_macro2::TokenStream {
         quote! {
@@ -97,4 +205,44 @@ impl StubProxyDocs {
             #[doc = "Panics if `setup()` has not been called before calling the stub function"]
         }
     }
+
+    /// Generates documentation attributes for the `setup_when` function.
+    pub(crate) fn setup_when_docs(&self) -> proc_macro2::TokenStream {
+        let setup_when_example = &self.setup_when_example;
+
+        quote! {
+            #[doc = "Sets up the stub to return the value for the calls matching the predicate."]
+            #[doc = ""]
+            #[doc = "The predicate receives the `Args` of the call, which borrow the arguments by name."]
+            #[doc = "The conditions are checked in the order they were set up. The other calls return the value"]
+            #[doc = "of `setup()` / `setup_from_iter()` / `setup_panic()` if set up, else they run the real implementation."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_when_example]
+            #[doc = "```"]
+        }
+    }
+
+    /// Generates documentation attributes for the `setup_for_caller` function.
+    pub(crate) fn setup_for_caller_docs(&self) -> proc_macro2::TokenStream {
+        let setup_for_caller_example = &self.setup_for_caller_example;
+
+        quote! {
+            #[doc = "Sets up the stub to return the value for the calls from the source files at the path."]
+            #[doc = ""]
+            #[doc = "A path ending with `/` matches the files in the directory, other paths match a single file."]
+            #[doc = "The paths are compared to the end of the caller's file, e.g. `src/handlers/user.rs`."]
+            #[doc = "The conditions are checked with the ones of `setup_when()` in the order they were set up."]
+            #[doc = "The other calls return the value of `setup()` / `setup_from_iter()` / `setup_panic()`"]
+            #[doc = "if set up, else they run the real implementation."]
+            #[doc = ""]
+            #[doc = "# Examples"]
+            #[doc = ""]
+            #[doc = "```ignore"]
+            #[doc = #setup_for_caller_example]
+            #[doc = "```"]
+        }
+    }
 }
@@ -0,0 +1,28 @@
+use syn::parse::{Parse, ParseStream};
+use syn::Token;
+
+/// Structure to parse the stub_function attribute arguments
+pub(crate) struct StubFunctionArgs {
+    pub(crate) track_caller: bool,
+}
+
+impl Parse for StubFunctionArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut track_caller = false;
+
+        // Parse "track_caller" syntax
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            if key == "track_caller" {
+                track_caller = true;
+            }
+
+            // Allow trailing comma or end of input
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(StubFunctionArgs { track_caller })
+    }
+}
@@ -2,20 +2,43 @@ use proc_macro::TokenStream;
 use syn::{parse_macro_input};
 
 mod param_utils;
+mod crate_scanner;
+#[allow(dead_code)]
 mod use_tree_processor;
+#[allow(dead_code)]
 mod use_statement_processor;
+#[allow(dead_code)]
 mod inline_processor;
 mod function_mock;
 mod function_fake;
 mod function_stub;
+mod trait_mock;
+mod struct_mock;
+mod method_mock;
+mod mock_module;
+mod test_function;
 mod return_utils;
+mod export_utils;
+mod doc_utils;
+mod naming_utils;
+mod type_utils;
+mod debug_utils;
+#[cfg(test)]
+mod expansion_bench;
 
-use crate::function_mock::{process_mock_function};
+use crate::function_mock::{process_mock_foreign_mod, process_mock_function};
 use crate::function_fake::{process_fake_function};
 use crate::function_mock::mock_args::MockFunctionArgs;
 use crate::function_stub::{process_stub_function};
-use crate::inline_processor::process_inline;
-use crate::use_statement_processor::process_use_statement;
+use crate::function_stub::stub_args::StubFunctionArgs;
+use crate::mock_module::process_mock_module;
+use crate::trait_mock::process_mock_trait;
+use crate::struct_mock::process_mock_struct;
+use crate::struct_mock::struct_args::MockStructArgs;
+use crate::method_mock::{parse_method_names, process_mock_method};
+use crate::test_function::process_test_function;
+use crate::crate_scanner::process_mocks_in_crate;
+use crate::debug_utils::dump_expansion;
 
 /// Attribute macro that generates a mockable version of a function.
 ///
@@ -26,11 +49,53 @@ use crate::use_statement_processor::process_use_statement;
 ///
 /// # Generated Mock Module Methods
 ///
-/// - `setup(fn)` - Sets a custom implementation for the mock
+/// - `setup(fn)` - Sets a custom implementation for the mock and returns a `MockHandle` for later assertions
+/// - `setup_ref(fn)` - Like `setup`, but the implementation borrows the parameters instead of receiving a clone
+/// - `setup_returning(value)` - Sets the mock to return a clone of the value on every call
+/// - `returning_indexed(fn)` - Like `setup`, but the implementation also receives the zero-based index of the call
+/// - `setup_iter(items)` - Returns a new iterator over the items on every call
+///   (only generated if the function returns `impl Iterator<Item = T>`, which is boxed in test builds)
+/// - `setup_batch(f)` - Computes the result of every item of a `Vec<T>` param separately
+///   (only generated if the function takes a single `Vec<T>` parameter and returns a `Vec<R>`)
+/// - `setup_ok(value)` / `setup_err(error)` - Shorthands for `setup_returning(Ok(value))` / `setup_returning(Err(error))`
+///   (only generated if the function returns a `Result<T, E>`)
+/// - `configure(|m| ...)` - Configures the implementation and expectations (e.g. `m.expect_times(n)`) in one closure
+/// - `assert_times_msg(n, msg)` / `assert_with_msg(params, msg)` - Like `assert_times` / `assert_with_tuple`,
+///   but a failure is labeled with the message, e.g. to tell which of several identical assertions failed
+/// - `verify()` - Verifies the expectations set with `configure`
+/// - `checkpoint()` - Verifies the expectations and discards them, starting a new phase of the test
+/// - `with(fn, body)` - Sets a custom implementation for the duration of `body` and clears the mock afterward
 /// - `clear()` - Resets the mock to its uninitialized state
+/// - `clear_implementation()` / `clear_history()` - Resets only the implementation / only the call history
+/// - `assert_with_matchers(matchers)` - Asserts a call with parameters matching the `fnmock::matchers`
+/// - `assert_times_in_context(id, n)` - Asserts the number of calls made inside `fnmock::with_context(id, ..)`
+/// - `assert_item_passed(item)` - Asserts that the item was passed in the `Vec<T>` param of any call
+/// - `assert_calls_exact(&[..])` - Asserts the complete, ordered call history with a diff on failure
+/// - `verify_no_more_interactions()` - Asserts that every call was covered by `assert_with*()` or `assert_calls_exact()`
+/// - `setup_panic(message)` - Makes every call panic with the message after recording it
+/// - `setup_pending()` - Makes every call return a never completing future (only generated for async functions)
+/// - `with_latency(duration)` / `with_jitter(range)` - Delays the results of the calls (only generated for async functions)
+/// - `yield_before_returning()` / `was_cancelled()` / `cancelled_calls()` - Makes every call yield once before returning
+///   and checks if it was dropped there (only generated for async functions)
+/// - `forbid()` - Makes every call panic immediately with the parameters it was called with
+/// - `set_call_budget(n)` - Makes every call after the first `n` calls panic immediately until the mock is cleared
+/// - `require()` - Makes a call of the real implementation panic until the mock is cleared
+/// - `add_precondition(check)` / `add_postcondition(check)` - Checks the parameters (and the result) of every call,
+///   panicking with the location of the violated check and the call data
+/// - `dedup_consecutive_calls()` - Records identical consecutive calls once with a count until the mock is cleared
+/// - `index_calls()` - Maintains a hash index of the recorded calls for O(1) `assert_with` (requires `Hash` params)
+/// - `record_timestamps()` - Records the time of every call for `call_timestamps()` / `durations_between_calls()`
+/// - `assert_called_at_most_per(n, duration)` - Asserts that no time window of the duration contains more than `n` calls
+/// - `snapshot()` / `restore(&snapshot)` - Captures the configuration and restores it later on, clearing the call history
+/// - `export_state()` / `import_state(state)` - Copies the configuration and expectations of the mock to another thread
 /// - `is_set()` - Checks if the mock has been configured
 /// - `assert_times(n)` - Verifies the function was called exactly n times
 /// - `assert_with(params)` - Verifies the function was called with specific parameters
+/// - `assert_with_tuple(params)` - Same as `assert_with`, but takes the parameters as a tuple
+/// - `assert_with_ref(&params)` - Same as `assert_with_tuple`, but borrows the parameters
+/// - `was_called()` / `was_called_with(params)` - Non-panicking checks of the call history
+/// - `format_calls()` / `print_calls()` - Renders the call history as an aligned table
+/// - `calls()` - Returns a copy of the call history
 ///
 /// # Ignoring of parameters
 ///
@@ -45,11 +110,190 @@ use crate::use_statement_processor::process_use_statement;
 /// }
 /// ```
 ///
+/// Function pointer parameters (`fn(..)`) don't need to be ignored, they are recorded as `fnmock::fn_ptr::FnPtr`,
+/// which is compared by identity and printed with the name given by `fnmock::named_fn!`.
+///
+/// `&str` parameters are recorded as `String`, and the assertions accept a `&str` or a `String`.
+/// Explicitly 'static references like `&'static str` are recorded as they are.
+/// With `own_refs` other reference parameters like `&[T]` don't need to be ignored either,
+/// they are recorded as the owned values created by `ToOwned` (`Vec<T>`):
+///
+/// ```ignore
+/// #[mock_function(own_refs)]
+/// pub(crate) fn find_users(ids: &[u32]) -> Vec<String> {
+///     Vec::new()
+/// }
+///
+/// find_users_mock::setup(|ids: Vec<u32>| ids.iter().map(|id| format!("user_{}", id)).collect());
+/// ```
+///
+/// # Groups
+///
+/// Mocks can be assigned to a named group, which `fnmock::group("db").clear()` / `.verify_all()`
+/// operate on together:
+///
+/// ```ignore
+/// #[mock_function(group = "db")]
+/// pub(crate) fn fetch_user(id: u32) -> Result<String, String> {
+///     Ok(format!("user_{}", id))
+/// }
+/// ```
+///
+/// # Returning closures
+///
+/// Functions returning `impl Fn(..)` / `impl FnMut(..)` / `impl FnOnce(..)` return a boxed closure in test builds,
+/// like functions returning `impl Iterator`. The implementation of the mock is a factory building a new closure per call:
+///
+/// ```ignore
+/// name_validator_mock::setup(|min_len| Box::new(move |name| name.len() > min_len));
+/// ```
+///
+/// Any other `impl Trait` return type is boxed as `Box<dyn Trait>` as well, which has to implement the trait,
+/// and `impl Future` as `Pin<Box<dyn Future>>`:
+///
+/// ```ignore
+/// user_badge_mock::setup(|_| Box::new("admin"));
+/// fetch_avatar_mock::setup(|_| Box::pin(async { Vec::new() }));
+/// ```
+///
+/// # Mocks overriding fakes
+///
+/// With `fake`, a fake module (`fetch_user_fake`) is generated next to the mock module. A call uses the mock
+/// if it is configured, else the fake, else the real implementation. The order of the mock and the fake
+/// is configured with `fnmock::resolution::set_order`:
+///
+/// ```ignore
+/// #[mock_function(fake)]
+/// pub(crate) fn fetch_user(id: u32) -> Result<String, String> {
+///     Ok(format!("user_{}", id))
+/// }
+/// ```
+///
+/// # Type-state assertions
+///
+/// With `type_state`, the assertions (`assert_*()`, `verify()` and `verify_no_more_interactions()`) are private
+/// and only available on the `MockHandle` returned by the `setup*()` functions, which can't be created otherwise.
+/// The functions that don't set up an implementation, like `configure()`, `require()` and `restore()`, return nothing.
+/// Asserting on a mock that wasn't set up in the test is a compile error instead of a misleading result:
+///
+/// ```ignore
+/// #[mock_function(type_state)]
+/// pub(crate) fn fetch_user(id: u32) -> Result<String, String> {
+///     Ok(format!("user_{}", id))
+/// }
+///
+/// let fetch_user = fetch_user_mock::setup_ok("alice".to_string());
+/// // ...
+/// fetch_user.assert_with(1);
+/// ```
+///
+/// # In-place interception
+///
+/// The interception is injected into the original function, so the call sites keep calling
+/// `fetch_user` and need no rewriting, while the calls are recorded by the full mock.
+/// `in_place` states that explicitly and can be combined with the other arguments:
+///
+/// ```ignore
+/// #[mock_function(in_place, group = "db")]
+/// pub(crate) fn fetch_user(id: u32) -> Result<String, String> {
+///     Ok(format!("user_{}", id))
+/// }
+/// ```
+///
+/// # Conditional compilation
+///
+/// The `#[cfg(..)]` attributes of the function are carried over to the generated modules,
+/// so a function behind a feature is only mocked if the feature is enabled:
+///
+/// ```ignore
+/// #[mock_function]
+/// #[cfg(feature = "postgres")]
+/// pub fn database_url() -> String {
+///     "postgres://localhost/users".to_string()
+/// }
+/// ```
+///
+/// The other attributes of the function, like doc comments, `#[inline]` or `#[must_use]`, are kept on the function.
+/// An `unsafe fn` stays unsafe, `di` and `instantiate` don't support unsafe functions.
+///
+/// # Foreign functions
+///
+/// On an `extern` block, the macro mocks every declared function. In test builds the block is replaced
+/// by `unsafe` functions with the ABI of the block, which call `<function_name>_mock` and panic if it isn't configured,
+/// so the tests don't link the foreign library. Functions defined in Rust keep their ABI, e.g. `extern "C"`.
+///
+/// ```ignore
+/// #[mock_function]
+/// extern "C" {
+///     pub fn abs(value: i32) -> i32;
+/// }
+/// ```
+///
+/// # Dependency injection
+///
+/// With `di` the function isn't intercepted. Instead, a trait named after the function (`FetchUser`),
+/// a struct implementing it with an injectable function pointer (`FetchUserFn`, defaulting to the function)
+/// and a test double implementing it with the mock module (`MockFetchUser`) are generated:
+///
+/// ```ignore
+/// #[mock_function(di)]
+/// pub fn fetch_user(id: u32) -> Result<String, String> {
+///     Ok(format!("user_{}", id))
+/// }
+///
+/// fn greet(users: &impl FetchUser) -> String {
+///     users.fetch_user(1).unwrap_or_default()
+/// }
+///
+/// #[test]
+/// fn test_greet() {
+///     fetch_user_mock::setup(|_| Ok("alice".to_string()));
+///     assert_eq!(greet(&MockFetchUser), "alice");
+/// }
+/// ```
+///
+/// # Generic functions
+///
+/// `instantiate(..)` generates a mock module per listed instantiation of a generic function, named after
+/// the concrete types (`parse_u32_mock`, `parse_string_mock`). Calls of other instantiations run the real implementation:
+///
+/// ```ignore
+/// #[mock_function(instantiate(T = u32, T = String))]
+/// pub fn parse<T: FromStr>(raw: String) -> Option<T> {
+///     raw.parse().ok()
+/// }
+///
+/// #[test]
+/// fn test_parse() {
+///     parse_u32_mock::setup(|_| Some(42));
+///     assert_eq!(parse::<u32>("1".to_string()), Some(42));
+/// }
+/// ```
+///
+/// # Associated functions
+///
+/// A module can't be generated inside an impl block, so associated functions like constructors are mocked
+/// by `#[mock_struct]` on the impl block. If some of its functions are marked with `#[mock_function]`,
+/// only those are mocked, with the arguments of their attribute, in `<type_name>_mock::<function_name>`:
+///
+/// ```ignore
+/// #[mock_struct]
+/// impl UserService {
+///     #[mock_function(ignore = [timeout])]
+///     pub fn connect(url: String, timeout: Duration) -> Result<Self, String> {
+///         // Real implementation
+///     }
+/// }
+///
+/// user_service_mock::connect::setup(|url| Err(format!("{} unreachable", url)));
+/// ```
+///
 /// # Requirements
 ///
-/// - Function must not have `self` parameters (standalone functions only)
+/// - Function must not have `self` parameters (standalone functions only, see `#[mock_struct]` for methods)
 /// - Not ignored function parameters must implement `Clone`, `Debug`, and `PartialEq` (for assertions)
-/// - Not ignored function parameters must be `'static` (no references allowed - use owned types like `String` instead of `&str`)
+/// - Not ignored function parameters must be `'static` (no references other than `&str` and `&'static` references allowed - use owned types like `Vec<T>` instead of `&[T]`, or `own_refs`)
+/// - Returned references are stored as `'static` references, e.g. `&'static str` for `-> &str`
 ///
 /// # Example
 ///
@@ -99,15 +343,37 @@ use crate::use_statement_processor::process_use_statement;
 /// but not protected within a single test that uses multiple threads.
 #[proc_macro_attribute]
 pub fn mock_function(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as syn::ItemFn);
+    let input = parse_macro_input!(item as syn::Item);
     let args = if attr.is_empty() {
-        MockFunctionArgs { ignore: Vec::new() }
+        MockFunctionArgs::default()
     } else {
         parse_macro_input!(attr as MockFunctionArgs)
     };
 
-    match process_mock_function(input, args.ignore) {
-        Ok(expanded) => TokenStream::from(expanded),
+    let input = match input {
+        syn::Item::Fn(input) => input,
+        syn::Item::ForeignMod(foreign_mod) => {
+            return match process_mock_foreign_mod(foreign_mod, args) {
+                Ok(expanded) => {
+                    dump_expansion("mock_function", &syn::Ident::new("extern_block", proc_macro2::Span::call_site()), &expanded);
+                    TokenStream::from(expanded)
+                }
+                Err(e) => e.to_compile_error().into(),
+            };
+        }
+        input => {
+            return syn::Error::new_spanned(input, "mock_function only supports functions and `extern` blocks")
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let fn_name = input.sig.ident.clone();
+    match process_mock_function(input, args.ignore, args.group, args.fake, args.type_state, args.di, args.own_refs, args.instantiations) {
+        Ok(expanded) => {
+            dump_expansion("mock_function", &fn_name, &expanded);
+            TokenStream::from(expanded)
+        }
         Err(e) => e.to_compile_error().into(),
     }
 }
@@ -177,8 +443,12 @@ pub fn mock_function(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn fake_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as syn::ItemFn);
 
+    let fn_name = input.sig.ident.clone();
     match process_fake_function(input) {
-        Ok(expanded) => TokenStream::from(expanded),
+        Ok(expanded) => {
+            dump_expansion("fake_function", &fn_name, &expanded);
+            TokenStream::from(expanded)
+        }
         Err(e) => e.to_compile_error().into(),
     }
 }
@@ -192,11 +462,25 @@ pub fn fake_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// # Generated Stub Module Methods
 ///
-/// - `setup(return_value)` - Sets the predetermined return value for the stub
+/// - `setup(return_value)` - Sets the predetermined return value for the stub (accepts any `impl Into<ReturnType>`)
+/// - `setup_from_iter(values)` - Returns the next value of the iterator on every call
+/// - `setup_panic(message)` - Makes every call to the stub panic with the message
+/// - `setup_when(predicate, return_value)` - Returns the value for the calls matching the predicate over the `Args` of the call,
+///   the other calls run the real implementation (only generated if the parameters are named and don't contain references)
+/// - `setup_for_caller(path, return_value)` - Returns the value for the calls from the source files at the path,
+///   e.g. `"src/handlers/user.rs"` (only generated with `#[stub_function(track_caller)]`)
+/// - `snapshot()` / `restore(&snapshot)` - Captures the return value and restores it later on
+/// - `require()` - Makes a call of the real implementation panic until the stub is cleared
 /// - `clear()` - Resets the stub to its uninitialized state
 /// - `is_set()` - Checks if the stub has been configured
 /// - `get_return_value()` - Gets the current stubbed return value
 ///
+/// # Caller-aware stubs
+///
+/// `#[stub_function(track_caller)]` makes the function `#[track_caller]` in test builds, so the stub
+/// sees the location it is called from and `setup_for_caller` can restrict it to some code paths.
+/// It isn't supported on async functions.
+///
 /// # Difference from Mocks and Fakes
 ///
 /// Stubs - in contrast to mocks and fakes - provide canned responses without behavior verification or custom logic.
@@ -210,6 +494,7 @@ pub fn fake_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// - Function must not have `self` parameters (standalone functions only)
 /// - Return type must implement `Clone` (since the stub may be called multiple times)
+/// - Returned references are stored as `'static` references, e.g. `&'static str` for `-> &str`
 ///
 /// # Example
 ///
@@ -248,10 +533,346 @@ pub fn fake_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// between tests but **not thread-safe** if the same function is stubbed in parallel
 /// test threads.
 #[proc_macro_attribute]
-pub fn stub_function(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn stub_function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::ItemFn);
+    let args = parse_macro_input!(attr as StubFunctionArgs);
+
+    let fn_name = input.sig.ident.clone();
+    match process_stub_function(input, args.track_caller) {
+        Ok(expanded) => {
+            dump_expansion("stub_function", &fn_name, &expanded);
+            TokenStream::from(expanded)
+        }
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Attribute macro that generates a mock implementation of a trait.
+///
+/// The trait stays unchanged. In test mode the macro generates:
+/// 1. A `Mock<TraitName>` unit struct implementing the trait, which can be injected wherever
+///    a `dyn Trait` or a generic bound is used
+/// 2. A `<trait_name>_mock` module containing a mock module per method, with the same control
+///    and assertion methods as the modules generated by `#[mock_function]`, and a `clear()` function
+///    clearing the mocks of all methods
+///
+/// The mocks are thread-local, so all instances of the mock struct share them.
+///
+/// # Async methods
+///
+/// `async fn` methods are mocked like async functions. `#[async_trait]` is supported as well,
+/// if it is placed below `#[mock_trait]`, so the mock implementation is rewritten the same way.
+/// Placed above, it desugars the methods before `#[mock_trait]` sees them, which is rejected with an error.
+///
+/// # Requirements
+///
+/// - The trait and its methods must not be generic, and the trait must only contain methods
+/// - Every method must take `self` and must not return `impl Trait`
+/// - Method parameters must implement `Clone`, `Debug`, and `PartialEq` and must be `'static`
+///
+/// # Example
+///
+/// ```ignore
+/// use fnmock::derive::mock_trait;
+///
+/// #[mock_trait]
+/// pub trait UserRepository {
+///     fn find_user(&self, id: u32) -> Option<String>;
+/// }
+///
+/// fn greet(repository: &dyn UserRepository, id: u32) -> String {
+///     format!("Hello, {}!", repository.find_user(id).unwrap_or_default())
+/// }
+///
+/// #[test]
+/// fn test_greet() {
+///     user_repository_mock::find_user::setup(|_| Some("alice".to_string()));
+///
+///     assert_eq!(greet(&MockUserRepository, 1), "Hello, alice!");
+///     user_repository_mock::find_user::assert_with(1);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn mock_trait(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::ItemTrait);
+
+    let trait_name = input.ident.clone();
+    match process_mock_trait(input) {
+        Ok(expanded) => {
+            dump_expansion("mock_trait", &trait_name, &expanded);
+            TokenStream::from(expanded)
+        }
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Attribute macro that generates mocks for the methods of an inherent impl block.
+///
+/// This macro modifies every method of the impl block to check (in test mode) if a mock implementation
+/// has been configured and generates:
+/// 1. The methods with injected mock checking logic (calls the mock if set, otherwise executes normally)
+/// 2. A `<type_name>_mock` module containing a mock module per method, with the same control
+///    and assertion methods as the modules generated by `#[mock_function]`, and a `clear()` function
+///    clearing the mocks of all methods
+///
+/// The receiver (`self`, `&self` or `&mut self`) isn't recorded, so the mock of a method is shared
+/// by all instances of the type. Associated functions without `self`, e.g. constructors, are mocked as well.
+/// `Self` in the parameters and the return type is replaced by the type in the mock modules.
+///
+/// # Skipping methods
+///
+/// Methods that can't be mocked can be left unchanged with `skip`:
+///
+/// ```ignore
+/// #[mock_struct(skip = [parse_name])]
+/// impl UserService {
+///     fn parse_name(raw: &str) -> &str {
+///         raw.trim()
+///     }
+/// }
+/// ```
+///
+/// # Selecting methods
+///
+/// If some methods are marked with `#[mock_function]`, only those are mocked. The arguments `ignore`, `group`, `own_refs`
+/// and `type_state` of the attribute apply to the mock of the method. The attribute is consumed by `#[mock_struct]`,
+/// so it doesn't have to be imported:
+///
+/// ```ignore
+/// #[mock_struct]
+/// impl UserService {
+///     #[mock_function(group = "db")]
+///     pub fn connect(url: String) -> Result<Self, String> {
+///         // Real implementation
+///     }
+///
+///     pub fn url(&self) -> &str {
+///         &self.url
+///     }
+/// }
+/// ```
+///
+/// # Requirements
+///
+/// - The impl block must be an inherent impl of a non-generic type (use `#[mock_trait]` for traits)
+/// - Not skipped methods must not be generic, `const` or `unsafe` and must not return `impl Trait`
+/// - The parameters of not skipped methods must implement `Clone`, `Debug`, and `PartialEq` and must be `'static`
+///
+/// # Example
+///
+/// ```ignore
+/// use fnmock::derive::mock_struct;
+///
+/// pub struct UserService {
+///     url: String,
+/// }
+///
+/// #[mock_struct]
+/// impl UserService {
+///     pub fn find_user(&self, id: u32) -> Option<String> {
+///         // Real implementation
+///         None
+///     }
+/// }
+///
+/// #[test]
+/// fn test_with_struct_mock() {
+///     user_service_mock::find_user::setup(|_| Some("alice".to_string()));
+///
+///     let service = UserService { url: "test".to_string() };
+///     assert_eq!(service.find_user(1), Some("alice".to_string()));
+///     user_service_mock::find_user::assert_with(1);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn mock_struct(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::ItemImpl);
+    let args = parse_macro_input!(attr as MockStructArgs);
+
+    let type_name = match &*input.self_ty {
+        syn::Type::Path(type_path) => type_path.path.segments.last().map(|segment| segment.ident.clone()),
+        _ => None,
+    };
+    match process_mock_struct(input, args.skip) {
+        Ok(expanded) => {
+            if let Some(type_name) = type_name {
+                dump_expansion("mock_struct", &type_name, &expanded);
+            }
+            TokenStream::from(expanded)
+        }
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Attribute macro that generates mockable shims for single methods of an inherent impl block.
+///
+/// The impl block stays unchanged, so the macro is an alternative to `#[mock_struct]` if only a few
+/// call sites of a method have to be mocked. For every method named in the attribute it generates:
+/// 1. An extension trait named after the method (`FindUserShim`) with a `<method>_shim` method implemented for the type,
+///    which calls the mock (in test mode, if it is configured) or the original method
+/// 2. A mock module per method in `<type_name>_mock`, with the same control and assertion methods as the modules
+///    generated by `#[mock_function]`, and a `clear()` function clearing the mocks of all shimmed methods
+///
+/// The call sites that should be mockable call the shim instead of the method.
+///
+/// # Requirements
+///
+/// - The impl block must be an inherent impl of a non-generic type
+/// - The named methods must take `self`, must not be generic, `const` or `unsafe` and must not return `impl Trait`
+/// - The parameters of the named methods must implement `Clone`, `Debug`, and `PartialEq` and must be `'static`
+///
+/// # Example
+///
+/// ```ignore
+/// use fnmock::derive::mock_method;
+///
+/// #[mock_method(find_user)]
+/// impl UserService {
+///     pub fn find_user(&self, id: u32) -> Option<String> {
+///         // Real implementation
+///         None
+///     }
+/// }
+///
+/// fn greet(service: &UserService, id: u32) -> String {
+///     format!("Hello, {}!", service.find_user_shim(id).unwrap_or_default())
+/// }
+///
+/// #[test]
+/// fn test_greet() {
+///     user_service_mock::find_user::setup(|_| Some("alice".to_string()));
+///
+///     assert_eq!(greet(&UserService::default(), 1), "Hello, alice!");
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn mock_method(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::ItemImpl);
+
+    let type_name = match &*input.self_ty {
+        syn::Type::Path(type_path) => type_path.path.segments.last().map(|segment| segment.ident.clone()),
+        _ => None,
+    };
+    match parse_method_names(attr.into()).and_then(|method_names| process_mock_method(input, method_names)) {
+        Ok(expanded) => {
+            if let Some(type_name) = type_name {
+                dump_expansion("mock_method", &type_name, &expanded);
+            }
+            TokenStream::from(expanded)
+        }
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Attribute macro that adds a `mocks` submodule aggregating the doubles of a module.
+///
+/// The generated `mocks` module contains lifecycle functions scoped to the doubles
+/// defined directly in the annotated module:
+///
+/// - `mocks::clear_all()` - Clears all mocks, fakes and stubs of the module
+/// - `mocks::verify_all()` - Verifies the expectations of all mocks of the module, like calling `verify()` on each
+///
+/// Like `fnmock::clear_all_mocks!()`, the functions only affect the doubles configured on the current thread.
+///
+/// # Example
+///
+/// ```ignore
+/// use fnmock::derive::{mock_function, mock_module};
+///
+/// #[mock_module]
+/// pub(crate) mod db {
+///     use super::*;
+///
+///     #[mock_function]
+///     pub(crate) fn fetch_user(id: u32) -> Result<String, String> {
+///         Ok(format!("user_{}", id))
+///     }
+/// }
+///
+/// #[test]
+/// fn test_with_module_mocks() {
+///     db::fetch_user_mock::configure(|m| { m.ok("mock user".to_string()).expect_times(1); });
+///
+///     // ...
+///
+///     db::mocks::verify_all();
+///     db::mocks::clear_all();
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn mock_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::ItemMod);
+
+    let mod_name = input.ident.clone();
+    match process_mock_module(input) {
+        Ok(expanded) => {
+            dump_expansion("mock_module", &mod_name, &expanded);
+            TokenStream::from(expanded)
+        }
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Attribute macro for tests that detects mock configurations leaking in from previous tests.
+///
+/// Adds `#[test]` (unless the function already has a test attribute like `#[tokio::test]`)
+/// and calls `fnmock::registry::begin_test()` before the body, which bumps the generation of the thread.
+/// A mock configured in a previous generation, e.g. by a previous test on a reused thread that didn't clear it,
+/// panics with a "stale mock configuration leaked from a previous test" error when it is called.
+/// `fnmock::clear_all_mocks!()` bumps the generation as well.
+///
+/// # Example
+///
+/// ```ignore
+/// #[fnmock::test]
+/// fn test_handle_user() {
+///     fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+///
+///     handle_user(1);
+///
+///     fetch_user_mock::assert_times(1);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as syn::ItemFn);
 
-    match process_stub_function(input) {
+    match process_test_function(input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Macro listing every double generated in the crate as a `&'static [fnmock::inventory::GeneratedDouble]`.
+///
+/// Enables a single meta-test asserting conventions, e.g. that every function of the `db` modules has a mock.
+/// The source files are parsed at expansion time, starting at `src/lib.rs`, `src/main.rs` or `src/bin/<name>.rs`
+/// and following `mod` declarations and their `#[path]` attributes.
+///
+/// Listed are the doubles of functions and `extern` blocks annotated with `#[mock_function]`, `#[fake_function]`
+/// or `#[stub_function]`, the fakes of `#[mock_function(fake)]`, the mocks of every instantiation of
+/// `instantiate(..)` and the methods mocked with `#[mock_trait]`, `#[mock_struct]` and `#[mock_method]`.
+/// The `#[cfg]` attributes aren't evaluated, so doubles that are compiled out are listed as well.
+/// Modules generated by macros or whose file isn't found, e.g. one written by a build script, aren't scanned,
+/// so their doubles are missing.
+///
+/// # Example
+///
+/// ```ignore
+/// use fnmock::inventory::DoubleKind;
+///
+/// #[test]
+/// fn test_every_db_double_is_a_mock() {
+///     let doubles = fnmock::derive::mocks_in_crate!();
+///
+///     for double in doubles.iter().filter(|double| double.module_path.ends_with("::db")) {
+///         assert_eq!(double.kind, DoubleKind::Mock, "{} isn't mocked", double.function_path());
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn mocks_in_crate(_input: TokenStream) -> TokenStream {
+    match process_mocks_in_crate() {
         Ok(expanded) => TokenStream::from(expanded),
         Err(e) => e.to_compile_error().into(),
     }
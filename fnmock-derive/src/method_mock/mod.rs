@@ -0,0 +1,229 @@
+use quote::{quote, ToTokens};
+use syn::__private::TokenStream2;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::Token;
+use crate::export_utils::{double_cfg, double_visibility, item_cfg};
+use crate::function_mock::create_mock_implementation::{create_mock_call, create_mock_module};
+use crate::naming_utils::{snake_case, upper_camel_case};
+use crate::param_utils::{create_param_type, create_tuple_from_param_names, elide_ref_params, filter_params, record_fn_pointers, record_owned_refs, validate_static_params};
+use crate::return_utils::extract_return_type;
+use crate::type_utils::{replace_self, static_lifetimes};
+
+/// Parses the names of the methods to shim, e.g. `find_user, save_user`.
+pub(crate) fn parse_method_names(attr: TokenStream2) -> syn::Result<Vec<syn::Ident>> {
+    let names = Punctuated::<syn::Ident, Token![,]>::parse_terminated.parse2(attr)?;
+    if names.is_empty() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "mock_method requires the names of the methods to shim, e.g. #[mock_method(find_user)]",
+        ));
+    }
+    Ok(names.into_iter().collect())
+}
+
+/// Processes an inherent impl block and generates a shim and a mock for each of the named methods.
+///
+/// This is the main entry point for the mock_method attribute macro. It takes an impl block
+/// and the names of methods in it and generates (besides the unchanged impl block):
+/// 1. An extension trait per method, e.g. `FindUserShim`, with a `<method>_shim` method, which is implemented
+///    for the type (in test mode, calls the mock if it is configured; otherwise calls the original method)
+/// 2. A `<type_name>_mock` module (test-only) containing a mock module per method, like the ones of `#[mock_function]`,
+///    and a `clear()` function clearing all of them
+///
+/// # Arguments
+///
+/// * `mock_impl` - The impl block containing the methods
+/// * `method_names` - The names of the methods to shim
+///
+/// # Returns
+///
+/// - `Ok(TokenStream2)` - The impl block, the shims and the mock modules
+/// - `Err(syn::Error)` - If the impl block implements a trait or is generic, or a method can't be shimmed
+///
+/// # Validation
+///
+/// The function validates that:
+/// - The impl block is an inherent impl of a non-generic type
+/// - The named methods exist, take `self`, aren't generic, `const` or `unsafe` and don't return `impl Trait`
+/// - The parameters of the named methods are 'static (no references)
+pub(crate) fn process_mock_method(mock_impl: syn::ItemImpl, method_names: Vec<syn::Ident>) -> syn::Result<TokenStream2> {
+    if let Some((_, trait_path, _)) = &mock_impl.trait_ {
+        return Err(syn::Error::new_spanned(
+            trait_path,
+            "mock_method only supports inherent impl blocks, put #[mock_trait] on the trait instead",
+        ));
+    }
+    if !mock_impl.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&mock_impl.generics, "mock_method doesn't support generic impl blocks"));
+    }
+    let syn::Type::Path(type_path) = &*mock_impl.self_ty else {
+        return Err(syn::Error::new_spanned(&mock_impl.self_ty, "mock_method only supports impl blocks of named types"));
+    };
+    let type_name = &type_path.path.segments.last().expect("a type path has a segment").ident;
+
+    let impl_cfg = item_cfg(&mock_impl.attrs);
+    let mock_mod_name = syn::Ident::new(&format!("{}_mock", snake_case(&type_name.to_string())), type_name.span());
+
+    let mut shims = Vec::new();
+    let mut method_modules = Vec::new();
+    let mut method_cfgs = Vec::new();
+    let mut has_pub_method = false;
+    for method_name in &method_names {
+        let method = mock_impl.items.iter().find_map(|item| match item {
+            syn::ImplItem::Fn(method) if method.sig.ident == *method_name => Some(method),
+            _ => None,
+        });
+        let Some(method) = method else {
+            return Err(syn::Error::new_spanned(method_name, format!("Method '{}' not found in impl block", method_name)));
+        };
+        let (shim, method_module) = create_method_shim(method, &mock_mod_name, &mock_impl.self_ty, &impl_cfg)?;
+        shims.push(shim);
+        method_modules.push(method_module);
+        method_cfgs.push(item_cfg(&method.attrs));
+        has_pub_method |= matches!(method.vis, syn::Visibility::Public(_));
+    }
+
+    // The module is exported with the `mocks` feature, if one of its method modules is
+    let mock_mod_visibility = match has_pub_method {
+        true => double_visibility(&syn::parse_quote! { pub }),
+        false => double_visibility(&syn::Visibility::Inherited),
+    };
+    let mock_cfg = double_cfg();
+    let clear_doc = format!("Clears the mocks of all shimmed methods of [`{}`].", type_name);
+
+    Ok(quote! {
+        #mock_impl
+
+        #(#shims)*
+
+        #impl_cfg
+        #mock_cfg
+        #mock_mod_visibility mod #mock_mod_name {
+            use super::*;
+
+            #[doc = #clear_doc]
+            #mock_mod_visibility fn clear() {
+                // The method modules compiled out by their `#[cfg]` are skipped
+                #(#method_cfgs #method_names::clear();)*
+            }
+
+            #(#method_modules)*
+        }
+    })
+}
+
+/// Generates the extension trait shimming a method, its implementation for the type and the mock module of the method.
+///
+/// The parameters are renamed to plain names, since a method without a body can declare `_` or `mut` patterns.
+fn create_method_shim(
+    method: &syn::ImplItemFn,
+    mock_mod_name: &syn::Ident,
+    self_ty: &syn::Type,
+    impl_cfg: &TokenStream2,
+) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let sig = &method.sig;
+    if sig.receiver().is_none() {
+        return Err(syn::Error::new_spanned(sig, "mock_method only supports methods taking `self`, use #[mock_struct] for associated functions"));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&sig.generics, "mock_method doesn't support generic methods"));
+    }
+    if sig.constness.is_some() || sig.unsafety.is_some() {
+        return Err(syn::Error::new_spanned(sig, "mock_method doesn't support `const` and `unsafe` methods"));
+    }
+    if let syn::ReturnType::Type(_, return_type) = &sig.output {
+        if matches!(**return_type, syn::Type::ImplTrait(_)) {
+            return Err(syn::Error::new_spanned(return_type, "mock_method doesn't support methods returning `impl Trait`"));
+        }
+    }
+
+    let method_name = &sig.ident;
+    let shim_name = syn::Ident::new(&format!("{}_shim", method_name), method_name.span());
+    let trait_name = syn::Ident::new(&format!("{}Shim", upper_camel_case(&method_name.to_string())), method_name.span());
+
+    let mut receiver = sig.receiver().expect("the receiver was checked").clone();
+    receiver.mutability = None;
+    let mut param_names = Vec::new();
+    let mut param_types = Vec::new();
+    let mut params = Punctuated::<syn::FnArg, Token![,]>::new();
+    for (index, input) in sig.inputs.iter().enumerate() {
+        let syn::FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+        let name = match &*pat_type.pat {
+            syn::Pat::Ident(pat_ident) => pat_ident.ident.clone(),
+            _ => syn::Ident::new(&format!("arg{}", index), proc_macro2::Span::call_site()),
+        };
+        let ty = replace_self(&pat_type.ty, self_ty);
+        params.push(syn::parse_quote! { #name: #ty });
+        param_names.push(name);
+        param_types.push(&pat_type.ty);
+    }
+    validate_static_params(&params, &[])?;
+
+    let params_type = create_param_type(&record_owned_refs(&record_fn_pointers(&params)), &[]);
+    let params_to_tuple = create_tuple_from_param_names(&params, &[]);
+    let return_type = static_lifetimes(&replace_self(&extract_return_type(&sig.output), self_ty));
+
+    let method_mod_path = quote! { #mock_mod_name::#method_name };
+    let mock_call = create_mock_call(&method_mod_path, &params_to_tuple, sig.asyncness);
+    let original_call = match sig.asyncness {
+        Some(_) => quote! { self.#method_name(#(#param_names),*).await },
+        None => quote! { self.#method_name(#(#param_names),*) },
+    };
+
+    let fn_visibility = &method.vis;
+    let fn_asyncness = sig.asyncness;
+    let fn_output = &sig.output;
+    let method_cfg = item_cfg(&method.attrs);
+    let mock_cfg = double_cfg();
+    let trait_doc = format!(
+        "Shim of [`{}::{}`], which calls the mock in `{}::{}` if it is configured, generated by `#[mock_method]`",
+        self_ty.to_token_stream(), method_name, mock_mod_name, method_name,
+    );
+
+    let shim = quote! {
+        #impl_cfg
+        #method_cfg
+        #[doc = #trait_doc]
+        #[allow(async_fn_in_trait)]
+        #fn_visibility trait #trait_name {
+            #fn_asyncness fn #shim_name(#receiver, #(#param_names: #param_types),*) #fn_output;
+        }
+
+        #impl_cfg
+        #method_cfg
+        impl #trait_name for #self_ty {
+            #[inline]
+            #fn_asyncness fn #shim_name(#receiver, #(#param_names: #param_types),*) #fn_output {
+                // Call the mock implementation if set (only in test mode or with the `mocks` feature)
+                #mock_cfg
+                if #method_mod_path::is_set() {
+                    return #mock_call;
+                }
+                #mock_cfg
+                #method_mod_path::check_real_call();
+
+                #original_call
+            }
+        }
+    };
+
+    let method_module = create_mock_module(
+        method_name.clone(),
+        double_visibility(fn_visibility),
+        params_type,
+        return_type,
+        &params,
+        &[],
+        fn_asyncness,
+        params_to_tuple,
+        elide_ref_params(&filter_params(&params, &[])),
+        None,
+        None,
+        false,
+    );
+
+    Ok((shim, quote! { #method_cfg #method_module }))
+}
@@ -0,0 +1,55 @@
+use quote::quote;
+use syn::__private::TokenStream2;
+use crate::export_utils::{double_cfg, double_visibility};
+
+/// Processes an inline module and adds a `mocks` submodule aggregating its doubles.
+///
+/// The doubles register themselves with their module path once they are configured,
+/// so the submodule finds them through `fnmock::registry` without scanning the items of the module.
+/// Only the doubles defined directly in the module are affected, not the ones of nested modules.
+///
+/// # Arguments
+///
+/// * `item_mod` - The module the `mocks` submodule is added to
+///
+/// # Returns
+///
+/// - `Ok(TokenStream2)` - The module with the `mocks` submodule appended to its items
+/// - `Err(syn::Error)` - If the module isn't inline (`mod db;`)
+pub(crate) fn process_mock_module(mut item_mod: syn::ItemMod) -> syn::Result<TokenStream2> {
+    let Some((_, items)) = item_mod.content.as_mut() else {
+        return Err(syn::Error::new_spanned(
+            &item_mod,
+            "#[mock_module] requires an inline module with its items in braces",
+        ));
+    };
+
+    let mocks_visibility = double_visibility(&item_mod.vis);
+    let mocks_cfg = double_cfg();
+
+    items.push(syn::parse_quote! {
+        /// Lifecycle functions for the doubles of this module, generated by `#[mock_module]`
+        ///
+        /// Like `fnmock::clear_all_mocks!()`, they only affect the doubles configured on the current thread.
+        #mocks_cfg
+        #mocks_visibility mod mocks {
+            /// Returns the module path of the parent module, which the doubles are registered with.
+            fn parent_module_path() -> &'static str {
+                module_path!().strip_suffix("::mocks").unwrap()
+            }
+
+            /// Clears all mocks, fakes and stubs of this module used on the current thread.
+            #mocks_visibility fn clear_all() {
+                fnmock::registry::clear_module(parent_module_path())
+            }
+
+            /// Verifies the expectations of all mocks of this module, like calling `verify()` on each of them.
+            #[track_caller]
+            #mocks_visibility fn verify_all() {
+                fnmock::registry::verify_module(parent_module_path())
+            }
+        }
+    });
+
+    Ok(quote! { #item_mod })
+}
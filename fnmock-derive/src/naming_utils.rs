@@ -0,0 +1,27 @@
+/// Converts a snake_case function name to an UpperCamelCase type name, e.g. `fetch_user` to `FetchUser`.
+pub(crate) fn upper_camel_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Converts an UpperCamelCase type name to the snake_case name of its mock module, e.g. `UserRepository` to `user_repository`.
+pub(crate) fn snake_case(name: &str) -> String {
+    let mut snake_case = String::new();
+    for (index, char) in name.chars().enumerate() {
+        if char.is_uppercase() {
+            if index > 0 {
+                snake_case.push('_');
+            }
+            snake_case.extend(char.to_lowercase());
+        } else {
+            snake_case.push(char);
+        }
+    }
+    snake_case
+}
@@ -2,6 +2,7 @@ use quote::quote;
 use syn::{FnArg, Type};
 use syn::punctuated::Punctuated;
 use syn::token::Comma;
+use crate::type_utils::mentions_names;
 
 /// Creates a type representation for function parameters.
 ///
@@ -53,8 +54,134 @@ pub(crate) fn create_param_type(fn_inputs: &Punctuated<FnArg, Comma>, ignore_ind
     if param_types.len() == 1 {
         param_types[0].as_ref().clone()
     } else {
-        // Multiple parameters or no parameters use tuple syntax
-        syn::parse2(quote! { (#(#param_types),*) }).unwrap()
+        // Multiple parameters or no parameters use tuple syntax,
+        // built directly instead of re-parsing generated tokens
+        Type::Tuple(syn::TypeTuple {
+            paren_token: Default::default(),
+            elems: param_types.into_iter().map(|ty| ty.as_ref().clone()).collect(),
+        })
+    }
+}
+
+/// Replaces the function pointer parameter types with the `fnmock::fn_ptr::FnPtr` recorded by the mock.
+///
+/// Function pointers are recorded by identity and printed with their registered name,
+/// so they don't need to be ignored. Used with `create_param_type` for the params type of a mock.
+///
+/// # Examples
+///
+/// - `fn foo(x: i32, op: fn(i32) -> i32)` → `fn foo(x: i32, op: fnmock::fn_ptr::FnPtr<fn(i32) -> i32>)`
+pub(crate) fn record_fn_pointers(fn_inputs: &Punctuated<FnArg, Comma>) -> Punctuated<FnArg, Comma> {
+    fn_inputs
+        .iter()
+        .map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) if matches!(*pat_type.ty, Type::BareFn(_)) => {
+                let mut pat_type = pat_type.clone();
+                let ty = &pat_type.ty;
+                pat_type.ty = Box::new(syn::parse_quote! { fnmock::fn_ptr::FnPtr<#ty> });
+                syn::FnArg::Typed(pat_type)
+            }
+            arg => arg.clone(),
+        })
+        .collect()
+}
+
+/// Replaces the reference parameter types with the owned types recorded by the mock.
+///
+/// `&str` is always recorded as `String`, other references only with `own_refs`. The referenced values are
+/// converted with `ToOwned` when they are recorded, see `create_tuple_from_param_names`.
+/// Used with `create_param_type` for the params type of a mock.
+///
+/// # Examples
+///
+/// - `fn foo(x: i32, name: &str)` → `fn foo(x: i32, name: String)`
+/// - `fn foo(ids: &[u32])` → `fn foo(ids: <[u32] as ToOwned>::Owned)`, which is `Vec<u32>`
+pub(crate) fn record_owned_refs(fn_inputs: &Punctuated<FnArg, Comma>) -> Punctuated<FnArg, Comma> {
+    fn_inputs
+        .iter()
+        .map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) if is_str_ref(&pat_type.ty) => {
+                let mut pat_type = pat_type.clone();
+                pat_type.ty = Box::new(syn::parse_quote! { String });
+                syn::FnArg::Typed(pat_type)
+            }
+            syn::FnArg::Typed(pat_type) if is_owned_ref(&pat_type.ty) => {
+                let mut pat_type = pat_type.clone();
+                let Type::Reference(reference) = &*pat_type.ty else { unreachable!("checked by is_owned_ref") };
+                let elem = &reference.elem;
+                pat_type.ty = Box::new(syn::parse_quote! { <#elem as ::std::borrow::ToOwned>::Owned });
+                syn::FnArg::Typed(pat_type)
+            }
+            arg => arg.clone(),
+        })
+        .collect()
+}
+
+/// Gets the indices of the reference parameters, which are recorded as owned values with `own_refs`.
+///
+/// Only references to 'static types are converted, e.g. `&str` and `&[u32]`, but not `&[&str]`.
+pub(crate) fn owned_ref_indices(fn_inputs: &Punctuated<FnArg, Comma>) -> Vec<usize> {
+    fn_inputs
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, arg)| match arg {
+            syn::FnArg::Typed(pat_type) if is_owned_ref(&pat_type.ty) => Some(idx),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replaces the reference parameter types with shared references without named lifetimes,
+/// so the assertions of a mock recording them as owned values can declare the parameters.
+/// `&str` parameters accept a `&str` or a `String` in the assertions.
+///
+/// # Examples
+///
+/// - `fn foo(name: &'a mut String)` → `fn foo(name: &String)`
+/// - `fn foo(name: &str)` → `fn foo(name: impl AsRef<str>)`
+pub(crate) fn elide_ref_params(fn_inputs: &Punctuated<FnArg, Comma>) -> Punctuated<FnArg, Comma> {
+    fn_inputs
+        .iter()
+        .map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) if is_str_ref(&pat_type.ty) => {
+                let mut pat_type = pat_type.clone();
+                pat_type.ty = Box::new(syn::parse_quote! { impl AsRef<str> });
+                syn::FnArg::Typed(pat_type)
+            }
+            syn::FnArg::Typed(pat_type) if is_owned_ref(&pat_type.ty) => {
+                let mut pat_type = pat_type.clone();
+                if let Type::Reference(reference) = &mut *pat_type.ty {
+                    reference.lifetime = None;
+                    reference.mutability = None;
+                }
+                if let syn::Pat::Ident(pat_ident) = &mut *pat_type.pat {
+                    pat_ident.mutability = None;
+                }
+                syn::FnArg::Typed(pat_type)
+            }
+            arg => arg.clone(),
+        })
+        .collect()
+}
+
+/// Checks if the type is a reference to a type without references, which `ToOwned` converts to a 'static value.
+/// Explicitly 'static references are recorded as they are.
+fn is_owned_ref(ty: &Type) -> bool {
+    matches!(ty, Type::Reference(reference) if !is_static_ref(reference) && !contains_reference(&reference.elem))
+}
+
+/// Checks if the reference is shared and has the 'static lifetime, e.g. `&'static str`, so it can be recorded as it is.
+fn is_static_ref(reference: &syn::TypeReference) -> bool {
+    reference.mutability.is_none() && reference.lifetime.as_ref().is_some_and(|lifetime| lifetime.ident == "static")
+}
+
+/// Checks if the type is `&str`, which is always recorded as `String`. `&'static str` is recorded as it is.
+fn is_str_ref(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() && !is_static_ref(reference) => {
+            matches!(&*reference.elem, Type::Path(type_path) if type_path.qself.is_none() && type_path.path.is_ident("str"))
+        }
+        _ => false,
     }
 }
 
@@ -72,8 +199,8 @@ pub(crate) fn create_param_type(fn_inputs: &Punctuated<FnArg, Comma>, ignore_ind
 pub(crate) fn get_param_names(fn_inputs: &Punctuated<FnArg, Comma>) -> Vec<&syn::Pat> {
     fn_inputs
         .iter()
-        .filter_map(|arg| match arg {
-            syn::FnArg::Typed(pat_type) => Some(&*pat_type.pat),
+        .map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) => &*pat_type.pat,
             syn::FnArg::Receiver(_) => panic!(
                 "mock_function/fake_function does not support methods with 'self' parameters"
             ),
@@ -131,19 +258,45 @@ pub(crate) fn filter_params(fn_inputs: &Punctuated<FnArg, Comma>, ignore_indices
 /// - `fn foo(x: i32, y: String)` → `(x, y)`
 /// - `fn foo(x: i32, y: String, z: &str)` with `ignore_indices = [2]` → `(x, y)`
 /// - `fn foo(x: i32, y: &str)` with `ignore_indices = [1]` → `x`
+/// - `fn foo(x: i32, op: fn(i32) -> i32)` → `(x, fnmock::fn_ptr::FnPtr::new(op))`
+/// - `fn foo(x: i32, name: &str)` → `(x, ToOwned::to_owned(AsRef::<str>::as_ref(&name)))`
+/// - `fn foo(x: i32, ids: &[u32])` with `own_refs` → `(x, ToOwned::to_owned(&*ids))`
 ///
 /// # Panics
 ///
 /// Panics if the function has a `self` parameter, as methods cannot be mocked.
 pub(crate) fn create_tuple_from_param_names(fn_inputs: &Punctuated<FnArg, Comma>, ignore_indices: &[usize]) -> proc_macro2::TokenStream {
-    let param_names: Vec<_> = get_param_names(fn_inputs)
-        .into_iter()
+    let param_names: Vec<_> = fn_inputs
+        .iter()
         .enumerate()
-        .filter_map(|(idx, name)| {
+        .filter_map(|(idx, arg)| {
             if ignore_indices.contains(&idx) {
-                None
-            } else {
-                Some(name)
+                return None;
+            }
+            match arg {
+                // Function pointers are recorded by identity, see `record_fn_pointers`
+                syn::FnArg::Typed(pat_type) if matches!(*pat_type.ty, Type::BareFn(_)) => {
+                    let name = &pat_type.pat;
+                    Some(quote! { fnmock::fn_ptr::FnPtr::new(#name) })
+                }
+                // References are recorded as owned values, see `record_owned_refs`.
+                // The assertions take `impl AsRef<str>` for `&str`, see `elide_ref_params`
+                syn::FnArg::Typed(pat_type) if is_str_ref(&pat_type.ty) => {
+                    let name = &pat_type.pat;
+                    Some(quote! { ::std::borrow::ToOwned::to_owned(::std::convert::AsRef::<str>::as_ref(&#name)) })
+                }
+                syn::FnArg::Typed(pat_type) if is_owned_ref(&pat_type.ty) => {
+                    let name = &pat_type.pat;
+                    Some(quote! { ::std::borrow::ToOwned::to_owned(&*#name) })
+                }
+                syn::FnArg::Typed(pat_type) => {
+                    let name = &pat_type.pat;
+                    Some(quote! { #name })
+                }
+                syn::FnArg::Receiver(_) => panic!(
+                    "mock_function does not support methods with 'self' parameters. \
+                     Only standalone functions can be mocked."
+                ),
             }
         })
         .collect();
@@ -161,11 +314,13 @@ pub(crate) fn create_tuple_from_param_names(fn_inputs: &Punctuated<FnArg, Comma>
 /// Checks if a type contains references (fails the 'static bound).
 ///
 /// Returns true if the type is a reference or contains references that would
-/// prevent it from satisfying the 'static lifetime bound.
+/// prevent it from satisfying the 'static lifetime bound. Explicitly 'static references
+/// like `&'static str` satisfy it, unless the referenced type contains other references.
 fn contains_reference(ty: &Type) -> bool {
     match ty {
+        Type::Reference(reference) if is_static_ref(reference) => contains_reference(&reference.elem),
         Type::Reference(_) => true,
-        Type::Tuple(tuple) => tuple.elems.iter().any(|t| contains_reference(t)),
+        Type::Tuple(tuple) => tuple.elems.iter().any(contains_reference),
         Type::Array(arr) => contains_reference(&arr.elem),
         Type::Slice(slice) => contains_reference(&slice.elem),
         Type::Paren(paren) => contains_reference(&paren.elem),
@@ -177,7 +332,8 @@ fn contains_reference(ty: &Type) -> bool {
 /// Validates that all non-ignored function parameters satisfy the 'static bound.
 ///
 /// Returns an error if any non-ignored parameter contains references, as the mock infrastructure
-/// requires all parameters to be 'static (no borrowed data). Ignored parameters are skipped.
+/// requires all parameters to be 'static (no borrowed data). Ignored parameters are skipped,
+/// as well as `&str` parameters, which are recorded as `String`.
 ///
 /// # Arguments
 ///
@@ -194,16 +350,128 @@ pub(crate) fn validate_static_params(fn_inputs: &Punctuated<FnArg, Comma>, ignor
             continue;
         }
         if let FnArg::Typed(pat_type) = arg {
-            if contains_reference(&pat_type.ty) {
+            if contains_reference(&pat_type.ty) && !is_str_ref(&pat_type.ty) {
                 return Err(syn::Error::new_spanned(
                     &pat_type.ty,
                     "mock_function requires all non-ignored parameters to be 'static. \
-                     Parameters cannot contain references. \
-                     Consider using owned types like String instead of &str, \
-                     or Vec<T> instead of &[T], or mark the parameter with #[mock_function(ignore=[param])]."
+                     Parameters cannot contain references other than &str and &'static references. \
+                     Consider using owned types like Vec<T> instead of &[T], mark the parameter with #[mock_function(ignore=[param])], \
+                     or record references as owned values with #[mock_function(own_refs)]."
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validates that the mock module doesn't depend on the generics of the function.
+///
+/// The mock module isn't generic, so the recorded parameters and the return type can't mention
+/// type or const parameters of the function, and the recorded parameters can't be `impl Trait`.
+/// Lifetimes, bounds and where clauses are kept on the function and don't affect the mock.
+///
+/// # Arguments
+///
+/// * `fn_generics` - The generics of the function
+/// * `fn_inputs` - The function parameters
+/// * `fn_output` - The return type
+/// * `ignore_indices` - Indices of the parameters that aren't recorded
+///
+/// # Returns
+///
+/// - `Ok(())` if the mock module doesn't need the generics
+/// - `Err(syn::Error)` pointing at the first recorded type mentioning a generic parameter
+pub(crate) fn validate_generic_params(
+    fn_generics: &syn::Generics,
+    fn_inputs: &Punctuated<FnArg, Comma>,
+    fn_output: &syn::ReturnType,
+    ignore_indices: &[usize],
+) -> syn::Result<()> {
+    let impl_keyword = syn::Ident::new("impl", proc_macro2::Span::call_site());
+    let generic_names: Vec<&syn::Ident> = fn_generics.params.iter().filter_map(|param| match param {
+        syn::GenericParam::Type(type_param) => Some(&type_param.ident),
+        syn::GenericParam::Const(const_param) => Some(&const_param.ident),
+        syn::GenericParam::Lifetime(_) => None,
+    }).collect();
+
+    for (idx, arg) in fn_inputs.iter().enumerate() {
+        if ignore_indices.contains(&idx) {
+            continue;
+        }
+        if let FnArg::Typed(pat_type) = arg {
+            if mentions_names(&pat_type.ty, &[&impl_keyword]) {
+                return Err(syn::Error::new_spanned(
+                    &pat_type.ty,
+                    "mock_function can't record `impl Trait` parameters. \
+                     Mark the parameter with #[mock_function(ignore=[param])] or use a concrete type."
                 ));
             }
+            if mentions_names(&pat_type.ty, &generic_names) {
+                return Err(syn::Error::new_spanned(
+                    &pat_type.ty,
+                    "mock_function can't record parameters of a generic type. \
+                     List the instantiations to mock with #[mock_function(instantiate(T = ..))], \
+                     or mark the parameter with #[mock_function(ignore=[param])]."
+                ));
+            }
+        }
+    }
+    if let syn::ReturnType::Type(_, return_type) = fn_output {
+        if mentions_names(return_type, &generic_names) {
+            return Err(syn::Error::new_spanned(
+                return_type,
+                "mock_function can't mock a generic return type. \
+                 List the instantiations to mock with #[mock_function(instantiate(T = ..))]."
+            ));
         }
     }
     Ok(())
 }
+
+/// Gets the names and types of the parameters for an `Args` struct borrowing them.
+///
+/// The struct only has a single lifetime for the borrows, so parameters whose types
+/// contain references, lifetimes or `impl Trait` aren't supported, as well as patterns other than names.
+///
+/// # Returns
+///
+/// - `Some(fields)` - The names and types of the parameters
+/// - `None` - If the function has no parameters or one of them isn't supported
+///
+/// # Examples
+///
+/// - `fn foo(id: u32, mut name: String)` → `Some([(id, u32), (name, String)])`
+/// - `fn foo(name: &str)` → `None`
+/// - `fn foo()` → `None`
+pub(crate) fn get_args_fields(fn_inputs: &Punctuated<FnArg, Comma>) -> Option<Vec<(syn::Ident, Type)>> {
+    if fn_inputs.is_empty() {
+        return None;
+    }
+
+    fn_inputs
+        .iter()
+        .map(|arg| {
+            let FnArg::Typed(pat_type) = arg else {
+                return None;
+            };
+            let syn::Pat::Ident(pat_ident) = &*pat_type.pat else {
+                return None;
+            };
+            let ty = &*pat_type.ty;
+            if pat_ident.by_ref.is_some() || pat_ident.subpat.is_some() || borrows(quote! { #ty }) {
+                return None;
+            }
+            Some((pat_ident.ident.clone(), ty.clone()))
+        })
+        .collect()
+}
+
+/// Checks if the tokens of a type contain a reference, a lifetime or an `impl Trait`.
+fn borrows(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Punct(punct) => punct.as_char() == '&' || punct.as_char() == '\'',
+        proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(group) => borrows(group.stream()),
+        proc_macro2::TokenTree::Literal(_) => false,
+    })
+}
@@ -1,4 +1,3 @@
-use quote::quote;
 
 /// Extracts the return type from a function signature.
 ///
@@ -17,7 +16,191 @@ use quote::quote;
 /// - `fn foo() -> Result<(), Error>` → `Result<(), Error>`
 pub(crate) fn extract_return_type(return_type: &syn::ReturnType) -> syn::Type {
     match return_type {
-        syn::ReturnType::Default => syn::parse2(quote! { () }).unwrap(),
+        syn::ReturnType::Default => syn::Type::Tuple(syn::TypeTuple {
+            paren_token: Default::default(),
+            elems: syn::punctuated::Punctuated::new(),
+        }),
         syn::ReturnType::Type(_, ty) => (**ty).clone(),
     }
 }
+
+/// Extracts the `Ok` and `Err` types from a `Result<T, E>` return type.
+///
+/// The detection is syntactic: the last path segment has to be named `Result`
+/// and carry exactly two type arguments. Aliases with a fixed error type
+/// (like `std::io::Result<T>`) are therefore not detected.
+///
+/// # Returns
+///
+/// - `Some((T, E))` for `Result<T, E>`
+/// - `None` for any other type
+///
+/// # Examples
+///
+/// - `Result<String, Error>` → `Some((String, Error))`
+/// - `std::result::Result<(), String>` → `Some(((), String))`
+/// - `io::Result<String>` → `None`
+/// - `String` → `None`
+pub(crate) fn extract_result_types(return_type: &syn::Type) -> Option<(syn::Type, syn::Type)> {
+    let syn::Type::Path(type_path) = return_type else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+
+    let types: Vec<_> = args.args
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty.clone()),
+            _ => None,
+        })
+        .collect();
+
+    match types.as_slice() {
+        [ok_type, err_type] => Some((ok_type.clone(), err_type.clone())),
+        _ => None,
+    }
+}
+
+/// Checks if a type is known to implement `Copy`.
+///
+/// Like `extract_result_types`, the detection is syntactic, so only primitives, shared references
+/// and tuples / arrays of them are detected. Other `Copy` types (like user-defined structs) return `false`.
+///
+/// # Examples
+///
+/// - `bool`, `u32`, `f64`, `char` → `true`
+/// - `&'static str` → `true`
+/// - `(u32, bool)`, `[u8; 4]`, `()` → `true`
+/// - `String`, `Option<u32>`, `&mut u32` → `false`
+pub(crate) fn is_copy_type(ty: &syn::Type) -> bool {
+    const COPY_PRIMITIVES: &[&str] = &[
+        "bool", "char", "f32", "f64",
+        "i8", "i16", "i32", "i64", "i128", "isize",
+        "u8", "u16", "u32", "u64", "u128", "usize",
+    ];
+
+    match ty {
+        syn::Type::Path(type_path) => {
+            type_path.qself.is_none()
+                && type_path.path.get_ident()
+                    .is_some_and(|ident| COPY_PRIMITIVES.iter().any(|primitive| ident == primitive))
+        }
+        syn::Type::Reference(reference) => reference.mutability.is_none(),
+        syn::Type::Tuple(tuple) => tuple.elems.iter().all(is_copy_type),
+        syn::Type::Array(array) => is_copy_type(&array.elem),
+        syn::Type::Paren(paren) => is_copy_type(&paren.elem),
+        syn::Type::Group(group) => is_copy_type(&group.elem),
+        _ => false,
+    }
+}
+
+/// Extracts the item type of an `impl Iterator<Item = T>` return type.
+///
+/// Like `extract_result_types`, the detection is syntactic: one of the bounds has to be
+/// named `Iterator` and bind the `Item` type.
+///
+/// # Returns
+///
+/// - `Some(T)` for `impl Iterator<Item = T>` (with any additional bounds, like `+ Send`)
+/// - `None` for any other type
+pub(crate) fn extract_iterator_item(return_type: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::ImplTrait(impl_trait) = return_type else {
+        return None;
+    };
+
+    impl_trait.bounds.iter().find_map(|bound| {
+        let syn::TypeParamBound::Trait(trait_bound) = bound else {
+            return None;
+        };
+        let segment = trait_bound.path.segments.last()?;
+        if segment.ident != "Iterator" {
+            return None;
+        }
+        let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+            return None;
+        };
+        args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(assoc.ty.clone()),
+            _ => None,
+        })
+    })
+}
+
+/// Extracts the element type of a `Vec<T>` type.
+///
+/// Like `extract_iterator_item`, the detection is syntactic: the last path segment has to be
+/// named `Vec` with a single type argument, so `std::vec::Vec<T>` is detected as well.
+///
+/// # Returns
+///
+/// - `Some(T)` for `Vec<T>`
+/// - `None` for any other type, including tuples of several params
+pub(crate) fn extract_vec_item(ty: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Vec" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.iter().collect::<Vec<_>>().as_slice() {
+        [syn::GenericArgument::Type(item_type)] => Some(item_type.clone()),
+        _ => None,
+    }
+}
+
+/// Checks if a type is an `impl Future<Output = T>`.
+///
+/// Like `extract_iterator_item`, the detection is syntactic: one of the bounds has to be named `Future`.
+///
+/// # Examples
+///
+/// - `impl Future<Output = u32>` → `true`
+/// - `impl std::future::Future<Output = ()> + Send` → `true`
+/// - `Pin<Box<dyn Future<Output = u32>>>`, `impl Iterator<Item = u32>` → `false`
+pub(crate) fn is_impl_future(return_type: &syn::Type) -> bool {
+    let syn::Type::ImplTrait(impl_trait) = return_type else {
+        return false;
+    };
+
+    impl_trait.bounds.iter().any(|bound| {
+        let syn::TypeParamBound::Trait(trait_bound) = bound else {
+            return false;
+        };
+        trait_bound.path.segments.last().is_some_and(|segment| segment.ident == "Future")
+    })
+}
+
+/// Converts an `impl Trait` type into the boxed trait object `Box<dyn Trait>` with the same bounds.
+///
+/// A boxed future has to be pinned to implement `Future`, so `impl Future` is converted to `Pin<Box<dyn Future>>`.
+/// Other traits have to be implemented for `Box<dyn Trait>`, which std does for `Iterator`, `Fn`, `Display`,
+/// `Debug`, `Error`, `Read` and `Write`, among others.
+///
+/// # Examples
+///
+/// - `impl Iterator<Item = u32>` → `Box<dyn Iterator<Item = u32>>`
+/// - `impl Iterator<Item = u32> + Send` → `Box<dyn Iterator<Item = u32> + Send>`
+/// - `impl Fn(u32) -> bool` → `Box<dyn Fn(u32) -> bool>`
+/// - `impl Display` → `Box<dyn Display>`
+/// - `impl Future<Output = u32>` → `Pin<Box<dyn Future<Output = u32>>>`
+pub(crate) fn box_impl_trait(return_type: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::ImplTrait(impl_trait) = return_type else {
+        return None;
+    };
+    let bounds = &impl_trait.bounds;
+
+    match is_impl_future(return_type) {
+        true => Some(syn::parse_quote! { ::std::pin::Pin<Box<dyn #bounds>> }),
+        false => Some(syn::parse_quote! { Box<dyn #bounds> }),
+    }
+}
@@ -0,0 +1,233 @@
+use quote::{quote, ToTokens};
+use syn::__private::TokenStream2;
+use crate::export_utils::{double_cfg, double_visibility, item_cfg};
+use crate::function_mock::create_mock_implementation::{create_mock_function, create_mock_module};
+use crate::function_mock::get_ignore_indices;
+use crate::function_mock::mock_args::{find_mock_function_attr, is_mock_function_attr, parse_mock_function_args, MockFunctionArgs};
+use crate::naming_utils::snake_case;
+use crate::param_utils::{create_param_type, create_tuple_from_param_names, elide_ref_params, filter_params, owned_ref_indices, record_fn_pointers, record_owned_refs, validate_static_params};
+use crate::return_utils::extract_return_type;
+use crate::type_utils::{replace_self, static_lifetimes};
+
+pub(crate) mod struct_args;
+
+/// Processes an inherent impl block and generates a mock for each of its methods.
+///
+/// This is the main entry point for the mock_struct attribute macro. It takes an impl block and generates:
+/// 1. The impl block with mock checking logic injected into every method (in test mode, checks if the mock
+///    of the method is configured and calls it; otherwise executes the original implementation)
+/// 2. A `<type_name>_mock` module (test-only) containing a mock module per method, like the ones of `#[mock_function]`,
+///    and a `clear()` function clearing all of them
+///
+/// The receiver isn't recorded, so the mocks of a method are shared by all instances of the type.
+///
+/// # Arguments
+///
+/// * `mock_impl` - The impl block to create the mocks for
+/// * `skip` - The names of the methods that stay unchanged
+///
+/// # Returns
+///
+/// - `Ok(TokenStream2)` - The impl block with the injected mock checking logic and the mock modules
+/// - `Err(syn::Error)` - If the impl block implements a trait or is generic, or a method can't be mocked
+///
+/// # Validation
+///
+/// The function validates that:
+/// - The impl block is an inherent impl of a non-generic type
+/// - The not skipped methods aren't generic, `const` or `unsafe` and don't return `impl Trait`
+/// - The parameters of the not skipped methods are 'static (no references)
+pub(crate) fn process_mock_struct(mock_impl: syn::ItemImpl, skip: Vec<String>) -> syn::Result<TokenStream2> {
+    if let Some((_, trait_path, _)) = &mock_impl.trait_ {
+        return Err(syn::Error::new_spanned(
+            trait_path,
+            "mock_struct only supports inherent impl blocks, put #[mock_trait] on the trait instead",
+        ));
+    }
+    if !mock_impl.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&mock_impl.generics, "mock_struct doesn't support generic impl blocks"));
+    }
+    let syn::Type::Path(type_path) = &*mock_impl.self_ty else {
+        return Err(syn::Error::new_spanned(&mock_impl.self_ty, "mock_struct only supports impl blocks of named types"));
+    };
+    let type_name = &type_path.path.segments.last().expect("a type path has a segment").ident;
+
+    for skipped in &skip {
+        let exists = mock_impl.items.iter().any(|item| matches!(item, syn::ImplItem::Fn(method) if method.sig.ident == skipped));
+        if !exists {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!("Method '{}' not found in impl block", skipped),
+            ));
+        }
+    }
+
+    let impl_cfg = item_cfg(&mock_impl.attrs);
+    let mock_mod_name = syn::Ident::new(&format!("{}_mock", snake_case(&type_name.to_string())), type_name.span());
+    let self_ty = &*mock_impl.self_ty;
+
+    let is_selective = is_selective(&mock_impl.items);
+
+    let mut items = Vec::new();
+    let mut method_names = Vec::new();
+    let mut method_cfgs = Vec::new();
+    let mut method_modules = Vec::new();
+    let mut has_pub_method = false;
+    for item in &mock_impl.items {
+        let args = match item {
+            syn::ImplItem::Fn(method) => method_mock_args(method, &skip, is_selective)?,
+            _ => None,
+        };
+        match (item, args) {
+            (syn::ImplItem::Fn(method), Some(args)) => {
+                let (mocked_method, method_module) = create_method_mock(method, &mock_mod_name, self_ty, args)?;
+                items.push(mocked_method);
+                method_modules.push(method_module);
+                method_names.push(&method.sig.ident);
+                method_cfgs.push(item_cfg(&method.attrs));
+                has_pub_method |= matches!(method.vis, syn::Visibility::Public(_));
+            }
+            (item, _) => items.push(item.to_token_stream()),
+        }
+    }
+
+    // The module is exported with the `mocks` feature, if one of its method modules is
+    let mock_mod_visibility = match has_pub_method {
+        true => double_visibility(&syn::parse_quote! { pub }),
+        false => double_visibility(&syn::Visibility::Inherited),
+    };
+    let mock_cfg = double_cfg();
+    let clear_doc = format!("Clears the mocks of all methods of [`{}`].", type_name);
+
+    let syn::ItemImpl { attrs, impl_token, self_ty, .. } = &mock_impl;
+    Ok(quote! {
+        #(#attrs)*
+        #impl_token #self_ty {
+            #(#items)*
+        }
+
+        #impl_cfg
+        #mock_cfg
+        #mock_mod_visibility mod #mock_mod_name {
+            use super::*;
+
+            #[doc = #clear_doc]
+            #mock_mod_visibility fn clear() {
+                // The method modules compiled out by their `#[cfg]` are skipped
+                #(#method_cfgs #method_names::clear();)*
+            }
+
+            #(#method_modules)*
+        }
+    })
+}
+
+/// Generates a method with the injected mock checking logic and the mock module of the method.
+///
+/// `Self` isn't available in the mock module, so it is replaced by the type in the recorded parameters and the result.
+/// The `#[mock_function]` attribute of the method is removed, its arguments are passed as `args`.
+fn create_method_mock(
+    method: &syn::ImplItemFn,
+    mock_mod_name: &syn::Ident,
+    self_ty: &syn::Type,
+    args: MockFunctionArgs,
+) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let sig = &method.sig;
+    if args.fake || args.di || !args.instantiations.is_empty() {
+        return Err(syn::Error::new_spanned(sig, "`fake`, `di` and `instantiate` aren't supported for methods"));
+    }
+    let skip_hint = format!("or skip it with #[mock_struct(skip = [{}])]", sig.ident);
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&sig.generics, format!("mock_struct doesn't support generic methods, {}", skip_hint)));
+    }
+    if sig.constness.is_some() || sig.unsafety.is_some() {
+        return Err(syn::Error::new_spanned(sig, format!("mock_struct doesn't support `const` and `unsafe` methods, {}", skip_hint)));
+    }
+    if let syn::ReturnType::Type(_, return_type) = &sig.output {
+        if matches!(**return_type, syn::Type::ImplTrait(_)) {
+            return Err(syn::Error::new_spanned(return_type, format!("mock_struct doesn't support methods returning `impl Trait`, {}", skip_hint)));
+        }
+    }
+
+    // The receiver isn't recorded, and `mut` bindings are only needed by the original body
+    let mut params: syn::punctuated::Punctuated<syn::FnArg, syn::token::Comma> = sig.inputs
+        .iter()
+        .filter(|input| matches!(input, syn::FnArg::Typed(_)))
+        .cloned()
+        .collect();
+    for param in params.iter_mut() {
+        if let syn::FnArg::Typed(pat_type) = param {
+            if let syn::Pat::Ident(pat_ident) = &mut *pat_type.pat {
+                pat_ident.mutability = None;
+            }
+            *pat_type.ty = replace_self(&pat_type.ty, self_ty);
+        }
+    }
+    let ignore_indices = get_ignore_indices(&params, &args.ignore)?;
+    let unchecked_indices = match args.own_refs {
+        true => [ignore_indices.clone(), owned_ref_indices(&params)].concat(),
+        false => ignore_indices.clone(),
+    };
+    validate_static_params(&params, &unchecked_indices)?;
+
+    let method_name = &sig.ident;
+    let params_type = create_param_type(&record_owned_refs(&record_fn_pointers(&params)), &ignore_indices);
+    let params_to_tuple = create_tuple_from_param_names(&params, &ignore_indices);
+    let return_type = static_lifetimes(&replace_self(&extract_return_type(&sig.output), self_ty));
+
+    let method_cfg = item_cfg(&method.attrs);
+    let method_attrs = method.attrs.iter().filter(|attr| !is_mock_function_attr(attr));
+    let method_attrs = quote! { #(#method_attrs)* };
+    let mocked_method = create_mock_function(
+        method_name.clone(),
+        method.vis.clone(),
+        sig.asyncness,
+        sig.unsafety,
+        sig.abi.clone(),
+        &sig.generics,
+        &sig.inputs,
+        sig.output.clone(),
+        &method.block,
+        quote! { #mock_mod_name::#method_name },
+        params_to_tuple.clone(),
+        None,
+        None,
+        &method_attrs,
+    );
+
+    let method_module = create_mock_module(
+        method_name.clone(),
+        double_visibility(&method.vis),
+        params_type,
+        return_type,
+        &params,
+        &ignore_indices,
+        sig.asyncness,
+        params_to_tuple,
+        elide_ref_params(&filter_params(&params, &ignore_indices)),
+        None,
+        args.group,
+        args.type_state,
+    );
+
+    Ok((mocked_method, quote! { #method_cfg #method_module }))
+}
+
+/// Checks whether some methods of the impl block are marked with `#[mock_function]`, so only those are mocked.
+pub(crate) fn is_selective(items: &[syn::ImplItem]) -> bool {
+    items.iter().any(|item| matches!(item, syn::ImplItem::Fn(method) if find_mock_function_attr(&method.attrs).is_some()))
+}
+
+/// Returns the arguments of the mock of a method, or None if the method stays unchanged.
+///
+/// The skipped methods stay unchanged, and if the impl block `is_selective`, so do the methods without `#[mock_function]`.
+/// The other methods are mocked with the arguments of their `#[mock_function]` attribute, if any.
+pub(crate) fn method_mock_args(method: &syn::ImplItemFn, skip: &[String], is_selective: bool) -> syn::Result<Option<MockFunctionArgs>> {
+    if skip.iter().any(|skipped| method.sig.ident == skipped) {
+        return Ok(None);
+    }
+    match find_mock_function_attr(&method.attrs) {
+        Some(attr) => Ok(Some(parse_mock_function_args(attr)?)),
+        None => Ok((!is_selective).then(MockFunctionArgs::default)),
+    }
+}
@@ -0,0 +1,33 @@
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+/// Structure to parse the mock_struct attribute arguments
+pub(crate) struct MockStructArgs {
+    pub(crate) skip: Vec<String>,
+}
+
+impl Parse for MockStructArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut skip = Vec::new();
+
+        // Parse "skip = [...]" syntax
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            if key == "skip" {
+                input.parse::<Token![=]>()?;
+                let content;
+                syn::bracketed!(content in input);
+                let names: Punctuated<syn::Ident, Token![,]> = content.parse_terminated(syn::Ident::parse, Token![,])?;
+                skip = names.into_iter().map(|id| id.to_string()).collect();
+            }
+
+            // Allow trailing comma or end of input
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(MockStructArgs { skip })
+    }
+}
@@ -0,0 +1,38 @@
+use quote::quote;
+use syn::__private::TokenStream2;
+
+/// Processes a test function, so it starts a new generation of the doubles of its thread.
+///
+/// Adds `#[test]`, unless the function already has a test attribute like `#[tokio::test]`,
+/// which is applied after this attribute and receives the function with the injected call.
+///
+/// # Arguments
+///
+/// * `item_fn` - The test function
+///
+/// # Returns
+///
+/// - `Ok(TokenStream2)` - The test function calling `fnmock::registry::begin_test()` before its body
+/// - `Err(syn::Error)` - If the function has parameters
+pub(crate) fn process_test_function(mut item_fn: syn::ItemFn) -> syn::Result<TokenStream2> {
+    if !item_fn.sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item_fn.sig.inputs,
+            "#[fnmock::test] functions can't have parameters",
+        ));
+    }
+
+    item_fn.block.stmts.insert(0, syn::parse_quote! {
+        fnmock::registry::begin_test();
+    });
+
+    let has_test_attribute = item_fn.attrs.iter().any(|attr| {
+        attr.path().segments.last().is_some_and(|segment| segment.ident == "test")
+    });
+    let test_attribute = (!has_test_attribute).then(|| quote! { #[::core::prelude::v1::test] });
+
+    Ok(quote! {
+        #test_attribute
+        #item_fn
+    })
+}
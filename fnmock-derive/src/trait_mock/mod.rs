@@ -0,0 +1,174 @@
+use quote::quote;
+use syn::__private::TokenStream2;
+use crate::export_utils::{double_cfg, double_visibility, item_cfg};
+use crate::function_mock::create_mock_implementation::{create_mock_call, create_mock_module};
+use crate::naming_utils::snake_case;
+use crate::param_utils::{create_param_type, create_tuple_from_param_names, elide_ref_params, filter_params, record_fn_pointers, record_owned_refs, validate_static_params};
+use crate::return_utils::extract_return_type;
+use crate::type_utils::static_lifetimes;
+
+/// Processes a trait and generates a mock implementing it.
+///
+/// This is the main entry point for the mock_trait attribute macro. It takes a trait
+/// definition and generates (besides the unchanged trait):
+/// 1. A `Mock<TraitName>` unit struct implementing the trait (test-only), which forwards every call to the mock of the method
+/// 2. A `<trait_name>_mock` module (test-only) containing a mock module per method, like the ones of `#[mock_function]`,
+///    and a `clear()` function clearing all of them
+///
+/// # Arguments
+///
+/// * `mock_trait` - The trait item to create the mock for
+///
+/// # Returns
+///
+/// - `Ok(TokenStream2)` - The trait, the mock struct and the mock modules
+/// - `Err(syn::Error)` - If the trait has generics, associated types or constants, or a method can't be mocked
+///
+/// # Validation
+///
+/// The function validates that:
+/// - The trait and its methods aren't generic and the trait only contains methods
+/// - Every method takes `self` and doesn't return `impl Trait`
+/// - The parameters are 'static (no references)
+/// - `#[async_trait]`, if used, is placed below the attribute, so the methods aren't desugared yet
+pub(crate) fn process_mock_trait(mock_trait: syn::ItemTrait) -> syn::Result<TokenStream2> {
+    if !mock_trait.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&mock_trait.generics, "mock_trait doesn't support generic traits"));
+    }
+
+    let item_cfg = item_cfg(&mock_trait.attrs);
+    let trait_name = &mock_trait.ident;
+    let struct_name = syn::Ident::new(&format!("Mock{}", trait_name), trait_name.span());
+    let mock_mod_name = syn::Ident::new(&format!("{}_mock", snake_case(&trait_name.to_string())), trait_name.span());
+    let mock_mod_visibility = double_visibility(&mock_trait.vis);
+
+    // `#[async_trait]` rewrites the async methods of the trait, so the implementation has to be rewritten the same way
+    let async_trait = mock_trait.attrs.iter().filter(|attr| {
+        attr.path().segments.last().is_some_and(|segment| segment.ident == "async_trait")
+    });
+
+    let mut methods = Vec::new();
+    let mut method_modules = Vec::new();
+    for item in &mock_trait.items {
+        let syn::TraitItem::Fn(method) = item else {
+            return Err(syn::Error::new_spanned(item, "mock_trait only supports traits containing methods"));
+        };
+        let (method, method_module) = create_method_mock(method, &mock_mod_name, &mock_mod_visibility)?;
+        methods.push(method);
+        method_modules.push(method_module);
+    }
+    let method_names = mock_trait.items.iter().filter_map(|item| match item {
+        syn::TraitItem::Fn(method) => Some(&method.sig.ident),
+        _ => None,
+    });
+
+    let mock_cfg = double_cfg();
+    let struct_doc = format!(
+        "Mock implementing [`{}`], whose methods are configured and asserted with the modules in `{}`.",
+        trait_name, mock_mod_name,
+    );
+
+    Ok(quote! {
+        #mock_trait
+
+        #item_cfg
+        #mock_cfg
+        #[doc = #struct_doc]
+        #[doc = ""]
+        #[doc = "The mocks are thread-local like the ones of `#[mock_function]`, so all instances share them."]
+        #[derive(Clone, Copy, Debug, Default)]
+        #mock_mod_visibility struct #struct_name;
+
+        #item_cfg
+        #mock_cfg
+        #(#async_trait)*
+        impl #trait_name for #struct_name {
+            #(#methods)*
+        }
+
+        #item_cfg
+        #mock_cfg
+        #mock_mod_visibility mod #mock_mod_name {
+            use super::*;
+
+            /// Clears the mocks of all methods of the trait.
+            #mock_mod_visibility fn clear() {
+                #(#method_names::clear();)*
+            }
+
+            #(#method_modules)*
+        }
+    })
+}
+
+/// Generates the implementation of a trait method calling its mock, and the mock module of the method.
+///
+/// The parameters are renamed to plain names, since a method without a body can declare `_` or `mut` patterns.
+fn create_method_mock(
+    method: &syn::TraitItemFn,
+    mock_mod_name: &syn::Ident,
+    mock_mod_visibility: &TokenStream2,
+) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let sig = &method.sig;
+    // `#[async_trait]` above `#[mock_trait]` has already desugared the method into a generic one returning a boxed future
+    if sig.generics.lifetimes().any(|param| param.lifetime.ident == "async_trait") {
+        return Err(syn::Error::new_spanned(sig, "put #[async_trait] below #[mock_trait], so the async methods are mocked before they are desugared"));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&sig.generics, "mock_trait doesn't support generic methods"));
+    }
+    if sig.receiver().is_none() {
+        return Err(syn::Error::new_spanned(sig, "mock_trait only supports methods taking `self`"));
+    }
+    if let syn::ReturnType::Type(_, return_type) = &sig.output {
+        if matches!(**return_type, syn::Type::ImplTrait(_)) {
+            return Err(syn::Error::new_spanned(return_type, "mock_trait doesn't support methods returning `impl Trait`"));
+        }
+    }
+
+    let method_name = &sig.ident;
+    let mut impl_sig = sig.clone();
+    let mut fn_inputs = syn::punctuated::Punctuated::new();
+    for (index, input) in impl_sig.inputs.iter_mut().enumerate() {
+        let syn::FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+        let name = match &*pat_type.pat {
+            syn::Pat::Ident(pat_ident) => pat_ident.ident.clone(),
+            _ => syn::Ident::new(&format!("arg{}", index), proc_macro2::Span::call_site()),
+        };
+        *pat_type.pat = syn::parse_quote! { #name };
+        fn_inputs.push(input.clone());
+    }
+    validate_static_params(&fn_inputs, &[])?;
+
+    let params_type = create_param_type(&record_owned_refs(&record_fn_pointers(&fn_inputs)), &[]);
+    let params_to_tuple = create_tuple_from_param_names(&fn_inputs, &[]);
+    let return_type = static_lifetimes(&extract_return_type(&sig.output));
+
+    let method_mod_path = quote! { #mock_mod_name::#method_name };
+    let mock_call = create_mock_call(&method_mod_path, &params_to_tuple, sig.asyncness);
+    let method = quote! {
+        #[allow(unused_variables)]
+        #impl_sig {
+            #mock_call
+        }
+    };
+
+    let method_module = create_mock_module(
+        method_name.clone(),
+        mock_mod_visibility.clone(),
+        params_type,
+        return_type,
+        &fn_inputs,
+        &[],
+        sig.asyncness,
+        params_to_tuple,
+        elide_ref_params(&filter_params(&fn_inputs, &[])),
+        None,
+        None,
+        false,
+    );
+
+    Ok((method, method_module))
+}
@@ -0,0 +1,88 @@
+use proc_macro2::{Group, TokenTree};
+use quote::ToTokens;
+use syn::__private::TokenStream2;
+
+/// Replaces `Self` in the type with the type of the impl block, e.g. `Result<Self, String>` with `Result<UserService, String>`.
+pub(crate) fn replace_self(ty: &syn::Type, self_ty: &syn::Type) -> syn::Type {
+    substitute_types(ty, &[(syn::Ident::new("Self", proc_macro2::Span::call_site()), self_ty.clone())])
+}
+
+/// Replaces the named types in the type, e.g. the type parameter `T` in `Option<T>` with `u32`.
+///
+/// The replacement works on the tokens, so every identifier with a substituted name is replaced,
+/// which is enough for type parameters and `Self`.
+pub(crate) fn substitute_types(ty: &syn::Type, substitutions: &[(syn::Ident, syn::Type)]) -> syn::Type {
+    fn substitute_in_stream(tokens: TokenStream2, substitutions: &[(syn::Ident, syn::Type)]) -> TokenStream2 {
+        tokens
+            .into_iter()
+            .map(|tree| match tree {
+                TokenTree::Ident(ident) => match substitutions.iter().find(|(name, _)| *name == ident) {
+                    Some((_, substitute)) => substitute.to_token_stream(),
+                    None => TokenTree::Ident(ident).into(),
+                },
+                TokenTree::Group(group) => {
+                    let mut substituted = Group::new(group.delimiter(), substitute_in_stream(group.stream(), substitutions));
+                    substituted.set_span(group.span());
+                    TokenTree::Group(substituted).into()
+                }
+                tree => tree.into(),
+            })
+            .collect()
+    }
+
+    syn::parse2(substitute_in_stream(ty.to_token_stream(), substitutions)).unwrap_or_else(|_| ty.clone())
+}
+
+/// Checks whether the type mentions one of the names, e.g. the type parameter `T` in `Vec<T>`.
+pub(crate) fn mentions_names(ty: &syn::Type, names: &[&syn::Ident]) -> bool {
+    fn mentions_in_stream(tokens: TokenStream2, names: &[&syn::Ident]) -> bool {
+        tokens.into_iter().any(|tree| match tree {
+            TokenTree::Ident(ident) => names.iter().any(|name| **name == ident),
+            TokenTree::Group(group) => mentions_in_stream(group.stream(), names),
+            _ => false,
+        })
+    }
+
+    mentions_in_stream(ty.to_token_stream(), names)
+}
+
+/// Replaces the lifetimes of the references and lifetime arguments in the type with `'static`,
+/// e.g. `&str` with `&'static str` and `Option<Cow<'a, str>>` with `Option<Cow<'static, str>>`.
+///
+/// The mock and stub modules can't name the lifetimes of a function, so a returned reference is stored
+/// as a 'static one, which coerces to the lifetime of the function. Function pointers, closures and
+/// trait objects are kept, since their lifetimes are higher-ranked.
+pub(crate) fn static_lifetimes(ty: &syn::Type) -> syn::Type {
+    let mut ty = ty.clone();
+    make_lifetimes_static(&mut ty);
+    ty
+}
+
+fn make_lifetimes_static(ty: &mut syn::Type) {
+    match ty {
+        syn::Type::Reference(reference) => {
+            reference.lifetime = Some(syn::Lifetime::new("'static", proc_macro2::Span::call_site()));
+            make_lifetimes_static(&mut reference.elem);
+        }
+        syn::Type::Path(type_path) => {
+            for segment in type_path.path.segments.iter_mut() {
+                let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments else {
+                    continue;
+                };
+                for arg in args.args.iter_mut() {
+                    match arg {
+                        syn::GenericArgument::Lifetime(lifetime) => lifetime.ident = syn::Ident::new("static", lifetime.ident.span()),
+                        syn::GenericArgument::Type(ty) => make_lifetimes_static(ty),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        syn::Type::Tuple(tuple) => tuple.elems.iter_mut().for_each(make_lifetimes_static),
+        syn::Type::Array(array) => make_lifetimes_static(&mut array.elem),
+        syn::Type::Slice(slice) => make_lifetimes_static(&mut slice.elem),
+        syn::Type::Paren(paren) => make_lifetimes_static(&mut paren.elem),
+        syn::Type::Group(group) => make_lifetimes_static(&mut group.elem),
+        _ => {}
+    }
+}
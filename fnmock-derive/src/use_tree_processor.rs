@@ -3,8 +3,6 @@
 //! This module handles the transformation of use statements to extract function names
 //! and generate corresponding mock function names.
 
-use syn;
-
 /// Recursively processes a use tree to extract function names and generate mock names.
 ///
 /// This function traverses the syntax tree of a use statement, collecting the module path in the `base_path` vector
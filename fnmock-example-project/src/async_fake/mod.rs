@@ -7,6 +7,15 @@ pub mod db {
         Ok(format!("user_{}", id))
     }
 
+    // Unsafe functions stay unsafe, the fake is set up like the one of a safe function
+    /// # Safety
+    ///
+    /// `id` must be the id of an existing user
+    #[fake_function]
+    pub async unsafe fn fetch_existing_user(id: u32) -> String {
+        format!("user_{}", id)
+    }
+
     #[cfg(test)]
     mod mock {
         use super::*;
@@ -20,17 +29,22 @@ pub mod db {
     }
 }
 
-use db::fetch_user;
+use db::{fetch_existing_user, fetch_user};
 
 pub async fn handle_user(id: u32) -> Result<String, String> {
     fetch_user(id).await
 }
 
+pub async fn handle_existing_user(id: u32) -> String {
+    // SAFETY: the example only handles existing users
+    unsafe { fetch_existing_user(id) }.await
+}
+
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::db::fetch_user_fake;
+    use super::db::{fetch_existing_user_fake, fetch_user_fake};
 
     // CAUTION: DO NOT USE MULTIPLE THREADS FOR TESTING (see README.md)
     // #[tokio::test] is single threaded by default
@@ -45,4 +59,11 @@ mod tests {
 
         assert_eq!(res.unwrap(), "mock user_42".to_string());
     }
+
+    #[tokio::test]
+    async fn test_unsafe_function_fake() {
+        fetch_existing_user_fake::setup(|id| format!("fake user_{}", id));
+
+        assert_eq!(handle_existing_user(3).await, "fake user_3");
+    }
 }
\ No newline at end of file
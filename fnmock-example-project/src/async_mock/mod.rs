@@ -6,9 +6,41 @@ pub mod db {
         // Real implementation
         Ok(format!("user_{}", id))
     }
+
+    // The future is pinned and boxed in test builds, so the mock returns `Box::pin(async { .. })`
+    #[mock_function]
+    pub fn fetch_avatar(id: u32) -> impl std::future::Future<Output = Vec<u8>> {
+        async move { vec![id as u8] }
+    }
+
+    // The boxed iterator is `Send`, but the canned items of `setup_iter` can't be exported to other threads
+    #[mock_function]
+    pub fn list_user_ids(limit: u32) -> impl Iterator<Item = u32> + Send {
+        0..limit
+    }
+}
+
+use db::{fetch_avatar, fetch_user, list_user_ids};
+
+pub async fn avatar_size(id: u32) -> usize {
+    fetch_avatar(id).await.len()
 }
 
-use db::fetch_user;
+// Fetches the users concurrently in background tasks
+pub async fn fetch_users(ids: Vec<u32>) -> Vec<Result<String, String>> {
+    let tasks: Vec<_> = ids.into_iter().map(|id| fnmock::spawn(fetch_user(id))).collect();
+
+    let mut users = Vec::new();
+    for task in tasks {
+        users.push(task.await.unwrap());
+    }
+    users
+}
+
+// Fetches the first users in background tasks
+pub async fn fetch_first_users(limit: u32) -> Vec<Result<String, String>> {
+    fetch_users(list_user_ids(limit).collect()).await
+}
 
 pub async fn handle_user(id: u32) {
     let _user = fetch_user(id).await;
@@ -20,7 +52,8 @@ pub async fn handle_user(id: u32) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::db::fetch_user_mock;
+    use super::db::{fetch_avatar_mock, fetch_user_mock, list_user_ids_mock};
+    use std::time::Duration;
 
     // CAUTION: DO NOT USE MULTIPLE THREADS FOR TESTING (see README.md)
     // #[tokio::test] is single threaded by default
@@ -39,4 +72,135 @@ mod tests {
 
         // No cleanup needed, since mocks are thread / test specific
     }
+
+    // Paused time skips the timeout instantly, without waiting for it
+    #[tokio::test(start_paused = true)]
+    async fn test_setup_pending_triggers_timeout() {
+        fetch_user_mock::setup_pending();
+
+        let result = tokio::time::timeout(std::time::Duration::from_secs(30), handle_user(42)).await;
+
+        assert!(result.is_err(), "handle_user should time out");
+        fetch_user_mock::assert_with(42);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_with_latency_delays_result() {
+        fetch_user_mock::setup(|id| Ok(format!("user_{}", id)));
+        fetch_user_mock::with_latency(Duration::from_millis(200));
+        fetch_user_mock::with_jitter(Duration::ZERO..Duration::from_millis(50));
+
+        let start = tokio::time::Instant::now();
+        let user = fetch_user(1).await;
+
+        // With the paused clock the delay is skipped, but still elapses on the clock
+        let elapsed = start.elapsed();
+        assert_eq!(user, Ok("user_1".to_string()));
+        assert!((Duration::from_millis(200)..Duration::from_millis(250)).contains(&elapsed));
+
+        // The slow call loses the race against the timeout
+        let result = tokio::time::timeout(Duration::from_millis(100), fetch_user(2)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_durations_between_calls() {
+        fetch_user_mock::setup(|id| Ok(format!("user_{}", id)));
+        fetch_user_mock::record_timestamps();
+
+        // A polling loop, that should wait a second between the requests
+        for id in 0..3 {
+            handle_user(id).await;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        assert_eq!(fetch_user_mock::call_timestamps().len(), 3);
+        assert_eq!(fetch_user_mock::durations_between_calls(), vec![Duration::from_secs(1); 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_assert_called_at_most_per() {
+        fetch_user_mock::setup(|id| Ok(format!("user_{}", id)));
+        fetch_user_mock::record_timestamps();
+
+        // A rate limiter allowing 2 requests per second
+        let mut interval = tokio::time::interval(Duration::from_millis(500));
+        for id in 0..6 {
+            interval.tick().await;
+            handle_user(id).await;
+        }
+
+        fetch_user_mock::assert_called_at_most_per(2, Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_select_cancels_in_flight_call() {
+        fetch_user_mock::setup(|id| Ok(format!("user_{}", id)));
+        fetch_user_mock::yield_before_returning();
+
+        // The ready branch wins while the mocked call is in flight
+        tokio::select! {
+            biased;
+            _ = std::future::ready(()) => {}
+            _ = handle_user(1) => panic!("handle_user should be cancelled"),
+        }
+        assert!(!fetch_user_mock::was_cancelled(), "an unpolled call is not in flight yet");
+
+        tokio::select! {
+            biased;
+            _ = handle_user(2) => panic!("handle_user should be cancelled"),
+            _ = std::future::ready(()) => {}
+        }
+        assert!(fetch_user_mock::was_cancelled());
+        assert_eq!(fetch_user_mock::cancelled_calls(), 1);
+
+        // Awaited calls complete after yielding
+        handle_user(3).await;
+        assert_eq!(fetch_user_mock::cancelled_calls(), 1);
+    }
+    // The worker threads don't share the mocks of the test thread, `fnmock::spawn` installs them in the tasks
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_spawn_propagates_mocks_to_tasks() {
+        fetch_user_mock::setup(|id| Ok(format!("mock_user_{}", id)));
+
+        let users = fetch_users(vec![1, 2, 3]).await;
+
+        assert_eq!(users, vec![
+            Ok("mock_user_1".to_string()),
+            Ok("mock_user_2".to_string()),
+            Ok("mock_user_3".to_string()),
+        ]);
+    }
+
+    // Mocks with canned items can't be exported, so they are skipped unless the task calls them
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_spawn_skips_mocks_with_canned_items() {
+        list_user_ids_mock::setup_iter([4, 7]);
+        fetch_user_mock::setup(|id| Ok(format!("mock_user_{}", id)));
+
+        let users = fetch_first_users(2).await;
+
+        assert_eq!(users, vec![Ok("mock_user_4".to_string()), Ok("mock_user_7".to_string())]);
+    }
+
+    // A current-thread runtime polls the task on the test thread, which keeps its calls and configuration
+    #[tokio::test]
+    async fn test_spawn_on_current_thread_keeps_calls() {
+        fetch_user_mock::setup(|id| Ok(format!("mock_user_{}", id)));
+        handle_user(1).await;
+
+        let users = fetch_users(vec![2]).await;
+
+        assert_eq!(users, vec![Ok("mock_user_2".to_string())]);
+        fetch_user_mock::assert_times(2);
+    }
+
+    #[tokio::test]
+    async fn test_impl_future_return_is_boxed() {
+        fetch_avatar_mock::setup(|id| Box::pin(async move { vec![0; id as usize] }));
+
+        assert_eq!(avatar_size(16).await, 16);
+
+        fetch_avatar_mock::assert_with(16);
+    }
 }
\ No newline at end of file
@@ -7,6 +7,86 @@ pub mod db {
         Ok(format!("user_{}", id))
     }
 
+    // The iterator is boxed in test builds, so the mock can return canned items
+    #[mock_function]
+    pub fn list_user_ids(limit: u32) -> impl Iterator<Item = u32> {
+        0..limit
+    }
+
+    // The closure is boxed in test builds as well, so every call of the mock builds a new closure
+    #[mock_function]
+    pub fn name_validator(min_len: usize) -> impl Fn(&str) -> bool {
+        move |name| name.len() >= min_len
+    }
+
+    // Function pointers are recorded by identity, so they don't need to be ignored
+    #[mock_function]
+    pub fn total_backoff(attempts: u32, backoff: fn(u32) -> u64) -> u64 {
+        (1..=attempts).map(backoff).sum()
+    }
+
+    // The assertions are only available on the handle returned by the setups,
+    // so asserting on the mock without setting it up doesn't compile
+    #[mock_function(type_state)]
+    pub fn write_audit_log(entry: String) -> Result<(), String> {
+        println!("Audit: {}", entry);
+        Ok(())
+    }
+
+    // Batch API with a result per user, so `setup_batch` and `assert_item_passed` are generated
+    #[mock_function]
+    pub fn save_users(ids: Vec<u32>) -> Vec<Result<(), String>> {
+        ids.iter().map(|id| {
+            println!("Saving user {}", id);
+            Ok(())
+        }).collect()
+    }
+
+    // `&str` parameters are recorded as `String`, so they don't need to be ignored.
+    // The doc comment and the other attributes are kept on the function
+    /// Parses the id of a user from a name like `user_42`
+    #[mock_function]
+    #[must_use]
+    #[inline]
+    pub fn find_user_id(name: &str) -> Option<u32> {
+        name.strip_prefix("user_")?.parse().ok()
+    }
+
+    // 'static references satisfy the 'static bound, so they are recorded as they are
+    #[mock_function]
+    pub fn record_metric(name: &'static str, value: u64) -> bool {
+        println!("Metric {}: {}", name, value);
+        true
+    }
+
+    // Any other `impl Trait` is boxed in test builds as well, if the trait is implemented for `Box<dyn Trait>`
+    #[mock_function]
+    pub fn user_badge(id: u32) -> impl std::fmt::Display {
+        format!("#{}", id)
+    }
+
+    // Unsafe functions stay unsafe, the mock is set up like the one of a safe function
+    /// # Safety
+    ///
+    /// `raw` must be an id returned by the database, which always fits into a `u32`
+    #[mock_function]
+    pub unsafe fn user_id_unchecked(raw: u64) -> u32 {
+        raw as u32
+    }
+
+    // The cfg is carried over to the mock modules, so only one `database_url_mock` exists in every build
+    #[mock_function]
+    #[cfg(feature = "postgres")]
+    pub fn database_url() -> String {
+        "postgres://localhost/users".to_string()
+    }
+
+    #[mock_function]
+    #[cfg(not(feature = "postgres"))]
+    pub fn database_url() -> String {
+        "sqlite://users.db".to_string()
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -22,7 +102,66 @@ pub mod db {
     }
 }
 
-use db::fetch_user;
+use db::{fetch_user, find_user_id, record_metric, user_badge, list_user_ids, name_validator, save_users, total_backoff, write_audit_log, user_id_unchecked};
+
+pub fn count_active_users(limit: u32) -> usize {
+    list_user_ids(limit).filter(|id| id % 2 == 0).count()
+}
+
+pub fn count_valid_names(names: &[&str]) -> usize {
+    let is_valid = name_validator(3);
+    names.iter().filter(|name| is_valid(name)).count()
+}
+
+pub fn linear_backoff(attempt: u32) -> u64 {
+    attempt as u64 * 100
+}
+
+pub fn exponential_backoff(attempt: u32) -> u64 {
+    100 << attempt
+}
+
+pub fn retry_budget(attempts: u32) -> u64 {
+    total_backoff(attempts, linear_backoff)
+}
+
+// Retries until the user is fetched, which never ends if the database keeps failing
+pub fn fetch_user_with_retry(id: u32) -> String {
+    loop {
+        if let Ok(user) = fetch_user(id) {
+            return user;
+        }
+    }
+}
+
+pub fn delete_account(id: u32) -> Result<(), String> {
+    write_audit_log(format!("deleted user {}", id))
+}
+
+// Saves the users in batches of two and returns the number of saved users
+pub fn sync_users(ids: &[u32]) -> usize {
+    ids.chunks(2)
+        .flat_map(|batch| save_users(batch.to_vec()))
+        .filter(Result::is_ok)
+        .count()
+}
+
+pub fn find_user_ids(names: &[&str]) -> Vec<u32> {
+    names.iter().filter_map(|name| find_user_id(name)).collect()
+}
+
+pub fn report_sync(synced: usize) -> bool {
+    record_metric("users_synced", synced as u64)
+}
+
+pub fn profile_title(id: u32, name: &str) -> String {
+    format!("{} {}", name, user_badge(id))
+}
+
+pub fn load_user_id(raw: u64) -> u32 {
+    // SAFETY: the example only loads ids returned by the database
+    unsafe { user_id_unchecked(raw) }
+}
 
 pub fn handle_user(id: u32) {
     let _user = fetch_user(id);
@@ -34,7 +173,14 @@ pub fn handle_user(id: u32) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::db::fetch_user_mock;
+    use super::db::{database_url_mock, fetch_user_mock, find_user_id_mock, record_metric_mock, list_user_ids_mock, name_validator_mock, save_users_mock, total_backoff_mock, write_audit_log_mock, user_badge_mock, user_id_unchecked_mock};
+
+    // Shared fixture, that only applies the default behavior if the test didn't configure the mock yet
+    fn setup_default_user() {
+        if !fetch_user_mock::is_set() {
+            fetch_user_mock::setup(|id| Ok(format!("default_user_{}", id)));
+        }
+    }
 
     #[test]
     fn test_with_mock() {
@@ -51,4 +197,505 @@ mod tests {
 
         // No cleanup needed, since mocks are thread / test specific
     }
+
+    #[test]
+    fn test_with_setup_returning() {
+        // Return the same value for every call, regardless of the parameters
+        fetch_user_mock::setup_returning(Ok("mock user".to_string()));
+
+        handle_user(1);
+        handle_user(2);
+
+        fetch_user_mock::assert_times(2);
+        fetch_user_mock::assert_with(2);
+    }
+
+    #[test]
+    fn test_returning_indexed() {
+        // The first call fails, retries succeed
+        fetch_user_mock::returning_indexed(|call_index, id| match call_index {
+            0 => Err("timeout".to_string()),
+            _ => Ok(format!("user_{}", id)),
+        });
+
+        assert_eq!(fetch_user(1), Err("timeout".to_string()));
+        assert_eq!(fetch_user(1), Ok("user_1".to_string()));
+        fetch_user_mock::assert_times(2);
+    }
+
+    #[test]
+    fn test_setup_panic() {
+        fetch_user_mock::setup_panic("db connection lost");
+
+        // The caller is expected to survive a panicking dependency
+        let result = std::panic::catch_unwind(|| handle_user(7));
+
+        assert!(result.is_err());
+        fetch_user_mock::assert_with(7);
+    }
+
+    #[test]
+    fn test_not_initialized_panic_suggests_setup() {
+        list_user_ids_mock::setup_iter([1]);
+
+        let panic = std::panic::catch_unwind(|| fetch_user_mock::call(1)).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("fetch_user_mock mock not initialized on thread 'basic_mock::tests::test_not_initialized_panic_suggests_setup'"));
+        assert!(message.contains("\nMocks configured on this thread: fnmock_example_project::basic_mock::db::list_user_ids_mock"));
+        assert!(message.contains("\n    fetch_user_mock::setup(|id| -> Result<String, String> { todo!() });"));
+    }
+
+    #[test]
+    fn test_setup_iter() {
+        list_user_ids_mock::setup_iter([2, 3, 4]);
+
+        // Every call gets a new iterator over the items
+        assert_eq!(count_active_users(10), 2);
+        assert_eq!(list_user_ids(10).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        // Custom implementations return a boxed iterator
+        list_user_ids_mock::setup(|limit| Box::new((0..limit).rev()));
+        assert_eq!(list_user_ids(3).collect::<Vec<_>>(), vec![2, 1, 0]);
+        list_user_ids_mock::assert_times(3);
+    }
+
+    #[test]
+    fn test_setup_closure_factory() {
+        assert_eq!(count_valid_names(&["al", "bob"]), 1);
+
+        // The implementation is a factory, which builds a fresh closure on every call
+        name_validator_mock::setup(|min_len| Box::new(move |name| name.len() > min_len));
+
+        assert_eq!(count_valid_names(&["al", "bob", "carl"]), 1);
+        assert_eq!(count_valid_names(&["dave"]), 1);
+        name_validator_mock::assert_times(2);
+        name_validator_mock::assert_with(3);
+    }
+
+    #[test]
+    fn test_function_pointer_params() {
+        total_backoff_mock::setup(|(attempts, backoff)| backoff.get()(attempts));
+
+        assert_eq!(retry_budget(3), 300);
+
+        total_backoff_mock::assert_with(3, linear_backoff);
+        assert!(!total_backoff_mock::was_called_with(3, exponential_backoff));
+    }
+
+    #[test]
+    #[should_panic(expected = "(3, exponential_backoff)")]
+    fn test_function_pointer_params_are_named_in_messages() {
+        total_backoff_mock::setup(|_| 0);
+
+        retry_budget(3);
+
+        total_backoff_mock::assert_with(3, fnmock::named_fn!(exponential_backoff));
+    }
+
+    #[test]
+    fn test_with_configure() {
+        // All configuration in one place
+        let handle = fetch_user_mock::configure(|m| {
+            m.ok("mock user".to_string());
+            m.expect_times(2);
+        });
+
+        handle_user(1);
+        handle_user(2);
+
+        handle.verify();
+        fetch_user_mock::assert_with(2);
+    }
+
+    #[test]
+    fn test_snapshot_and_restore_between_phases() {
+        // Arrange once
+        fetch_user_mock::configure(|m| {
+            m.ok("mock user".to_string());
+            m.expect_times(1);
+        });
+        let arranged = fetch_user_mock::snapshot();
+
+        // First phase, which reconfigures the mock
+        fetch_user_mock::setup_err("db down".to_string());
+        assert!(fetch_user(1).is_err());
+
+        // Second phase, starting from the arranged configuration without the calls of the first phase
+        fetch_user_mock::restore(&arranged);
+        handle_user(2);
+        fetch_user_mock::verify();
+        fetch_user_mock::assert_with(2);
+    }
+
+    #[test]
+    fn test_export_state_to_spawned_thread() {
+        fetch_user_mock::configure(|m| {
+            m.ok("mock user".to_string());
+            m.expect_times(1);
+        });
+        let state = fetch_user_mock::export_state();
+
+        // The spawned thread has its own, unconfigured mock until the state is imported
+        std::thread::spawn(move || {
+            fetch_user_mock::import_state(state);
+
+            handle_user(3);
+
+            fetch_user_mock::verify();
+            fetch_user_mock::assert_with(3);
+        }).join().unwrap();
+
+        fetch_user_mock::assert_times(0);
+    }
+
+    #[test]
+    fn test_dedup_consecutive_calls() {
+        fetch_user_mock::configure(|m| {
+            m.ok("mock user".to_string()).dedup_consecutive_calls();
+        });
+
+        for _ in 0..1000 {
+            handle_user(1);
+        }
+        handle_user(2);
+
+        // The assertions still see every call
+        fetch_user_mock::assert_times(1001);
+        fetch_user_mock::assert_with(2);
+        assert_eq!(fetch_user_mock::calls().len(), 1001);
+    }
+
+    #[test]
+    fn test_assert_times_in_context() {
+        fetch_user_mock::setup_ok("mock user".to_string());
+
+        fnmock::with_context("request-1", || {
+            handle_user(1);
+            handle_user(2);
+        });
+        fnmock::with_context("request-2", || handle_user(3));
+
+        fetch_user_mock::assert_times_in_context("request-1", 2);
+        fetch_user_mock::assert_times_in_context("request-2", 1);
+        fetch_user_mock::assert_times(3);
+    }
+
+    #[test]
+    fn test_assert_calls_exact() {
+        fetch_user_mock::setup_ok("mock user".to_string());
+
+        handle_user(1);
+        handle_user(2);
+
+        fetch_user_mock::assert_calls_exact(&[1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected fetch_user_mock mock calls to match exactly (- expected, + actual):\n  1\n- 2\n+ 3")]
+    fn test_assert_calls_exact_fails_with_diff() {
+        fetch_user_mock::setup_ok("mock user".to_string());
+
+        handle_user(1);
+        handle_user(3);
+
+        fetch_user_mock::assert_calls_exact(&[1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "after retry loop: Expected fetch_user_mock mock to be called with 2")]
+    fn test_assertions_with_context_message() {
+        fetch_user_mock::setup_ok("mock user".to_string());
+
+        handle_user(1);
+        fetch_user_mock::assert_times_msg(1, "after first request");
+        fetch_user_mock::assert_with_msg(1, "after first request");
+
+        handle_user(1);
+        fetch_user_mock::assert_times_msg(2, "after retry loop");
+        fetch_user_mock::assert_with_msg(2, "after retry loop");
+    }
+
+    #[test]
+    fn test_contract_checks_on_every_call() {
+        fetch_user_mock::setup(|id| Ok(format!("user_{}", id)));
+        fetch_user_mock::add_precondition(|id| *id > 0);
+        fetch_user_mock::add_postcondition(|id, user| user.as_ref().is_ok_and(|name| name.ends_with(&id.to_string())));
+
+        handle_user(1);
+        let result = std::panic::catch_unwind(|| handle_user(0));
+
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("fetch_user_mock mock precondition added at"));
+        assert!(message.contains("was violated by the call with 0"));
+    }
+
+    #[test]
+    fn test_type_state_assertions_on_setup_handle() {
+        let audit_log = write_audit_log_mock::setup_ok(());
+
+        delete_account(3).unwrap();
+
+        // write_audit_log_mock::assert_times(1) doesn't compile, the handle proves the mock was set up
+        audit_log.assert_times(1);
+        audit_log.assert_with("deleted user 3".to_string());
+    }
+
+    #[test]
+    fn test_type_state_modifiers_return_no_handle() {
+        // Only setting up an implementation creates a handle, so the modifiers can't be used to assert
+        let () = write_audit_log_mock::configure(|mock| { mock.expect_times(1); });
+        let () = write_audit_log_mock::require();
+        let audit_log = write_audit_log_mock::setup_ok(());
+
+        delete_account(4).unwrap();
+
+        audit_log.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "fetch_user_mock mock exceeded its call budget of 3 calls, call 4 was made with 7")]
+    fn test_call_budget_stops_runaway_retry_loop() {
+        fetch_user_mock::setup_err("connection refused".to_string());
+        fetch_user_mock::set_call_budget(3);
+
+        fetch_user_with_retry(7);
+    }
+
+    #[test]
+    #[should_panic(expected = "fetch_user_mock mock is forbidden, but was called with 7")]
+    fn test_forbid() {
+        fetch_user_mock::forbid();
+
+        handle_user(7);
+    }
+
+    #[test]
+    fn test_index_calls() {
+        fetch_user_mock::configure(|m| {
+            m.ok("mock user".to_string()).index_calls();
+        });
+
+        for id in 0..10_000 {
+            handle_user(id);
+        }
+
+        fetch_user_mock::assert_with(9_999);
+        assert!(!fetch_user_mock::was_called_with(10_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected fetch_user_mock mock to be called")]
+    fn test_verify_fails_on_unmet_expectation() {
+        fetch_user_mock::configure(|m| {
+            m.implementation(|id| Ok(format!("user_{}", id))).expect_times(2);
+        });
+
+        handle_user(1);
+
+        fetch_user_mock::verify();
+    }
+
+    #[test]
+    fn test_multi_stage_with_clear_history() {
+        fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+
+        // Stage 1
+        handle_user(1);
+        fetch_user_mock::assert_times(1);
+
+        // Stage 2 keeps the implementation, but only asserts its own calls
+        fetch_user_mock::clear_history();
+        handle_user(2);
+        handle_user(3);
+        fetch_user_mock::assert_times(2);
+
+        // Stage 3 uses the real implementation again, while keeping the history
+        fetch_user_mock::clear_implementation();
+        assert_eq!(db::fetch_user(4), Ok("user_4".to_string()));
+        fetch_user_mock::assert_times(2);
+    }
+
+    #[test]
+    fn test_with_scoped_mock() {
+        let calls = fetch_user_mock::with(|_| Ok("mock user".to_string()), || {
+            handle_user(42);
+
+            fetch_user_mock::assert_with(42);
+            fetch_user_mock::assert_times(1);
+            1
+        });
+
+        assert_eq!(calls, 1);
+        // The mock is cleared after the body ran
+        assert!(!fetch_user_mock::is_set());
+    }
+
+    #[test]
+    fn test_panic_message_prefix() {
+        // The prefix is shared by all threads, so it is usually set once for the whole test suite
+        fnmock::messages::set_prefix("[{module_path}] ");
+
+        let panic = std::panic::catch_unwind(|| fetch_user_mock::assert_times(1)).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.contains("[fnmock_example_project::basic_mock::db::fetch_user_mock] Expected fetch_user_mock"));
+
+        fnmock::messages::clear_prefix();
+    }
+
+    #[test]
+    fn test_fixture_keeps_configured_mock() {
+        fetch_user_mock::setup(|_| Err("user not found".to_string()));
+        setup_default_user();
+
+        assert_eq!(db::fetch_user(1), Err("user not found".to_string()));
+    }
+
+    #[test]
+    fn test_fixture_applies_default() {
+        assert!(!fetch_user_mock::is_set());
+        setup_default_user();
+
+        assert_eq!(db::fetch_user(1), Ok("default_user_1".to_string()));
+    }
+
+    #[test]
+    fn test_strict_mode_reports_repeated_setup() {
+        fnmock::strict::set_repeated_setup(fnmock::strict::Strictness::Panic);
+        fetch_user_mock::setup(|_| Err("user not found".to_string()));
+
+        // A helper overriding the setup of the test is reported with both call sites
+        let panic = std::panic::catch_unwind(|| {
+            fetch_user_mock::setup(|id| Ok(format!("default_user_{}", id)));
+        }).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("fetch_user_mock mock was set up again without being cleared"));
+        // Both setups and the definition of the mocked function
+        assert_eq!(message.matches("src/basic_mock/mod.rs:").count(), 3);
+
+        // Setting the mock up again after clearing it is fine
+        fetch_user_mock::clear();
+        fetch_user_mock::setup(|id| Ok(format!("default_user_{}", id)));
+        fnmock::strict::set_repeated_setup(fnmock::strict::Strictness::Allow);
+    }
+
+    #[test]
+    fn test_required_mock_catches_real_call() {
+        fetch_user_mock::require();
+
+        // The test forgot to set up the mock, so the real implementation would run
+        let panic = std::panic::catch_unwind(|| db::fetch_user(1)).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("fetch_user_mock mock is required, but the real implementation was called"));
+    }
+
+    #[test]
+    fn test_checkpoint_allows_reconfiguring_called_mock() {
+        fnmock::strict::set_setup_after_call(fnmock::strict::Strictness::Panic);
+        fetch_user_mock::configure(|mock| {
+            mock.implementation(|id| Ok(format!("user_{}", id))).expect_times(1);
+        });
+        assert_eq!(db::fetch_user(1), Ok("user_1".to_string()));
+
+        // Without the checkpoint the setup would panic, since the mock was already called
+        fetch_user_mock::checkpoint();
+        fetch_user_mock::setup(|_| Err("user not found".to_string()));
+
+        assert_eq!(db::fetch_user(1), Err("user not found".to_string()));
+        fetch_user_mock::assert_times(2);
+    }
+    #[test]
+    fn test_setup_batch_with_per_item_outcomes() {
+        save_users_mock::setup_batch(|id| if id == 3 { Err("duplicate user".to_string()) } else { Ok(()) });
+
+        assert_eq!(sync_users(&[1, 2, 3, 4, 5]), 4);
+
+        // The items are checked across all batches
+        save_users_mock::assert_times(3);
+        save_users_mock::assert_item_passed(3);
+        save_users_mock::assert_item_passed(5);
+    }
+    #[test]
+    fn test_setup_batch_restored_from_snapshot() {
+        save_users_mock::setup_batch(|_| Ok(()));
+        let arranged = save_users_mock::snapshot();
+
+        save_users_mock::setup_batch(|_| Err("database down".to_string()));
+        assert_eq!(sync_users(&[1, 2]), 0);
+
+        // The per-item function is part of the snapshot
+        save_users_mock::restore(&arranged);
+        assert_eq!(sync_users(&[1, 2]), 2);
+    }
+    #[test]
+    fn test_setup_batch_on_scoped_thread() {
+        save_users_mock::setup_batch(|id| if id > 100 { Err("unknown user".to_string()) } else { Ok(()) });
+
+        let synced = fnmock::scope(|scope| scope.spawn(|| sync_users(&[1, 101, 2])).join().unwrap());
+
+        assert_eq!(synced, 2);
+    }
+    #[test]
+    fn test_mock_follows_cfg_of_function() {
+        database_url_mock::setup(|_| "memory://users".to_string());
+
+        assert_eq!(db::database_url(), "memory://users");
+        database_url_mock::assert_times(1);
+    }
+    // A fuzz target passes the input of the fuzzer instead of the fixed inputs
+    #[test]
+    fn test_fuzzed_batch_results() {
+        let inputs: [&[u8]; 3] = [&[], &[0, 1, 1, 0, 2], &[255, 3, 17, 42, 0, 9, 1]];
+
+        for input in inputs {
+            fnmock::fuzz::set_input(input);
+            save_users_mock::setup_batch(|_| match fnmock::fuzz::ratio(1, 4) {
+                true => Err("connection reset".to_string()),
+                false => Ok(()),
+            });
+
+            assert!(sync_users(&[1, 2, 3, 4]) <= 4);
+        }
+    }
+
+    #[test]
+    fn test_str_params_recorded_as_string() {
+        find_user_id_mock::setup(|name: String| (name == "alice").then_some(1));
+
+        assert_eq!(find_user_ids(&["alice", "bob"]), vec![1]);
+
+        find_user_id_mock::assert_times(2);
+        // The assertions accept a `&str` or a `String`
+        find_user_id_mock::assert_with("alice");
+        let name = String::from("bob");
+        find_user_id_mock::assert_with(name);
+    }
+
+    #[test]
+    fn test_static_str_params_recorded_verbatim() {
+        record_metric_mock::setup(|(name, _): (&'static str, u64)| name.starts_with("users_"));
+
+        assert!(report_sync(3));
+
+        record_metric_mock::assert_with("users_synced", 3);
+    }
+
+    #[test]
+    fn test_impl_display_return_is_boxed() {
+        user_badge_mock::setup(|_| Box::new("admin"));
+
+        assert_eq!(profile_title(1, "Alice"), "Alice admin");
+    }
+
+    #[test]
+    fn test_unsafe_function_mock() {
+        user_id_unchecked_mock::setup(|_| 7);
+
+        assert_eq!(load_user_id(42), 7);
+
+        user_id_unchecked_mock::assert_with(42);
+    }
 }
\ No newline at end of file
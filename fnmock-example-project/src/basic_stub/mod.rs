@@ -6,17 +6,86 @@ pub mod config {
         // Real implementation
         "production_config".to_string()
     }
+
+    // Copy return types are stubbed without RefCell and clones
+    #[stub_function]
+    pub fn is_cache_enabled() -> bool {
+        true
+    }
+
+    // Stubs of functions with parameters can be restricted to some of the calls with setup_when
+    #[stub_function]
+    pub fn get_setting(service: String, key: String) -> String {
+        format!("{}.{}", service, key)
+    }
+
+    #[stub_function]
+    pub fn is_enabled_for(user_id: u32) -> bool {
+        user_id.is_multiple_of(2)
+    }
+
+    // Stubs of functions tracking their caller can be restricted to the calls from some files with setup_for_caller
+    #[stub_function(track_caller)]
+    pub fn get_region() -> String {
+        "eu-west-1".to_string()
+    }
+
+    // Unsafe functions stay unsafe
+    /// # Safety
+    ///
+    /// The config cache must have been loaded before
+    #[stub_function]
+    pub unsafe fn cached_config() -> String {
+        "cached_config".to_string()
+    }
+
+    // Returned references are stored as 'static ones, so the stub returns a literal or a leaked value
+    #[stub_function]
+    pub fn label_for(key: &str) -> &str {
+        match key {
+            "greeting" => "Hello",
+            key => key,
+        }
+    }
+}
+pub mod report;
+
+use config::{cached_config, get_config, get_region, get_setting, label_for};
+
+pub fn endpoint() -> String {
+    format!("https://{}.example.com", get_region())
+}
+
+pub fn connection_string(service: &str) -> String {
+    format!("{}@{}", get_setting(service.to_string(), "user".to_string()), get_setting(service.to_string(), "host".to_string()))
 }
-use config::get_config;
 
 pub fn process_config() -> String {
     get_config()
 }
 
+pub fn process_cached_config() -> String {
+    // SAFETY: the example never unloads the config cache
+    unsafe { cached_config() }
+}
+
+pub fn greet(name: &str) -> String {
+    format!("{}, {}", label_for("greeting"), name)
+}
+
+// Randomness drawn from fnmock::rand can be stubbed in tests
+pub fn roll_dice() -> u64 {
+    fnmock::rand::random_u64() % 6 + 1
+}
+
+pub fn order_reference(customer: &str) -> String {
+    format!("{customer}/{}", fnmock::ids::new_uuid())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::config::{get_config_stub};
+    use super::config::{cached_config_stub, get_config_stub, get_region_stub, get_setting_stub, is_cache_enabled, is_cache_enabled_stub, is_enabled_for, is_enabled_for_stub, label_for_stub};
 
     #[test]
     fn test_stub_with_use_stub() {
@@ -32,4 +101,132 @@ mod tests {
         // Clean up
         get_config_stub::clear();
     }
-}
+
+    #[test]
+    fn test_stub_setup_with_into() {
+        // &str is converted into the String return type
+        get_config_stub::setup("test_config");
+
+        assert_eq!(process_config(), "test_config");
+    }
+
+    #[test]
+    fn test_setup_panic_simulates_crash() {
+        get_config_stub::setup_panic("config service crashed");
+
+        let result = std::panic::catch_unwind(process_config);
+
+        assert_eq!(result.unwrap_err().downcast_ref::<String>().unwrap(), "config service crashed");
+    }
+
+    #[test]
+    fn test_setup_when_stubs_matching_calls() {
+        get_setting_stub::setup_when(|args| args.key == "host", "localhost");
+
+        // The other calls run the real implementation
+        assert_eq!(connection_string("db"), "db.user@localhost");
+
+        // Copy stubs support conditions as well, and fall back to the value returned for every call
+        is_enabled_for_stub::setup_when(|args| *args.user_id == 7, true);
+        assert!(is_enabled_for(7));
+        assert!(!is_enabled_for(3));
+        is_enabled_for_stub::setup(false);
+        assert!(is_enabled_for(7));
+        assert!(!is_enabled_for(4));
+    }
+
+    #[test]
+    fn test_real_implementation_check_reports_unmatched_call() {
+        fnmock::strict::set_real_implementation(fnmock::strict::Strictness::Panic);
+        get_setting_stub::setup_when(|args| args.key == "host", "localhost");
+
+        // The user isn't stubbed, so the call falls through to the real implementation
+        let panic = std::panic::catch_unwind(|| connection_string("db")).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("the real implementation was called, although get_setting_stub stub was configured in this test"));
+        fnmock::strict::set_real_implementation(fnmock::strict::Strictness::Allow);
+    }
+
+    #[test]
+    fn test_setup_for_caller_stubs_calls_from_file() {
+        get_region_stub::setup_for_caller("basic_stub/report.rs", "us-east-1");
+
+        assert_eq!(report::report_header(), "Report for us-east-1");
+        // The calls from other files run the real implementation
+        assert_eq!(endpoint(), "https://eu-west-1.example.com");
+    }
+
+    #[test]
+    fn test_setup_from_iter() {
+        // A sequence of configs, e.g. for a reloading service
+        get_config_stub::setup_from_iter(["v1", "v2"].map(String::from));
+        assert_eq!(process_config(), "v1");
+        assert_eq!(process_config(), "v2");
+
+        // Copy stubs can be fed by infinite iterators
+        is_cache_enabled_stub::setup_from_iter([true, false].into_iter().cycle());
+        assert!(is_cache_enabled());
+        assert!(!is_cache_enabled());
+        assert!(is_cache_enabled());
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        get_config_stub::setup("arranged_config");
+        let arranged = get_config_stub::snapshot();
+
+        // A phase reconfiguring the stub
+        get_config_stub::setup("reloaded_config");
+        assert_eq!(process_config(), "reloaded_config");
+
+        // The next phase starts from the arranged configuration again
+        get_config_stub::restore(&arranged);
+        assert_eq!(process_config(), "arranged_config");
+    }
+
+    #[test]
+    fn test_stubbed_randomness() {
+        fnmock::rand::random_u64_stub::setup_from_iter([0, 5, 6]);
+
+        assert_eq!([roll_dice(), roll_dice(), roll_dice()], [1, 6, 1]);
+    }
+
+    #[test]
+    fn test_stubbed_ids() {
+        fnmock::ids::new_uuid_stub::setup_from_iter(["a", "b"]);
+
+        assert_eq!(order_reference("alice"), "alice/a");
+        assert_eq!(order_reference("bob"), "bob/b");
+    }
+
+    #[test]
+    fn test_copy_stub() {
+        assert!(is_cache_enabled());
+
+        is_cache_enabled_stub::setup(false);
+        assert!(!is_cache_enabled());
+
+        is_cache_enabled_stub::clear();
+        assert!(!is_cache_enabled_stub::is_set());
+        assert!(is_cache_enabled());
+    }
+
+    #[test]
+    fn test_stub_returning_reference() {
+        label_for_stub::setup("Hi");
+        assert_eq!(greet("Alice"), "Hi, Alice");
+
+        // Values built in the test are leaked to live as long as the stub
+        let label: &'static str = Box::leak(format!("{}!", "Hey").into_boxed_str());
+        label_for_stub::setup(label);
+        assert_eq!(greet("Bob"), "Hey!, Bob");
+    }
+
+    #[test]
+    fn test_unsafe_function_stub() {
+        cached_config_stub::setup("stubbed_cached_config");
+
+        assert_eq!(process_cached_config(), "stubbed_cached_config");
+    }
+}
\ No newline at end of file
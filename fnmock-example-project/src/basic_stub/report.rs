@@ -0,0 +1,5 @@
+use super::config::get_region;
+
+pub fn report_header() -> String {
+    format!("Report for {}", get_region())
+}
\ No newline at end of file
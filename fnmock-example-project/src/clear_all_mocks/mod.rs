@@ -0,0 +1,95 @@
+pub mod db {
+    use fnmock::derive::{fake_function, mock_function, stub_function};
+
+    #[mock_function]
+    pub fn fetch_user(id: u32) -> Result<String, String> {
+        Ok(format!("user_{}", id))
+    }
+
+    #[fake_function]
+    pub fn fetch_notes(id: u32) -> Result<String, String> {
+        Ok(format!("notes_{}", id))
+    }
+
+    #[stub_function]
+    pub fn get_db_url() -> String {
+        "postgres://localhost".to_string()
+    }
+}
+
+use db::{fetch_notes, fetch_user, get_db_url};
+
+pub fn load_profile(id: u32) -> Result<String, String> {
+    let user = fetch_user(id)?;
+    let notes = fetch_notes(id)?;
+
+    Ok(format!("{} ({}) from {}", user, notes, get_db_url()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::db::{fetch_notes_fake, fetch_user_mock, get_db_url_stub};
+
+    #[test]
+    fn test_clear_all_mocks() {
+        fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+        fetch_notes_fake::setup(|_| Ok("fake notes".to_string()));
+        get_db_url_stub::setup("sqlite://memory".to_string());
+
+        assert_eq!(load_profile(1), Ok("mock user (fake notes) from sqlite://memory".to_string()));
+
+        // Clears all doubles used on this thread at once
+        fnmock::clear_all_mocks!();
+
+        assert!(!fetch_user_mock::is_set());
+        assert!(!fetch_notes_fake::is_set());
+        assert!(!get_db_url_stub::is_set());
+        fetch_user_mock::assert_times(0);
+
+        // The real implementations are used again
+        assert_eq!(load_profile(1), Ok("user_1 (notes_1) from postgres://localhost".to_string()));
+    }
+
+    #[test]
+    fn test_dump_state() {
+        fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+        get_db_url_stub::setup("sqlite://memory".to_string());
+        let _ = fetch_user(1);
+        get_db_url_stub::clear();
+
+        // fnmock::dump_state() prints the same description
+        let state = fnmock::registry::format_state();
+
+        assert!(state.contains("clear_all_mocks::db::fetch_user_mock: fetch_user_mock mock (set, called 1 times)"));
+        assert!(state.contains("clear_all_mocks::db::get_db_url_stub: get_db_url_stub stub (not set)"));
+    }
+
+    #[fnmock::test]
+    fn test_detects_configuration_leaked_from_previous_test() {
+        fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+        assert_eq!(fetch_user(1), Ok("mock user".to_string()));
+
+        // Simulates the next test on a reused thread, which is started by #[fnmock::test]
+        fnmock::registry::begin_test();
+
+        let panic = std::panic::catch_unwind(|| fetch_user(1)).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.contains("stale mock configuration leaked from a previous test"));
+    }
+
+    #[test]
+    fn test_doubles_register_when_configured() {
+        // Using the real implementations doesn't initialize or register the doubles
+        assert_eq!(load_profile(1), Ok("user_1 (notes_1) from postgres://localhost".to_string()));
+        assert!(fnmock::registry::registered_names().is_empty());
+
+        fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+        fetch_user_mock::setup(|_| Ok("other mock user".to_string()));
+
+        assert_eq!(
+            fnmock::registry::registered_names(),
+            vec!["fnmock_example_project::clear_all_mocks::db::fetch_user_mock"]
+        );
+    }
+}
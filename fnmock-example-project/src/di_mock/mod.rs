@@ -0,0 +1,71 @@
+pub mod mail {
+    use fnmock::derive::mock_function;
+
+    // Generates the `SendEmail` trait, the `SendEmailFn` struct and the `MockSendEmail` test double,
+    // the function itself isn't intercepted
+    #[mock_function(di)]
+    pub fn send_email(to: String, body: String) -> Result<(), String> {
+        println!("Send email to {}: {}", to, body);
+        Ok(())
+    }
+}
+
+use mail::SendEmail;
+
+/// Sends an issue to the subscribers with the injected mailer
+pub struct Newsletter<M: SendEmail> {
+    mailer: M,
+}
+
+impl<M: SendEmail> Newsletter<M> {
+    pub fn new(mailer: M) -> Self {
+        Self { mailer }
+    }
+
+    /// Returns the number of subscribers the issue was sent to
+    pub fn publish(&self, subscribers: &[&str], issue: &str) -> usize {
+        subscribers
+            .iter()
+            .filter(|subscriber| self.mailer.send_email(subscriber.to_string(), issue.to_string()).is_ok())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::mail::{send_email_mock, MockSendEmail, SendEmailFn};
+
+    #[test]
+    fn test_mock_double_records_calls() {
+        send_email_mock::setup(|(to, _)| match to.as_str() {
+            "bob@example.com" => Err("mailbox full".to_string()),
+            _ => Ok(()),
+        });
+
+        let newsletter = Newsletter::new(MockSendEmail);
+        let sent = newsletter.publish(&["alice@example.com", "bob@example.com"], "Issue 1");
+
+        assert_eq!(sent, 1);
+        send_email_mock::assert_times(2);
+        send_email_mock::assert_with("alice@example.com".to_string(), "Issue 1".to_string());
+    }
+
+    #[test]
+    fn test_inject_function_pointer() {
+        let newsletter = Newsletter::new(SendEmailFn { send_email: |_, _| Err("smtp down".to_string()) });
+
+        assert_eq!(newsletter.publish(&["alice@example.com"], "Issue 1"), 0);
+    }
+
+    #[test]
+    fn test_function_is_not_intercepted() {
+        send_email_mock::setup(|_| Err("mock".to_string()));
+
+        // Only the injected double uses the mock, the default struct calls the real implementation
+        let newsletter = Newsletter::new(SendEmailFn::default());
+
+        assert_eq!(newsletter.publish(&["alice@example.com"], "Issue 1"), 1);
+        send_email_mock::assert_times(0);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,74 @@
+pub mod libc {
+    use fnmock::derive::mock_function;
+
+    // The declarations are replaced by mocked `unsafe extern "C"` functions in tests,
+    // so the tests don't link the C library
+    #[mock_function]
+    extern "C" {
+        pub fn abs(value: i32) -> i32;
+        pub fn atoi(text: *const std::ffi::c_char) -> i32;
+    }
+}
+
+pub mod progress {
+    use fnmock::derive::mock_function;
+
+    // Functions defined in Rust keep their ABI, so they can still be passed to C as callbacks
+    #[mock_function]
+    pub extern "C" fn on_progress(percent: u32) -> bool {
+        percent <= 100
+    }
+}
+
+use progress::on_progress;
+
+pub fn distance(a: i32, b: i32) -> i32 {
+    // SAFETY: abs has no preconditions
+    unsafe { libc::abs(a - b) }
+}
+
+pub fn parse_port(text: &std::ffi::CStr) -> i32 {
+    // SAFETY: the text is a valid nul terminated string
+    unsafe { libc::atoi(text.as_ptr()) }
+}
+
+pub fn progress_callback() -> extern "C" fn(u32) -> bool {
+    on_progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::libc::{abs_mock, atoi_mock};
+    use super::progress::on_progress_mock;
+
+    #[test]
+    fn test_extern_block_mock() {
+        abs_mock::setup(|value| value.wrapping_abs() * 2);
+
+        assert_eq!(distance(1, 4), 6);
+
+        abs_mock::assert_times(1);
+        abs_mock::assert_with(-3);
+    }
+
+    #[test]
+    fn test_extern_block_mock_with_pointer() {
+        atoi_mock::setup(|_| 8080);
+
+        assert_eq!(parse_port(c"8080"), 8080);
+
+        atoi_mock::assert_times(1);
+    }
+
+    #[test]
+    fn test_extern_c_function_mock() {
+        on_progress_mock::setup(|percent| percent < 50);
+
+        let callback = progress_callback();
+
+        assert!(callback(10));
+        assert!(!callback(90));
+        on_progress_mock::assert_times(2);
+    }
+}
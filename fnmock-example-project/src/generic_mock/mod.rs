@@ -0,0 +1,53 @@
+pub mod config {
+    use std::str::FromStr;
+    use fnmock::derive::mock_function;
+
+    // Generates `read_setting_u32_mock` and `read_setting_bool_mock`, other instantiations always run the real implementation
+    #[mock_function(instantiate(T = u32, T = bool))]
+    pub fn read_setting<T: FromStr>(key: String) -> Option<T> {
+        std::env::var(key).ok()?.parse().ok()
+    }
+}
+
+use config::read_setting;
+
+/// Returns the number of worker threads, or 1 if it isn't configured or disabled
+pub fn worker_count() -> u32 {
+    match read_setting::<bool>("WORKERS_ENABLED".to_string()) {
+        Some(true) => read_setting::<u32>("WORKER_COUNT".to_string()).unwrap_or(1),
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::config::{read_setting_bool_mock, read_setting_u32_mock};
+
+    #[test]
+    fn test_instantiations_have_separate_mocks() {
+        read_setting_bool_mock::setup(|_| Some(true));
+        read_setting_u32_mock::setup(|_| Some(8));
+
+        assert_eq!(worker_count(), 8);
+        read_setting_bool_mock::assert_times(1);
+        read_setting_bool_mock::assert_with("WORKERS_ENABLED".to_string());
+        read_setting_u32_mock::assert_with("WORKER_COUNT".to_string());
+    }
+
+    #[test]
+    fn test_unset_instantiation_runs_real_implementation() {
+        read_setting_bool_mock::setup(|_| Some(false));
+
+        assert_eq!(worker_count(), 1);
+        read_setting_u32_mock::assert_times(0);
+    }
+
+    #[test]
+    fn test_other_instantiations_are_not_mocked() {
+        read_setting_u32_mock::setup(|_| Some(8));
+
+        assert_eq!(read_setting::<u64>("FNMOCK_EXAMPLE_UNSET_SETTING".to_string()), None);
+        read_setting_u32_mock::assert_times(0);
+    }
+}
\ No newline at end of file
@@ -22,11 +22,29 @@ pub mod db {
         println!("Deleting user {}", id);
         Ok(())
     }
+
+    // Generic parameters are kept on the function, the ignored writer isn't recorded
+    #[mock_function(ignore = [log])]
+    pub fn export_user<W>(id: u32, log: W) -> Result<(), String>
+    where
+        W: std::io::Write,
+    {
+        let mut log = log;
+        writeln!(log, "Exporting user {}", id).map_err(|error| error.to_string())
+    }
+
+    // References are recorded as owned values instead of being ignored
+    #[mock_function(own_refs)]
+    pub fn find_user_by_email(email: &str, roles: &[String]) -> Option<u32> {
+        println!("Finding user {} with roles {:?}", email, roles);
+        None
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::db::{save_user, save_user_mock, update_record, update_record_mock, delete_user, delete_user_mock};
+    use super::db::{save_user, save_user_mock, update_record, update_record_mock, delete_user, delete_user_mock, export_user, export_user_mock, find_user_by_email, find_user_by_email_mock};
+    use fnmock::MockControl;
 
     #[test]
     fn test_save_user_with_ignored_timestamp() {
@@ -98,4 +116,199 @@ mod tests {
         // All three calls should match when checking with any timestamp (ignored)
         save_user_mock::assert_with(5, "Bob".to_string());
     }
-}
+
+    #[test]
+    fn test_assert_with_tuple_from_helper() {
+        fn assert_users_saved(expected: &[(u32, String)]) {
+            for params in expected {
+                save_user_mock::assert_with_tuple(params.clone());
+                // Or without cloning the expected params
+                save_user_mock::assert_with_ref(params);
+            }
+        }
+
+        save_user_mock::setup(|_| Ok(()));
+
+        let _ = save_user(1, "Alice".to_string(), 100);
+        let _ = save_user(2, "Bob".to_string(), 200);
+
+        assert_users_saved(&[(1, "Alice".to_string()), (2, "Bob".to_string())]);
+    }
+
+    #[test]
+    fn test_query_helpers_with_ignored_params() {
+        save_user_mock::setup(|_| Ok(()));
+
+        assert!(!save_user_mock::was_called(), "save_user must not be called before the test runs");
+
+        let _ = save_user(5, "Bob".to_string(), 100);
+
+        assert!(save_user_mock::was_called());
+        assert!(save_user_mock::was_called_with(5, "Bob".to_string()), "save_user should be called for Bob");
+        assert!(!save_user_mock::was_called_with(5, "Alice".to_string()));
+    }
+
+    #[test]
+    fn test_format_calls_renders_table_without_ignored_params() {
+        save_user_mock::setup(|_| Ok(()));
+
+        let _ = save_user(1, "Alice".to_string(), 100);
+        let _ = save_user(20, "Bob".to_string(), 200);
+
+        assert_eq!(save_user_mock::format_calls(), "\
+save_user_mock calls:
+| # | id | name    |
+|---|----|---------|
+| 1 | 1  | \"Alice\" |
+| 2 | 20 | \"Bob\"   |");
+    }
+
+    #[test]
+    fn test_format_calls_with_single_param() {
+        delete_user_mock::setup(|_| Ok(()));
+
+        assert_eq!(delete_user_mock::format_calls(), "delete_user_mock was not called");
+
+        let _ = delete_user(7);
+        delete_user_mock::print_calls();
+
+        assert_eq!(delete_user_mock::format_calls(), "\
+delete_user_mock calls:
+| # | id |
+|---|----|
+| 1 | 7  |");
+    }
+
+    // Test helper working with any mock, independent of its parameters and return type
+    fn assert_each_called_once(mocks: &[&dyn MockControl]) {
+        for mock in mocks {
+            assert!(mock.name().ends_with("_mock"));
+            mock.assert_times(1);
+        }
+    }
+
+    #[test]
+    fn test_mock_control_over_different_mocks() {
+        let save = save_user_mock::setup(|_| Ok(()));
+        let delete = delete_user_mock::setup(|_| Ok(()));
+
+        let _ = save_user(1, "Alice".to_string(), 100);
+        let _ = delete_user(1);
+
+        assert_each_called_once(&[&save, &delete]);
+
+        for mock in [&save as &dyn MockControl, &delete] {
+            mock.clear();
+        }
+        assert!(!save_user_mock::is_set());
+        assert_eq!(delete_user_mock::MockHandle.name(), "delete_user_mock");
+        delete_user_mock::assert_times(0);
+    }
+
+    #[test]
+    fn test_assert_with_matchers() {
+        use fnmock::matchers::{eq, gt, lt, not, MatcherExt};
+
+        save_user_mock::setup(|_| Ok(()));
+
+        let _ = save_user(42, "Alice".to_string(), 100);
+
+        save_user_mock::assert_with_matchers((gt(0).and(lt(100)), not(eq("admin"))));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected delete_user_mock mock to be called with params matching (> 10 or 0)")]
+    fn test_assert_with_matchers_fails_with_description() {
+        use fnmock::matchers::{eq, gt, MatcherExt};
+
+        delete_user_mock::setup(|_| Ok(()));
+
+        let _ = delete_user(5);
+
+        delete_user_mock::assert_with_matchers(gt(10).or(eq(0)));
+    }
+
+    #[test]
+    fn test_verify_no_more_interactions() {
+        let save = save_user_mock::setup(|_| Ok(()));
+        let delete = delete_user_mock::setup(|_| Ok(()));
+
+        let _ = save_user(1, "Alice".to_string(), 100);
+        let _ = delete_user(1);
+        let _ = delete_user(2);
+
+        save_user_mock::assert_with(1, "Alice".to_string());
+        delete_user_mock::assert_with(1);
+
+        let result = std::panic::catch_unwind(|| {
+            fnmock::verify::verify_no_more_interactions(&[&save, &delete])
+        });
+        let panic = result.unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(message.starts_with("Expected no more interactions with delete_user_mock mock, but found 1 unverified call: 2\n"));
+        // The messages point at the mocked function
+        assert!(message.ends_with("(function defined at fnmock-example-project/src/ignore_mock/mod.rs:21)"));
+
+        delete_user_mock::assert_with(2);
+        fnmock::verify::verify_no_more_interactions(&[&save, &delete]);
+    }
+
+    #[test]
+    fn test_setup_ref_borrows_params() {
+        // The params are recorded first and then borrowed, so they aren't cloned
+        save_user_mock::setup_ref(|(id, name)| {
+            if name.is_empty() { Err(format!("user {} has no name", id)) } else { Ok(()) }
+        });
+
+        assert_eq!(save_user(3, "".to_string(), 100), Err("user 3 has no name".to_string()));
+        assert_eq!(save_user(4, "Dana".to_string(), 100), Ok(()));
+
+        save_user_mock::assert_times(2);
+        save_user_mock::assert_with(3, "".to_string());
+    }
+
+    #[test]
+    fn test_verify_all_collecting_reports_every_failed_assertion() {
+        save_user_mock::setup(|_| Ok(()));
+        delete_user_mock::setup(|_| Ok(()));
+
+        let _ = save_user(1, "Alice".to_string(), 100);
+
+        let panic = std::panic::catch_unwind(|| {
+            fnmock::verify::verify_all_collecting(|| {
+                save_user_mock::assert_times(1);
+                save_user_mock::assert_with(2, "Bob".to_string());
+                delete_user_mock::assert_times(1);
+            })
+        }).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("2 mock assertions failed:"));
+        assert!(message.contains("Expected save_user_mock mock to be called with (2, \"Bob\")"));
+        assert!(message.contains("Expected delete_user_mock mock to be called 1 times, received 0"));
+    }
+
+    #[test]
+    fn test_generic_function_with_ignored_writer() {
+        export_user_mock::setup(|_| Err("disk full".to_string()));
+
+        assert_eq!(export_user(7, std::io::sink()), Err("disk full".to_string()));
+        assert_eq!(export_user(8, Vec::new()), Err("disk full".to_string()));
+
+        export_user_mock::assert_times(2);
+        export_user_mock::assert_with(7);
+    }
+
+    #[test]
+    fn test_references_recorded_as_owned_values() {
+        find_user_by_email_mock::setup(|(email, roles)| {
+            (email == "alice@example.com" && roles.contains(&"admin".to_string())).then_some(1)
+        });
+
+        assert_eq!(find_user_by_email("alice@example.com", &["admin".to_string()]), Some(1));
+        assert_eq!(find_user_by_email("bob@example.com", &[]), None);
+
+        find_user_by_email_mock::assert_times(2);
+        find_user_by_email_mock::assert_with("bob@example.com", &[]);
+    }
+}
\ No newline at end of file
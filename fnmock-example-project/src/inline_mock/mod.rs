@@ -14,6 +14,16 @@ pub fn handle_user(id: u32) {
     // Do something with the user
 }
 
+#[mock_function(in_place)]
+pub fn send_email(to: String, body: String) -> Result<(), String> {
+    // Real implementation
+    Err(format!("can't send {} to {} without a mail server", body, to))
+}
+
+pub fn notify(user: String) -> Result<(), String> {
+    send_email(user, "Welcome!".to_string())
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -34,4 +44,17 @@ mod tests {
 
         // No cleanup needed, since mocks are thread / test specific
     }
+
+    #[test]
+    fn test_in_place_mock() {
+        // Without a setup, the real implementation runs
+        assert!(notify("a@example.com".to_string()).is_err());
+
+        send_email_mock::setup(|(_, _)| Ok(()));
+
+        assert_eq!(notify("a@example.com".to_string()), Ok(()));
+
+        send_email_mock::assert_times(1);
+        send_email_mock::assert_with("a@example.com".to_string(), "Welcome!".to_string());
+    }
 }
\ No newline at end of file
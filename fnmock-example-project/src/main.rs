@@ -6,37 +6,112 @@ mod async_fake;
 mod async_stub;
 mod async_mock;
 mod ignore_mock;
+mod clear_all_mocks;
+mod matchers;
+mod mock_groups;
+mod di_mock;
+mod trait_mock;
+mod struct_mock;
+mod method_mock;
+mod generic_mock;
+mod ffi_mock;
 
 fn main() {
     println!("=== fnmock Example Project ===");
     
     // Call example functions to avoid unused warnings
     let _ = basic_mock::db::fetch_user(1);
+    let _ = basic_mock::db::database_url();
     basic_mock::handle_user(1);
+    let _ = basic_mock::count_active_users(1);
+    let _ = basic_mock::count_valid_names(&["test"]);
+    let _ = basic_mock::retry_budget(1);
+    let _ = basic_mock::fetch_user_with_retry(1);
+    let _ = basic_mock::delete_account(1);
+    let _ = basic_mock::exponential_backoff(1);
+    let _ = basic_mock::sync_users(&[1]);
+    let _ = basic_mock::find_user_ids(&["test"]);
+    let _ = basic_mock::report_sync(1);
+    let _ = basic_mock::profile_title(1, "test");
+    let _ = basic_mock::load_user_id(1);
     
     let _ = mock_and_fake::db::fetch_user(1);
     let _ = mock_and_fake::db::fetch_notes(1);
+    let _ = mock_and_fake::db::fetch_settings(1);
     let _ = mock_and_fake::handle_user(1);
     
     let _ = inline_mock::fetch_user(1);
     inline_mock::handle_user(1);
+    let _ = inline_mock::notify("test".to_string());
     
     let _ = basic_stub::config::get_config();
     let _ = basic_stub::process_config();
+    let _ = basic_stub::connection_string("example");
+    let _ = basic_stub::config::is_enabled_for(1);
+    let _ = basic_stub::endpoint();
+    let _ = basic_stub::greet("test");
+    let _ = basic_stub::process_cached_config();
+    let _ = basic_stub::report::report_header();
+    let _ = basic_stub::roll_dice();
+    let _ = basic_stub::order_reference("example");
     
     // Async functions
-    let _ = tokio::runtime::Runtime::new().unwrap().block_on(async {
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
         let _ = async_fake::db::fetch_user(1).await;
         let _ = async_fake::handle_user(1).await;
+        let _ = async_fake::handle_existing_user(1).await;
         
         let _ = async_stub::config::get_config(1).await;
         let _ = async_stub::process_config(1).await;
         
         let _ = async_mock::db::fetch_user(1).await;
         async_mock::handle_user(1).await;
+        let _ = async_mock::avatar_size(1).await;
+        let _ = async_mock::fetch_users(vec![1]).await;
+        let _ = async_mock::fetch_first_users(1).await;
+
+        let repository = trait_mock::repository::InMemoryRepository::default();
+        let _ = trait_mock::announce(&trait_mock::repository::ConsoleNotifier, &repository, 1).await;
     });
     
     let _ = ignore_mock::db::save_user(1, "test".to_string(), 0);
     let _ = ignore_mock::db::update_record(1, "test".to_string(), &[1, 2], 0);
     let _ = ignore_mock::db::delete_user(1);
+    let _ = ignore_mock::db::export_user(1, std::io::sink());
+    let _ = ignore_mock::db::find_user_by_email("test", &[]);
+
+    let _ = clear_all_mocks::load_profile(1);
+
+    let _ = matchers::welcome_user(1, "test");
+    let _ = matchers::send_newsletter_to(&[]);
+
+    let _ = mock_groups::rename_user(1, "test".to_string());
+    let _ = mock_groups::template::welcome_subject("test".to_string());
+
+    let mut repository = trait_mock::repository::InMemoryRepository::default();
+    let _ = trait_mock::rename_user(&mut repository, 1, "test");
+    let _ = trait_mock::greet(&repository, 1);
+
+    let _ = struct_mock::rename_user(1, "test".to_string());
+    let _ = struct_mock::login("test");
+    if let Ok(service) = struct_mock::service::UserService::connect("test".to_string()) {
+        let _ = service.url();
+        #[cfg(feature = "postgres")]
+        let _ = service.vacuum();
+    }
+
+    let http_client = method_mock::client::HttpClient::default();
+    let _ = method_mock::fetch_profile(&http_client, 1);
+    let _ = http_client.header("host");
+    #[cfg(feature = "postgres")]
+    let _ = http_client.delete("/".to_string());
+
+    let _ = generic_mock::worker_count();
+    let _ = generic_mock::config::read_setting::<String>("test".to_string());
+
+    let _ = ffi_mock::distance(1, 2);
+    let _ = ffi_mock::parse_port(c"80");
+    let _ = ffi_mock::progress_callback()(1);
+
+    let _ = di_mock::Newsletter::new(di_mock::mail::SendEmailFn::default()).publish(&["test"], "test");
 }
@@ -0,0 +1,121 @@
+pub mod mail {
+    use fnmock::derive::mock_function;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct User {
+        pub id: u32,
+        pub name: String,
+        pub email: String,
+        pub is_admin: bool,
+    }
+
+    #[mock_function]
+    pub fn send_mail(user: User, subject: String) -> Result<(), String> {
+        println!("Sending {} to {}", subject, user.email);
+        Ok(())
+    }
+
+    #[mock_function]
+    pub fn send_newsletter(recipients: Vec<u32>) -> Result<(), String> {
+        println!("Sending newsletter to {:?}", recipients);
+        Ok(())
+    }
+}
+
+use mail::{send_mail, send_newsletter, User};
+
+pub fn welcome_user(id: u32, name: &str) -> Result<(), String> {
+    let user = User {
+        id,
+        name: name.to_string(),
+        email: format!("{}@example.com", name),
+        is_admin: false,
+    };
+    send_mail(user, format!("Welcome, {}!", name))
+}
+
+pub fn send_newsletter_to(users: &[User]) -> Result<(), String> {
+    let recipients = users.iter().filter(|user| !user.is_admin).map(|user| user.id).collect();
+    send_newsletter(recipients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::mail::{send_mail_mock, send_newsletter_mock};
+    use fnmock::matchers::{
+        any, contains, contains_element, ends_with, eq, field, is_empty, len_eq, not, starts_with, unordered_eq,
+        Matcher, MatcherExt,
+    };
+
+    #[test]
+    fn test_field_matchers() {
+        send_mail_mock::setup(|_| Ok(()));
+
+        welcome_user(42, "alice").unwrap();
+
+        // Only the relevant fields are asserted, instead of constructing the whole user
+        send_mail_mock::assert_with_matchers((
+            field(|user: &User| &user.id, eq(42)).and(field(|user: &User| &user.is_admin, eq(false))),
+            any(),
+        ));
+    }
+
+    #[test]
+    fn test_string_matchers() {
+        send_mail_mock::setup(|_| Ok(()));
+
+        welcome_user(7, "bob").unwrap();
+
+        send_mail_mock::assert_with_matchers((
+            field(|user: &User| &user.email, ends_with("@example.com")),
+            starts_with("Welcome").and(contains("bob")),
+        ));
+    }
+
+    #[test]
+    fn test_collection_matchers() {
+        send_newsletter_mock::setup(|_| Ok(()));
+
+        let user = |id, is_admin| User { id, name: String::new(), email: String::new(), is_admin };
+        send_newsletter_to(&[user(3, false), user(1, true), user(2, false)]).unwrap();
+
+        send_newsletter_mock::assert_with_matchers(len_eq(2).and(not(contains_element(1))));
+        send_newsletter_mock::assert_with_matchers(unordered_eq([2, 3]));
+        send_newsletter_mock::assert_with_matchers(not(is_empty()));
+    }
+
+    // Domain specific matcher, plugging into the assertions like the built-in matchers
+    struct CompanyEmail;
+
+    impl Matcher<User> for CompanyEmail {
+        fn matches(&self, user: &User) -> bool {
+            user.email.ends_with("@example.com")
+        }
+
+        fn describe(&self) -> String {
+            "company email".to_string()
+        }
+    }
+
+    impl MatcherExt for CompanyEmail {}
+
+    #[test]
+    fn test_custom_matcher() {
+        send_mail_mock::setup(|_| Ok(()));
+
+        welcome_user(42, "alice").unwrap();
+
+        send_mail_mock::assert_with_matchers((CompanyEmail.and(field(|user: &User| &user.id, eq(42))), any()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected send_mail_mock mock to be called with params matching (not company email, _)")]
+    fn test_custom_matcher_failure_message() {
+        send_mail_mock::setup(|_| Ok(()));
+
+        welcome_user(42, "alice").unwrap();
+
+        send_mail_mock::assert_with_matchers((not(CompanyEmail), any()));
+    }
+}
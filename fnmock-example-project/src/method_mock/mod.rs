@@ -0,0 +1,68 @@
+pub mod client {
+    use fnmock::derive::mock_method;
+
+    #[derive(Default)]
+    pub struct HttpClient {
+        base_url: String,
+    }
+
+    // Only `get` and `delete` are shimmed, the impl block stays unchanged
+    #[mock_method(get, delete)]
+    impl HttpClient {
+        pub fn get(&self, path: String) -> Result<String, String> {
+            Ok(format!("GET {}{}", self.base_url, path))
+        }
+
+        // The shim and the mock module only exist with the feature, `http_client_mock::clear()` skips them otherwise
+        #[cfg(feature = "postgres")]
+        pub fn delete(&self, path: String) -> Result<String, String> {
+            Ok(format!("DELETE {}{}", self.base_url, path))
+        }
+
+        pub fn header(&self, name: &str) -> Option<&str> {
+            (name == "host").then_some(self.base_url.as_str())
+        }
+    }
+}
+
+use client::{GetShim, HttpClient};
+
+// Routes the call through the shim, so it can be mocked
+pub fn fetch_profile(client: &HttpClient, id: u32) -> Result<String, String> {
+    client.get_shim(format!("/users/{}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::client::http_client_mock;
+
+    #[test]
+    fn test_shim_calls_mock() {
+        http_client_mock::get::setup(|_| Err("timeout".to_string()));
+
+        let result = fetch_profile(&HttpClient::default(), 1);
+
+        assert_eq!(result, Err("timeout".to_string()));
+        http_client_mock::get::assert_with("/users/1".to_string());
+    }
+
+    #[test]
+    fn test_shim_calls_original_method_without_mock() {
+        let client = HttpClient::default();
+
+        assert_eq!(fetch_profile(&client, 1), Ok("GET /users/1".to_string()));
+        // Calling the method directly bypasses the mock
+        http_client_mock::get::setup(|_| Err("timeout".to_string()));
+        assert_eq!(client.get("/".to_string()), Ok("GET /".to_string()));
+    }
+
+    #[test]
+    fn test_clear_resets_all_shimmed_methods() {
+        http_client_mock::get::setup(|_| Err("timeout".to_string()));
+
+        http_client_mock::clear();
+
+        assert!(!http_client_mock::get::is_set());
+    }
+}
\ No newline at end of file
@@ -11,6 +11,12 @@ pub mod db {
     pub fn fetch_notes(id: u32) -> Result<String, String> {
         Ok(format!("notes_{}", id))
     }
+
+    // Generates fetch_settings_mock and fetch_settings_fake, the mock overrides the fake
+    #[mock_function(fake)]
+    pub fn fetch_settings(id: u32) -> Result<String, String> {
+        Ok(format!("settings_{}", id))
+    }
 }
 
 use db::fetch_user;
@@ -27,7 +33,7 @@ pub fn handle_user(id: u32) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::db::{fetch_notes_mock, fetch_user_fake};
+    use super::db::{fetch_notes_mock, fetch_settings, fetch_settings_fake, fetch_settings_mock, fetch_user_fake};
 
     #[test]
     fn test_handle_invalid_user() {
@@ -48,4 +54,59 @@ mod tests {
 
         // No cleanup needed, since fakes are thread / test specific as well
     }
-}
\ No newline at end of file
+
+    // Test helper configuring the mock, the test doesn't need to know where the mock lives
+    fn setup_notes() -> fetch_notes_mock::MockHandle {
+        fetch_notes_mock::setup(|id| Ok(format!("mock notes_{}", id)))
+    }
+
+    #[test]
+    fn test_with_mock_handle() {
+        fetch_user_fake::setup(|id| Ok(format!("user_{}", id)));
+        let notes = setup_notes();
+
+        handle_user(1).unwrap();
+        handle_user(2).unwrap();
+
+        notes.assert_times(2);
+        notes.assert_with_ref(&2);
+        assert_eq!(notes.calls(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_handle_user_notes_error() {
+        fetch_user_fake::setup(|id| Ok(format!("user_{}", id)));
+        // Shorthand for fetch_notes_mock::setup(|_| Err("notes not found".to_string()))
+        fetch_notes_mock::setup_err("notes not found".to_string());
+
+        let err = handle_user(42).unwrap_err();
+
+        assert_eq!(err, "notes not found");
+        fetch_notes_mock::assert_with(42);
+
+        fetch_notes_mock::setup_ok("notes".to_string());
+
+        assert_eq!(handle_user(42), Ok(()));
+        fetch_notes_mock::assert_times(2);
+    }
+
+    #[test]
+    fn test_mock_overrides_fake() {
+        assert_eq!(fetch_settings(1), Ok("settings_1".to_string()));
+
+        fetch_settings_fake::setup(|id| Ok(format!("fake_settings_{}", id)));
+        assert_eq!(fetch_settings(1), Ok("fake_settings_1".to_string()));
+
+        fetch_settings_mock::setup_err("unavailable".to_string());
+        assert_eq!(fetch_settings(1), Err("unavailable".to_string()));
+
+        fnmock::resolution::set_order(fnmock::resolution::Order::FakeFirst);
+        assert_eq!(fetch_settings(2), Ok("fake_settings_2".to_string()));
+
+        // Only the call resolved to the mock is recorded
+        fetch_settings_mock::assert_times(1);
+        fetch_settings_mock::clear();
+        fnmock::resolution::set_order(fnmock::resolution::Order::MockFirst);
+        assert_eq!(fetch_settings(3), Ok("fake_settings_3".to_string()));
+    }
+}
@@ -0,0 +1,162 @@
+pub mod db {
+    use fnmock::derive::mock_function;
+
+    #[mock_function(group = "db")]
+    pub fn fetch_user(id: u32) -> Result<String, String> {
+        Ok(format!("user_{}", id))
+    }
+
+    #[mock_function(group = "db")]
+    pub fn save_user(id: u32, name: String) -> Result<(), String> {
+        println!("Save user {}: {}", id, name);
+        Ok(())
+    }
+}
+
+// Generates `mail::mocks` with lifecycle functions for the doubles of this module
+#[fnmock::derive::mock_module]
+pub mod mail {
+    use fnmock::derive::{mock_function, stub_function};
+
+    #[stub_function]
+    pub fn sender_address() -> String {
+        "noreply@example.com".to_string()
+    }
+
+    #[mock_function(group = "mail")]
+    pub fn send_email(to: String, body: String) -> Result<(), String> {
+        println!("Send email from {} to {}: {}", sender_address(), to, body);
+        Ok(())
+    }
+}
+
+// The file name differs from the module name, `mocks_in_crate!()` follows the `#[path]` attribute
+#[path = "welcome_template.rs"]
+pub mod template;
+
+use db::{fetch_user, save_user};
+use mail::send_email;
+
+pub fn rename_user(id: u32, name: String) -> Result<(), String> {
+    let old_name = fetch_user(id)?;
+    save_user(id, name.clone())?;
+    send_email(old_name, format!("You are now called {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::db::{fetch_user_mock, save_user_mock};
+    use super::mail::{send_email_mock, sender_address_stub};
+
+    fn setup_db() {
+        fetch_user_mock::configure(|m| {
+            m.ok("alice".to_string()).expect_times(1);
+        });
+        save_user_mock::configure(|m| {
+            m.ok(()).expect_times(1);
+        });
+    }
+
+    #[test]
+    fn test_verify_all_in_group() {
+        setup_db();
+        send_email_mock::setup_ok(());
+
+        rename_user(1, "bob".to_string()).unwrap();
+
+        // Verifies the expectations of fetch_user_mock and save_user_mock
+        fnmock::group("db").verify_all();
+        send_email_mock::assert_with("alice".to_string(), "You are now called bob".to_string());
+    }
+
+    #[test]
+    fn test_verify_all_reports_every_unmet_expectation() {
+        setup_db();
+
+        let result = std::panic::catch_unwind(|| fnmock::group("db").verify_all());
+
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("fetch_user_mock"));
+        assert!(message.contains("save_user_mock"));
+    }
+
+    #[test]
+    fn test_try_verify_all_returns_report() {
+        setup_db();
+        send_email_mock::setup_ok(());
+
+        fetch_user(1).unwrap();
+        let report = fnmock::try_verify_all();
+
+        assert!(!report.is_success());
+        let failures: Vec<&str> = report.failures().map(|mock| mock.name).collect();
+        assert_eq!(failures, vec!["fnmock_example_project::mock_groups::db::save_user_mock"]);
+        // The mail mock has no expectations, so it passes
+        assert_eq!(report.mocks().len(), 3);
+    }
+
+    #[test]
+    fn test_clear_group() {
+        setup_db();
+        send_email_mock::setup_ok(());
+
+        // Only clears the persistence layer
+        fnmock::group("db").clear();
+
+        assert!(!fetch_user_mock::is_set());
+        assert!(!save_user_mock::is_set());
+        assert!(send_email_mock::is_set());
+    }
+
+    #[test]
+    fn test_module_mocks() {
+        setup_db();
+        send_email_mock::configure(|m| {
+            m.ok(()).expect_times(1);
+        });
+        sender_address_stub::setup("test@example.com");
+
+        rename_user(1, "bob".to_string()).unwrap();
+        super::mail::mocks::verify_all();
+
+        // Only clears the doubles of the mail module
+        super::mail::mocks::clear_all();
+        assert!(!send_email_mock::is_set());
+        assert!(!sender_address_stub::is_set());
+        assert!(fetch_user_mock::is_set());
+    }
+
+    #[test]
+    fn test_every_db_function_is_mocked() {
+        // Lists the doubles of the whole crate, parsed from its source files
+        let doubles = fnmock::derive::mocks_in_crate!();
+        let db_module = format!("{}::db", module_path!().strip_suffix("::tests").unwrap());
+
+        let db_doubles: Vec<_> = doubles.iter().filter(|double| double.module_path == db_module).collect();
+
+        assert_eq!(db_doubles.iter().map(|double| double.function).collect::<Vec<_>>(), ["fetch_user", "save_user"]);
+        assert!(db_doubles.iter().all(|double| double.kind == fnmock::inventory::DoubleKind::Mock));
+        assert!(doubles.iter().any(|double| double.module == "sender_address_stub"));
+    }
+
+    #[test]
+    fn test_doubles_of_methods_fakes_and_instantiations_are_listed() {
+        let doubles = fnmock::derive::mocks_in_crate!();
+        let modules: Vec<_> = doubles.iter().map(|double| double.module).collect();
+
+        // Methods are listed with the module of their type or trait, skipped methods aren't
+        assert!(modules.contains(&"user_service_mock::connect"));
+        assert!(!modules.contains(&"user_service_mock::url"));
+        assert!(modules.contains(&"user_repository_mock::save_user"));
+        assert!(modules.contains(&"http_client_mock::get"));
+        assert!(doubles.iter().any(|double| double.function == "UserRepository::find_user"));
+
+        let template_module = format!("{}::template", module_path!().strip_suffix("::tests").unwrap());
+        assert!(doubles.iter().any(|double| double.module_path == template_module && double.module == "welcome_subject_mock"));
+
+        assert!(modules.contains(&"fetch_settings_fake"));
+        assert!(modules.contains(&"read_setting_u32_mock"));
+        assert!(!modules.contains(&"read_setting_mock"));
+    }
+}
@@ -0,0 +1,6 @@
+use fnmock::derive::mock_function;
+
+#[mock_function]
+pub fn welcome_subject(name: String) -> String {
+    format!("Welcome, {}", name)
+}
@@ -0,0 +1,125 @@
+pub mod service {
+    use fnmock::derive::mock_struct;
+
+    pub struct UserService {
+        url: String,
+        cache: Vec<(u32, String)>,
+    }
+
+    // Generates `user_service_mock` with a mock module per method
+    #[mock_struct(skip = [url])]
+    impl UserService {
+        pub fn connect(url: String) -> Result<Self, String> {
+            println!("Connect to {}", url);
+            Ok(Self { url, cache: Vec::new() })
+        }
+
+        pub fn find_user(&self, id: u32) -> Option<String> {
+            println!("Find user {} at {}", id, self.url);
+            self.cache.iter().find(|(cached, _)| *cached == id).map(|(_, name)| name.clone())
+        }
+
+        pub fn save_user(&mut self, id: u32, name: String) -> Result<(), String> {
+            self.cache.push((id, name));
+            Ok(())
+        }
+
+        pub fn url(&self) -> &str {
+            &self.url
+        }
+
+        // The mock module of the method only exists with the feature, `user_service_mock::clear()` skips it otherwise
+        #[cfg(feature = "postgres")]
+        pub fn vacuum(&self) -> Result<(), String> {
+            println!("Vacuum {}", self.url);
+            Ok(())
+        }
+    }
+
+    pub struct Session {
+        token: String,
+    }
+
+    // Only the constructor is mocked, without recording the time to live
+    #[mock_struct]
+    impl Session {
+        #[mock_function(ignore = [ttl])]
+        pub fn open(user: String, ttl: std::time::Duration) -> Result<Self, String> {
+            Ok(Self { token: format!("{}-{}", user, ttl.as_secs()) })
+        }
+
+        pub fn token(&self) -> &str {
+            &self.token
+        }
+    }
+}
+
+use service::{Session, UserService};
+
+pub fn rename_user(id: u32, name: String) -> Result<(), String> {
+    let mut service = UserService::connect("postgres://localhost/users".to_string())?;
+    service.find_user(id).ok_or("user not found".to_string())?;
+    service.save_user(id, name)
+}
+
+pub fn login(user: &str) -> Result<String, String> {
+    let session = Session::open(user.to_string(), std::time::Duration::from_secs(60))?;
+    Ok(session.token().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::service::{session_mock, user_service_mock};
+
+    #[test]
+    fn test_mock_methods_with_receivers() {
+        user_service_mock::find_user::setup(|_| Some("alice".to_string()));
+        user_service_mock::save_user::setup(|_| Ok(()));
+
+        rename_user(1, "bob".to_string()).unwrap();
+
+        // The constructor wasn't mocked, so the real implementation ran
+        user_service_mock::connect::assert_times(0);
+        user_service_mock::find_user::assert_with(1);
+        user_service_mock::save_user::assert_with(1, "bob".to_string());
+    }
+
+    #[test]
+    fn test_mock_constructor() {
+        user_service_mock::connect::setup(|url| Err(format!("{} unreachable", url)));
+
+        let result = rename_user(1, "bob".to_string());
+
+        assert_eq!(result, Err("postgres://localhost/users unreachable".to_string()));
+        user_service_mock::find_user::assert_times(0);
+    }
+
+    #[test]
+    fn test_unmocked_methods_run_real_implementation() {
+        let mut service = UserService::connect("test".to_string()).unwrap();
+        service.save_user(1, "alice".to_string()).unwrap();
+
+        assert_eq!(service.find_user(1), Some("alice".to_string()));
+        assert_eq!(service.url(), "test");
+    }
+
+    #[test]
+    fn test_mock_selected_associated_function() {
+        session_mock::open::setup(|user| Err(format!("{} is locked", user)));
+
+        assert_eq!(login("alice"), Err("alice is locked".to_string()));
+        session_mock::open::assert_with("alice".to_string());
+    }
+
+    #[test]
+    fn test_clear_resets_all_method_mocks() {
+        user_service_mock::find_user::setup(|_| None);
+        user_service_mock::save_user::setup(|_| Ok(()));
+
+        user_service_mock::clear();
+
+        assert!(!user_service_mock::find_user::is_set());
+        assert!(!user_service_mock::save_user::is_set());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,130 @@
+pub mod repository {
+    use fnmock::derive::mock_trait;
+
+    #[mock_trait]
+    pub trait UserRepository {
+        fn find_user(&self, id: u32) -> Option<String>;
+        fn save_user(&mut self, id: u32, name: String) -> Result<(), String>;
+    }
+
+    #[mock_trait]
+    pub(crate) trait Notifier {
+        async fn notify(&self, message: String) -> Result<(), String>;
+    }
+
+    /// Real repository, which is replaced by `MockUserRepository` in tests
+    #[derive(Default)]
+    pub struct InMemoryRepository {
+        users: std::collections::HashMap<u32, String>,
+    }
+
+    impl UserRepository for InMemoryRepository {
+        fn find_user(&self, id: u32) -> Option<String> {
+            self.users.get(&id).cloned()
+        }
+
+        fn save_user(&mut self, id: u32, name: String) -> Result<(), String> {
+            self.users.insert(id, name);
+            Ok(())
+        }
+    }
+
+    pub(crate) struct ConsoleNotifier;
+
+    impl Notifier for ConsoleNotifier {
+        async fn notify(&self, message: String) -> Result<(), String> {
+            println!("{}", message);
+            Ok(())
+        }
+    }
+}
+
+use repository::{Notifier, UserRepository};
+
+// Takes the repository as a trait object
+pub fn greet(repository: &dyn UserRepository, id: u32) -> String {
+    match repository.find_user(id) {
+        Some(name) => format!("Hello, {}!", name),
+        None => "Hello, stranger!".to_string(),
+    }
+}
+
+// Takes the repository as a generic parameter
+pub fn rename_user<R: UserRepository>(repository: &mut R, id: u32, name: &str) -> Result<(), String> {
+    repository.find_user(id).ok_or("user not found".to_string())?;
+    repository.save_user(id, name.to_string())
+}
+
+pub(crate) async fn announce(notifier: &impl Notifier, repository: &dyn UserRepository, id: u32) -> Result<(), String> {
+    notifier.notify(greet(repository, id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::repository::{notifier_mock, user_repository_mock, MockNotifier, MockUserRepository};
+    use fnmock::derive::mock_trait;
+
+    // `#[async_trait]` below `#[mock_trait]` boxes the futures of the mock like the ones of the trait,
+    // so the trait can be used as a trait object
+    #[mock_trait]
+    #[async_trait::async_trait]
+    trait Mailer {
+        async fn send(&self, to: String, body: String) -> Result<(), String>;
+    }
+
+    async fn send_welcome(mailer: &dyn Mailer, to: &str) -> Result<(), String> {
+        mailer.send(to.to_string(), "Welcome!".to_string()).await
+    }
+
+    #[test]
+    fn test_mock_trait_object() {
+        user_repository_mock::find_user::setup(|id| (id == 1).then(|| "alice".to_string()));
+
+        assert_eq!(greet(&MockUserRepository, 1), "Hello, alice!");
+        assert_eq!(greet(&MockUserRepository, 2), "Hello, stranger!");
+        user_repository_mock::find_user::assert_times(2);
+    }
+
+    #[test]
+    fn test_mock_generic_bound() {
+        user_repository_mock::find_user::setup(|_| Some("alice".to_string()));
+        user_repository_mock::save_user::setup(|_| Ok(()));
+
+        rename_user(&mut MockUserRepository, 1, "bob").unwrap();
+
+        user_repository_mock::save_user::assert_with(1, "bob".to_string());
+    }
+
+    #[test]
+    fn test_clear_clears_all_methods() {
+        user_repository_mock::find_user::setup(|_| None);
+        user_repository_mock::save_user::setup(|_| Ok(()));
+
+        user_repository_mock::clear();
+
+        assert!(!user_repository_mock::find_user::is_set());
+        assert!(!user_repository_mock::save_user::is_set());
+    }
+
+    #[tokio::test]
+    async fn test_mock_async_method() {
+        user_repository_mock::find_user::setup(|_| Some("alice".to_string()));
+        notifier_mock::notify::setup(|_| Err("offline".to_string()));
+
+        let result = announce(&MockNotifier, &MockUserRepository, 1).await;
+
+        assert_eq!(result, Err("offline".to_string()));
+        notifier_mock::notify::assert_with("Hello, alice!".to_string());
+    }
+
+    #[tokio::test]
+    async fn test_mock_async_trait_method() {
+        mailer_mock::send::setup(|(to, _)| Err(format!("{} bounced", to)));
+
+        let result = send_welcome(&MockMailer, "alice@example.com").await;
+
+        assert_eq!(result, Err("alice@example.com bounced".to_string()));
+        mailer_mock::send::assert_with("alice@example.com".to_string(), "Welcome!".to_string());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,71 @@
+/// Renders the difference between the expected and the actual calls of a mock line by line.
+///
+/// The lines are aligned with the longest common subsequence of both lists, so a single missing
+/// or unexpected call doesn't mark all following calls as different.
+///
+/// # Arguments
+///
+/// - `expected` - the `Debug` representation of the expected calls
+/// - `actual` - the `Debug` representation of the recorded calls
+///
+/// # Returns
+///
+/// The diff, with matching calls prefixed by two spaces, missing calls by `- ` and unexpected calls by `+ `, e.g.
+///
+/// ```text
+///   1
+/// - 2
+/// + 3
+/// ```
+pub(crate) fn format_call_diff(expected: &[String], actual: &[String]) -> String {
+    // common[i][j] is the length of the longest common subsequence of expected[i..] and actual[j..]
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j == actual.len() || (i < expected.len() && common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_format_call_diff_marks_missing_and_unexpected_calls() {
+        let diff = format_call_diff(&strings(&["1", "2", "3"]), &strings(&["1", "4", "3", "5"]));
+        assert_eq!(diff, "  1\n- 2\n+ 4\n  3\n+ 5");
+    }
+
+    #[test]
+    fn test_format_call_diff_without_calls() {
+        let diff = format_call_diff(&strings(&["1"]), &[]);
+        assert_eq!(diff, "- 1");
+    }
+}
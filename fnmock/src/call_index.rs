@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Hash index over the call history of a `FunctionMock`
+///
+/// Maps the hash of the recorded params to their positions in the call history,
+/// so looking up whether the mock was called with some params doesn't scan the whole history.
+/// The hash function is stored as a function pointer, so `FunctionMock` only requires `Hash`
+/// from params that are actually indexed.
+///
+/// # Fields
+///
+/// - `hash` - hashes the params
+/// - `positions` - the positions of the calls in the call history by the hash of their params
+pub(crate) struct CallIndex<Params> {
+    hash: fn(&Params) -> u64,
+    positions: HashMap<u64, Vec<usize>>,
+}
+
+fn hash_params<Params: Hash>(params: &Params) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    params.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<Params> CallIndex<Params> {
+    /// Creates an index over the already recorded calls.
+    pub(crate) fn new(calls: &[Params]) -> Self
+    where
+        Params: Hash,
+    {
+        let mut index = Self {
+            hash: hash_params::<Params>,
+            positions: HashMap::new(),
+        };
+        for (position, params) in calls.iter().enumerate() {
+            index.insert(params, position);
+        }
+        index
+    }
+
+    pub(crate) fn insert(&mut self, params: &Params, position: usize) {
+        self.positions.entry((self.hash)(params)).or_default().push(position);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.positions.clear();
+    }
+
+    /// Returns the positions of the calls with `params`, only comparing the calls with the same hash.
+    pub(crate) fn positions_of<'a>(&'a self, calls: &'a [Params], params: &'a Params) -> impl Iterator<Item = usize> + 'a
+    where
+        Params: PartialEq,
+    {
+        self.positions
+            .get(&(self.hash)(params))
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(move |&position| calls[position] == *params)
+    }
+
+    /// Checks if `params` are among `calls`, only comparing the calls with the same hash.
+    pub(crate) fn contains(&self, calls: &[Params], params: &Params) -> bool
+    where
+        Params: PartialEq,
+    {
+        self.positions_of(calls, params).next().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_finds_recorded_params() {
+        let calls = vec![(1, "a"), (2, "b")];
+        let index = CallIndex::new(&calls);
+
+        assert!(index.contains(&calls, &(2, "b")));
+        assert!(!index.contains(&calls, &(2, "a")));
+    }
+
+    #[test]
+    fn test_positions_of_returns_every_matching_call() {
+        let calls = vec![1, 2, 1];
+        let index = CallIndex::new(&calls);
+
+        assert_eq!(index.positions_of(&calls, &1).collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_insert_adds_new_calls() {
+        let mut calls = vec![1];
+        let mut index = CallIndex::new(&calls);
+
+        calls.push(2);
+        index.insert(&2, 1);
+
+        assert!(index.contains(&calls, &2));
+    }
+}
@@ -0,0 +1,138 @@
+/// Renders the call history of a mock as an aligned table.
+///
+/// Normally you don't need to call this function.
+/// It backs the generated `format_calls` proxies, e.g. `fetch_user_mock::format_calls()`.
+///
+/// # Arguments
+///
+/// - `name` - the name of the mock for the title
+/// - `param_names` - the names of the recorded (non-ignored) parameters
+/// - `rows` - the `Debug` representation of every parameter for each call
+///
+/// # Returns
+///
+/// The table, e.g.
+///
+/// ```text
+/// fetch_user_mock calls:
+/// | # | id | name    |
+/// |---|----|---------|
+/// | 1 | 1  | "alice" |
+/// | 2 | 2  | "bob"   |
+/// ```
+pub fn format_call_table(name: &str, param_names: &[&str], rows: &[Vec<String>]) -> String {
+    if rows.is_empty() {
+        return format!("{} was not called", name);
+    }
+
+    let mut headers = vec!["#".to_string()];
+    headers.extend(param_names.iter().map(|param_name| param_name.to_string()));
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let mut cells = vec![(idx + 1).to_string()];
+            cells.extend(row.iter().cloned());
+            cells
+        })
+        .collect();
+
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| {
+            cells
+                .iter()
+                .map(|row| row[column].chars().count())
+                .chain(std::iter::once(header.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut table = format!("{} calls:\n", name);
+    table.push_str(&format_row(&headers, &widths));
+    table.push_str(&format_separator(&widths));
+    for row in &cells {
+        table.push_str(&format_row(row, &widths));
+    }
+
+    // The last line doesn't need a line break
+    table.pop();
+    table
+}
+
+fn format_row(cells: &[String], widths: &[usize]) -> String {
+    let mut row = String::from("|");
+    for (cell, width) in cells.iter().zip(widths) {
+        let padding = width - cell.chars().count();
+        row.push_str(&format!(" {}{} |", cell, " ".repeat(padding)));
+    }
+    row.push('\n');
+    row
+}
+
+fn format_separator(widths: &[usize]) -> String {
+    let mut separator = String::from("|");
+    for width in widths {
+        separator.push_str(&format!("{}|", "-".repeat(width + 2)));
+    }
+    separator.push('\n');
+    separator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_call_table_without_calls() {
+        let table = format_call_table("fetch_user_mock", &["id"], &[]);
+        assert_eq!(table, "fetch_user_mock was not called");
+    }
+
+    #[test]
+    fn test_format_call_table_aligns_columns() {
+        let rows = vec![
+            vec!["1".to_string(), "\"alice\"".to_string()],
+            vec!["20".to_string(), "\"bob\"".to_string()],
+        ];
+
+        let table = format_call_table("fetch_user_mock", &["id", "name"], &rows);
+
+        assert_eq!(table, "\
+fetch_user_mock calls:
+| # | id | name    |
+|---|----|---------|
+| 1 | 1  | \"alice\" |
+| 2 | 20 | \"bob\"   |");
+    }
+
+    #[test]
+    fn test_format_call_table_without_params() {
+        let rows = vec![vec![], vec![]];
+
+        let table = format_call_table("get_time_mock", &[], &rows);
+
+        assert_eq!(table, "\
+get_time_mock calls:
+| # |
+|---|
+| 1 |
+| 2 |");
+    }
+
+    #[test]
+    fn test_format_call_table_counts_chars_instead_of_bytes() {
+        let rows = vec![vec!["\"ä\"".to_string()]];
+
+        let table = format_call_table("greet_mock", &["name"], &rows);
+
+        assert_eq!(table, "\
+greet_mock calls:
+| # | name |
+|---|------|
+| 1 | \"ä\"  |");
+    }
+}
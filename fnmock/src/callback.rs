@@ -0,0 +1,303 @@
+//! Spies on callbacks passed into the code under test
+//!
+//! APIs taking a callback, like an event subscription, can't be mocked with the attribute macros,
+//! since the callback is a value instead of a function. `mock_callback!` creates a closure backed by
+//! a `FunctionMock` to pass in, and a `CallbackSpy` to configure it and assert the calls it received.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let (callback, callback_spy) = fnmock::mock_callback!(fn(Event) -> ());
+//!
+//! subscribe(callback);
+//! publish_all(&events);
+//!
+//! callback_spy.assert_times(3);
+//! callback_spy.assert_with(Event::Closed);
+//! ```
+
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
+use crate::function_mock::FunctionMock;
+use crate::mock_control::MockControl;
+
+/// Handle to the mock behind a callback created with `mock_callback!`
+///
+/// The parameters of the callback are recorded like the parameters of a generated mock,
+/// as a single value for one parameter and as a tuple for several parameters.
+///
+/// The callback and the spy share the mock, so the spy can be cloned and kept after the
+/// callback was moved into the code under test. Both are bound to the thread they were created on.
+pub struct CallbackSpy<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    name: &'static str,
+    mock: Rc<RefCell<FunctionMock<Params, Result>>>,
+    default: Option<fn(Params) -> Result>,
+}
+
+impl<Params, Result> CallbackSpy<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    /// Creates a spy with an unconfigured mock.
+    ///
+    /// Normally you don't need to call this function, it backs `mock_callback!`.
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - the name used in the messages of the spy, e.g. `callback`
+    /// - `definition` - where the callback was created, e.g. `src/events.rs:12`
+    pub fn new(name: &'static str, definition: &'static str) -> Self {
+        Self {
+            name,
+            mock: Rc::new(RefCell::new(FunctionMock::new(name).with_definition(definition))),
+            default: None,
+        }
+    }
+
+    /// Sets the implementation used until the callback is set up and after it is cleared,
+    /// without counting as a setup.
+    pub fn with_default(mut self, default: fn(Params) -> Result) -> Self {
+        self.mock.borrow_mut().setup(default);
+        self.default = Some(default);
+        self
+    }
+
+    /// Calls the mock with the params of a call of the callback.
+    pub fn call(&self, params: Params) -> Result {
+        self.mock.borrow_mut().call(params)
+    }
+
+    /// Sets the implementation of the callback.
+    #[track_caller]
+    pub fn setup(&self, new_f: fn(Params) -> Result) {
+        let mut mock = self.mock.borrow_mut();
+        mock.record_setup(std::panic::Location::caller());
+        mock.setup(new_f);
+    }
+
+    /// Makes the callback return a clone of the value on every call, ignoring the params.
+    #[track_caller]
+    pub fn setup_returning(&self, return_value: Result)
+    where
+        Result: Clone,
+    {
+        let mut mock = self.mock.borrow_mut();
+        mock.record_setup(std::panic::Location::caller());
+        mock.setup_returning(return_value);
+    }
+
+    /// Resets the implementation to the default and clears the call history of the callback.
+    pub fn clear(&self) {
+        let mut mock = self.mock.borrow_mut();
+        mock.clear();
+        if let Some(default) = self.default {
+            mock.setup(default);
+        }
+    }
+
+    /// Returns a copy of the call history.
+    pub fn calls(&self) -> Vec<Params> {
+        self.mock.borrow().calls().to_vec()
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.mock.borrow().call_count()
+    }
+
+    pub fn was_called(&self) -> bool {
+        self.mock.borrow().was_called()
+    }
+
+    pub fn was_called_with(&self, params: &Params) -> bool {
+        self.mock.borrow().was_called_with(params)
+    }
+
+    #[track_caller]
+    pub fn assert_times(&self, expected_num_of_calls: u32) {
+        self.mock.borrow().assert_times(expected_num_of_calls)
+    }
+
+    /// Asserts like `assert_times`, labeling a failure with `message`.
+    #[track_caller]
+    pub fn assert_times_msg(&self, expected_num_of_calls: u32, message: &str) {
+        self.mock.borrow().assert_times_msg(expected_num_of_calls, message)
+    }
+
+    #[track_caller]
+    pub fn assert_with(&self, params: Params) {
+        self.mock.borrow().assert_with(params)
+    }
+
+    /// Asserts like `assert_with`, labeling a failure with `message`.
+    #[track_caller]
+    pub fn assert_with_msg(&self, params: Params, message: &str) {
+        self.mock.borrow().assert_with_msg(params, message)
+    }
+
+    /// Asserts that the complete, ordered call history equals `expected`.
+    #[track_caller]
+    pub fn assert_calls_exact(&self, expected: &[Params]) {
+        self.mock.borrow().assert_calls_exact(expected)
+    }
+
+    /// Asserts that every recorded call was covered by `assert_with` or `assert_calls_exact`.
+    #[track_caller]
+    pub fn verify_no_more_interactions(&self) {
+        self.mock.borrow().verify_no_more_interactions()
+    }
+}
+
+impl<Params, Result> Clone for CallbackSpy<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            mock: self.mock.clone(),
+            default: self.default,
+        }
+    }
+}
+
+impl<Params, Result> Debug for CallbackSpy<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CallbackSpy").field(&self.mock.borrow()).finish()
+    }
+}
+
+impl<Params, Result> MockControl for CallbackSpy<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn clear(&self) {
+        CallbackSpy::clear(self)
+    }
+
+    #[track_caller]
+    fn assert_times(&self, expected_num_of_calls: u32) {
+        CallbackSpy::assert_times(self, expected_num_of_calls)
+    }
+
+    fn check_no_more_interactions(&self) -> std::result::Result<(), String> {
+        self.mock.borrow().check_no_more_interactions()
+    }
+}
+
+/// Creates a callback closure backed by a mock and a `CallbackSpy` to configure and assert it.
+///
+/// Takes the signature of the callback as a function pointer type with up to 4 parameters.
+/// Callbacks without a return type or returning `()` record their calls without being set up,
+/// callbacks returning a value have to be set up with `setup` or `setup_returning` before they are called.
+///
+/// # Example
+///
+/// ```ignore
+/// let (on_event, on_event_spy) = fnmock::mock_callback!(fn(Event));
+/// let (filter, filter_spy) = fnmock::mock_callback!(fn(u32, String) -> bool);
+/// filter_spy.setup(|(id, _)| id > 1);
+///
+/// subscribe(filter, on_event);
+///
+/// on_event_spy.assert_times(3);
+/// filter_spy.assert_with((2, "user".to_string()));
+/// ```
+#[macro_export]
+macro_rules! mock_callback {
+    (fn($($param:ty),* $(,)?)) => {
+        $crate::mock_callback!(fn($($param),*) -> ())
+    };
+    (fn($($param:ty),* $(,)?) -> ()) => {{
+        let (callback, spy) = $crate::mock_callback!(@create fn($($param),*) -> ());
+        (callback, spy.with_default(|_| ()))
+    }};
+    (fn($($param:ty),* $(,)?) -> $ret:ty) => {
+        $crate::mock_callback!(@create fn($($param),*) -> $ret)
+    };
+    (@create fn() -> $ret:ty) => {{
+        let spy = $crate::callback::CallbackSpy::<(), $ret>::new("callback", concat!(file!(), ":", line!()));
+        let mock = spy.clone();
+        (move || mock.call(()), spy)
+    }};
+    (@create fn($a:ty) -> $ret:ty) => {{
+        let spy = $crate::callback::CallbackSpy::<$a, $ret>::new("callback", concat!(file!(), ":", line!()));
+        let mock = spy.clone();
+        (move |a: $a| mock.call(a), spy)
+    }};
+    (@create fn($a:ty, $b:ty) -> $ret:ty) => {{
+        let spy = $crate::callback::CallbackSpy::<($a, $b), $ret>::new("callback", concat!(file!(), ":", line!()));
+        let mock = spy.clone();
+        (move |a: $a, b: $b| mock.call((a, b)), spy)
+    }};
+    (@create fn($a:ty, $b:ty, $c:ty) -> $ret:ty) => {{
+        let spy = $crate::callback::CallbackSpy::<($a, $b, $c), $ret>::new("callback", concat!(file!(), ":", line!()));
+        let mock = spy.clone();
+        (move |a: $a, b: $b, c: $c| mock.call((a, b, c)), spy)
+    }};
+    (@create fn($a:ty, $b:ty, $c:ty, $d:ty) -> $ret:ty) => {{
+        let spy = $crate::callback::CallbackSpy::<($a, $b, $c, $d), $ret>::new("callback", concat!(file!(), ":", line!()));
+        let mock = spy.clone();
+        (move |a: $a, b: $b, c: $c, d: $d| mock.call((a, b, c, d)), spy)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    fn publish(events: &[u32], callback: impl Fn(u32)) {
+        for event in events {
+            callback(*event);
+        }
+    }
+
+    #[test]
+    fn test_unit_callback_records_calls_without_setup() {
+        let (callback, spy) = crate::mock_callback!(fn(u32));
+
+        publish(&[1, 2, 3], callback);
+
+        spy.assert_times(3);
+        spy.assert_with(2);
+        assert_eq!(spy.calls(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_callback_with_several_params_records_tuples() {
+        let (callback, spy) = crate::mock_callback!(fn(u32, String) -> bool);
+        spy.setup(|(id, _)| id > 1);
+
+        assert!(!callback(1, "a".to_string()));
+        assert!(callback(2, "b".to_string()));
+
+        spy.assert_calls_exact(&[(1, "a".to_string()), (2, "b".to_string())]);
+    }
+
+    #[test]
+    fn test_clear_restores_default() {
+        let (callback, spy) = crate::mock_callback!(fn() -> ());
+        callback();
+
+        spy.clear();
+        callback();
+
+        spy.assert_times(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "callback mock not initialized")]
+    fn test_callback_returning_value_without_setup_panics() {
+        let (callback, _spy) = crate::mock_callback!(fn(u32) -> bool);
+
+        callback(1);
+    }
+}
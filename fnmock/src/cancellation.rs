@@ -0,0 +1,107 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Future returning `Pending` once before completing, independent of the async runtime
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Guard calling `on_cancel` if it is dropped before being disarmed
+struct CancellationGuard {
+    on_cancel: Option<fn()>,
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        if let Some(on_cancel) = self.on_cancel.take() {
+            on_cancel();
+        }
+    }
+}
+
+/// Yields once to the async runtime and calls `on_cancel` if the future is dropped meanwhile.
+///
+/// The generated async mocks await this after being called, if they are set up with
+/// `yield_before_returning()`, so callers racing them in `select!` can cancel the in-flight call.
+///
+/// # Example
+///
+/// ```ignore
+/// fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+/// fetch_user_mock::yield_before_returning();
+///
+/// tokio::select! {
+///     _ = fetch_user(1) => unreachable!(),
+///     _ = std::future::ready(()) => {}
+/// }
+///
+/// assert!(fetch_user_mock::was_cancelled());
+/// ```
+pub async fn cancellation_point(on_cancel: fn()) {
+    let mut guard = CancellationGuard { on_cancel: Some(on_cancel) };
+    YieldNow { yielded: false }.await;
+    guard.on_cancel = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::task::Waker;
+
+    thread_local! {
+        static CANCELLED: Cell<u32> = const { Cell::new(0) };
+    }
+
+    fn record_cancelled() {
+        CANCELLED.with(|cancelled| cancelled.set(cancelled.get() + 1));
+    }
+
+    fn poll<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+        future.poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn test_cancellation_point_completes_after_yielding_once() {
+        let mut future = Box::pin(cancellation_point(record_cancelled));
+
+        assert_eq!(poll(future.as_mut()), Poll::Pending);
+        assert_eq!(poll(future.as_mut()), Poll::Ready(()));
+        drop(future);
+
+        assert_eq!(CANCELLED.with(Cell::get), 0);
+    }
+
+    #[test]
+    fn test_dropping_pending_cancellation_point_calls_on_cancel() {
+        let mut future = Box::pin(cancellation_point(record_cancelled));
+
+        assert_eq!(poll(future.as_mut()), Poll::Pending);
+        drop(future);
+
+        assert_eq!(CANCELLED.with(Cell::get), 1);
+    }
+
+    #[test]
+    fn test_dropping_unpolled_cancellation_point_does_not_call_on_cancel() {
+        // The mock wasn't called yet, so nothing was in flight
+        drop(cancellation_point(record_cancelled));
+
+        assert_eq!(CANCELLED.with(Cell::get), 0);
+    }
+}
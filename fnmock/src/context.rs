@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::rc::Rc;
+
+// The context of the innermost `with_context` call, shared with the calls recorded in it
+thread_local! {
+    static CURRENT_CONTEXT: RefCell<Option<Rc<str>>> = const { RefCell::new(None) };
+}
+
+/// Guard restoring the outer context when `with_context` returns or its body panics
+struct ContextGuard {
+    outer: Option<Rc<str>>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let outer = self.outer.take();
+        CURRENT_CONTEXT.with(|context| *context.borrow_mut() = outer);
+    }
+}
+
+/// Runs `body` and tags all mock calls made in it with the context `id`.
+///
+/// When a test drives multiple logical requests through the same mocks, the calls can be
+/// asserted per request with the generated `assert_times_in_context` proxies.
+/// Nested calls tag the calls with the innermost context. Like the mocks, the context is thread-local.
+///
+/// # Example
+///
+/// ```ignore
+/// fnmock::with_context("request-1", || handle_request(1));
+/// fnmock::with_context("request-2", || handle_request(2));
+///
+/// fetch_user_mock::assert_times_in_context("request-1", 1);
+/// ```
+///
+/// # Returns
+///
+/// The return value of `body`
+pub fn with_context<T>(id: impl Display, body: impl FnOnce() -> T) -> T {
+    let id: Rc<str> = id.to_string().into();
+    let outer = CURRENT_CONTEXT.with(|context| context.borrow_mut().replace(id));
+    let _guard = ContextGuard { outer };
+    body()
+}
+
+/// Returns the context of the current `with_context` call or None.
+pub(crate) fn current_context() -> Option<Rc<str>> {
+    CURRENT_CONTEXT.with(|context| context.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_context_sets_context_in_body() {
+        assert_eq!(current_context(), None);
+
+        let context = with_context("request-1", current_context);
+
+        assert_eq!(context.as_deref(), Some("request-1"));
+        assert_eq!(current_context(), None);
+    }
+
+    #[test]
+    fn test_nested_with_context_restores_outer_context() {
+        with_context(1, || {
+            with_context(2, || assert_eq!(current_context().as_deref(), Some("2")));
+            assert_eq!(current_context().as_deref(), Some("1"));
+        });
+    }
+
+    #[test]
+    fn test_with_context_restores_context_when_body_panics() {
+        let result = std::panic::catch_unwind(|| with_context("request-1", || panic!("failure")));
+
+        assert!(result.is_err());
+        assert_eq!(current_context(), None);
+    }
+}
@@ -0,0 +1,447 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Display, Formatter};
+use std::panic::Location;
+use crate::function_stub::{CallerPath, StubConditions, StubSnapshot};
+use crate::messages::{panic_not_initialized, panic_snapshot_of_iterator, panic_values_exhausted};
+use crate::strict::{self, RealCallGuard};
+
+/// Struct for stubbing a function with a `Copy` return type
+///
+/// Works like `FunctionStub`, but stores the return value in a `Cell`, so the stub doesn't need
+/// to be wrapped in a `RefCell` and returning the value is a plain copy without borrow checks.
+/// The `stub_function` macro uses it instead of `FunctionStub` if the return type is known to be `Copy`
+/// (primitives like `bool` and `u32`, shared references and tuples / arrays of them).
+///
+/// # Generics
+///
+/// - `ReturnType: Copy` - the return type of the stubbed function
+///
+/// # Usage
+///
+/// ```ignore
+/// pub(crate) mod is_feature_enabled_stub {
+///     use fnmock::copy_function_stub::CopyFunctionStub;
+///
+///     thread_local! {
+///         static STUB: CopyFunctionStub<bool> = CopyFunctionStub::new("is_feature_enabled");
+///     }
+///
+///     pub(crate) fn get_return_value() -> bool {
+///         STUB.with(|stub| { stub.get_return_value() })
+///     }
+///
+///     // ...
+/// }
+/// ```
+///
+/// # Fields
+///
+/// - `name` - the name of the function for display purposes when panicking
+/// - `return_value` - the stubbed return value or None
+/// - `panic_message` - the message every call panics with, if set up with `setup_panic`
+/// - `values` - the iterator yielding the return value of each call, if set up with `setup_from_iter`
+/// - `has_values` - whether `values` is set, so the other setups are checked without borrowing it
+/// - `conditions` - the return values of the calls matching a predicate, if set up with `setup_when` or `setup_for_caller`
+/// - `has_conditions` - whether `conditions` isn't empty, so the calls of a stub without conditions don't borrow it
+/// - `setup_location` - where the stub was last set up through its module, or None since it was cleared
+/// - `definition` - the location of the function appended to the messages, e.g. `src/db.rs:12`, or None
+/// - `real_call_guard` - whether the real implementation may run, see `fnmock::strict::set_real_implementation`
+pub struct CopyFunctionStub<ReturnType>
+where
+    ReturnType: Copy,
+{
+    name: &'static str,
+    return_value: Cell<Option<ReturnType>>,
+    panic_message: Cell<Option<&'static str>>,
+    values: RefCell<Option<Box<dyn Iterator<Item = ReturnType>>>>,
+    has_values: Cell<bool>,
+    conditions: RefCell<StubConditions<ReturnType>>,
+    has_conditions: Cell<bool>,
+    setup_location: Cell<Option<&'static Location<'static>>>,
+    definition: Option<&'static str>,
+    real_call_guard: Cell<RealCallGuard>,
+}
+
+impl<ReturnType> CopyFunctionStub<ReturnType>
+where
+    ReturnType: Copy,
+{
+    pub const fn new(function_name: &'static str) -> Self {
+        Self {
+            name: function_name,
+            return_value: Cell::new(None),
+            panic_message: Cell::new(None),
+            values: RefCell::new(None),
+            has_values: Cell::new(false),
+            conditions: RefCell::new(StubConditions::new()),
+            has_conditions: Cell::new(false),
+            setup_location: Cell::new(None),
+            definition: None,
+            real_call_guard: Cell::new(RealCallGuard::new()),
+        }
+    }
+
+    /// Sets the location of the stubbed function, e.g. `src/db.rs:12`, which is appended to the panic messages of the stub.
+    pub const fn with_definition(mut self, definition: &'static str) -> Self {
+        self.definition = Some(definition);
+        self
+    }
+
+    // --- Stubbing ---
+
+    /// Records where the stub is set up, reporting a repeated setup as configured in `fnmock::strict`.
+    pub fn record_setup(&self, location: &'static Location<'static>) {
+        strict::check_setup(self.name, "stub", None, self.definition, self.setup_location.get(), location);
+        self.setup_location.set(Some(location));
+    }
+
+    pub fn setup(&self, new_r: ReturnType) {
+        self.record_configured();
+        self.return_value.set(Some(new_r));
+        self.panic_message.set(None);
+        self.set_values(None);
+    }
+
+    /// Returns the next value of `values` on every call, e.g. `1..` for an ID generator.
+    ///
+    /// The stub panics once the values are exhausted.
+    pub fn setup_from_iter<I>(&self, values: I)
+    where
+        I: IntoIterator<Item = ReturnType>,
+        I::IntoIter: 'static,
+    {
+        self.record_configured();
+        self.return_value.set(None);
+        self.panic_message.set(None);
+        self.set_values(Some(Box::new(values.into_iter())));
+    }
+
+    /// Makes every call panic with `message`, simulating a crashing dependency.
+    pub fn setup_panic(&self, message: &'static str) {
+        self.record_configured();
+        self.return_value.set(None);
+        self.panic_message.set(Some(message));
+        self.set_values(None);
+    }
+
+    /// Returns `return_value` for the calls matching `predicate`, see `FunctionStub::setup_when`.
+    pub fn setup_when(&self, predicate: Box<dyn Any>, return_value: ReturnType) {
+        self.record_configured();
+        self.conditions.borrow_mut().push(predicate, return_value);
+        self.has_conditions.set(true);
+    }
+
+    /// Returns `return_value` for the calls from the source files at `path`, see `FunctionStub::setup_for_caller`.
+    pub fn setup_for_caller(&self, path: &'static str, return_value: ReturnType) {
+        self.record_configured();
+        self.conditions.borrow_mut().push(Box::new(CallerPath(path)), return_value);
+        self.has_conditions.set(true);
+    }
+
+    pub fn clear(&self) {
+        self.return_value.set(None);
+        self.panic_message.set(None);
+        self.set_values(None);
+        self.clear_conditions();
+        self.setup_location.set(None);
+        self.update_real_call_guard(RealCallGuard::clear);
+    }
+
+    /// Declares the stub required, see `FunctionStub::require`.
+    pub fn require(&self) {
+        self.update_real_call_guard(RealCallGuard::require);
+    }
+
+    /// Checks that the real implementation of the function may run, see `FunctionStub::check_real_call`.
+    pub fn check_real_call(&self) {
+        self.real_call_guard.get().check(self.name, "stub", None, self.definition);
+    }
+
+    fn set_values(&self, values: Option<Box<dyn Iterator<Item = ReturnType>>>) {
+        if values.is_none() && !self.has_values.get() {
+            return;
+        }
+        self.has_values.set(values.is_some());
+        self.values.replace(values);
+    }
+
+    fn clear_conditions(&self) {
+        if self.has_conditions.replace(false) {
+            self.conditions.borrow_mut().clear();
+        }
+    }
+
+    fn record_configured(&self) {
+        self.update_real_call_guard(RealCallGuard::record_configured);
+    }
+
+    fn update_real_call_guard(&self, update: fn(&mut RealCallGuard)) {
+        let mut guard = self.real_call_guard.get();
+        update(&mut guard);
+        self.real_call_guard.set(guard);
+    }
+
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.has_default() || self.has_conditions.get()
+    }
+
+    /// Checks if the stub returns a value for every call, in contrast to `setup_when`.
+    ///
+    /// Only reads the `Cell`s, so a stub set up with `setup` never borrows a `RefCell`.
+    fn has_default(&self) -> bool {
+        self.return_value.get().is_some() || self.panic_message.get().is_some() || self.has_values.get()
+    }
+
+    // --- Snapshots ---
+
+    /// Captures the configuration of the stub, so it can be restored with `restore` later on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stub is set up with `setup_from_iter`, whose iterator can't be copied.
+    pub fn snapshot(&self) -> StubSnapshot<ReturnType> {
+        if self.has_values.get() {
+            panic_snapshot_of_iterator(self.name, self.definition)
+        }
+        StubSnapshot {
+            return_value: self.return_value.get(),
+            panic_message: self.panic_message.get(),
+        }
+    }
+
+    /// Replaces the configuration of the stub with the snapshot.
+    ///
+    /// The conditions of `setup_when` aren't captured, so they are removed.
+    pub fn restore(&self, snapshot: &StubSnapshot<ReturnType>) {
+        self.record_configured();
+        self.return_value.set(snapshot.return_value);
+        self.panic_message.set(snapshot.panic_message);
+        self.set_values(None);
+        self.clear_conditions();
+    }
+
+    #[inline]
+    pub fn get_return_value(&self) -> ReturnType {
+        match (self.return_value.get(), self.panic_message.get()) {
+            (Some(return_value), _) => return_value,
+            (None, Some(message)) => panic!("{}", message),
+            (None, None) => self.next_value(),
+        }
+    }
+
+    /// Returns the value of the first matching condition, see `FunctionStub::get_return_value_when`.
+    #[inline]
+    pub fn get_return_value_when<P: 'static>(&self, matches: impl Fn(&P) -> bool) -> Option<ReturnType> {
+        self.get_return_value_for(None, matches)
+    }
+
+    /// Returns the value of the first condition matching the call or its caller, see `FunctionStub::get_return_value_for_caller`.
+    #[inline]
+    pub fn get_return_value_for_caller<P: 'static>(
+        &self,
+        caller: &Location<'_>,
+        matches: impl Fn(&P) -> bool,
+    ) -> Option<ReturnType> {
+        self.get_return_value_for(Some(caller), matches)
+    }
+
+    fn get_return_value_for<P: 'static>(
+        &self,
+        caller: Option<&Location<'_>>,
+        matches: impl Fn(&P) -> bool,
+    ) -> Option<ReturnType> {
+        // A stub without conditions, e.g. set up with `setup`, doesn't borrow them
+        let condition_value = match self.has_conditions.get() {
+            true => self.conditions.borrow().find(caller, matches).copied(),
+            false => None,
+        };
+        match condition_value {
+            Some(return_value) => Some(return_value),
+            None => self.has_default().then(|| self.get_return_value()),
+        }
+    }
+
+    fn next_value(&self) -> ReturnType {
+        let next_value = match self.values.borrow_mut().as_mut() {
+            Some(values) => values.next(),
+            None => panic_not_initialized(self.name, "stub", self.definition),
+        };
+        next_value.unwrap_or_else(|| panic_values_exhausted(self.name, self.definition))
+    }
+}
+
+// The return value isn't required to implement Debug, so only whether it is set is shown
+impl<ReturnType> Debug for CopyFunctionStub<ReturnType>
+where
+    ReturnType: Copy,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CopyFunctionStub")
+            .field("name", &self.name)
+            .field("is_set", &self.is_set())
+            .finish()
+    }
+}
+
+impl<ReturnType> Display for CopyFunctionStub<ReturnType>
+where
+    ReturnType: Copy,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.is_set() { "set" } else { "not set" };
+        write!(f, "{} stub ({})", self.name, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_creates_stub_without_return_value() {
+        let stub: CopyFunctionStub<bool> = CopyFunctionStub::new("is_enabled");
+        assert_eq!(stub.name, "is_enabled");
+        assert!(!stub.is_set());
+    }
+
+    #[test]
+    fn test_get_return_value_returns_configured_value() {
+        let stub: CopyFunctionStub<u32> = CopyFunctionStub::new("get_port");
+        stub.setup(8080);
+
+        assert_eq!(stub.get_return_value(), 8080);
+        assert_eq!(stub.get_return_value(), 8080);
+    }
+
+    #[test]
+    fn test_setup_overwrites_return_value() {
+        let stub: CopyFunctionStub<bool> = CopyFunctionStub::new("is_enabled");
+        stub.setup(true);
+        stub.setup(false);
+
+        assert!(!stub.get_return_value());
+    }
+
+    #[test]
+    fn test_clear_removes_return_value() {
+        let stub: CopyFunctionStub<bool> = CopyFunctionStub::new("is_enabled");
+        stub.setup(true);
+
+        stub.clear();
+
+        assert!(!stub.is_set());
+    }
+
+    #[test]
+    #[should_panic(expected = "is_enabled stub not initialized")]
+    fn test_get_return_value_panics_when_not_initialized() {
+        let stub: CopyFunctionStub<bool> = CopyFunctionStub::new("is_enabled");
+        stub.get_return_value();
+    }
+
+    #[test]
+    #[should_panic(expected = "port lookup failed")]
+    fn test_setup_panic_panics_with_message() {
+        let stub: CopyFunctionStub<u16> = CopyFunctionStub::new("get_port");
+        stub.setup_panic("port lookup failed");
+        stub.get_return_value();
+    }
+
+    #[test]
+    fn test_setup_from_iter_returns_next_value_per_call() {
+        let stub: CopyFunctionStub<u64> = CopyFunctionStub::new("next_id");
+        stub.setup_from_iter(1..);
+
+        assert_eq!(stub.get_return_value(), 1);
+        assert_eq!(stub.get_return_value(), 2);
+
+        stub.clear();
+        assert!(!stub.is_set());
+    }
+
+    #[test]
+    fn test_default_value_is_checked_without_borrowing_values() {
+        let stub: CopyFunctionStub<u64> = CopyFunctionStub::new("next_id");
+        stub.setup_from_iter(1..);
+        stub.setup(7);
+
+        // A borrowed iterator would make `has_default` panic if it still borrowed the values
+        let _values = stub.values.borrow_mut();
+        assert!(stub.has_default());
+        assert_eq!(stub.get_return_value(), 7);
+    }
+
+    #[test]
+    fn test_calls_without_conditions_dont_borrow_them() {
+        type Predicate = fn(&u64) -> bool;
+        let stub: CopyFunctionStub<u64> = CopyFunctionStub::new("next_id");
+        stub.setup(7);
+
+        // Borrowed conditions would make the lookup panic if it still borrowed them
+        let _conditions = stub.conditions.borrow_mut();
+        assert!(stub.is_set());
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&1)), Some(7));
+    }
+
+    #[test]
+    fn test_clear_removes_conditions() {
+        type Predicate = fn(&u64) -> bool;
+        let stub: CopyFunctionStub<u64> = CopyFunctionStub::new("next_id");
+        stub.setup_when(Box::new((|id| *id == 1) as Predicate), 3);
+        assert!(stub.is_set());
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&1)), Some(3));
+
+        stub.clear();
+        assert!(!stub.is_set());
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&1)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "port lookup failed")]
+    fn test_restore_replaces_configuration_with_snapshot() {
+        let stub: CopyFunctionStub<u16> = CopyFunctionStub::new("get_port");
+        stub.setup_panic("port lookup failed");
+        let snapshot = stub.snapshot();
+
+        stub.setup(8080);
+        stub.restore(&snapshot);
+
+        stub.get_return_value();
+    }
+
+    #[test]
+    fn test_with_static_str() {
+        let stub: CopyFunctionStub<&'static str> = CopyFunctionStub::new("get_env");
+        stub.setup("test");
+
+        assert_eq!(stub.get_return_value(), "test");
+    }
+
+    #[test]
+    fn test_with_borrowed_str() {
+        let env = String::from("test");
+        let stub: CopyFunctionStub<&str> = CopyFunctionStub::new("get_env");
+        stub.setup(&env);
+
+        assert_eq!(stub.get_return_value(), "test");
+    }
+
+    #[test]
+    fn test_display_summarizes_stub() {
+        let stub: CopyFunctionStub<bool> = CopyFunctionStub::new("is_enabled");
+        assert_eq!(stub.to_string(), "is_enabled stub (not set)");
+    }
+    #[test]
+    fn test_require_until_cleared() {
+        let stub: CopyFunctionStub<bool> = CopyFunctionStub::new("is_enabled");
+        stub.require();
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| stub.check_real_call())).unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "is_enabled stub is required, but the real implementation was called");
+
+        stub.clear();
+        stub.check_real_call();
+    }
+}
@@ -0,0 +1,128 @@
+//! Recording of function pointer parameters
+//!
+//! Function pointers are compared by their address and printed as an address, which makes
+//! failure messages hard to read. The generated mocks record parameters of type `fn(..)`
+//! as [`FnPtr`], which is compared by identity and printed with the name of the function,
+//! if the function was named with `fnmock::named_fn!` before.
+//!
+//! # Example
+//!
+//! ```ignore
+//! #[mock_function]
+//! pub fn apply(value: u32, op: fn(u32) -> u32) -> u32 {
+//!     op(value)
+//! }
+//!
+//! // In the test, the recorded parameter is a `FnPtr<fn(u32) -> u32>`
+//! apply_mock::setup(|(value, op)| op.get()(value));
+//!
+//! apply(2, double);
+//!
+//! // Fails with "Expected apply_mock mock to be called with (2, triple)"
+//! apply_mock::assert_with(2, fnmock::named_fn!(triple));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock};
+
+// Function addresses are the same on every thread, so the names are shared by all threads
+static NAMES: OnceLock<Mutex<HashMap<String, &'static str>>> = OnceLock::new();
+
+fn names() -> &'static Mutex<HashMap<String, &'static str>> {
+    NAMES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Registers the name printed for the function at `address`.
+///
+/// Normally you don't need to call this function, it backs `fnmock::named_fn!`.
+pub fn register_name(address: *const (), name: &'static str) {
+    names().lock().unwrap().insert(format!("{:?}", address), name);
+}
+
+/// Function pointer parameter recorded by a mock, compared by identity
+///
+/// Printed with the name registered with `fnmock::named_fn!`, or as `fn@<address>` otherwise.
+/// The function can be called with `op.get()(..)` or `(*op)(..)`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FnPtr<F>(F);
+
+impl<F: Copy> FnPtr<F> {
+    pub fn new(function: F) -> Self {
+        Self(function)
+    }
+
+    /// Returns the function pointer.
+    pub fn get(&self) -> F {
+        self.0
+    }
+}
+
+impl<F> Deref for FnPtr<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.0
+    }
+}
+
+// The Debug implementation of function pointers prints their address like the one of raw pointers
+impl<F: Debug> Debug for FnPtr<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let address = format!("{:?}", self.0);
+        match names().lock().unwrap().get(&address) {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "fn@{}", address),
+        }
+    }
+}
+
+/// Names a function for the messages of the mocks recording it as a parameter and returns it.
+///
+/// # Example
+///
+/// ```ignore
+/// apply_mock::assert_with(2, fnmock::named_fn!(double));
+/// ```
+#[macro_export]
+macro_rules! named_fn {
+    ($function:path) => {{
+        $crate::fn_ptr::register_name($function as *const (), stringify!($function));
+        $function
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn double(x: u32) -> u32 {
+        x * 2
+    }
+
+    fn triple(x: u32) -> u32 {
+        x * 3
+    }
+
+    #[test]
+    fn test_fn_ptr_is_compared_by_identity() {
+        let double_ptr = FnPtr::new(double as fn(u32) -> u32);
+
+        assert_eq!(double_ptr, FnPtr::new(double as fn(u32) -> u32));
+        assert_ne!(double_ptr, FnPtr::new(triple as fn(u32) -> u32));
+        assert_eq!(double_ptr.get()(2), 4);
+        assert_eq!((*double_ptr)(2), 4);
+    }
+
+    #[test]
+    fn test_fn_ptr_prints_registered_name() {
+        let triple_ptr = FnPtr::new(triple as fn(u32) -> u32);
+        assert!(format!("{:?}", triple_ptr).starts_with("fn@0x"));
+
+        let named = crate::named_fn!(triple);
+
+        assert_eq!(named(1), 3);
+        assert_eq!(format!("{:?}", triple_ptr), "triple");
+    }
+}
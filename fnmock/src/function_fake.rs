@@ -1,10 +1,15 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::panic::Location;
+use crate::messages::panic_not_initialized;
+use crate::strict;
+
 /// Struct for faking a function with a custom implementation
 ///
 /// Fakes - in contrast to mocks - do not let you make assertions about if and how the function was called.
 ///
 /// # Generics
 ///
-/// - `Function: 'static + Copy` - the function type
+/// - `Function: Copy` - the function type
 ///   - Typically a function pointer like `fn(Args) -> Return`. Closures can be coerced to `fn` types if they do not capture any variables.
 ///
 /// # Usage
@@ -15,7 +20,7 @@
 ///
 /// The function `calculate` is supposed to be faked for testing.
 ///
-/// ```
+/// ```ignore
 /// pub(crate) fn calculate(x: i32, y: i32) -> i32 {
 ///     x + y
 /// }
@@ -24,7 +29,7 @@
 /// Now we create a fake function - it should be the same signature,
 /// but with `_fake` at the end of the name and the body calls the fake implementation.
 ///
-/// ```
+/// ```ignore
 /// pub(crate) fn calculate_fake(x: i32, y: i32) -> i32 {
 ///     calculate_fake::get_implementation()(x, y)
 /// }
@@ -32,7 +37,7 @@
 ///
 /// Create a module named `calculate_fake` with the fake infrastructure:
 ///
-/// ```
+/// ```ignore
 /// pub(crate) mod calculate_fake {
 ///     use fnmock::function_fake::FunctionFake;
 ///     
@@ -62,42 +67,88 @@
 ///
 /// - `name` - the name of the function for display purposes when panicking
 /// - `implementation` - the fake function implementation or None
+/// - `setup_location` - where the fake was last set up through its module, or None since it was cleared
+/// - `definition` - the location of the function appended to the messages, e.g. `src/db.rs:12`, or None
 pub struct FunctionFake<Function>
 where
-    Function: 'static + Copy,
+    Function: Copy,
 {
-    name: String,
+    name: &'static str,
     implementation: Option<Function>,
+    setup_location: Option<&'static Location<'static>>,
+    definition: Option<&'static str>,
 }
 
 impl<Function> FunctionFake<Function>
 where
-    Function: 'static + Copy,
+    Function: Copy,
 {
-    pub fn new(function_name: &str) -> Self {
+    pub const fn new(function_name: &'static str) -> Self {
         Self {
-            name: function_name.to_string(),
+            name: function_name,
             implementation: None,
+            setup_location: None,
+            definition: None,
         }
     }
 
+    /// Sets the location of the faked function, e.g. `src/db.rs:12`, which is appended to the panic messages of the fake.
+    pub const fn with_definition(mut self, definition: &'static str) -> Self {
+        self.definition = Some(definition);
+        self
+    }
+
     // --- Faking ---
 
+    /// Records where the fake is set up, reporting a repeated setup as configured in `fnmock::strict`.
+    pub fn record_setup(&mut self, location: &'static Location<'static>) {
+        strict::check_setup(self.name, "fake", None, self.definition, self.setup_location, location);
+        self.setup_location = Some(location);
+    }
+
     pub fn setup(&mut self, new_f: Function) {
         self.implementation = Some(new_f);
     }
 
     pub fn clear(&mut self) {
         self.implementation = None;
+        self.setup_location = None;
     }
 
+    #[inline]
     pub fn is_set(&self) -> bool {
         self.implementation.is_some()
     }
 
-    pub fn get_implementation(&self) -> Function
-    {
-        self.implementation.expect(format!("{} fake not initialized", self.name).as_str())
+    #[inline]
+    pub fn get_implementation(&self) -> Function {
+        match self.implementation {
+            Some(implementation) => implementation,
+            None => panic_not_initialized(self.name, "fake", self.definition),
+        }
+    }
+}
+
+// The implementation can't be printed, so only whether it is set is shown
+impl<Function> Debug for FunctionFake<Function>
+where
+    Function: Copy,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FunctionFake")
+            .field("name", &self.name)
+            .field("is_set", &self.is_set())
+            .finish()
+    }
+}
+
+impl<Function> Display for FunctionFake<Function>
+where
+    Function: Copy,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.is_set() { "set" } else { "not set" };
+        write!(f, "{} fake ({})", self.name, state)
     }
 }
 
@@ -204,6 +255,7 @@ mod tests {
 
     #[test]
     fn test_with_unit_return_type() {
+        #[allow(clippy::unused_unit)]
         fn void_fake(_x: i32) -> () {
             // Do nothing
         }
@@ -216,6 +268,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::type_complexity)]
     fn test_with_result_return_type() {
         fn divide_fake(a: i32, b: i32) -> Result<i32, String> {
             if b == 0 {
@@ -276,4 +329,18 @@ mod tests {
         let fake: FunctionFake<fn(i32) -> i32> = FunctionFake::new("my_custom_function");
         assert_eq!(fake.name, "my_custom_function");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_debug_shows_state() {
+        let fake: FunctionFake<fn(i32, i32) -> i32> = FunctionFake::new("add");
+
+        assert_eq!(format!("{:?}", fake), "FunctionFake { name: \"add\", is_set: false }");
+    }
+
+    #[test]
+    fn test_display_summarizes_fake() {
+        let mut fake: FunctionFake<fn(i32, i32) -> i32> = FunctionFake::new("add");
+        fake.setup(add_fake_implementation);
+        assert_eq!(fake.to_string(), "add fake (set)");
+    }
+}
@@ -1,12 +1,164 @@
-use std::fmt::Debug;
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt::{Debug, Display, Formatter};
+use std::convert::Infallible;
+use std::hash::Hash;
+use std::ops::Range;
+use std::panic::Location;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::call_index::CallIndex;
+use crate::latency::Latency;
+use crate::matchers::Matcher;
+use crate::mock_state::MockState;
+use crate::verify::MockStatus;
+
+/// Mock implementation receiving the params either owned or borrowed
+///
+/// Owned params have to be cloned for the call history, borrowed params are
+/// moved into the call history before the implementation is called.
+/// The implementations are stored as plain function pointers instead of boxed closures,
+/// which doesn't require the params and the result to be `'static`.
+///
+/// `Data` is the data of `Producing`, which is `Infallible` in a `MockExport`,
+/// so an exported implementation can be sent to another thread.
+enum Implementation<Params, Result, Data = Rc<dyn Any>> {
+    Owned(fn(Params) -> Result),
+    Borrowed(fn(&Params) -> Result),
+    /// Receives the zero-based index of the call in addition to the params
+    Indexed(fn(usize, Params) -> Result),
+    /// A fixed return value and the function cloning it
+    Returning(Result, fn(&Result) -> Result),
+    /// Data and the function producing a new return value from it, e.g. an iterator over canned items
+    Producing(Data, fn(&dyn Any) -> Result),
+    /// Shared data and the function computing the return value from it and the params, e.g. the per-item function of `setup_batch`
+    Parameterized(Arc<dyn Any + Send + Sync>, fn(&dyn Any, Params) -> Result),
+    /// Every call panics
+    Forbidden,
+    /// Every call is recorded and panics with the message
+    Panicking(&'static str),
+    /// Every call is recorded and never completes, only supported by async functions
+    Pending,
+}
+
+/// Check of the params of every call added with `add_precondition`, with the location it was added at
+type Precondition<Params> = (fn(&Params) -> bool, &'static Location<'static>);
+
+/// Check of the params and the result of every call added with `add_postcondition`,
+/// with the function formatting the result and the location it was added at
+type Postcondition<Params, Result> = (fn(&Params, &Result) -> bool, fn(&Result) -> String, &'static Location<'static>);
+
+/// Number of calls stored inline with the `smallvec` feature, which covers most tests
+#[cfg(feature = "smallvec")]
+const INLINE_CALLS: usize = 4;
+
+/// Call history of a mock
+#[cfg(feature = "smallvec")]
+type CallHistory<Params> = smallvec::SmallVec<[Params; INLINE_CALLS]>;
+
+/// Call history of a mock
+#[cfg(not(feature = "smallvec"))]
+type CallHistory<Params> = Vec<Params>;
+
+impl<Params, Result, Data> Implementation<Params, Result, Data> {
+    /// Copies the implementation, cloning a fixed return value and copying the data of `Producing` with `copy_data`.
+    fn copy<NewData>(&self, copy_data: impl FnOnce(&Data) -> NewData) -> Implementation<Params, Result, NewData> {
+        match self {
+            Implementation::Owned(implementation) => Implementation::Owned(*implementation),
+            Implementation::Borrowed(implementation) => Implementation::Borrowed(*implementation),
+            Implementation::Indexed(implementation) => Implementation::Indexed(*implementation),
+            Implementation::Returning(return_value, clone) => Implementation::Returning(clone(return_value), *clone),
+            Implementation::Producing(data, produce) => Implementation::Producing(copy_data(data), *produce),
+            Implementation::Parameterized(data, call) => Implementation::Parameterized(Arc::clone(data), *call),
+            Implementation::Forbidden => Implementation::Forbidden,
+            Implementation::Panicking(message) => Implementation::Panicking(message),
+            Implementation::Pending => Implementation::Pending,
+        }
+    }
+}
+
+/// Configuration of a `FunctionMock` without its call history, see `MockExport` and `MockSnapshot`
+///
+/// The configuration is the implementation, the expectations, the contract checks and the enabled recording options.
+/// The call index isn't part of it, because it requires the params to be `Hash`.
+struct MockConfiguration<Params, Result, Data> {
+    implementation: Option<Implementation<Params, Result, Data>>,
+    expected_times: Option<u32>,
+    call_budget: Option<u32>,
+    preconditions: Vec<Precondition<Params>>,
+    postconditions: Vec<Postcondition<Params, Result>>,
+    dedup_calls: bool,
+    yields: bool,
+    record_timestamps: bool,
+    latency: Option<Latency>,
+}
+
+impl<Params, Result, Data> MockConfiguration<Params, Result, Data> {
+    fn copy<NewData>(&self, copy_data: impl FnOnce(&Data) -> NewData) -> MockConfiguration<Params, Result, NewData> {
+        MockConfiguration {
+            implementation: self.implementation.as_ref().map(|implementation| implementation.copy(copy_data)),
+            expected_times: self.expected_times,
+            call_budget: self.call_budget,
+            preconditions: self.preconditions.clone(),
+            postconditions: self.postconditions.clone(),
+            dedup_calls: self.dedup_calls,
+            yields: self.yields,
+            record_timestamps: self.record_timestamps,
+            latency: self.latency.clone(),
+        }
+    }
+}
+
+/// Configuration of a `FunctionMock` copied with `export`, which can be sent to another thread
+///
+/// Mocks set up with `setup_producing` can't be exported, because their data isn't `Send`.
+pub struct MockExport<Params, Result>(MockConfiguration<Params, Result, Infallible>);
+
+impl<Params, Result> Clone for MockExport<Params, Result> {
+    fn clone(&self) -> Self {
+        MockExport(self.0.copy(|never| match *never {}))
+    }
+}
+
+/// Configuration of a `FunctionMock` captured with `snapshot`, which can be restored any number of times
+pub struct MockSnapshot<Params, Result>(MockConfiguration<Params, Result, Rc<dyn Any>>);
+
+/// Returns the context of the last recorded call or None if it wasn't made in a context.
+///
+/// A free function, so it can be used while the run lengths of the mock are borrowed.
+fn last_call_context(calls_len: usize, contexts: &[(usize, Rc<str>)]) -> Option<&Rc<str>> {
+    contexts
+        .last()
+        .filter(|(position, _)| position + 1 == calls_len)
+        .map(|(_, context)| context)
+}
+
+/// Records an unverified call in the call history and the index, if calls are indexed
+///
+/// A free function, so it can be used while the implementation of the mock is borrowed.
+fn record<Params>(
+    calls: &mut CallHistory<Params>,
+    verified: &mut Vec<Cell<bool>>,
+    call_index: &mut Option<CallIndex<Params>>,
+    params: Params,
+) {
+    calls.push(params);
+    verified.push(Cell::new(false));
+    if let Some(call_index) = call_index {
+        call_index.insert(&calls[calls.len() - 1], calls.len() - 1);
+    }
+}
 
 /// Struct containing the Data for mocking a Function
 ///
-/// The functions parameters can't contain non 'static variables.
+/// The params and the result aren't required to be `'static`, so a `FunctionMock` living on the stack
+/// can record borrowed params. The generated mocks store it in a `thread_local!`,
+/// which still requires `'static` types there.
 ///
 /// # Generics
 ///
-/// - `Params: Clone + PartialEq + Debug + 'static` - the parameters of the mocked function as a tuple
+/// - `Params: Clone + PartialEq + Debug` - the parameters of the mocked function as a tuple
 /// - `Result` - the result of the function
 ///
 /// # Usage
@@ -17,7 +169,7 @@ use std::fmt::Debug;
 ///
 /// The function send_email is supposed to be mocked.
 ///
-/// ```
+/// ```ignore
 /// pub(crate) fn send_email(user: String, body: String) -> Result<(), String> {
 ///     print!("Send email to {0}: {1}\n", user, body);
 ///     Ok(())
@@ -28,7 +180,7 @@ use std::fmt::Debug;
 /// but with _mock at the end of the name and the body replaced with `send_email_mock::call`.
 /// It is important, when passing the parameters, to put them in a tuple or the function will break.
 ///
-/// ```
+/// ```ignore
 /// pub(crate) fn send_email_mock(user: String, body: String) -> Result<(), String> {
 ///     send_email_mock::call((user, body))
 /// }
@@ -36,7 +188,7 @@ use std::fmt::Debug;
 ///
 /// But where does `send_email_mock::call` come from? Now we create a module named `send_email_mock`.
 ///
-/// ```
+/// ```ignore
 /// pub(crate) mod send_email_mock {
 ///     type Params = (String, String); // The params of the function in a tuple
 ///     type Return = Result<(), String>; // The return type
@@ -67,286 +219,1459 @@ use std::fmt::Debug;
 ///
 /// # Fields
 ///
-/// - `name` - the name of the function for display purposes when asserting
-/// - `implementation` - the mock function with the params in a tuple or None
-/// - `calls` - vector to hold all calls to the mock
+/// - `state` - the state not depending on `Params` and `Result`, like the name and the expectations
+/// - `implementation` - the mock implementation with the params in a tuple (owned or borrowed) or None
+/// - `calls` - vector to hold all calls to the mock (a `SmallVec` with the `smallvec` feature),
+///   one entry per run of identical consecutive calls if `dedup_consecutive_calls` is enabled
+/// - `run_lengths` - the number of calls of each entry in `calls` or None if calls aren't deduplicated
+/// - `call_count` - the total number of calls
+/// - `verified` - whether each entry in `calls` was covered by an assertion, see `check_no_more_interactions`
+/// - `contexts` - the positions in `calls` of the calls made in a `fnmock::with_context` call with their context
+/// - `call_index` - hash index over `calls` if enabled with `index_calls` or None
+/// - `yields` - whether async calls yield once before returning, see `yield_before_returning`
+/// - `cancelled_calls` - the number of async calls dropped before they returned
+/// - `timestamps` - the time of every call if enabled with `record_timestamps` or None
+/// - `latency` - the simulated delay of async calls if set with `with_latency` / `with_jitter` or None
+/// - `preconditions` - the checks of the params of every call added with `add_precondition`
+/// - `postconditions` - the checks of the params and the result of every call added with `add_postcondition`
 pub struct FunctionMock<Params, Result>
 where
-    Params: Clone + PartialEq + Debug + 'static
+    Params: Clone + PartialEq + Debug,
 {
-    name: String,
-    implementation: Option<fn(Params) -> Result>,
-    calls: Vec<Params>
+    state: MockState,
+    implementation: Option<Implementation<Params, Result>>,
+    calls: CallHistory<Params>,
+    run_lengths: Option<Vec<u32>>,
+    call_count: usize,
+    verified: Vec<Cell<bool>>,
+    contexts: Vec<(usize, Rc<str>)>,
+    call_index: Option<CallIndex<Params>>,
+    yields: bool,
+    cancelled_calls: usize,
+    timestamps: Option<Vec<Instant>>,
+    latency: Option<Latency>,
+    preconditions: Vec<Precondition<Params>>,
+    postconditions: Vec<Postcondition<Params, Result>>,
 }
 
 impl<Params, Result> FunctionMock<Params, Result>
 where
-    Params: Clone + PartialEq + Debug + 'static,
+    Params: Clone + PartialEq + Debug,
 {
-    pub fn new(function_name: &str) -> Self {
+    pub const fn new(function_name: &'static str) -> Self {
         Self {
-            name: function_name.to_string(),
+            state: MockState::new(function_name),
             implementation: None,
-            calls: Vec::new(),
+            #[cfg(feature = "smallvec")]
+            calls: CallHistory::new_const(),
+            #[cfg(not(feature = "smallvec"))]
+            calls: CallHistory::new(),
+            run_lengths: None,
+            call_count: 0,
+            verified: Vec::new(),
+            contexts: Vec::new(),
+            call_index: None,
+            yields: false,
+            cancelled_calls: 0,
+            timestamps: None,
+            latency: None,
+            preconditions: Vec::new(),
+            postconditions: Vec::new(),
         }
     }
 
+    pub const fn with_module_path(mut self, module_path: &'static str) -> Self {
+        self.state.module_path = Some(module_path);
+        self
+    }
+
+    /// Sets a `setup` call matching the signature of the function, which the panic of an unconfigured call suggests.
+    pub const fn with_setup_example(mut self, setup_example: &'static str) -> Self {
+        self.state.setup_example = Some(setup_example);
+        self
+    }
+
+    /// Sets the location of the mocked function, e.g. `src/db.rs:12`, which is appended to all messages of the mock.
+    pub const fn with_definition(mut self, definition: &'static str) -> Self {
+        self.state.definition = Some(definition);
+        self
+    }
+
     // --- Mocking ---
 
+    fn set_implementation(&mut self, implementation: Implementation<Params, Result>) {
+        self.implementation = Some(implementation);
+        self.state.record_configured();
+    }
+
+    /// Records where the mock is set up, reporting a repeated setup as configured in `fnmock::strict`.
+    ///
+    /// Called by the generated setup proxies before they change the implementation.
+    pub fn record_setup(&mut self, location: &'static Location<'static>) {
+        self.state.record_setup(location, self.call_count);
+    }
+
     pub fn setup(&mut self, new_f: fn(Params) -> Result) {
-        self.implementation = Some(new_f);
+        self.set_implementation(Implementation::Owned(new_f));
     }
 
-    pub fn clear(&mut self) {
-        self.implementation = None;
-        self.calls = Vec::new();
+    /// Sets an implementation borrowing the params, so they don't need to be cloned for the call history.
+    pub fn setup_ref(&mut self, new_f: fn(&Params) -> Result) {
+        self.set_implementation(Implementation::Borrowed(new_f));
     }
 
-    pub fn is_set(&self) -> bool {
-        self.implementation.is_some()
+    pub fn setup_returning(&mut self, return_value: Result)
+    where
+        Result: Clone,
+    {
+        // The params are ignored, so they are moved into the call history without cloning
+        self.set_implementation(Implementation::Returning(return_value, Result::clone));
     }
 
-    // --- Execute ---
+    /// Returns a new value produced from `data` on every call, ignoring the params.
+    ///
+    /// Used for return types that can't be cloned, like the boxed iterator of a function
+    /// returning `impl Iterator`, which is produced from a canned list of items.
+    pub fn setup_producing(&mut self, data: Box<dyn Any>, produce: fn(&dyn Any) -> Result) {
+        self.set_implementation(Implementation::Producing(Rc::from(data), produce));
+    }
 
-    pub fn call(&mut self, params: Params) -> Result {
-        let implementation = self.implementation.as_ref()
-            .expect(format!("{} mock not initialized", self.name).as_str());
+    /// Sets an implementation receiving `data` and the params.
+    ///
+    /// Unlike `setup_producing`, the data is shared between threads, so the mock can still be exported,
+    /// e.g. for the per-item function of `setup_batch`.
+    pub fn setup_parameterized(&mut self, data: Arc<dyn Any + Send + Sync>, call: fn(&dyn Any, Params) -> Result) {
+        self.set_implementation(Implementation::Parameterized(data, call));
+    }
 
-        self.calls.push(params.clone());
-        implementation(params)
+    /// Sets an implementation receiving the zero-based index of the call and the params.
+    ///
+    /// Lets the behavior vary by call without a hand-rolled counter, e.g. for pagination.
+    /// The index counts the calls since the history was last cleared.
+    pub fn returning_indexed(&mut self, new_f: fn(usize, Params) -> Result) {
+        self.set_implementation(Implementation::Indexed(new_f));
     }
 
-    // --- Assert ---
+    /// Makes every call panic with `message`, simulating a crashing dependency.
+    ///
+    /// Unlike `forbid`, the calls are recorded, so the assertions still see them.
+    pub fn setup_panic(&mut self, message: &'static str) {
+        self.set_implementation(Implementation::Panicking(message));
+    }
 
-    pub fn assert_times(&self, expected_num_of_calls: u32) {
-        assert_eq!(self.calls.len(), expected_num_of_calls as usize,
-                   "Expected {} mock to be called {} times, received {}",
-                   self.name, self.calls.len(), expected_num_of_calls);
+    /// Makes every call of the async function never complete, see `try_call`.
+    ///
+    /// Lets timeout branches of the caller be tested deterministically, e.g. with paused time.
+    pub fn setup_pending(&mut self) {
+        self.set_implementation(Implementation::Pending);
     }
 
-    pub fn assert_with(&self, params: Params) {
-        let mut was_called_with = false;
+    /// Makes every call panic immediately with the params it was called with.
+    ///
+    /// Unlike `assert_times(0)` at the end of a test, this fails at the moment the function is called.
+    pub fn forbid(&mut self) {
+        self.set_implementation(Implementation::Forbidden);
+    }
 
-        for called_params in self.calls.iter() {
-            if *called_params == params {
-                was_called_with = true;
-            }
+    pub fn clear(&mut self) {
+        self.clear_implementation();
+        self.clear_history();
+        self.run_lengths = None;
+        self.call_index = None;
+        self.yields = false;
+        self.timestamps = None;
+        self.latency = None;
+    }
+
+    pub fn clear_implementation(&mut self) {
+        self.state.expected_times = None;
+        self.state.call_budget = None;
+        self.state.real_call_guard.clear();
+        self.preconditions.clear();
+        self.postconditions.clear();
+        self.state.setup_location = None;
+        self.state.calls_before_checkpoint = self.call_count;
+        if self.implementation.take().is_some() {
+            self.state.remove_configured();
         }
+    }
 
-        assert!(was_called_with, "Expected {} mock to be called with {:?}", self.name, params);
+    pub fn clear_history(&mut self) {
+        // Keeps the capacity, so a mock reused in the same test doesn't reallocate its history
+        self.calls.clear();
+        if let Some(run_lengths) = self.run_lengths.as_mut() {
+            run_lengths.clear();
+        }
+        if let Some(call_index) = self.call_index.as_mut() {
+            call_index.clear();
+        }
+        self.verified.clear();
+        self.contexts.clear();
+        self.call_count = 0;
+        self.state.calls_before_checkpoint = 0;
+        self.cancelled_calls = 0;
+        if let Some(timestamps) = self.timestamps.as_mut() {
+            timestamps.clear();
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.implementation.is_some()
+    }
 
-    // Helper mock function for testing
-    fn add_mock_implementation(params: (i32, i32)) -> i32 {
-        params.0 + params.1
+    /// Records identical consecutive calls as a single entry with a count until the mock is cleared.
+    ///
+    /// Saves memory for mocks called thousands of times with the same params in a loop.
+    /// The assertions count every call, but `calls` returns one entry per run,
+    /// use `call_runs` to get the number of calls of each entry.
+    pub fn dedup_consecutive_calls(&mut self) {
+        if self.run_lengths.is_none() {
+            self.run_lengths = Some(vec![1; self.calls.len()]);
+        }
     }
 
-    fn multiply_mock_implementation(params: (i32, i32)) -> i32 {
-        params.0 * params.1
+    /// Maintains a hash index over the call history until the mock is cleared.
+    ///
+    /// Makes `was_called_with` and the `assert_with` assertions O(1) instead of a scan over all calls,
+    /// which matters for mocks recording tens of thousands of calls.
+    pub fn index_calls(&mut self)
+    where
+        Params: Hash,
+    {
+        if self.call_index.is_none() {
+            self.call_index = Some(CallIndex::new(&self.calls));
+        }
     }
 
-    fn string_concat_mock_implementation(params: (String, String)) -> String {
-        format!("{}{}", params.0, params.1)
+    /// Makes the calls of the async function yield once before returning until the mock is cleared.
+    ///
+    /// The calls are in flight at the yield point, so callers dropping them, e.g. in `select!`,
+    /// can be verified with `cancelled_calls`. See `fnmock::cancellation::cancellation_point`.
+    pub fn yield_before_returning(&mut self) {
+        self.yields = true;
     }
 
-    #[test]
-    fn test_new_creates_mock_with_correct_name() {
-        let mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("test_function");
-        assert_eq!(mock.name, "test_function");
-        assert!(mock.implementation.is_none());
-        assert!(mock.calls.is_empty());
+    pub fn yields_before_returning(&self) -> bool {
+        self.yields
     }
 
-    #[test]
-    fn test_mock_implementation_sets_function() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        assert!(mock.implementation.is_some());
+    /// Delays the results of the async function by `latency` until the mock is cleared.
+    ///
+    /// See `fnmock::latency::sleep` for how the delay is awaited.
+    pub fn with_latency(&mut self, latency: Duration) {
+        self.latency.get_or_insert_with(Latency::new).set_base(latency);
     }
 
-    #[test]
-    fn test_call_executes_mocked_function() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        
-        let result = mock.call((5, 3));
-        assert_eq!(result, 8);
+    /// Delays the results of the async function by an additional random duration in `jitter`.
+    ///
+    /// The durations are pseudo-random with a fixed seed, so they are the same on every run of a test.
+    pub fn with_jitter(&mut self, jitter: Range<Duration>) {
+        self.latency.get_or_insert_with(Latency::new).set_jitter(jitter);
     }
 
-    #[test]
-    #[should_panic(expected = "add mock not initialized")]
-    fn test_call_panics_when_not_initialized() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.call((5, 3));
+    /// Returns the delay of the next result, if a latency or jitter is set.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        self.latency.as_mut().map(Latency::next_delay)
     }
 
-    #[test]
-    fn test_call_records_parameters() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        
-        mock.call((5, 3));
-        mock.call((10, 20));
-        
-        assert_eq!(mock.calls.len(), 2);
-        assert_eq!(mock.calls[0], (5, 3));
-        assert_eq!(mock.calls[1], (10, 20));
+    /// Records a call dropped before it returned.
+    pub fn record_cancelled(&mut self) {
+        self.cancelled_calls += 1;
     }
 
-    #[test]
-    fn test_clear_mock_resets_state() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        mock.call((5, 3));
-        mock.call((10, 20));
+    /// Records the time of every call until the mock is cleared, see `call_timestamps`.
+    ///
+    /// With the `tokio` feature the times follow tokio's paused clock.
+    pub fn record_timestamps(&mut self) {
+        if self.timestamps.is_none() {
+            self.timestamps = Some(Vec::new());
+        }
+    }
 
-        assert_eq!(mock.calls[0], (5, 3));
-        assert_eq!(mock.calls[1], (10, 20));
-        
-        mock.clear();
-        
-        assert!(mock.implementation.is_none());
-        assert!(mock.calls.is_empty());
+    /// Sets the number of calls `verify` expects.
+    pub fn expect_times(&mut self, expected_num_of_calls: u32) {
+        self.state.expected_times = Some(expected_num_of_calls);
     }
 
-    #[test]
-    fn test_mock_can_be_replaced() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("math");
-        mock.setup(add_mock_implementation);
-        
-        let result1 = mock.call((5, 3));
-        assert_eq!(result1, 8);
-        
-        mock.setup(multiply_mock_implementation);
-        let result2 = mock.call((5, 3));
-        assert_eq!(result2, 15);
+    /// Makes every call after the first `max_calls` calls panic immediately with its params.
+    ///
+    /// Catches runaway loops, e.g. retries hammering the mocked dependency, at the excess call
+    /// instead of when the test times out. The calls are counted since the history was last cleared,
+    /// the excess calls aren't recorded.
+    pub fn set_call_budget(&mut self, max_calls: u32) {
+        self.state.call_budget = Some(max_calls);
     }
 
-    #[test]
-    fn test_assert_times_passes_with_correct_count() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        
-        mock.call((1, 2));
-        mock.call((3, 4));
-        mock.call((5, 6));
-        
-        mock.assert_times(3);
+    /// Declares the mock required, so the real implementation of the function panics when it runs, until the mock is cleared.
+    ///
+    /// Catches calls silently falling through to real I/O, e.g. after a helper cleared the mock.
+    pub fn require(&mut self) {
+        self.state.real_call_guard.require();
     }
 
-    #[test]
-    #[should_panic(expected = "Expected add mock to be called 2 times, received 5")]
-    fn test_assert_times_fails_with_wrong_count() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        
-        mock.call((1, 2));
-        mock.call((3, 4));
-        
-        mock.assert_times(5);
+    /// Checks that the real implementation of the function may run, which the mocked function calls before running it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mock is required, or if it was configured in the current test and
+    /// `fnmock::strict::set_real_implementation` is set to `Strictness::Panic`
+    pub fn check_real_call(&self) {
+        self.state.check_real_call();
     }
 
-    #[test]
-    fn test_assert_times_with_zero_calls() {
-        let mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.assert_times(0);
+    /// Checks the params of every call with `check` until the mock is cleared,
+    /// making a call violating it panic with the location `check` was added at and the params.
+    ///
+    /// Turns the mock into a contract checker for the calls the code under test makes.
+    pub fn add_precondition(&mut self, check: fn(&Params) -> bool, location: &'static Location<'static>) {
+        self.preconditions.push((check, location));
     }
 
-    #[test]
-    fn test_assert_with_passes_when_called_with_params() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        
-        mock.call((5, 3));
-        mock.call((10, 20));
-        
-        mock.assert_with((5, 3));
-        mock.assert_with((10, 20));
+    /// Checks the params and the result of every call with `check` until the mock is cleared,
+    /// making a call violating it panic with the location `check` was added at, the params and the result.
+    pub fn add_postcondition(&mut self, check: fn(&Params, &Result) -> bool, location: &'static Location<'static>)
+    where
+        Result: Debug,
+    {
+        self.postconditions.push((check, |result| format!("{:?}", result), location));
     }
 
-    #[test]
-    #[should_panic(expected = "Expected add mock to be called with (7, 8)")]
-    fn test_assert_with_fails_when_not_called_with_params() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        
-        mock.call((5, 3));
-        mock.assert_with((7, 8));
+    // --- Export ---
+
+    fn configuration(&self) -> MockConfiguration<Params, Result, Rc<dyn Any>> {
+        MockConfiguration {
+            implementation: self.implementation.as_ref().map(|implementation| implementation.copy(Rc::clone)),
+            expected_times: self.state.expected_times,
+            call_budget: self.state.call_budget,
+            preconditions: self.preconditions.clone(),
+            postconditions: self.postconditions.clone(),
+            dedup_calls: self.run_lengths.is_some(),
+            yields: self.yields,
+            record_timestamps: self.timestamps.is_some(),
+            latency: self.latency.clone(),
+        }
     }
 
-    #[test]
-    fn test_assert_with_finds_params_among_multiple_calls() {
-        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
-        mock.setup(add_mock_implementation);
-        
-        mock.call((1, 1));
-        mock.call((2, 2));
-        mock.call((3, 3));
-        mock.call((4, 4));
-        
-        mock.assert_with((3, 3));
+    /// Clears the mock and applies the configuration, so the call history starts empty.
+    fn apply_configuration(&mut self, configuration: MockConfiguration<Params, Result, Rc<dyn Any>>) {
+        self.clear();
+
+        if let Some(implementation) = configuration.implementation {
+            self.set_implementation(implementation);
+        }
+        self.state.expected_times = configuration.expected_times;
+        self.state.call_budget = configuration.call_budget;
+        self.preconditions = configuration.preconditions;
+        self.postconditions = configuration.postconditions;
+        if configuration.dedup_calls {
+            self.dedup_consecutive_calls();
+        }
+        self.yields = configuration.yields;
+        if configuration.record_timestamps {
+            self.record_timestamps();
+        }
+        self.latency = configuration.latency;
     }
 
-    #[test]
-    fn test_with_string_parameters() {
-        let mut mock: FunctionMock<(String, String), String> = FunctionMock::new("concat");
-        mock.setup(string_concat_mock_implementation);
-        
-        let result = mock.call(("Hello".to_string(), "World".to_string()));
-        assert_eq!(result, "HelloWorld");
-        
-        mock.assert_times(1);
-        mock.assert_with(("Hello".to_string(), "World".to_string()));
+    /// Copies the configuration of the mock, so it can be imported on another thread with `import`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mock is set up with `setup_producing`, whose data can't be sent to another thread.
+    pub fn export(&self) -> MockExport<Params, Result> {
+        MockExport(self.configuration().copy(|_| self.state.panic_not_exportable()))
     }
 
-    #[test]
-    fn test_with_single_parameter() {
-        fn double_mock(params: i32) -> i32 {
-            params * 2
+    /// Copies the configuration of the mock like `export`, or returns None if it is set up with `setup_producing`.
+    pub fn try_export(&self) -> Option<MockExport<Params, Result>> {
+        match self.implementation {
+            Some(Implementation::Producing(..)) => None,
+            _ => Some(self.export()),
         }
-        
-        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("double");
-        mock.setup(double_mock);
-        
-        let result = mock.call(5);
-        assert_eq!(result, 10);
-        
-        mock.assert_times(1);
-        mock.assert_with(5);
     }
 
-    #[test]
-    fn test_with_unit_return_type() {
-        fn void_mock(_params: i32) -> () {
-            // Do nothing
+    /// Replaces the configuration of the mock with one exported with `export`, e.g. on another thread.
+    ///
+    /// Clears the mock first, so the call history starts empty.
+    pub fn import(&mut self, export: MockExport<Params, Result>) {
+        self.apply_configuration(export.0.copy(|never| match *never {}));
+    }
+
+    /// Captures the configuration of the mock, so it can be restored with `restore` later on.
+    pub fn snapshot(&self) -> MockSnapshot<Params, Result> {
+        MockSnapshot(self.configuration())
+    }
+
+    /// Replaces the configuration of the mock with the snapshot and clears the call history.
+    pub fn restore(&mut self, snapshot: &MockSnapshot<Params, Result>) {
+        self.apply_configuration(snapshot.0.copy(Rc::clone));
+    }
+
+    // --- Execute ---
+
+    #[inline]
+    pub fn call(&mut self, params: Params) -> Result {
+        match self.try_call(params) {
+            Some(result) => result,
+            None => self.state.panic_pending(),
         }
-        
-        let mut mock: FunctionMock<i32, ()> = FunctionMock::new("void_fn");
-        mock.setup(void_mock);
-        
-        mock.call(42);
-        mock.assert_times(1);
-        mock.assert_with(42);
     }
 
-    #[test]
-    fn test_with_result_return_type() {
-        fn result_mock(params: (i32, i32)) -> Result<i32, String> {
-            if params.1 == 0 {
-                Err("Division by zero".to_string())
-            } else {
-                Ok(params.0 / params.1)
+    /// Calls the mock like `call`, but returns None if the call should never complete.
+    ///
+    /// Used by async functions, which await a never completing future instead,
+    /// if the mock was set up with `setup_pending`.
+    #[inline]
+    pub fn try_call(&mut self, params: Params) -> Option<Result> {
+        let Some(implementation) = self.implementation.as_ref() else {
+            self.state.panic_not_initialized()
+        };
+        self.state.check_generation();
+
+        if let Implementation::Forbidden = implementation {
+            self.state.panic_forbidden(&params)
+        }
+        if let Some(call_budget) = self.state.call_budget {
+            if self.call_count >= call_budget as usize {
+                self.state.panic_call_budget_exceeded(call_budget, &params)
             }
         }
-        
-        let mut mock: FunctionMock<(i32, i32), Result<i32, String>> = FunctionMock::new("divide");
-        mock.setup(result_mock);
-        
+        for (check, location) in &self.preconditions {
+            if !check(&params) {
+                self.state.panic_precondition_violated(location, &params)
+            }
+        }
+
+        let result = self.record_and_run(params);
+
+        // The params of the call are the last recorded ones, even if the implementation took them
+        if let Some(result) = &result {
+            for (check, format_result, location) in &self.postconditions {
+                let params = self.calls.last().unwrap();
+                if !check(params, result) {
+                    self.state.panic_postcondition_violated(location, params, &format_result(result))
+                }
+            }
+        }
+        result
+    }
+
+    /// Records the call and runs the implementation, which is set.
+    #[inline]
+    fn record_and_run(&mut self, params: Params) -> Option<Result> {
+        let Some(implementation) = self.implementation.as_ref() else {
+            self.state.panic_not_initialized()
+        };
+
+        let index = self.call_count;
+        self.call_count += 1;
+        if let Some(timestamps) = self.timestamps.as_mut() {
+            timestamps.push(crate::latency::now());
+        }
+        let context = crate::context::current_context();
+
+        if let Some(run_lengths) = self.run_lengths.as_mut() {
+            // Only compared with dedup, since comparing the params costs as much as the params are large
+            let continues_run = self.calls.last() == Some(&params)
+                && last_call_context(self.calls.len(), &self.contexts) == context.as_ref();
+            if continues_run {
+                // The params are already recorded, so they can be passed on without cloning
+                *run_lengths.last_mut().unwrap() += 1;
+                // The run contains a new call, which isn't covered by earlier assertions
+                self.verified.last().unwrap().set(false);
+                return match implementation {
+                    Implementation::Owned(implementation) => Some(implementation(params)),
+                    Implementation::Borrowed(implementation) => Some(implementation(&params)),
+                    Implementation::Indexed(implementation) => Some(implementation(index, params)),
+                    Implementation::Returning(return_value, clone) => Some(clone(return_value)),
+                    Implementation::Producing(data, produce) => Some(produce(data.as_ref())),
+                    Implementation::Parameterized(data, call) => Some(call(data.as_ref(), params)),
+                    Implementation::Panicking(message) => panic!("{}", message),
+                    Implementation::Pending => None,
+                    Implementation::Forbidden => unreachable!(),
+                };
+            }
+            run_lengths.push(1);
+        }
+
+        if let Some(context) = context {
+            self.contexts.push((self.calls.len(), context));
+        }
+
+        match implementation {
+            Implementation::Owned(implementation) => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params.clone());
+                Some(implementation(params))
+            }
+            Implementation::Borrowed(implementation) => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params);
+                Some(implementation(self.calls.last().unwrap()))
+            }
+            Implementation::Indexed(implementation) => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params.clone());
+                Some(implementation(index, params))
+            }
+            Implementation::Returning(return_value, clone) => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params);
+                Some(clone(return_value))
+            }
+            Implementation::Producing(data, produce) => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params);
+                Some(produce(data.as_ref()))
+            }
+            Implementation::Parameterized(data, call) => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params.clone());
+                Some(call(data.as_ref(), params))
+            }
+            Implementation::Panicking(message) => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params);
+                panic!("{}", message)
+            }
+            Implementation::Pending => {
+                record(&mut self.calls, &mut self.verified, &mut self.call_index, params);
+                None
+            }
+            Implementation::Forbidden => unreachable!(),
+        }
+    }
+
+    // --- Query ---
+
+    /// Returns the recorded calls, one entry per run of identical calls with `dedup_consecutive_calls`.
+    pub fn calls(&self) -> &[Params] {
+        &self.calls
+    }
+
+    /// Returns the recorded calls with the number of identical consecutive calls they stand for.
+    ///
+    /// The count is always 1 without `dedup_consecutive_calls`.
+    pub fn call_runs(&self) -> impl Iterator<Item = (&Params, u32)> {
+        self.calls.iter().enumerate().map(|(position, params)| (params, self.run_length(position)))
+    }
+
+    fn run_length(&self, position: usize) -> u32 {
+        self.run_lengths.as_ref().map_or(1, |run_lengths| run_lengths[position])
+    }
+
+    /// Returns the number of calls made in the `fnmock::with_context` context `context`.
+    pub fn call_count_in_context(&self, context: &str) -> usize {
+        self.contexts
+            .iter()
+            .filter(|(_, call_context)| **call_context == *context)
+            .map(|(position, _)| self.run_length(*position) as usize)
+            .sum()
+    }
+
+    /// Returns every recorded call, expanding the runs recorded with `dedup_consecutive_calls`.
+    pub fn expanded_calls(&self) -> impl Iterator<Item = &Params> {
+        self.call_runs().flat_map(|(params, run_length)| std::iter::repeat_n(params, run_length as usize))
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.call_count
+    }
+
+    /// Returns the time of every call since `record_timestamps` was called.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timestamps aren't recorded.
+    pub fn call_timestamps(&self) -> &[Instant] {
+        match &self.timestamps {
+            Some(timestamps) => timestamps,
+            None => self.state.panic_timestamps_not_recorded(),
+        }
+    }
+
+    /// Returns the time between each pair of consecutive calls, e.g. to test debouncing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timestamps aren't recorded.
+    pub fn durations_between_calls(&self) -> Vec<Duration> {
+        self.call_timestamps()
+            .windows(2)
+            .map(|pair| pair[1].duration_since(pair[0]))
+            .collect()
+    }
+
+    /// Returns the number of async calls dropped at the yield point of `yield_before_returning`.
+    pub fn cancelled_calls(&self) -> usize {
+        self.cancelled_calls
+    }
+
+    pub fn was_called(&self) -> bool {
+        self.call_count > 0
+    }
+
+    pub fn was_called_with(&self, params: &Params) -> bool {
+        if let Some(call_index) = &self.call_index {
+            return call_index.contains(&self.calls, params);
+        }
+        self.calls.iter().any(|called_params| called_params == params)
+    }
+
+    // --- Check ---
+    // Non-panicking versions of the assertions returning the failure message,
+    // which lets the generated proxies panic at the caller's location.
+
+    pub fn check_times(&self, expected_num_of_calls: u32) -> std::result::Result<(), String> {
+        self.state.check_times(self.call_count, expected_num_of_calls)
+    }
+
+    pub fn check_times_in_context(&self, context: &str, expected_num_of_calls: u32) -> std::result::Result<(), String> {
+        self.state.check_times_in_context(context, self.call_count_in_context(context), expected_num_of_calls)
+    }
+
+    /// Checks that no window of length `window` contains more than `max_calls` calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timestamps aren't recorded, see `record_timestamps`.
+    pub fn check_called_at_most_per(&self, max_calls: u32, window: Duration) -> std::result::Result<(), String> {
+        let timestamps = self.call_timestamps();
+
+        // Sliding window over the sorted timestamps, `end` is the first call outside the window starting at `start`
+        let mut busiest_window = None;
+        let mut end = 0;
+        for (start, timestamp) in timestamps.iter().enumerate() {
+            while end < timestamps.len() && timestamps[end].duration_since(*timestamp) < window {
+                end += 1;
+            }
+            if busiest_window.is_none_or(|(_, num_of_calls)| end - start > num_of_calls) {
+                busiest_window = Some((start, end - start));
+            }
+        }
+
+        self.state.check_called_at_most_per(max_calls, window, busiest_window)
+    }
+
+    /// Checks that the mock was called with `params` and marks the matching calls as verified.
+    pub fn check_with_ref(&self, params: &Params) -> std::result::Result<(), String> {
+        let mut was_called_with = false;
+        let mut mark_verified = |position: usize| {
+            self.verified[position].set(true);
+            was_called_with = true;
+        };
+        match &self.call_index {
+            Some(call_index) => call_index.positions_of(&self.calls, params).for_each(&mut mark_verified),
+            None => self.calls.iter()
+                .enumerate()
+                .filter(|(_, called_params)| *called_params == params)
+                .for_each(|(position, _)| mark_verified(position)),
+        }
+        self.state.check_called_with(was_called_with, params)
+    }
+
+    /// Checks that the mock was called with params matching `matcher` and marks the matching calls as verified.
+    pub fn check_with_matcher<M: Matcher<Params>>(&self, matcher: &M) -> std::result::Result<(), String> {
+        let mut was_called_matching = false;
+        for (params, verified) in self.calls.iter().zip(&self.verified) {
+            if matcher.matches(params) {
+                verified.set(true);
+                was_called_matching = true;
+            }
+        }
+        self.state.check_called_matching(was_called_matching, &matcher.describe())
+    }
+
+    /// Checks that `item` was passed in the collection param of any call, e.g. the `Vec` of a bulk API.
+    ///
+    /// Checks the union of the items of all calls, so it doesn't depend on how the items were split into calls.
+    /// The calls aren't covered for `check_no_more_interactions`, since the other items of the call aren't asserted.
+    pub fn check_item_passed<Item>(&self, item: &Item) -> std::result::Result<(), String>
+    where
+        for<'a> &'a Params: IntoIterator<Item = &'a Item>,
+        Item: PartialEq + Debug,
+    {
+        let was_passed = self.calls.iter().any(|params| params.into_iter().any(|passed| passed == item));
+        self.state.check_item_passed(was_passed, item)
+    }
+
+    /// Checks that every recorded call was covered by `assert_with` or `assert_calls_exact`.
+    pub fn check_no_more_interactions(&self) -> std::result::Result<(), String> {
+        let unverified: Vec<&dyn Debug> = self.calls.iter()
+            .zip(&self.verified)
+            .filter(|(_, verified)| !verified.get())
+            .map(|(params, _)| params as &dyn Debug)
+            .collect();
+        self.state.check_no_more_interactions(&unverified)
+    }
+
+    /// Checks that the complete, ordered call history equals `expected`.
+    pub fn check_calls_exact(&self, expected: &[Params]) -> std::result::Result<(), String> {
+        let matches = self.call_count == expected.len()
+            && self.expanded_calls().eq(expected.iter());
+        if matches {
+            self.verified.iter().for_each(|verified| verified.set(true));
+            return Ok(());
+        }
+
+        let actual: Vec<String> = self.expanded_calls()
+            .map(|params| format!("{:?}", params))
+            .collect();
+        let expected: Vec<String> = expected.iter().map(|params| format!("{:?}", params)).collect();
+        self.state.check_calls_exact(&expected, &actual)
+    }
+
+    pub fn check_expectations(&self) -> std::result::Result<(), String> {
+        self.state.check_expectations(self.call_count)
+    }
+
+    /// Returns the verification status of the mock for `fnmock::registry::try_verify_all`.
+    pub fn status(&self) -> MockStatus {
+        MockStatus {
+            is_set: self.is_set(),
+            call_count: self.call_count,
+            expected_calls: self.state.expected_times,
+            unmet_expectation: self.check_expectations().err(),
+        }
+    }
+
+    /// Checks the expectations and starts a new phase of the test.
+    ///
+    /// The expectations are discarded, so the mock can be configured again for the next phase
+    /// without tripping the checks of `fnmock::strict`. The implementation and the call history are kept.
+    pub fn checkpoint(&mut self) -> std::result::Result<(), String> {
+        let result = self.check_expectations();
+        self.state.expected_times = None;
+        self.state.setup_location = None;
+        self.state.calls_before_checkpoint = self.call_count;
+        result
+    }
+
+    // --- Assert ---
+
+    #[track_caller]
+    pub fn assert_times(&self, expected_num_of_calls: u32) {
+        if let Err(message) = self.check_times(expected_num_of_calls) {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    /// Asserts like `assert_times`, labeling a failure with `message`, e.g. `after retry loop`.
+    #[track_caller]
+    pub fn assert_times_msg(&self, expected_num_of_calls: u32, message: &str) {
+        if let Err(failure) = self.check_times(expected_num_of_calls) {
+            crate::verify::report_failure(crate::verify::with_context_message(message, failure))
+        }
+    }
+
+    /// Asserts the expectations set with `expect_times`.
+    #[track_caller]
+    pub fn verify(&self) {
+        if let Err(message) = self.check_expectations() {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    /// Asserts that the mock was called with params matching `matcher`.
+    #[track_caller]
+    pub fn assert_with_matchers<M: Matcher<Params>>(&self, matcher: M) {
+        if let Err(message) = self.check_with_matcher(&matcher) {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    /// Asserts the number of calls made in the `fnmock::with_context` context `context`.
+    #[track_caller]
+    pub fn assert_times_in_context(&self, context: &str, expected_num_of_calls: u32) {
+        if let Err(message) = self.check_times_in_context(context, expected_num_of_calls) {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    /// Asserts that no window of length `window` contains more than `max_calls` calls.
+    #[track_caller]
+    pub fn assert_called_at_most_per(&self, max_calls: u32, window: Duration) {
+        if let Err(message) = self.check_called_at_most_per(max_calls, window) {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    /// Asserts that the complete, ordered call history equals `expected`.
+    #[track_caller]
+    pub fn assert_calls_exact(&self, expected: &[Params]) {
+        if let Err(message) = self.check_calls_exact(expected) {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    /// Asserts that every recorded call was covered by `assert_with` or `assert_calls_exact`.
+    #[track_caller]
+    pub fn verify_no_more_interactions(&self) {
+        if let Err(message) = self.check_no_more_interactions() {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    #[track_caller]
+    pub fn assert_with(&self, params: Params) {
+        self.assert_with_ref(&params)
+    }
+
+    #[track_caller]
+    pub fn assert_with_ref(&self, params: &Params) {
+        if let Err(message) = self.check_with_ref(params) {
+            crate::verify::report_failure(message)
+        }
+    }
+
+    /// Asserts like `assert_with`, labeling a failure with `message`, e.g. `after retry loop`.
+    #[track_caller]
+    pub fn assert_with_msg(&self, params: Params, message: &str) {
+        if let Err(failure) = self.check_with_ref(&params) {
+            crate::verify::report_failure(crate::verify::with_context_message(message, failure))
+        }
+    }
+}
+
+// The implementation can't be printed, so only whether it is set is shown
+impl<Params, Result> Debug for FunctionMock<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("FunctionMock");
+        debug
+            .field("name", &self.state.name)
+            .field("module_path", &self.state.module_path)
+            .field("is_set", &self.is_set())
+            .field("call_count", &self.call_count)
+            .field("calls", &self.calls);
+        if let Some(run_lengths) = &self.run_lengths {
+            debug.field("run_lengths", run_lengths);
+        }
+        debug
+            .field("expected_times", &self.state.expected_times)
+            .finish()
+    }
+}
+
+impl<Params, Result> Display for FunctionMock<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.is_set() { "set" } else { "not set" };
+        write!(f, "{} mock ({}, called {} times)", self.state.name, state, self.call_count)
+    }
+}
+
+impl<Params, Result> Drop for FunctionMock<Params, Result>
+where
+    Params: Clone + PartialEq + Debug,
+{
+    // The thread-local mocks are dropped when their thread exits
+    fn drop(&mut self) {
+        self.clear_implementation();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helper mock function for testing
+    fn add_mock_implementation(params: (i32, i32)) -> i32 {
+        params.0 + params.1
+    }
+
+    fn multiply_mock_implementation(params: (i32, i32)) -> i32 {
+        params.0 * params.1
+    }
+
+    fn string_concat_mock_implementation(params: (String, String)) -> String {
+        format!("{}{}", params.0, params.1)
+    }
+
+    #[test]
+    fn test_new_creates_mock_with_correct_name() {
+        let mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("test_function");
+        assert_eq!(mock.state.name, "test_function");
+        assert!(mock.implementation.is_none());
+        assert!(mock.calls.is_empty());
+    }
+
+    #[test]
+    fn test_with_module_path_sets_module_path() {
+        let mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add").with_module_path("crate::math");
+        assert_eq!(mock.state.name, "add");
+        assert_eq!(mock.state.module_path, Some("crate::math"));
+    }
+
+    #[test]
+    fn test_mock_implementation_sets_function() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        assert!(mock.implementation.is_some());
+    }
+
+    #[test]
+    fn test_setup_returning_returns_value_for_any_params() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup_returning(42);
+
+        assert_eq!(mock.call((5, 3)), 42);
+        assert_eq!(mock.call((10, 20)), 42);
+        mock.assert_times(2);
+        mock.assert_with((10, 20));
+    }
+
+    #[test]
+    fn test_setup_returning_replaces_implementation() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.setup_returning(0);
+
+        assert_eq!(mock.call((5, 3)), 0);
+    }
+
+    #[test]
+    fn test_returning_indexed_receives_call_index() {
+        let mut mock: FunctionMock<u32, Vec<u32>> = FunctionMock::new("fetch_page");
+        mock.returning_indexed(|call_index, page_size| match call_index {
+            0 | 1 => vec![1; page_size as usize],
+            _ => Vec::new(),
+        });
+
+        assert_eq!(mock.call(2), vec![1, 1]);
+        assert_eq!(mock.call(2), vec![1, 1]);
+        assert_eq!(mock.call(2), Vec::<u32>::new());
+
+        mock.clear_history();
+        assert_eq!(mock.call(1), vec![1]);
+    }
+
+    #[test]
+    fn test_setup_panic_records_call_and_panics() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup_panic("db connection lost");
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mock.call((1, 2)))).unwrap_err();
+
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "db connection lost");
+        mock.assert_times(1);
+        mock.assert_with((1, 2));
+    }
+
+    #[test]
+    fn test_setup_pending_records_call_without_result() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup_pending();
+
+        assert_eq!(mock.try_call((1, 2)), None);
+        mock.assert_with((1, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock is pending, which is only supported for async functions")]
+    fn test_call_panics_when_pending() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup_pending();
+
+        mock.call((1, 2));
+    }
+
+    #[test]
+    fn test_cancelled_calls_are_counted_until_history_is_cleared() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.yield_before_returning();
+
+        mock.call((1, 2));
+        mock.record_cancelled();
+        assert!(mock.yields_before_returning());
+        assert_eq!(mock.cancelled_calls(), 1);
+
+        mock.clear_history();
+        assert_eq!(mock.cancelled_calls(), 0);
+
+        mock.clear();
+        assert!(!mock.yields_before_returning());
+    }
+
+    #[test]
+    fn test_next_delay_combines_latency_and_jitter() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        assert_eq!(mock.next_delay(), None);
+
+        mock.with_latency(Duration::from_millis(100));
+        assert_eq!(mock.next_delay(), Some(Duration::from_millis(100)));
+
+        mock.with_jitter(Duration::from_millis(10)..Duration::from_millis(20));
+        let delay = mock.next_delay().unwrap();
+        assert!((Duration::from_millis(110)..Duration::from_millis(120)).contains(&delay));
+
+        mock.clear();
+        assert_eq!(mock.next_delay(), None);
+    }
+
+    #[test]
+    fn test_record_timestamps_records_time_of_calls() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((0, 0));
+        mock.record_timestamps();
+
+        let before = Instant::now();
+        mock.call((1, 2));
+        std::thread::sleep(Duration::from_millis(5));
+        mock.call((3, 4));
+
+        let timestamps = mock.call_timestamps();
+        assert_eq!(timestamps.len(), 2);
+        assert!(timestamps[0] >= before);
+        assert!(mock.durations_between_calls()[0] >= Duration::from_millis(5));
+
+        mock.clear_history();
+        assert!(mock.call_timestamps().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock doesn't record timestamps, call record_timestamps() before the calls")]
+    fn test_call_timestamps_panics_when_not_recorded() {
+        let mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.call_timestamps();
+    }
+
+    #[test]
+    fn test_assert_called_at_most_per_checks_every_window() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.record_timestamps();
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        mock.timestamps = Some(vec![at(0), at(400), at(1000), at(1100), at(1300), at(2500)]);
+
+        mock.assert_called_at_most_per(4, Duration::from_secs(1));
+        assert_eq!(
+            mock.check_called_at_most_per(3, Duration::from_secs(1)),
+            Err("Expected add mock to be called at most 3 times per 1s, received 4 calls within 1s starting at call 2".to_string())
+        );
+        // The window ends before the call 1s after its start
+        mock.assert_called_at_most_per(1, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_setup_producing_produces_value_on_every_call() {
+        let mut mock: FunctionMock<u32, Box<dyn Iterator<Item = u32>>> = FunctionMock::new("list_ids");
+        mock.setup_producing(Box::new(vec![1_u32, 2, 3]), |items| {
+            Box::new(items.downcast_ref::<Vec<u32>>().unwrap().clone().into_iter())
+        });
+
+        assert_eq!(mock.call(10).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(mock.call(20).sum::<u32>(), 6);
+        mock.assert_with(20);
+    }
+
+    #[test]
+    fn test_call_executes_mocked_function() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        
+        let result = mock.call((5, 3));
+        assert_eq!(result, 8);
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock not initialized")]
+    fn test_call_panics_when_not_initialized() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.call((5, 3));
+    }
+
+    #[test]
+    fn test_call_panics_with_configured_mocks_and_setup_example() {
+        let mut configured: FunctionMock<i32, i32> = FunctionMock::new("configured").with_module_path("tests");
+        configured.setup(|x| x);
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add")
+            .with_setup_example("add_mock::setup(|(a, b)| todo!())");
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mock.call((5, 3)))).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.contains("\nMocks configured on this thread: tests::configured"));
+        assert!(message.ends_with("\nSet up the mock before the call, e.g.:\n    add_mock::setup(|(a, b)| todo!())"));
+    }
+
+    #[test]
+    fn test_call_panics_with_configuring_thread_when_configured_on_other_thread() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("cross_thread").with_module_path("tests");
+        mock.setup(|x| x);
+
+        let panic = std::thread::spawn(|| {
+            let mut thread_mock: FunctionMock<i32, i32> = FunctionMock::new("cross_thread").with_module_path("tests");
+            thread_mock.call(1);
+        }).join().unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.contains("cross_thread mock not initialized on thread '<unnamed>'"));
+        assert!(message.contains("but it is configured on thread 'function_mock::tests::test_call_panics_with_configuring_thread"));
+    }
+
+    #[test]
+    fn test_call_panics_without_thread_note_after_clear() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("cleared_cross_thread").with_module_path("tests");
+        mock.setup(|x| x);
+        mock.clear();
+
+        let panic = std::thread::spawn(|| {
+            let mut thread_mock: FunctionMock<i32, i32> = FunctionMock::new("cleared_cross_thread").with_module_path("tests");
+            thread_mock.call(1);
+        }).join().unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("cleared_cross_thread mock not initialized on thread '<unnamed>'"));
+        assert!(!message.contains("but it is configured on thread"));
+    }
+
+    #[test]
+    fn test_call_records_parameters() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        
+        mock.call((5, 3));
+        mock.call((10, 20));
+        
+        assert_eq!(mock.calls.len(), 2);
+        assert_eq!(mock.calls[0], (5, 3));
+        assert_eq!(mock.calls[1], (10, 20));
+    }
+
+    #[test]
+    fn test_clear_mock_resets_state() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((5, 3));
+        mock.call((10, 20));
+
+        assert_eq!(mock.calls[0], (5, 3));
+        assert_eq!(mock.calls[1], (10, 20));
+        
+        mock.clear();
+        
+        assert!(mock.implementation.is_none());
+        assert!(mock.calls.is_empty());
+    }
+
+    #[test]
+    fn test_clear_implementation_keeps_history() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((5, 3));
+
+        mock.clear_implementation();
+
+        assert!(mock.implementation.is_none());
+        assert_eq!(mock.calls(), [(5, 3)]);
+    }
+
+    #[test]
+    fn test_record_setup_after_clear_implementation() {
+        crate::strict::set_repeated_setup(crate::strict::Strictness::Panic);
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.record_setup(Location::caller());
+        mock.setup(add_mock_implementation);
+
+        mock.clear_implementation();
+
+        mock.record_setup(Location::caller());
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock was set up again without being cleared")]
+    fn test_record_setup_twice_in_strict_mode() {
+        crate::strict::set_repeated_setup(crate::strict::Strictness::Panic);
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.record_setup(Location::caller());
+        mock.setup(add_mock_implementation);
+
+        mock.record_setup(Location::caller());
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock was set up at ")]
+    fn test_record_setup_after_call_in_strict_mode() {
+        crate::strict::set_setup_after_call(crate::strict::Strictness::Panic);
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((1, 2));
+
+        mock.record_setup(Location::caller());
+    }
+
+    #[test]
+    fn test_checkpoint_starts_new_phase() {
+        crate::strict::set_setup_after_call(crate::strict::Strictness::Panic);
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.expect_times(1);
+        mock.call((1, 2));
+
+        assert_eq!(mock.checkpoint(), Ok(()));
+        mock.record_setup(Location::caller());
+        mock.expect_times(1);
+        mock.call((3, 4));
+
+        // Only the calls since the checkpoint count for the new expectations
+        assert_eq!(mock.check_expectations(), Ok(()));
+        assert_eq!(mock.calls(), [(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock has a stale mock configuration leaked from a previous test")]
+    fn test_call_configured_in_previous_generation() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+
+        crate::registry::begin_test();
+
+        mock.call((1, 2));
+    }
+
+    #[test]
+    fn test_clear_history_keeps_implementation() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((5, 3));
+
+        mock.clear_history();
+
+        assert!(mock.calls.is_empty());
+        assert_eq!(mock.call((1, 1)), 2);
+        mock.assert_times(1);
+    }
+
+    #[test]
+    fn test_mock_can_be_replaced() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("math");
+        mock.setup(add_mock_implementation);
+        
+        let result1 = mock.call((5, 3));
+        assert_eq!(result1, 8);
+        
+        mock.setup(multiply_mock_implementation);
+        let result2 = mock.call((5, 3));
+        assert_eq!(result2, 15);
+    }
+
+    #[test]
+    fn test_calls_returns_recorded_params_in_order() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+
+        mock.call((1, 2));
+        mock.call((3, 4));
+
+        assert_eq!(mock.calls(), &[(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn test_was_called_is_false_without_calls() {
+        let mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        assert!(!mock.was_called());
+    }
+
+    #[test]
+    fn test_was_called_is_true_after_call() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+
+        mock.call((1, 2));
+
+        assert!(mock.was_called());
+    }
+
+    #[test]
+    fn test_was_called_with_matches_recorded_params() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+
+        mock.call((1, 2));
+        mock.call((3, 4));
+
+        assert!(mock.was_called_with(&(1, 2)));
+        assert!(mock.was_called_with(&(3, 4)));
+        assert!(!mock.was_called_with(&(5, 6)));
+    }
+
+    #[test]
+    fn test_was_called_with_is_false_after_clear() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((1, 2));
+
+        mock.clear();
+
+        assert!(!mock.was_called_with(&(1, 2)));
+    }
+
+    #[test]
+    fn test_assert_times_passes_with_correct_count() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        
+        mock.call((1, 2));
+        mock.call((3, 4));
+        mock.call((5, 6));
+        
+        mock.assert_times(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected add mock to be called 5 times, received 2")]
+    fn test_assert_times_fails_with_wrong_count() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        
+        mock.call((1, 2));
+        mock.call((3, 4));
+        
+        mock.assert_times(5);
+    }
+
+    #[test]
+    fn test_assert_times_with_zero_calls() {
+        let mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.assert_times(0);
+    }
+
+    #[test]
+    fn test_check_times_returns_message_on_wrong_count() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((1, 2));
+
+        assert_eq!(mock.check_times(1), Ok(()));
+        assert_eq!(mock.check_times(2), Err("Expected add mock to be called 2 times, received 1".to_string()));
+    }
+
+    #[test]
+    fn test_check_with_ref_returns_message_when_not_called_with_params() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.call((1, 2));
+
+        assert_eq!(mock.check_with_ref(&(1, 2)), Ok(()));
+        assert_eq!(mock.check_with_ref(&(7, 8)), Err("Expected add mock to be called with (7, 8)".to_string()));
+    }
+
+    #[test]
+    fn test_assert_with_passes_when_called_with_params() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        
+        mock.call((5, 3));
+        mock.call((10, 20));
+        
+        mock.assert_with((5, 3));
+        mock.assert_with((10, 20));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected add mock to be called with (7, 8)")]
+    fn test_assert_with_fails_when_not_called_with_params() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        
+        mock.call((5, 3));
+        mock.assert_with((7, 8));
+    }
+
+    #[test]
+    fn test_assert_with_ref_passes_when_called_with_params() {
+        let mut mock: FunctionMock<Vec<String>, usize> = FunctionMock::new("count");
+        mock.setup(|params| params.len());
+
+        let expected = vec!["a".to_string(), "b".to_string()];
+        mock.call(expected.clone());
+
+        mock.assert_with_ref(&expected);
+        // The expected value can still be used after the assertion
+        assert_eq!(expected.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected add mock to be called with (7, 8)")]
+    fn test_assert_with_ref_fails_when_not_called_with_params() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+
+        mock.call((5, 3));
+        mock.assert_with_ref(&(7, 8));
+    }
+
+    #[test]
+    fn test_assert_with_finds_params_among_multiple_calls() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        
+        mock.call((1, 1));
+        mock.call((2, 2));
+        mock.call((3, 3));
+        mock.call((4, 4));
+        
+        mock.assert_with((3, 3));
+    }
+
+    #[test]
+    fn test_with_string_parameters() {
+        let mut mock: FunctionMock<(String, String), String> = FunctionMock::new("concat");
+        mock.setup(string_concat_mock_implementation);
+        
+        let result = mock.call(("Hello".to_string(), "World".to_string()));
+        assert_eq!(result, "HelloWorld");
+        
+        mock.assert_times(1);
+        mock.assert_with(("Hello".to_string(), "World".to_string()));
+    }
+
+    #[test]
+    fn test_with_single_parameter() {
+        fn double_mock(params: i32) -> i32 {
+            params * 2
+        }
+        
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("double");
+        mock.setup(double_mock);
+        
+        let result = mock.call(5);
+        assert_eq!(result, 10);
+        
+        mock.assert_times(1);
+        mock.assert_with(5);
+    }
+
+    #[test]
+    fn test_with_unit_return_type() {
+        #[allow(clippy::unused_unit)]
+        fn void_mock(_params: i32) -> () {
+            // Do nothing
+        }
+        
+        let mut mock: FunctionMock<i32, ()> = FunctionMock::new("void_fn");
+        mock.setup(void_mock);
+        
+        mock.call(42);
+        mock.assert_times(1);
+        mock.assert_with(42);
+    }
+
+    #[test]
+    fn test_with_result_return_type() {
+        fn result_mock(params: (i32, i32)) -> Result<i32, String> {
+            if params.1 == 0 {
+                Err("Division by zero".to_string())
+            } else {
+                Ok(params.0 / params.1)
+            }
+        }
+        
+        let mut mock: FunctionMock<(i32, i32), Result<i32, String>> = FunctionMock::new("divide");
+        mock.setup(result_mock);
+        
         let result1 = mock.call((10, 2));
         assert_eq!(result1, Ok(5));
         
@@ -357,14 +1682,592 @@ mod tests {
     }
 
     #[test]
-    fn test_multiple_calls_preserve_order() {
+    fn test_multiple_calls_preserve_order() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        
+        mock.call(1);
+        mock.call(2);
+        mock.call(3);
+        
+        assert_eq!(mock.calls(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_debug_shows_state_and_calls() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add").with_module_path("crate::math");
+        mock.setup(add_mock_implementation);
+        mock.call((1, 2));
+
+        assert_eq!(
+            format!("{:?}", mock),
+            "FunctionMock { name: \"add\", module_path: Some(\"crate::math\"), is_set: true, call_count: 1, calls: [(1, 2)], expected_times: None }"
+        );
+    }
+
+    #[test]
+    fn test_display_summarizes_mock() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        assert_eq!(mock.to_string(), "identity mock (not set, called 0 times)");
+
+        mock.setup(|x| x);
+        mock.call(1);
+        mock.call(2);
+        assert_eq!(mock.to_string(), "identity mock (set, called 2 times)");
+    }
+
+    #[test]
+    fn test_verify_passes_without_expectations() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.call(1);
+
+        mock.verify();
+    }
+
+    #[test]
+    fn test_verify_passes_when_expectation_is_met() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.expect_times(2);
+
+        mock.call(1);
+        mock.call(2);
+
+        mock.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected identity mock to be called 2 times, received 1")]
+    fn test_verify_fails_when_expectation_is_not_met() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.expect_times(2);
+
+        mock.call(1);
+
+        mock.verify();
+    }
+
+    #[test]
+    fn test_clear_removes_expectations() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.expect_times(2);
+
+        mock.clear();
+
+        assert_eq!(mock.check_expectations(), Ok(()));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct NotClonedParams(i32);
+
+    // Panics if the params are cloned, to verify the borrowed implementations don't clone
+    impl Clone for NotClonedParams {
+        fn clone(&self) -> Self {
+            panic!("params were cloned")
+        }
+    }
+
+    #[test]
+    fn test_setup_ref_does_not_clone_params() {
+        let mut mock: FunctionMock<NotClonedParams, i32> = FunctionMock::new("double");
+        mock.setup_ref(|params| params.0 * 2);
+
+        assert_eq!(mock.call(NotClonedParams(21)), 42);
+        mock.assert_with_ref(&NotClonedParams(21));
+    }
+
+    #[test]
+    fn test_setup_returning_does_not_clone_params() {
+        let mut mock: FunctionMock<NotClonedParams, i32> = FunctionMock::new("double");
+        mock.setup_returning(0);
+
+        assert_eq!(mock.call(NotClonedParams(21)), 0);
+        mock.assert_times(1);
+    }
+
+    #[test]
+    fn test_clear_history_keeps_capacity() {
         let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
         mock.setup(|x| x);
-        
+        for i in 0..100 {
+            mock.call(i);
+        }
+        let capacity = mock.calls.capacity();
+
+        mock.clear();
+
+        assert!(mock.calls.is_empty());
+        assert_eq!(mock.calls.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_records_borrowed_params() {
+        let user = String::from("alice");
+        let mut mock: FunctionMock<&str, usize> = FunctionMock::new("name_length");
+        mock.setup(|name| name.len());
+
+        assert_eq!(mock.call(&user), 5);
+        assert!(mock.was_called_with(&"alice"));
+        mock.assert_times(1);
+    }
+
+    #[test]
+    fn test_setup_returning_borrowed_result() {
+        let config = String::from("test_config");
+        let mut mock: FunctionMock<(), &str> = FunctionMock::new("get_config");
+        mock.setup_returning(&config);
+
+        assert_eq!(mock.call(()), "test_config");
+    }
+
+    #[test]
+    fn test_dedup_consecutive_calls_records_runs() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.dedup_consecutive_calls();
+
+        for _ in 0..1000 {
+            assert_eq!(mock.call(1), 1);
+        }
+        mock.call(2);
+        mock.call(1);
+
+        assert_eq!(mock.calls(), [1, 2, 1]);
+        assert_eq!(mock.call_runs().collect::<Vec<_>>(), [(&1, 1000), (&2, 1), (&1, 1)]);
+        mock.assert_times(1002);
+        mock.assert_with(2);
+        assert_eq!(mock.to_string(), "identity mock (set, called 1002 times)");
+    }
+
+    #[test]
+    fn test_dedup_consecutive_calls_keeps_earlier_calls() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup_returning(0);
+        mock.call(1);
+        mock.call(1);
+
+        mock.dedup_consecutive_calls();
+        mock.call(1);
+
+        assert_eq!(mock.call_runs().collect::<Vec<_>>(), [(&1, 1), (&1, 2)]);
+        mock.assert_times(3);
+    }
+
+    #[test]
+    fn test_clear_disables_dedup_consecutive_calls() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.dedup_consecutive_calls();
+        mock.call(1);
+        mock.call(1);
+
+        mock.clear_history();
+        assert_eq!(mock.call_count(), 0);
+
+        mock.clear();
+        mock.setup(|x| x);
+        mock.call(1);
+        mock.call(1);
+
+        assert_eq!(mock.calls(), [1, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock is forbidden, but was called with (1, 2)")]
+    fn test_forbid_panics_on_call() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.forbid();
+
+        mock.call((1, 2));
+    }
+
+    #[test]
+    fn test_forbid_is_cleared() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.forbid();
+        assert!(mock.is_set());
+
+        mock.clear();
+        assert!(!mock.is_set());
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock exceeded its call budget of 2 calls, call 3 was made with (5, 6)")]
+    fn test_call_budget_panics_on_excess_call() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(|(x, y)| x + y);
+        mock.set_call_budget(2);
+
+        mock.call((1, 2));
+        mock.call((3, 4));
+        mock.call((5, 6));
+    }
+
+    #[test]
+    fn test_call_budget_counts_calls_since_history_was_cleared() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(|(x, y)| x + y);
+        mock.set_call_budget(1);
+
+        mock.call((1, 2));
+        mock.clear_history();
+        assert_eq!(mock.call((3, 4)), 7);
+
+        mock.clear();
+        mock.setup(|(x, y)| x + y);
+        mock.call((1, 2));
+        mock.call((1, 2));
+    }
+
+    #[test]
+    fn test_conditions_pass_for_valid_calls() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(|(x, y)| x + y);
+        mock.add_precondition(|(x, _)| *x >= 0, Location::caller());
+        mock.add_postcondition(|(x, y), sum| *sum == x + y, Location::caller());
+
+        assert_eq!(mock.call((1, 2)), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock precondition added at fnmock/src/function_mock.rs:")]
+    fn test_precondition_violation_panics_with_params() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(|(x, y)| x + y);
+        mock.add_precondition(|(x, _)| *x >= 0, Location::caller());
+
+        mock.call((-1, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "was violated by the call with (1, 2) returning 4")]
+    fn test_postcondition_violation_panics_with_params_and_result() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(|(x, y)| x + y + 1);
+        mock.add_postcondition(|(x, y), sum| *sum == x + y, Location::caller());
+
+        mock.call((1, 2));
+    }
+
+    #[test]
+    fn test_conditions_are_cleared() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.add_precondition(|_| false, Location::caller());
+
+        mock.clear();
+        mock.setup(|(x, y)| x + y);
+
+        assert_eq!(mock.call((1, 2)), 3);
+    }
+
+    #[test]
+    fn test_check_real_call_panics_until_required_mock_is_cleared() {
+        let mut mock: FunctionMock<u32, u32> = FunctionMock::new("fetch_mock");
+        mock.require();
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mock.check_real_call())).unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "fetch_mock mock is required, but the real implementation was called");
+
+        mock.clear();
+        mock.check_real_call();
+    }
+
+    #[test]
+    fn test_check_item_passed_checks_items_of_all_calls() {
+        let mut mock: FunctionMock<Vec<u32>, Vec<bool>> = FunctionMock::new("save_all");
+        mock.setup(|ids| ids.iter().map(|id| id % 2 == 0).collect());
+
+        assert_eq!(mock.call(vec![1, 2]), vec![false, true]);
+        mock.call(vec![3]);
+
+        assert_eq!(mock.check_item_passed(&3), Ok(()));
+        assert_eq!(mock.check_item_passed(&4), Err("Expected save_all mock to be passed the item 4 in any call".to_string()));
+    }
+
+    #[test]
+    fn test_assert_calls_exact_with_matching_history() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(|(x, y)| x + y);
+        mock.call((1, 2));
+        mock.call((3, 4));
+
+        mock.assert_calls_exact(&[(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn test_check_calls_exact_shows_diff() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(|(x, y)| x + y);
+        mock.call((1, 2));
+        mock.call((5, 6));
+
+        assert_eq!(
+            mock.check_calls_exact(&[(1, 2), (3, 4)]),
+            Err("Expected add mock calls to match exactly (- expected, + actual):\n  (1, 2)\n- (3, 4)\n+ (5, 6)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_assert_calls_exact_with_dedup_consecutive_calls() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.dedup_consecutive_calls();
+        mock.call(1);
         mock.call(1);
         mock.call(2);
-        mock.call(3);
-        
-        assert_eq!(mock.calls, vec![1, 2, 3]);
+
+        mock.assert_calls_exact(&[1, 1, 2]);
+        assert!(mock.check_calls_exact(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_verify_no_more_interactions_after_assert_with() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.call(1);
+        mock.call(2);
+        mock.call(1);
+
+        mock.assert_with(1);
+        assert_eq!(
+            mock.check_no_more_interactions(),
+            Err("Expected no more interactions with identity mock, but found 1 unverified call: 2".to_string())
+        );
+
+        mock.assert_with(2);
+        mock.verify_no_more_interactions();
+    }
+
+    #[test]
+    fn test_verify_no_more_interactions_after_assert_calls_exact() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.call(1);
+        mock.call(2);
+
+        mock.assert_calls_exact(&[1, 2]);
+        mock.verify_no_more_interactions();
+    }
+
+    #[test]
+    fn test_verify_no_more_interactions_with_index_calls() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.index_calls();
+        mock.call(1);
+        mock.call(2);
+
+        mock.assert_with(2);
+        assert!(mock.check_no_more_interactions().is_err());
+
+        mock.assert_with(1);
+        mock.verify_no_more_interactions();
+    }
+
+    #[test]
+    fn test_new_call_in_dedup_run_is_unverified() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.dedup_consecutive_calls();
+        mock.call(1);
+        mock.assert_with(1);
+
+        mock.call(1);
+
+        assert!(mock.check_no_more_interactions().is_err());
+    }
+
+    #[test]
+    fn test_assert_times_in_context() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+
+        crate::with_context("request-1", || {
+            mock.call(1);
+            mock.call(2);
+        });
+        crate::with_context("request-2", || mock.call(3));
+        mock.call(4);
+
+        mock.assert_times_in_context("request-1", 2);
+        mock.assert_times_in_context("request-2", 1);
+        mock.assert_times_in_context("request-3", 0);
+        mock.assert_times(4);
+        assert_eq!(
+            mock.check_times_in_context("request-2", 2),
+            Err("Expected identity mock to be called 2 times in context \"request-2\", received 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dedup_consecutive_calls_splits_runs_by_context() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.dedup_consecutive_calls();
+
+        crate::with_context("request-1", || {
+            mock.call(1);
+            mock.call(1);
+        });
+        crate::with_context("request-2", || mock.call(1));
+
+        assert_eq!(mock.call_runs().collect::<Vec<_>>(), [(&1, 2), (&1, 1)]);
+        mock.assert_times_in_context("request-1", 2);
+        mock.assert_times_in_context("request-2", 1);
+    }
+
+    #[test]
+    fn test_assert_with_matchers() {
+        use crate::matchers::{eq, gt, lt, not, MatcherExt};
+
+        let mut mock: FunctionMock<(i32, String), ()> = FunctionMock::new("save_user");
+        mock.setup(|_| ());
+        mock.call((42, "alice".to_string()));
+
+        mock.assert_with_matchers((gt(0).and(lt(100)), not(eq("admin"))));
+        mock.verify_no_more_interactions();
+        assert_eq!(
+            mock.check_with_matcher(&(gt(0), eq("admin"))),
+            Err("Expected save_user mock to be called with params matching (> 0, \"admin\")".to_string())
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_index_calls_finds_params() {
+        let mut mock: FunctionMock<(i32, String), i32> = FunctionMock::new("add");
+        mock.setup(|(x, _)| x);
+        mock.call((1, "a".to_string()));
+        mock.index_calls();
+
+        for i in 2..10_000 {
+            mock.call((i, i.to_string()));
+        }
+
+        assert!(mock.was_called_with(&(1, "a".to_string())));
+        assert!(mock.was_called_with(&(9_999, "9999".to_string())));
+        assert!(!mock.was_called_with(&(1, "b".to_string())));
+        mock.assert_with((5_000, "5000".to_string()));
+    }
+
+    #[test]
+    fn test_clear_history_clears_index() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+        mock.index_calls();
+        mock.call(1);
+
+        mock.clear_history();
+        assert!(!mock.was_called_with(&1));
+
+        mock.call(1);
+        assert!(mock.was_called_with(&1));
+    }
+
+    #[test]
+    fn test_import_copies_exported_configuration_to_other_thread() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.expect_times(1);
+        mock.call((1, 1));
+        let export = mock.export();
+
+        std::thread::spawn(move || {
+            let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+            mock.import(export);
+
+            assert_eq!(mock.call((2, 3)), 5);
+            assert!(mock.check_expectations().is_ok());
+        }).join().unwrap();
+
+        // The original mock keeps its configuration and history
+        assert_eq!(mock.call((2, 2)), 4);
+        mock.assert_times(2);
+    }
+
+    #[test]
+    fn test_import_clones_return_value_and_clears_history() {
+        let mut source: FunctionMock<i32, String> = FunctionMock::new("to_string");
+        source.setup_returning("mocked".to_string());
+        source.record_timestamps();
+
+        let mut mock: FunctionMock<i32, String> = FunctionMock::new("to_string");
+        mock.forbid();
+        mock.import(source.export());
+        mock.call(1);
+
+        assert_eq!(source.call(2), "mocked");
+        assert_eq!(mock.calls.len(), 1);
+        assert_eq!(mock.call_timestamps().len(), 1);
+    }
+
+    #[test]
+    fn test_restore_replaces_configuration_and_clears_history() {
+        let mut mock: FunctionMock<(i32, i32), i32> = FunctionMock::new("add");
+        mock.setup(add_mock_implementation);
+        mock.expect_times(1);
+        let snapshot = mock.snapshot();
+
+        mock.setup(multiply_mock_implementation);
+        assert_eq!(mock.call((2, 3)), 6);
+
+        mock.restore(&snapshot);
+        assert!(mock.calls.is_empty());
+        assert_eq!(mock.call((2, 3)), 5);
+        assert!(mock.check_expectations().is_ok());
+    }
+
+    #[test]
+    fn test_restore_shares_produced_data() {
+        let mut mock: FunctionMock<(), Vec<u32>> = FunctionMock::new("list");
+        mock.setup_producing(Box::new(vec![1_u32]), |data| data.downcast_ref::<Vec<u32>>().unwrap().clone());
+        let snapshot = mock.snapshot();
+
+        mock.clear();
+        mock.restore(&snapshot);
+
+        assert_eq!(mock.call(()), vec![1]);
+    }
+
+    #[test]
+    fn test_export_of_parameterized_mock_keeps_data() {
+        let mut mock: FunctionMock<u32, u32> = FunctionMock::new("offset");
+        mock.setup_parameterized(Arc::new(100_u32), |offset, id| offset.downcast_ref::<u32>().unwrap() + id);
+        let export = mock.export();
+
+        let result = std::thread::spawn(move || {
+            let mut mock: FunctionMock<u32, u32> = FunctionMock::new("offset");
+            mock.import(export);
+            mock.call(1)
+        }).join().unwrap();
+
+        assert_eq!(result, 101);
+        assert_eq!(mock.call(2), 102);
+        mock.assert_with(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "list mock is set up with canned items, which can't be exported")]
+    fn test_export_of_producing_mock_panics() {
+        let mut mock: FunctionMock<(), Vec<u32>> = FunctionMock::new("list");
+        mock.setup_producing(Box::new(vec![1_u32]), |data| data.downcast_ref::<Vec<u32>>().unwrap().clone());
+
+        mock.export();
+    }
+
+    #[test]
+    #[cfg(feature = "smallvec")]
+    fn test_few_calls_are_stored_inline() {
+        let mut mock: FunctionMock<i32, i32> = FunctionMock::new("identity");
+        mock.setup(|x| x);
+
+        for i in 0..INLINE_CALLS as i32 {
+            mock.call(i);
+        }
+        assert!(!mock.calls.spilled());
+
+        mock.call(-1);
+        assert!(mock.calls.spilled());
+    }
+}
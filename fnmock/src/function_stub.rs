@@ -1,3 +1,80 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Formatter};
+use std::panic::Location;
+use crate::messages::{panic_not_initialized, panic_snapshot_of_iterator, panic_values_exhausted};
+use crate::strict::{self, RealCallGuard};
+
+/// Configuration of a stub captured with `snapshot`, which can be restored any number of times
+///
+/// Shared by `FunctionStub` and `CopyFunctionStub`.
+pub struct StubSnapshot<ReturnType> {
+    pub(crate) return_value: Option<ReturnType>,
+    pub(crate) panic_message: Option<&'static str>,
+}
+
+/// Return values of a stub set up with `setup_when` or `setup_for_caller`, each returned for the calls matching its predicate
+///
+/// The predicates are `fn(&Args) -> bool` pointers over the `Args` struct of the generated stub module
+/// or `CallerPath`s, which are type-erased, since the stubs are generic over the return type only.
+/// Shared by `FunctionStub` and `CopyFunctionStub`.
+pub(crate) struct StubConditions<ReturnType> {
+    conditions: Vec<(Box<dyn Any>, ReturnType)>,
+}
+
+impl<ReturnType> StubConditions<ReturnType> {
+    pub(crate) const fn new() -> Self {
+        Self { conditions: Vec::new() }
+    }
+
+    pub(crate) fn push(&mut self, predicate: Box<dyn Any>, return_value: ReturnType) {
+        self.conditions.push((predicate, return_value));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.conditions.clear();
+    }
+
+    /// Returns the value of the first condition whose predicate of type `P` matches,
+    /// or whose `CallerPath` matches the caller, if the call passes one.
+    pub(crate) fn find<P: 'static>(
+        &self,
+        caller: Option<&Location<'_>>,
+        matches: impl Fn(&P) -> bool,
+    ) -> Option<&ReturnType> {
+        self.conditions
+            .iter()
+            .find(|(predicate, _)| {
+                predicate.downcast_ref::<P>().is_some_and(&matches)
+                    || caller.is_some_and(|caller| {
+                        predicate.downcast_ref::<CallerPath>().is_some_and(|path| path.matches(caller.file()))
+                    })
+            })
+            .map(|(_, return_value)| return_value)
+    }
+}
+
+/// Path of the source files whose calls a condition of `setup_for_caller` matches
+///
+/// A path ending with `/` matches the files in the directory, e.g. `src/handlers/`,
+/// other paths match a single file, e.g. `src/handlers/user.rs`.
+/// Paths are compared to the end of the caller location, so they don't depend on the workspace root.
+pub(crate) struct CallerPath(pub(crate) &'static str);
+
+impl CallerPath {
+    pub(crate) fn matches(&self, file: &str) -> bool {
+        // `file!()` uses the separator of the host, the paths use `/`
+        let file = file.replace('\\', "/");
+        let path = self.0;
+        let in_directory = path.ends_with('/') && (file.starts_with(path) || file.contains(&format!("/{}", path)));
+        in_directory || file == path || file.ends_with(&format!("/{}", path))
+    }
+}
+
 /// Struct for stubbing a function with predetermined return values
 ///
 /// Stubs - in contrast to mocks and fakes - provide canned responses without behavior verification or custom logic.
@@ -5,7 +82,7 @@
 ///
 /// # Generics
 ///
-/// - `ReturnType: 'static + Clone` - the return type of the stubbed function
+/// - `ReturnType: Clone` - the return type of the stubbed function
 ///   - Must be cloneable since the stub may be called multiple times with the same return value
 ///
 /// # Usage
@@ -16,7 +93,7 @@
 ///
 /// The function `get_config` is supposed to be stubbed for testing.
 ///
-/// ```
+/// ```ignore
 /// pub(crate) fn get_config() -> String {
 ///     // Production code that reads from file or environment
 ///     std::fs::read_to_string("config.json").unwrap()
@@ -26,7 +103,7 @@
 /// Now we create a stub function - it should be the same signature,
 /// but with `_stub` at the end of the name and the body calls the stub return value.
 ///
-/// ```
+/// ```ignore
 /// pub(crate) fn get_config_stub() -> String {
 ///     get_config_stub::get_return_value()
 /// }
@@ -34,7 +111,7 @@
 ///
 /// Create a module named `get_config_stub` with the stub infrastructure:
 ///
-/// ```
+/// ```ignore
 /// pub(crate) mod get_config_stub {
 ///     use fnmock::function_stub::FunctionStub;
 ///     
@@ -63,41 +140,234 @@
 ///
 /// - `name` - the name of the function for display purposes when panicking
 /// - `return_value` - the stubbed return value or None
+/// - `panic_message` - the message every call panics with, if set up with `setup_panic`
+/// - `values` - the iterator yielding the return value of each call, if set up with `setup_from_iter`
+/// - `conditions` - the return values of the calls matching a predicate, if set up with `setup_when` or `setup_for_caller`
+/// - `setup_location` - where the stub was last set up through its module, or None since it was cleared
+/// - `definition` - the location of the function appended to the messages, e.g. `src/db.rs:12`, or None
+/// - `real_call_guard` - whether the real implementation may run, see `fnmock::strict::set_real_implementation`
 pub struct FunctionStub<ReturnType>
 where
-    ReturnType: 'static + Clone,
+    ReturnType: Clone,
 {
-    name: String,
+    name: &'static str,
     return_value: Option<ReturnType>,
+    panic_message: Option<&'static str>,
+    values: RefCell<Option<Box<dyn Iterator<Item = ReturnType>>>>,
+    conditions: StubConditions<ReturnType>,
+    setup_location: Option<&'static Location<'static>>,
+    definition: Option<&'static str>,
+    real_call_guard: RealCallGuard,
 }
 
 impl<ReturnType> FunctionStub<ReturnType>
 where
-    ReturnType: 'static + Clone,
+    ReturnType: Clone,
 {
-    pub fn new(function_name: &str) -> Self {
+    pub const fn new(function_name: &'static str) -> Self {
         Self {
-            name: function_name.to_string(),
+            name: function_name,
             return_value: None,
+            panic_message: None,
+            values: RefCell::new(None),
+            conditions: StubConditions::new(),
+            setup_location: None,
+            definition: None,
+            real_call_guard: RealCallGuard::new(),
         }
     }
 
+    /// Sets the location of the stubbed function, e.g. `src/db.rs:12`, which is appended to the panic messages of the stub.
+    pub const fn with_definition(mut self, definition: &'static str) -> Self {
+        self.definition = Some(definition);
+        self
+    }
+
     // --- Stubbing ---
 
+    /// Records where the stub is set up, reporting a repeated setup as configured in `fnmock::strict`.
+    pub fn record_setup(&mut self, location: &'static Location<'static>) {
+        strict::check_setup(self.name, "stub", None, self.definition, self.setup_location, location);
+        self.setup_location = Some(location);
+    }
+
     pub fn setup(&mut self, new_r: ReturnType) {
+        self.real_call_guard.record_configured();
         self.return_value = Some(new_r.clone());
+        self.panic_message = None;
+        self.values = RefCell::new(None);
+    }
+
+    /// Returns the next value of `values` on every call, e.g. `1..` for an ID generator.
+    ///
+    /// The stub panics once the values are exhausted.
+    pub fn setup_from_iter<I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = ReturnType>,
+        I::IntoIter: 'static,
+    {
+        self.real_call_guard.record_configured();
+        self.return_value = None;
+        self.panic_message = None;
+        self.values = RefCell::new(Some(Box::new(values.into_iter())));
+    }
+
+    /// Makes every call panic with `message`, simulating a crashing dependency.
+    pub fn setup_panic(&mut self, message: &'static str) {
+        self.real_call_guard.record_configured();
+        self.return_value = None;
+        self.panic_message = Some(message);
+        self.values = RefCell::new(None);
+    }
+
+    /// Returns `return_value` for the calls matching `predicate`, which is a `fn(&Args) -> bool`
+    /// over the arguments of the call, see `get_return_value_when`.
+    ///
+    /// The conditions are checked in the order they were set up,
+    /// the other calls return the value of the other setups or run the real implementation.
+    pub fn setup_when(&mut self, predicate: Box<dyn Any>, return_value: ReturnType) {
+        self.real_call_guard.record_configured();
+        self.conditions.push(predicate, return_value);
+    }
+
+    /// Returns `return_value` for the calls from the source files at `path`, see `CallerPath`.
+    ///
+    /// Only calls of functions annotated with `#[stub_function(track_caller)]` pass their caller,
+    /// see `get_return_value_for_caller`. The conditions are checked with the ones of `setup_when` in setup order.
+    pub fn setup_for_caller(&mut self, path: &'static str, return_value: ReturnType) {
+        self.real_call_guard.record_configured();
+        self.conditions.push(Box::new(CallerPath(path)), return_value);
     }
 
     pub fn clear(&mut self) {
         self.return_value = None;
+        self.panic_message = None;
+        self.values = RefCell::new(None);
+        self.conditions.clear();
+        self.setup_location = None;
+        self.real_call_guard.clear();
+    }
+
+    /// Declares the stub required, so the real implementation of the function panics when it runs, until the stub is cleared.
+    pub fn require(&mut self) {
+        self.real_call_guard.require();
     }
 
+    /// Checks that the real implementation of the function may run, which the stubbed function calls before running it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stub is required, or if it was configured in the current test and
+    /// `fnmock::strict::set_real_implementation` is set to `Strictness::Panic`
+    pub fn check_real_call(&self) {
+        self.real_call_guard.check(self.name, "stub", None, self.definition);
+    }
+
+    #[inline]
     pub fn is_set(&self) -> bool {
-        self.return_value.is_some()
+        self.has_default() || !self.conditions.is_empty()
+    }
+
+    /// Checks if the stub returns a value for every call, in contrast to `setup_when`.
+    fn has_default(&self) -> bool {
+        self.return_value.is_some() || self.panic_message.is_some() || self.values.borrow().is_some()
+    }
+
+    // --- Snapshots ---
+
+    /// Captures the configuration of the stub, so it can be restored with `restore` later on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stub is set up with `setup_from_iter`, whose iterator can't be copied.
+    pub fn snapshot(&self) -> StubSnapshot<ReturnType> {
+        if self.values.borrow().is_some() {
+            panic_snapshot_of_iterator(self.name, self.definition)
+        }
+        StubSnapshot {
+            return_value: self.return_value.clone(),
+            panic_message: self.panic_message,
+        }
     }
 
+    /// Replaces the configuration of the stub with the snapshot.
+    ///
+    /// The conditions of `setup_when` aren't captured, so they are removed.
+    pub fn restore(&mut self, snapshot: &StubSnapshot<ReturnType>) {
+        self.real_call_guard.record_configured();
+        self.return_value = snapshot.return_value.clone();
+        self.panic_message = snapshot.panic_message;
+        self.values = RefCell::new(None);
+        self.conditions.clear();
+    }
+
+    #[inline]
     pub fn get_return_value(&self) -> ReturnType {
-        self.return_value.clone().expect(format!("{} stub not initialized", self.name).as_str())
+        match (&self.return_value, self.panic_message) {
+            (Some(return_value), _) => return_value.clone(),
+            (None, Some(message)) => panic!("{}", message),
+            (None, None) => self.next_value(),
+        }
+    }
+
+    /// Returns the value of the first condition of `setup_when` whose predicate of type `P` matches,
+    /// else the value of the other setups, or None if the stub should run the real implementation.
+    #[inline]
+    pub fn get_return_value_when<P: 'static>(&self, matches: impl Fn(&P) -> bool) -> Option<ReturnType> {
+        self.get_return_value_for(None, matches)
+    }
+
+    /// Returns the value like `get_return_value_when`, also matching the conditions of `setup_for_caller`
+    /// against the location the stubbed function was called from.
+    #[inline]
+    pub fn get_return_value_for_caller<P: 'static>(
+        &self,
+        caller: &Location<'_>,
+        matches: impl Fn(&P) -> bool,
+    ) -> Option<ReturnType> {
+        self.get_return_value_for(Some(caller), matches)
+    }
+
+    fn get_return_value_for<P: 'static>(
+        &self,
+        caller: Option<&Location<'_>>,
+        matches: impl Fn(&P) -> bool,
+    ) -> Option<ReturnType> {
+        match self.conditions.find(caller, matches) {
+            Some(return_value) => Some(return_value.clone()),
+            None => self.has_default().then(|| self.get_return_value()),
+        }
+    }
+
+    fn next_value(&self) -> ReturnType {
+        let next_value = match self.values.borrow_mut().as_mut() {
+            Some(values) => values.next(),
+            None => panic_not_initialized(self.name, "stub", self.definition),
+        };
+        next_value.unwrap_or_else(|| panic_values_exhausted(self.name, self.definition))
+    }
+}
+
+// The return value isn't required to implement Debug, so only whether it is set is shown
+impl<ReturnType> Debug for FunctionStub<ReturnType>
+where
+    ReturnType: Clone,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FunctionStub")
+            .field("name", &self.name)
+            .field("is_set", &self.is_set())
+            .finish()
+    }
+}
+
+impl<ReturnType> Display for FunctionStub<ReturnType>
+where
+    ReturnType: Clone,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = if self.is_set() { "set" } else { "not set" };
+        write!(f, "{} stub ({})", self.name, state)
     }
 }
 
@@ -147,6 +417,116 @@ mod tests {
         assert!(stub.return_value.is_none());
     }
 
+    #[test]
+    #[should_panic(expected = "db connection lost")]
+    fn test_setup_panic_panics_with_message() {
+        let mut stub: FunctionStub<i32> = FunctionStub::new("get_value");
+        stub.setup_panic("db connection lost");
+
+        assert!(stub.is_set());
+        stub.get_return_value();
+    }
+
+    #[test]
+    fn test_get_return_value_when_matches_conditions_in_order() {
+        type Predicate = fn(&u32) -> bool;
+        let mut stub: FunctionStub<String> = FunctionStub::new("get_config");
+        stub.setup_when(Box::new((|id| *id == 42) as Predicate), "answer".to_string());
+        stub.setup_when(Box::new((|id| *id > 10) as Predicate), "large".to_string());
+
+        assert!(stub.is_set());
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&42)), Some("answer".to_string()));
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&11)), Some("large".to_string()));
+        // Not matching calls run the real implementation, unless the stub returns a value for every call
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&1)), None);
+
+        stub.setup("default".to_string());
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&1)), Some("default".to_string()));
+
+        stub.clear();
+        assert!(!stub.is_set());
+    }
+
+    #[test]
+    fn test_get_return_value_for_caller_matches_caller_path() {
+        type Predicate = fn(&u32) -> bool;
+        let caller = Location::caller();
+        let mut stub: FunctionStub<String> = FunctionStub::new("get_config");
+        stub.setup_for_caller("src/other.rs", "other".to_string());
+        stub.setup_for_caller("src/function_stub.rs", "file".to_string());
+        stub.setup_when(Box::new((|_| true) as Predicate), "any".to_string());
+
+        assert_eq!(stub.get_return_value_for_caller(caller, |predicate: &Predicate| predicate(&1)), Some("file".to_string()));
+        // Calls without a caller only match the predicates
+        assert_eq!(stub.get_return_value_when(|predicate: &Predicate| predicate(&1)), Some("any".to_string()));
+    }
+
+    #[test]
+    fn test_caller_path_matches_files_and_directories() {
+        assert!(CallerPath("src/handlers/user.rs").matches("src/handlers/user.rs"));
+        assert!(CallerPath("src/handlers/user.rs").matches("my-crate/src/handlers/user.rs"));
+        assert!(CallerPath("handlers/user.rs").matches("src\\handlers\\user.rs"));
+        assert!(!CallerPath("user.rs").matches("src/handlers/admin_user.rs"));
+        assert!(CallerPath("src/handlers/").matches("my-crate/src/handlers/user.rs"));
+        assert!(!CallerPath("src/handlers/").matches("src/jobs/user.rs"));
+    }
+
+    #[test]
+    fn test_restore_replaces_configuration_with_snapshot() {
+        let mut stub: FunctionStub<String> = FunctionStub::new("get_config");
+        stub.setup("arranged".to_string());
+        let snapshot = stub.snapshot();
+
+        stub.setup_from_iter(["changed".to_string()]);
+        stub.restore(&snapshot);
+        assert_eq!(stub.get_return_value(), "arranged");
+
+        stub.clear();
+        stub.restore(&snapshot);
+        assert_eq!(stub.get_return_value(), "arranged");
+    }
+
+    #[test]
+    #[should_panic(expected = "get_value stub is set up with setup_from_iter, whose iterator can't be captured in a snapshot")]
+    fn test_snapshot_of_iterator_panics() {
+        let mut stub: FunctionStub<i32> = FunctionStub::new("get_value");
+        stub.setup_from_iter(1..);
+
+        stub.snapshot();
+    }
+
+    #[test]
+    fn test_setup_replaces_setup_panic() {
+        let mut stub: FunctionStub<i32> = FunctionStub::new("get_value");
+        stub.setup_panic("db connection lost");
+        stub.setup(42);
+
+        assert_eq!(stub.get_return_value(), 42);
+    }
+
+    #[test]
+    fn test_setup_from_iter_returns_next_value_per_call() {
+        let mut stub: FunctionStub<String> = FunctionStub::new("next_name");
+        stub.setup_from_iter(["a", "b"].map(String::from));
+
+        assert!(stub.is_set());
+        assert_eq!(stub.get_return_value(), "a");
+        assert_eq!(stub.get_return_value(), "b");
+
+        stub.setup("c".to_string());
+        assert_eq!(stub.get_return_value(), "c");
+    }
+
+    #[test]
+    #[should_panic(expected = "next_id stub ran out of values, the iterator passed to setup_from_iter is exhausted")]
+    fn test_setup_from_iter_panics_when_exhausted() {
+        let mut stub: FunctionStub<Option<u32>> = FunctionStub::new("next_id");
+        stub.setup_from_iter([Some(1)]);
+
+        stub.get_return_value();
+        stub.get_return_value();
+    }
+
     #[test]
     fn test_stub_can_be_updated() {
         let mut stub: FunctionStub<i32> = FunctionStub::new("get_value");
@@ -251,4 +631,28 @@ mod tests {
         let stub: FunctionStub<i32> = FunctionStub::new("my_custom_function");
         assert_eq!(stub.name, "my_custom_function");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_debug_shows_state() {
+        let mut stub: FunctionStub<i32> = FunctionStub::new("get_answer");
+        stub.setup(42);
+
+        assert_eq!(format!("{:?}", stub), "FunctionStub { name: \"get_answer\", is_set: true }");
+    }
+
+    #[test]
+    fn test_display_summarizes_stub() {
+        let stub: FunctionStub<i32> = FunctionStub::new("get_answer");
+        assert_eq!(stub.to_string(), "get_answer stub (not set)");
+    }
+    #[test]
+    #[should_panic(expected = "the real implementation was called, although get_answer stub was configured in this test")]
+    fn test_check_real_call_after_unmatched_condition() {
+        crate::strict::set_real_implementation(crate::strict::Strictness::Panic);
+        let mut stub: FunctionStub<i32> = FunctionStub::new("get_answer");
+        stub.setup_when(Box::new(|id: &u32| *id == 1), 42);
+
+        assert_eq!(stub.get_return_value_when(|predicate: &fn(&u32) -> bool| predicate(&2)), None);
+        stub.check_real_call();
+    }
+}
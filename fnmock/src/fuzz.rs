@@ -0,0 +1,181 @@
+//! Mock responses derived from the input of a fuzz target
+//!
+//! Mock implementations are plain functions, so they draw their responses from the input
+//! stored on the current thread with `set_input` instead of capturing it. Every draw consumes
+//! the next bytes of the input, so the same input always produces the same responses,
+//! which keeps the crashes found by the fuzzer reproducible. Only available with the `fuzz` feature.
+//!
+//! The generated mocks only exist in test builds, so the crate under test has to enable the `mocks`
+//! feature for its fuzz targets, see the cross-crate mocking section of the README.
+//!
+//! # Example
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| {
+//!     fnmock::fuzz::set_input(data);
+//!     fetch_user_mock::setup(|_| fnmock::fuzz::arbitrary());
+//!     send_email_mock::setup(|_| fnmock::fuzz::choose(&[Ok(()), Err("mailbox full".to_string())]));
+//!
+//!     let _ = handle_user(1);
+//! });
+//! ```
+
+use std::cell::RefCell;
+pub use arbitrary::{Arbitrary, Unstructured};
+
+/// Input of the fuzz target and the number of bytes already consumed by the draws
+struct FuzzInput {
+    data: Vec<u8>,
+    position: usize,
+}
+
+// Like the doubles, the input is thread-local, so parallel tests don't share it
+thread_local! {
+    static INPUT: RefCell<FuzzInput> = const { RefCell::new(FuzzInput { data: Vec::new(), position: 0 }) };
+}
+
+fn register() {
+    crate::registry::register(module_path!(), clear, describe);
+}
+
+fn describe() -> String {
+    INPUT.with(|input| {
+        let input = input.borrow();
+        format!("fuzz input ({} of {} bytes left)", input.data.len() - input.position, input.data.len())
+    })
+}
+
+/// Stores the input of the fuzz target on the current thread, replacing the previous one.
+///
+/// The buffer is reused between the runs of the fuzz target, so it is only allocated
+/// while the inputs grow. The input is cleared by `fnmock::clear_all_mocks!()`.
+pub fn set_input(data: &[u8]) {
+    register();
+    INPUT.with(|input| {
+        let mut input = input.borrow_mut();
+        input.data.clear();
+        input.data.extend_from_slice(data);
+        input.position = 0;
+    })
+}
+
+/// Clears the input, so the draws return the values of an empty input.
+pub fn clear() {
+    INPUT.with(|input| {
+        let mut input = input.borrow_mut();
+        input.data.clear();
+        input.position = 0;
+    })
+}
+
+/// Returns the number of bytes of the input not consumed by a draw yet.
+pub fn remaining() -> usize {
+    INPUT.with(|input| {
+        let input = input.borrow();
+        input.data.len() - input.position
+    })
+}
+
+/// Runs the draw on the unconsumed bytes of the input and consumes the bytes it used.
+fn draw<T>(draw: impl FnOnce(&mut Unstructured) -> arbitrary::Result<T>) -> arbitrary::Result<T> {
+    INPUT.with(|input| {
+        let mut input = input.borrow_mut();
+        let position = input.position;
+        let mut unstructured = Unstructured::new(&input.data[position..]);
+
+        let result = draw(&mut unstructured);
+        let consumed = input.data.len() - position - unstructured.len();
+        input.position += consumed;
+        result
+    })
+}
+
+/// Draws a value from the next bytes of the input.
+///
+/// Once the input is exhausted, the value of an empty input is returned, e.g. `0`, `false` or an empty `String`,
+/// so the code under test keeps running instead of the fuzz target rejecting the input.
+///
+/// # Panics
+///
+/// Panics if the type can't be created from an empty input either, e.g. an uninhabited type
+pub fn arbitrary<T>() -> T
+where
+    T: for<'a> Arbitrary<'a>,
+{
+    draw(|unstructured| T::arbitrary(unstructured))
+        .or_else(|_| T::arbitrary(&mut Unstructured::new(&[])))
+        .unwrap_or_else(|error| panic!("{} can't be drawn from the fuzz input: {}", std::any::type_name::<T>(), error))
+}
+
+/// Chooses one of the options based on the next bytes of the input, e.g. a response of a mocked dependency.
+///
+/// # Panics
+///
+/// Panics if `options` is empty
+pub fn choose<T: Clone>(options: &[T]) -> T {
+    assert!(!options.is_empty(), "fnmock::fuzz::choose requires at least one option");
+
+    let index = draw(|unstructured| unstructured.choose_index(options.len())).unwrap_or(0);
+    options[index].clone()
+}
+
+/// Returns `true` with a probability of `numerator / denominator` based on the next bytes of the input.
+///
+/// Useful to let the fuzzer decide whether a mocked dependency fails. Returns `false` once the input is exhausted.
+///
+/// # Panics
+///
+/// Panics if `numerator` is 0 or greater than `denominator`
+pub fn ratio(numerator: u8, denominator: u8) -> bool {
+    assert!(0 < numerator && numerator <= denominator, "fnmock::fuzz::ratio requires 0 < numerator <= denominator");
+
+    // `Unstructured::ratio` returns `true` for an empty input, which would make every optional failure happen
+    draw(|unstructured| match unstructured.is_empty() {
+        true => Ok(false),
+        false => unstructured.ratio(numerator, denominator),
+    }).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draws_consume_input() {
+        set_input(&[1, 0, 0, 0, 7]);
+
+        assert_eq!(arbitrary::<u32>(), 1);
+        assert_eq!(remaining(), 1);
+        assert_eq!(arbitrary::<u8>(), 7);
+        assert_eq!(remaining(), 0);
+    }
+
+    #[test]
+    fn test_exhausted_input_returns_empty_values() {
+        set_input(&[]);
+
+        assert_eq!(arbitrary::<u64>(), 0);
+        assert_eq!(arbitrary::<String>(), "");
+        assert_eq!(choose(&["first", "second"]), "first");
+        assert!(!ratio(1, 2));
+    }
+
+    #[test]
+    fn test_same_input_produces_same_draws() {
+        let draws = || {
+            set_input(&[3, 200, 17, 42, 9, 1]);
+            (choose(&[1, 2, 3, 4]), ratio(1, 2), arbitrary::<Option<u16>>())
+        };
+
+        assert_eq!(draws(), draws());
+    }
+
+    #[test]
+    fn test_clear_all_mocks_clears_input() {
+        set_input(&[1, 2, 3]);
+
+        crate::clear_all_mocks!();
+
+        assert_eq!(remaining(), 0);
+    }
+}
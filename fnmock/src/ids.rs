@@ -0,0 +1,134 @@
+//! Stubbable id generation
+//!
+//! Code generating its ids with [`new_uuid`] can be made deterministic in tests
+//! with a single `setup` call on `new_uuid_stub`, which supports fixed values and sequences.
+//! Only available with the `uuid` feature, so other crates don't check the stub on every call.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // In the code under test
+//! let order = Order { id: fnmock::ids::new_uuid(), items };
+//!
+//! // In the test
+//! fnmock::ids::new_uuid_stub::setup_sequence();
+//! assert_eq!(create_order(items).id, "00000000-0000-4000-8000-000000000001");
+//! ```
+
+/// Formats 128 bits as a hyphenated version 4 UUID.
+fn format_uuid(high: u64, low: u64) -> String {
+    // Version 4 in the 13th hex digit and the RFC 4122 variant in the 17th
+    let high = (high & !0xF000) | 0x4000;
+    let low = (low & !(0xC000 << 48)) | (0x8000 << 48);
+
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xFFFF,
+        high & 0xFFFF,
+        low >> 48,
+        low & 0xFFFF_FFFF_FFFF,
+    )
+}
+
+/// Returns a new random version 4 UUID in its hyphenated form,
+/// or the value configured with `new_uuid_stub` on the current thread.
+///
+/// The randomness comes from [`crate::rand::random_u64`], so it isn't cryptographically secure.
+pub fn new_uuid() -> String {
+    if new_uuid_stub::is_set() {
+        return new_uuid_stub::get_return_value();
+    }
+    format_uuid(crate::rand::random_u64(), crate::rand::random_u64())
+}
+
+/// Stub of `new_uuid()`, cleared by `fnmock::clear_all_mocks!()` like the generated stubs
+pub mod new_uuid_stub {
+    use std::cell::RefCell;
+    use crate::function_stub::FunctionStub;
+
+    thread_local! {
+        static STUB: RefCell<FunctionStub<String>> = const { RefCell::new(FunctionStub::new("new_uuid_stub")) };
+    }
+
+    fn register() {
+        crate::registry::register(module_path!(), clear, || STUB.with(|stub| stub.borrow().to_string()));
+    }
+
+    #[track_caller]
+    fn record_setup() {
+        let location = std::panic::Location::caller();
+        STUB.with(|stub| stub.borrow_mut().record_setup(location))
+    }
+
+    /// Sets the value returned on every call.
+    #[track_caller]
+    pub fn setup(return_value: impl Into<String>) {
+        register();
+        record_setup();
+        STUB.with(|stub| stub.borrow_mut().setup(return_value.into()))
+    }
+
+    /// Returns the next value of the iterator on every call.
+    #[track_caller]
+    pub fn setup_from_iter<I>(values: I)
+    where
+        I: IntoIterator,
+        I::Item: Into<String> + 'static,
+        I::IntoIter: 'static,
+    {
+        register();
+        record_setup();
+        STUB.with(|stub| stub.borrow_mut().setup_from_iter(values.into_iter().map(Into::into)))
+    }
+
+    /// Returns the UUIDs `...-000000000001`, `...-000000000002` and so on, one per call.
+    #[track_caller]
+    pub fn setup_sequence() {
+        setup_from_iter((1..).map(|n| super::format_uuid(0, n)))
+    }
+
+    /// Resets the stub, so the function returns random UUIDs again.
+    pub fn clear() {
+        STUB.with(|stub| stub.borrow_mut().clear())
+    }
+
+    /// Checks if the stub has been configured.
+    pub fn is_set() -> bool {
+        STUB.with(|stub| stub.borrow().is_set())
+    }
+
+    /// Gets the configured return value.
+    pub fn get_return_value() -> String {
+        STUB.with(|stub| stub.borrow().get_return_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_uuid_is_random_version_4() {
+        let uuid = new_uuid();
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid.matches('-').count(), 4);
+        assert_eq!(&uuid[14..15], "4");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+        assert_ne!(uuid, new_uuid());
+    }
+
+    #[test]
+    fn test_stubbed_new_uuid_returns_fixed_values_and_sequences() {
+        new_uuid_stub::setup("fixed-id");
+        assert_eq!(new_uuid(), "fixed-id");
+
+        new_uuid_stub::setup_sequence();
+        assert_eq!(new_uuid(), "00000000-0000-4000-8000-000000000001");
+        assert_eq!(new_uuid(), "00000000-0000-4000-8000-000000000002");
+
+        crate::clear_all_mocks!();
+        assert!(!new_uuid_stub::is_set());
+    }
+}
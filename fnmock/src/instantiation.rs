@@ -0,0 +1,75 @@
+//! Dispatch of generic functions to the mocks of their instantiations
+//!
+//! `#[mock_function(instantiate(T = u32, T = String))]` generates a mock module per instantiation
+//! of a generic function, e.g. `parse_u32_mock` and `parse_string_mock`. The generic function compares
+//! its type parameters with the ones of every instantiation, and converts its parameters to the concrete
+//! types of the matching mock and the result of the mock back to the generic return type.
+//! Calls of other instantiations run the real implementation.
+//!
+//! # Example
+//!
+//! ```ignore
+//! #[mock_function(instantiate(T = u32, T = String))]
+//! pub fn parse<T: FromStr>(raw: String) -> Option<T> {
+//!     raw.parse().ok()
+//! }
+//!
+//! parse_u32_mock::setup(|_| Some(42));
+//!
+//! assert_eq!(parse::<u32>("1".to_string()), Some(42));
+//! assert_eq!(parse::<String>("1".to_string()), Some("1".to_string()));
+//! ```
+
+use std::any::{Any, TypeId};
+
+/// Checks whether the type parameters of a call are the ones of an instantiation.
+///
+/// Normally you don't need to call this function, the generated generic functions call it
+/// with tuples of their type parameters, e.g. `is_instantiation::<(T,), (u32,)>()`.
+#[inline]
+pub fn is_instantiation<Generic: 'static, Concrete: 'static>() -> bool {
+    TypeId::of::<Generic>() == TypeId::of::<Concrete>()
+}
+
+/// Converts a value between a generic type and the concrete type of an instantiation, e.g. `Option<T>` and `Option<u32>`.
+///
+/// Normally you don't need to call this function, the generated generic functions call it
+/// after `is_instantiation` matched.
+///
+/// # Panics
+///
+/// Panics if the types differ
+pub fn cast<From: 'static, To: 'static>(value: From) -> To {
+    match (Box::new(value) as Box<dyn Any>).downcast::<To>() {
+        Ok(value) => *value,
+        Err(_) => panic!(
+            "{} can't be converted to {}, the instantiation doesn't match",
+            std::any::type_name::<From>(),
+            std::any::type_name::<To>(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse<T: std::str::FromStr + 'static>(raw: &str) -> Option<T> {
+        if is_instantiation::<(T,), (u32,)>() {
+            return cast::<Option<u32>, Option<T>>(Some(42));
+        }
+        raw.parse().ok()
+    }
+
+    #[test]
+    fn test_dispatches_matching_instantiation() {
+        assert_eq!(parse::<u32>("1"), Some(42));
+        assert_eq!(parse::<u64>("1"), Some(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "u32 can't be converted to alloc::string::String")]
+    fn test_cast_panics_on_mismatch() {
+        cast::<u32, String>(1);
+    }
+}
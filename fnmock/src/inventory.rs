@@ -0,0 +1,33 @@
+/// Kind of a generated double
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoubleKind {
+    /// Generated by `#[mock_function]`
+    Mock,
+    /// Generated by `#[fake_function]`
+    Fake,
+    /// Generated by `#[stub_function]`
+    Stub,
+}
+
+/// Double generated in the crate, listed by `fnmock::derive::mocks_in_crate!()`
+///
+/// # Fields
+///
+/// - `module_path` - the module path of the function, e.g. `my_crate::db`
+/// - `function` - the name of the function, e.g. `fetch_user`, or `Type::method` for the methods of traits and types
+/// - `module` - the path of the generated module relative to `module_path`, e.g. `fetch_user_mock` or `user_service_mock::find_user`
+/// - `kind` - whether the double is a mock, fake or stub
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeneratedDouble {
+    pub module_path: &'static str,
+    pub function: &'static str,
+    pub module: &'static str,
+    pub kind: DoubleKind,
+}
+
+impl GeneratedDouble {
+    /// Returns the full path of the function, e.g. `my_crate::db::fetch_user`.
+    pub fn function_path(&self) -> String {
+        format!("{}::{}", self.module_path, self.function)
+    }
+}
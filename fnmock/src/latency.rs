@@ -0,0 +1,182 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Simulated delay of the calls of an async mock, see `FunctionMock::with_latency`
+///
+/// The jitter is sampled with a fixed seed, so a test sees the same delays on every run.
+#[derive(Clone, Debug)]
+pub(crate) struct Latency {
+    base: Duration,
+    jitter: Option<Range<Duration>>,
+    state: u64,
+}
+
+impl Latency {
+    pub(crate) fn new() -> Self {
+        Self {
+            base: Duration::ZERO,
+            jitter: None,
+            state: 0x2545_F491_4F6C_DD1D,
+        }
+    }
+
+    pub(crate) fn set_base(&mut self, base: Duration) {
+        self.base = base;
+    }
+
+    pub(crate) fn set_jitter(&mut self, jitter: Range<Duration>) {
+        self.jitter = Some(jitter);
+    }
+
+    /// Returns the delay of the next call, the base latency plus a sample of the jitter range.
+    pub(crate) fn next_delay(&mut self) -> Duration {
+        let Some(jitter) = &self.jitter else {
+            return self.base;
+        };
+
+        let span = jitter.end.saturating_sub(jitter.start).as_nanos() as u64;
+        if span == 0 {
+            return self.base + jitter.start;
+        }
+
+        // xorshift64, which is good enough for spreading delays
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.base + jitter.start + Duration::from_nanos(self.state % span)
+    }
+}
+
+/// Returns the current time, following tokio's paused clock with the `tokio` feature.
+pub(crate) fn now() -> Instant {
+    #[cfg(feature = "tokio")]
+    return tokio::time::Instant::now().into_std();
+
+    #[cfg(not(feature = "tokio"))]
+    Instant::now()
+}
+
+/// Waits for `duration` before completing.
+///
+/// Uses `tokio::time::sleep` with the `tokio` feature, so the delay follows tokio's paused clock.
+/// Without it, a timer thread wakes the future, which works with any async runtime.
+pub async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(not(feature = "tokio"))]
+    thread_sleep::ThreadSleep::new(duration).await;
+}
+
+#[cfg(not(feature = "tokio"))]
+mod thread_sleep {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::time::{Duration, Instant};
+
+    /// Future completing after a deadline, woken by a timer thread
+    pub(super) struct ThreadSleep {
+        deadline: Instant,
+        waker: Option<Arc<Mutex<Waker>>>,
+    }
+
+    impl ThreadSleep {
+        pub(super) fn new(duration: Duration) -> Self {
+            Self {
+                deadline: Instant::now() + duration,
+                waker: None,
+            }
+        }
+    }
+
+    impl Future for ThreadSleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if Instant::now() >= self.deadline {
+                return Poll::Ready(());
+            }
+
+            match &self.waker {
+                // The future may have moved to another task since the last poll
+                Some(waker) => waker.lock().unwrap().clone_from(cx.waker()),
+                None => {
+                    let waker = Arc::new(Mutex::new(cx.waker().clone()));
+                    let timer_waker = waker.clone();
+                    let deadline = self.deadline;
+                    std::thread::spawn(move || {
+                        std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                        timer_waker.lock().unwrap().wake_by_ref();
+                    });
+                    self.waker = Some(waker);
+                }
+            }
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_delay_without_jitter_returns_base() {
+        let mut latency = Latency::new();
+        latency.set_base(Duration::from_millis(50));
+
+        assert_eq!(latency.next_delay(), Duration::from_millis(50));
+        assert_eq!(latency.next_delay(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_next_delay_samples_jitter_range() {
+        let mut latency = Latency::new();
+        latency.set_base(Duration::from_millis(10));
+        latency.set_jitter(Duration::from_millis(5)..Duration::from_millis(20));
+
+        let delays: Vec<Duration> = (0..100).map(|_| latency.next_delay()).collect();
+
+        assert!(delays.iter().all(|delay| (Duration::from_millis(15)..Duration::from_millis(30)).contains(delay)));
+        assert!(delays.windows(2).any(|pair| pair[0] != pair[1]), "the delays should vary");
+    }
+
+    #[test]
+    fn test_next_delay_is_deterministic() {
+        let delays = || {
+            let mut latency = Latency::new();
+            latency.set_jitter(Duration::ZERO..Duration::from_secs(1));
+            (0..10).map(|_| latency.next_delay()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(delays(), delays());
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn test_thread_sleep_wakes_after_duration() {
+        use std::future::Future;
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct ThreadWaker(std::thread::Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut future = Box::pin(sleep(Duration::from_millis(20)));
+        let start = std::time::Instant::now();
+
+        while future.as_mut().poll(&mut Context::from_waker(&waker)) == Poll::Pending {
+            std::thread::park();
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -1,7 +1,67 @@
 pub mod function_mock;
 pub mod function_fake;
 pub mod function_stub;
+pub mod copy_function_stub;
+pub mod registry;
+pub mod scoped;
+pub mod messages;
+pub mod call_table;
+pub mod verify;
+pub mod context;
+pub mod matchers;
+pub mod cancellation;
+pub mod latency;
+#[cfg(feature = "rand")]
+pub mod rand;
+#[cfg(feature = "uuid")]
+pub mod ids;
+pub mod inventory;
+pub mod strict;
+pub mod callback;
+pub mod fn_ptr;
+pub mod resolution;
+pub mod propagation;
+pub mod instantiation;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+mod threads;
+mod mock_state;
+mod call_index;
+mod call_diff;
+mod mock_control;
+
+pub use mock_control::MockControl;
+pub use context::with_context;
+pub use registry::{dump_state, group, try_verify_all};
+pub use propagation::scope;
+#[cfg(feature = "tokio")]
+pub use propagation::spawn;
 
 pub mod derive {
     pub use fnmock_derive::*;
-}
\ No newline at end of file
+}
+
+pub use fnmock_derive::test;
+
+/// Clears all mocks, fakes and stubs used on the current thread.
+///
+/// Every generated double registers itself the first time it is configured on a thread,
+/// so this resets the whole configuration and call history of the current test at once.
+///
+/// # Example
+///
+/// ```ignore
+/// fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+/// get_config_stub::setup("test_config".to_string());
+///
+/// fnmock::clear_all_mocks!();
+///
+/// assert!(!fetch_user_mock::is_set());
+/// assert!(!get_config_stub::is_set());
+/// ```
+#[macro_export]
+macro_rules! clear_all_mocks {
+    () => {
+        $crate::registry::clear_all()
+    };
+}
@@ -0,0 +1,585 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// Condition on a recorded parameter, used by the `assert_with_matchers` assertions
+///
+/// Matchers describe the expected parameters partially, where `assert_with` requires them completely.
+/// Mocks with multiple parameters are matched with a tuple of matchers, one for each parameter.
+///
+/// # Example
+///
+/// ```ignore
+/// use fnmock::matchers::{gt, lt, eq, not, MatcherExt};
+///
+/// save_user_mock::assert_with_matchers((gt(0).and(lt(100)), not(eq("admin"))));
+/// ```
+///
+/// # Custom Matchers
+///
+/// Domain specific conditions are written by implementing `Matcher` for the parameter type.
+/// Implementing `MatcherExt` as well makes the custom matcher combinable with the other matchers.
+///
+/// ```ignore
+/// use fnmock::matchers::{Matcher, MatcherExt, starts_with};
+///
+/// struct ValidIban;
+///
+/// impl Matcher<String> for ValidIban {
+///     fn matches(&self, actual: &String) -> bool {
+///         actual.len() >= 15 && actual.chars().take(2).all(|c| c.is_ascii_uppercase())
+///     }
+///
+///     fn describe(&self) -> String {
+///         "valid IBAN".to_string()
+///     }
+/// }
+///
+/// impl MatcherExt for ValidIban {}
+///
+/// transfer_mock::assert_with_matchers((ValidIban.and(starts_with("DE")), any()));
+/// ```
+pub trait Matcher<T: ?Sized> {
+    /// Checks if `actual` matches.
+    fn matches(&self, actual: &T) -> bool;
+
+    /// Describes the matched values for failure messages, e.g. `> 0`.
+    fn describe(&self) -> String;
+}
+
+/// Combinators available on all matchers
+pub trait MatcherExt: Sized {
+    /// Matches if both matchers match.
+    fn and<M>(self, other: M) -> AndMatcher<Self, M> {
+        AndMatcher(self, other)
+    }
+
+    /// Matches if at least one of the matchers matches.
+    fn or<M>(self, other: M) -> OrMatcher<Self, M> {
+        OrMatcher(self, other)
+    }
+}
+
+// --- Values ---
+
+/// Matcher returned by `eq`
+#[derive(Clone, Debug)]
+pub struct EqMatcher<E>(E);
+
+/// Matches values equal to `expected`.
+pub fn eq<E>(expected: E) -> EqMatcher<E> {
+    EqMatcher(expected)
+}
+
+impl<E> MatcherExt for EqMatcher<E> {}
+
+impl<T: ?Sized + PartialEq<E>, E: Debug> Matcher<T> for EqMatcher<E> {
+    fn matches(&self, actual: &T) -> bool {
+        *actual == self.0
+    }
+
+    fn describe(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// Matcher returned by `any`
+#[derive(Clone, Copy, Debug)]
+pub struct AnyMatcher;
+
+/// Matches every value.
+pub fn any() -> AnyMatcher {
+    AnyMatcher
+}
+
+impl MatcherExt for AnyMatcher {}
+
+impl<T: ?Sized> Matcher<T> for AnyMatcher {
+    fn matches(&self, _actual: &T) -> bool {
+        true
+    }
+
+    fn describe(&self) -> String {
+        "_".to_string()
+    }
+}
+
+/// Generates a matcher comparing values with `PartialOrd`
+macro_rules! comparison_matcher {
+    ($name:ident, $function:ident, $operator:tt, $doc:literal) => {
+        #[doc = concat!("Matcher returned by `", stringify!($function), "`")]
+        #[derive(Clone, Debug)]
+        pub struct $name<E>(E);
+
+        #[doc = $doc]
+        pub fn $function<E>(bound: E) -> $name<E> {
+            $name(bound)
+        }
+
+        impl<E> MatcherExt for $name<E> {}
+
+        impl<T: ?Sized + PartialOrd<E>, E: Debug> Matcher<T> for $name<E> {
+            fn matches(&self, actual: &T) -> bool {
+                *actual $operator self.0
+            }
+
+            fn describe(&self) -> String {
+                format!(concat!(stringify!($operator), " {:?}"), self.0)
+            }
+        }
+    };
+}
+
+comparison_matcher!(GtMatcher, gt, >, "Matches values greater than `bound`.");
+comparison_matcher!(GeMatcher, ge, >=, "Matches values greater than or equal to `bound`.");
+comparison_matcher!(LtMatcher, lt, <, "Matches values less than `bound`.");
+comparison_matcher!(LeMatcher, le, <=, "Matches values less than or equal to `bound`.");
+
+// --- Strings ---
+
+/// Generates a matcher checking string parameters with a `str` method
+macro_rules! string_matcher {
+    ($name:ident, $function:ident, $method:ident, $doc:literal) => {
+        #[doc = concat!("Matcher returned by `", stringify!($function), "`")]
+        #[derive(Clone, Debug)]
+        pub struct $name<P>(P);
+
+        #[doc = $doc]
+        ///
+        /// Works for all parameters that can be viewed as a `str`, like `String` and `&str`.
+        pub fn $function<P: AsRef<str>>(pattern: P) -> $name<P> {
+            $name(pattern)
+        }
+
+        impl<P> MatcherExt for $name<P> {}
+
+        impl<T: ?Sized + AsRef<str>, P: AsRef<str>> Matcher<T> for $name<P> {
+            fn matches(&self, actual: &T) -> bool {
+                actual.as_ref().$method(self.0.as_ref())
+            }
+
+            fn describe(&self) -> String {
+                format!(concat!(stringify!($function), "({:?})"), self.0.as_ref())
+            }
+        }
+    };
+}
+
+string_matcher!(StartsWithMatcher, starts_with, starts_with, "Matches strings starting with `pattern`.");
+string_matcher!(EndsWithMatcher, ends_with, ends_with, "Matches strings ending with `pattern`.");
+string_matcher!(ContainsMatcher, contains, contains, "Matches strings containing `pattern`.");
+
+// --- Collections ---
+
+/// Parameter types the collection matchers work with
+///
+/// Implemented for `Vec`, slices, arrays and references to them.
+pub trait Collection {
+    type Item;
+
+    /// Returns the elements of the collection.
+    fn items(&self) -> &[Self::Item];
+}
+
+impl<E> Collection for Vec<E> {
+    type Item = E;
+
+    fn items(&self) -> &[E] {
+        self
+    }
+}
+
+impl<E> Collection for [E] {
+    type Item = E;
+
+    fn items(&self) -> &[E] {
+        self
+    }
+}
+
+impl<E, const N: usize> Collection for [E; N] {
+    type Item = E;
+
+    fn items(&self) -> &[E] {
+        self
+    }
+}
+
+impl<C: ?Sized + Collection> Collection for &C {
+    type Item = C::Item;
+
+    fn items(&self) -> &[C::Item] {
+        (**self).items()
+    }
+}
+
+/// Matcher returned by `contains_element`
+#[derive(Clone, Debug)]
+pub struct ContainsElementMatcher<E>(E);
+
+/// Matches collections containing an element equal to `element`.
+pub fn contains_element<E>(element: E) -> ContainsElementMatcher<E> {
+    ContainsElementMatcher(element)
+}
+
+impl<E> MatcherExt for ContainsElementMatcher<E> {}
+
+impl<T: ?Sized + Collection, E: Debug> Matcher<T> for ContainsElementMatcher<E>
+where
+    T::Item: PartialEq<E>,
+{
+    fn matches(&self, actual: &T) -> bool {
+        actual.items().iter().any(|item| *item == self.0)
+    }
+
+    fn describe(&self) -> String {
+        format!("contains_element({:?})", self.0)
+    }
+}
+
+/// Matcher returned by `len_eq`
+#[derive(Clone, Copy, Debug)]
+pub struct LenEqMatcher(usize);
+
+/// Matches collections with `len` elements.
+pub fn len_eq(len: usize) -> LenEqMatcher {
+    LenEqMatcher(len)
+}
+
+impl MatcherExt for LenEqMatcher {}
+
+impl<T: ?Sized + Collection> Matcher<T> for LenEqMatcher {
+    fn matches(&self, actual: &T) -> bool {
+        actual.items().len() == self.0
+    }
+
+    fn describe(&self) -> String {
+        format!("len_eq({})", self.0)
+    }
+}
+
+/// Matcher returned by `is_empty`
+#[derive(Clone, Copy, Debug)]
+pub struct IsEmptyMatcher;
+
+/// Matches empty collections.
+pub fn is_empty() -> IsEmptyMatcher {
+    IsEmptyMatcher
+}
+
+impl MatcherExt for IsEmptyMatcher {}
+
+impl<T: ?Sized + Collection> Matcher<T> for IsEmptyMatcher {
+    fn matches(&self, actual: &T) -> bool {
+        actual.items().is_empty()
+    }
+
+    fn describe(&self) -> String {
+        "is_empty()".to_string()
+    }
+}
+
+/// Matcher returned by `unordered_eq`
+#[derive(Clone, Debug)]
+pub struct UnorderedEqMatcher<E>(Vec<E>);
+
+/// Matches collections with the same elements as `expected` in any order.
+///
+/// Duplicates have to occur as often in the collection as in `expected`.
+pub fn unordered_eq<E>(expected: impl IntoIterator<Item = E>) -> UnorderedEqMatcher<E> {
+    UnorderedEqMatcher(expected.into_iter().collect())
+}
+
+impl<E> MatcherExt for UnorderedEqMatcher<E> {}
+
+impl<T: ?Sized + Collection, E: Debug> Matcher<T> for UnorderedEqMatcher<E>
+where
+    T::Item: PartialEq<E>,
+{
+    fn matches(&self, actual: &T) -> bool {
+        let items = actual.items();
+        if items.len() != self.0.len() {
+            return false;
+        }
+
+        // Every expected element is paired with a different equal item
+        let mut paired = vec![false; items.len()];
+        self.0.iter().all(|expected| {
+            let position = items
+                .iter()
+                .enumerate()
+                .position(|(idx, item)| !paired[idx] && *item == *expected);
+            match position {
+                Some(position) => {
+                    paired[position] = true;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!("unordered_eq({:?})", self.0)
+    }
+}
+
+// --- Combinators ---
+
+/// Matcher returned by `MatcherExt::and`
+#[derive(Clone, Debug)]
+pub struct AndMatcher<A, B>(A, B);
+
+impl<A, B> MatcherExt for AndMatcher<A, B> {}
+
+impl<T: ?Sized, A: Matcher<T>, B: Matcher<T>> Matcher<T> for AndMatcher<A, B> {
+    fn matches(&self, actual: &T) -> bool {
+        self.0.matches(actual) && self.1.matches(actual)
+    }
+
+    fn describe(&self) -> String {
+        format!("({} and {})", self.0.describe(), self.1.describe())
+    }
+}
+
+/// Matcher returned by `MatcherExt::or`
+#[derive(Clone, Debug)]
+pub struct OrMatcher<A, B>(A, B);
+
+impl<A, B> MatcherExt for OrMatcher<A, B> {}
+
+impl<T: ?Sized, A: Matcher<T>, B: Matcher<T>> Matcher<T> for OrMatcher<A, B> {
+    fn matches(&self, actual: &T) -> bool {
+        self.0.matches(actual) || self.1.matches(actual)
+    }
+
+    fn describe(&self) -> String {
+        format!("({} or {})", self.0.describe(), self.1.describe())
+    }
+}
+
+/// Matcher returned by `not`
+#[derive(Clone, Debug)]
+pub struct NotMatcher<M>(M);
+
+/// Matches values not matched by `matcher`.
+pub fn not<M>(matcher: M) -> NotMatcher<M> {
+    NotMatcher(matcher)
+}
+
+impl<M> MatcherExt for NotMatcher<M> {}
+
+impl<T: ?Sized, M: Matcher<T>> Matcher<T> for NotMatcher<M> {
+    fn matches(&self, actual: &T) -> bool {
+        !self.0.matches(actual)
+    }
+
+    fn describe(&self) -> String {
+        format!("not {}", self.0.describe())
+    }
+}
+
+// --- Fields ---
+
+/// Matcher returned by `field`
+pub struct FieldMatcher<T, V: ?Sized, F, M> {
+    accessor: F,
+    matcher: M,
+    // `fn` keeps the matcher `Send` and `Sync` independent of the matched types
+    types: PhantomData<fn(&T) -> &V>,
+}
+
+/// Matches values whose field, returned by `accessor`, matches `matcher`.
+///
+/// Targets a single field of a large struct parameter, instead of constructing a fully-populated expected struct.
+///
+/// # Example
+///
+/// ```ignore
+/// send_email_mock::assert_with_matchers(field(|user: &User| &user.id, eq(42)));
+/// ```
+pub fn field<T, V: ?Sized, F, M>(accessor: F, matcher: M) -> FieldMatcher<T, V, F, M>
+where
+    F: Fn(&T) -> &V,
+    M: Matcher<V>,
+{
+    FieldMatcher { accessor, matcher, types: PhantomData }
+}
+
+impl<T, V: ?Sized, F, M> MatcherExt for FieldMatcher<T, V, F, M> {}
+
+impl<T, V: ?Sized, F, M> Matcher<T> for FieldMatcher<T, V, F, M>
+where
+    F: Fn(&T) -> &V,
+    M: Matcher<V>,
+{
+    fn matches(&self, actual: &T) -> bool {
+        self.matcher.matches((self.accessor)(actual))
+    }
+
+    fn describe(&self) -> String {
+        format!("field({})", self.matcher.describe())
+    }
+}
+
+// --- Tuples ---
+// Mocks record multiple parameters as a tuple, so a tuple of matchers matches them element-wise.
+
+
+impl Matcher<()> for () {
+    fn matches(&self, _actual: &()) -> bool {
+        true
+    }
+
+    fn describe(&self) -> String {
+        "()".to_string()
+    }
+}
+
+/// Implements `Matcher` for a tuple of matchers
+macro_rules! tuple_matcher {
+    ($($matcher:ident $value:ident $idx:tt),+) => {
+
+        impl<$($value, $matcher: Matcher<$value>),+> Matcher<($($value,)+)> for ($($matcher,)+) {
+            fn matches(&self, actual: &($($value,)+)) -> bool {
+                $(self.$idx.matches(&actual.$idx))&&+
+            }
+
+            fn describe(&self) -> String {
+                let descriptions = [$(self.$idx.describe()),+];
+                format!("({})", descriptions.join(", "))
+            }
+        }
+    };
+}
+
+tuple_matcher!(M0 T0 0, M1 T1 1);
+tuple_matcher!(M0 T0 0, M1 T1 1, M2 T2 2);
+tuple_matcher!(M0 T0 0, M1 T1 1, M2 T2 2, M3 T3 3);
+tuple_matcher!(M0 T0 0, M1 T1 1, M2 T2 2, M3 T3 3, M4 T4 4);
+tuple_matcher!(M0 T0 0, M1 T1 1, M2 T2 2, M3 T3 3, M4 T4 4, M5 T5 5);
+tuple_matcher!(M0 T0 0, M1 T1 1, M2 T2 2, M3 T3 3, M4 T4 4, M5 T5 5, M6 T6 6);
+tuple_matcher!(M0 T0 0, M1 T1 1, M2 T2 2, M3 T3 3, M4 T4 4, M5 T5 5, M6 T6 6, M7 T7 7);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comparison_matchers() {
+        assert!(gt(0).matches(&1));
+        assert!(!gt(0).matches(&0));
+        assert!(ge(0).matches(&0));
+        assert!(lt(0).matches(&-1));
+        assert!(le(0).matches(&0));
+        assert_eq!(Matcher::<i32>::describe(&ge(5)), ">= 5");
+    }
+
+    #[test]
+    fn test_eq_matches_other_comparable_types() {
+        assert!(eq("admin").matches(&"admin".to_string()));
+        assert_eq!(Matcher::<String>::describe(&eq("admin")), "\"admin\"");
+    }
+
+    #[test]
+    fn test_combinators() {
+        let in_range = gt(0).and(lt(100));
+        assert!(in_range.matches(&50));
+        assert!(!in_range.matches(&100));
+
+        let outside = not(in_range).or(eq(50));
+        assert!(outside.matches(&100));
+        assert!(outside.matches(&50));
+        assert_eq!(Matcher::<i32>::describe(&outside), "(not (> 0 and < 100) or 50)");
+    }
+
+    #[test]
+    fn test_tuple_matchers_match_element_wise() {
+        let matcher = (gt(0).and(lt(100)), not(eq("admin")));
+
+        assert!(matcher.matches(&(1, "alice".to_string())));
+        assert!(!matcher.matches(&(1, "admin".to_string())));
+        assert_eq!(Matcher::<(i32, String)>::describe(&matcher), "((> 0 and < 100), not \"admin\")");
+    }
+
+    #[test]
+    fn test_string_matchers() {
+        let url = "https://example.com/users/42".to_string();
+
+        assert!(starts_with("https://").matches(&url));
+        assert!(ends_with("/42").matches(&url));
+        assert!(contains("users").matches(&url));
+        assert!(!contains("admin").matches("https://example.com"));
+        assert_eq!(Matcher::<String>::describe(&starts_with("https://")), "starts_with(\"https://\")");
+    }
+
+    #[test]
+    fn test_collection_matchers() {
+        let ids = vec![3, 1, 2];
+
+        assert!(contains_element(2).matches(&ids));
+        assert!(!contains_element(4).matches(&ids));
+        assert!(len_eq(3).matches(&ids));
+        assert!(is_empty().matches(&Vec::<u32>::new()));
+        assert!(!is_empty().matches(&[1]));
+        assert!(contains_element("a").matches(&vec!["a".to_string()]));
+        assert_eq!(Matcher::<Vec<i32>>::describe(&len_eq(3)), "len_eq(3)");
+    }
+
+    #[test]
+    fn test_unordered_eq_ignores_order_but_counts_duplicates() {
+        assert!(unordered_eq([1, 2, 3]).matches(&vec![3, 1, 2]));
+        assert!(unordered_eq([1, 1, 2]).matches(&[1, 2, 1]));
+        assert!(!unordered_eq([1, 1, 2]).matches(&[1, 2, 2]));
+        assert!(!unordered_eq([1, 2]).matches(&[1, 2, 3]));
+    }
+
+    #[derive(Debug)]
+    struct User {
+        id: u32,
+        name: String,
+    }
+
+    #[test]
+    fn test_field_matches_single_field() {
+        let user = User { id: 42, name: "alice".to_string() };
+
+        assert!(field(|user: &User| &user.id, eq(42)).matches(&user));
+        assert!(!field(|user: &User| &user.id, eq(7)).matches(&user));
+
+        let matcher = field(|user: &User| &user.id, gt(0)).and(field(|user: &User| &user.name, eq("alice")));
+        assert!(matcher.matches(&user));
+        assert_eq!(matcher.describe(), "(field(> 0) and field(\"alice\"))");
+    }
+
+    #[test]
+    fn test_any_matches_everything() {
+        assert!((any(), eq(1)).matches(&("anything", 1)));
+    }
+
+    struct ValidIban;
+
+    impl Matcher<String> for ValidIban {
+        fn matches(&self, actual: &String) -> bool {
+            actual.len() >= 15 && actual.chars().take(2).all(|c| c.is_ascii_uppercase())
+        }
+
+        fn describe(&self) -> String {
+            "valid IBAN".to_string()
+        }
+    }
+
+    impl MatcherExt for ValidIban {}
+
+    #[test]
+    fn test_custom_matcher_combines_with_builtin_matchers() {
+        let matcher = (ValidIban.and(starts_with("DE")), gt(0));
+
+        assert!(matcher.matches(&("DE89370400440532013000".to_string(), 100)));
+        assert!(!matcher.matches(&("FR1420041010050500013M02606".to_string(), 100)));
+        assert!(!matcher.matches(&("DE89".to_string(), 100)));
+        assert_eq!(
+            Matcher::<(String, i32)>::describe(&matcher),
+            "((valid IBAN and starts_with(\"DE\")), > 0)"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::RwLock;
+
+/// Placeholder in the prefix that is replaced with the module path of the mock
+pub const MODULE_PATH_PLACEHOLDER: &str = "{module_path}";
+
+// In contrast to the doubles, the prefix is shared by all threads,
+// so it only needs to be set once for the whole test suite.
+static PREFIX: RwLock<Option<String>> = RwLock::new(None);
+
+/// Sets a prefix for all mock panic messages.
+///
+/// The prefix is shared by all threads and prepended to every panic message of the mocks,
+/// e.g. failed assertions. `{module_path}` in the prefix is replaced with the module path
+/// of the generated mock module, which makes messages unambiguous if multiple modules contain
+/// a function with the same name.
+///
+/// # Example
+///
+/// ```ignore
+/// fnmock::messages::set_prefix("[{module_path}] ");
+///
+/// // Panics with "[my_crate::db::fetch_user_mock] Expected fetch_user_mock mock to be called ..."
+/// fetch_user_mock::assert_times(1);
+/// ```
+pub fn set_prefix(prefix: &str) {
+    *PREFIX.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(prefix.to_string());
+}
+
+/// Removes the prefix set with `set_prefix`.
+pub fn clear_prefix() {
+    *PREFIX.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+}
+
+/// Prepends the configured prefix to a panic message.
+///
+/// # Arguments
+///
+/// - `module_path` - the module path of the mock, if known
+/// - `message` - the panic message
+pub(crate) fn with_prefix(module_path: Option<&str>, message: String) -> String {
+    let prefix = PREFIX.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    apply_prefix(prefix.as_deref(), module_path, message)
+}
+
+/// Appends the location of the function the double was generated for to a message.
+///
+/// # Arguments
+///
+/// - `definition` - the location of the function, e.g. `src/db.rs:12`, if known
+/// - `message` - the panic message
+pub(crate) fn with_definition(definition: Option<&str>, message: String) -> String {
+    match definition {
+        Some(definition) => format!("{}\n(function defined at {})", message, definition),
+        None => message,
+    }
+}
+
+/// Panics because a stub or fake is used without being configured.
+///
+/// Kept out of line, so the checks on the hot path of the doubles stay small.
+///
+/// # Arguments
+///
+/// - `name` - the name of the double
+/// - `kind` - the kind of the double, e.g. `stub`
+/// - `definition` - the location of the function the double was generated for, if known
+#[cold]
+#[inline(never)]
+pub(crate) fn panic_not_initialized(name: &str, kind: &str, definition: Option<&str>) -> ! {
+    panic!("{}", with_definition(definition, format!("{} {} not initialized", name, kind)))
+}
+
+/// Panics because the values a stub was set up with are exhausted.
+///
+/// # Arguments
+///
+/// - `name` - the name of the stub
+/// - `definition` - the location of the stubbed function, if known
+#[cold]
+#[inline(never)]
+pub(crate) fn panic_values_exhausted(name: &str, definition: Option<&str>) -> ! {
+    panic!("{}", with_definition(definition, format!(
+        "{} stub ran out of values, the iterator passed to setup_from_iter is exhausted",
+        name
+    )))
+}
+
+/// Panics because a stub set up with an iterator can't be captured in a snapshot.
+///
+/// # Arguments
+///
+/// - `name` - the name of the stub
+/// - `definition` - the location of the stubbed function, if known
+#[cold]
+#[inline(never)]
+pub(crate) fn panic_snapshot_of_iterator(name: &str, definition: Option<&str>) -> ! {
+    panic!("{}", with_definition(definition, format!(
+        "{} stub is set up with setup_from_iter, whose iterator can't be captured in a snapshot",
+        name
+    )))
+}
+
+fn apply_prefix(prefix: Option<&str>, module_path: Option<&str>, message: String) -> String {
+    match prefix {
+        Some(prefix) => {
+            let prefix = prefix.replace(MODULE_PATH_PLACEHOLDER, module_path.unwrap_or("<unknown module>"));
+            format!("{}{}", prefix, message)
+        }
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_definition_appends_location() {
+        let message = with_definition(Some("src/db.rs:12"), "message".to_string());
+        assert_eq!(message, "message\n(function defined at src/db.rs:12)");
+    }
+
+    #[test]
+    fn test_apply_prefix_without_prefix() {
+        let message = apply_prefix(None, Some("crate::db"), "message".to_string());
+        assert_eq!(message, "message");
+    }
+
+    #[test]
+    fn test_apply_prefix_prepends_prefix() {
+        let message = apply_prefix(Some("[billing] "), Some("crate::db"), "message".to_string());
+        assert_eq!(message, "[billing] message");
+    }
+
+    #[test]
+    fn test_apply_prefix_replaces_module_path() {
+        let message = apply_prefix(Some("[{module_path}] "), Some("crate::db"), "message".to_string());
+        assert_eq!(message, "[crate::db] message");
+    }
+
+    #[test]
+    fn test_apply_prefix_with_unknown_module_path() {
+        let message = apply_prefix(Some("[{module_path}] "), None, "message".to_string());
+        assert_eq!(message, "[<unknown module>] message");
+    }
+}
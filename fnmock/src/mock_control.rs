@@ -0,0 +1,33 @@
+/// Common control functions of all mocks
+///
+/// Every generated mock module contains a zero-sized `MockHandle` implementing this trait,
+/// which forwards to the proxy functions of the module. This allows test utilities to
+/// operate on any set of mocks, independent of their parameters and return types.
+///
+/// # Example
+///
+/// ```ignore
+/// use fnmock::MockControl;
+///
+/// fn assert_not_called(mocks: &[&dyn MockControl]) {
+///     for mock in mocks {
+///         mock.assert_times(0);
+///     }
+/// }
+///
+/// assert_not_called(&[&fetch_user_mock::MockHandle, &send_email_mock::MockHandle]);
+/// ```
+pub trait MockControl {
+    /// Returns the name of the mock module, e.g. `fetch_user_mock`.
+    fn name(&self) -> &'static str;
+
+    /// Resets the implementation and the call history of the mock.
+    fn clear(&self);
+
+    /// Asserts that the mock was called exactly `expected_num_of_calls` times.
+    fn assert_times(&self, expected_num_of_calls: u32);
+
+    /// Checks that every recorded call was covered by `assert_with*()` or `assert_calls_exact()`,
+    /// see `fnmock::verify::verify_no_more_interactions`.
+    fn check_no_more_interactions(&self) -> Result<(), String>;
+}
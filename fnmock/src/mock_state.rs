@@ -0,0 +1,343 @@
+use std::fmt::Debug;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::call_diff::format_call_diff;
+use crate::messages::{with_definition, with_prefix};
+use crate::strict::RealCallGuard;
+use crate::{registry, strict, threads};
+
+/// State of a `FunctionMock` that doesn't depend on its params and return type
+///
+/// `FunctionMock` is instantiated once per mocked function. Keeping the bookkeeping and the message
+/// formatting in this non-generic struct compiles it only once, which keeps test binaries small.
+///
+/// # Fields
+///
+/// - `name` - the name of the function for display purposes when asserting
+/// - `module_path` - the module path of the mock for the message prefix (see `fnmock::messages`) or None
+/// - `expected_times` - the number of calls expected by `verify` or None
+/// - `call_budget` - the number of calls after which every call panics, if set with `set_call_budget`, or None
+/// - `setup_location` - where the mock was last set up through its module, or None since it was cleared
+/// - `calls_before_checkpoint` - the number of calls before the last `checkpoint`
+/// - `generation` - the generation of the thread the mock was configured in, see `fnmock::registry::generation`
+/// - `setup_example` - a `setup` call matching the signature of the function for the not initialized panic, or None
+/// - `definition` - the location of the mocked function appended to the messages, e.g. `src/db.rs:12`, or None
+/// - `real_call_guard` - whether the real implementation may run, see `fnmock::strict::set_real_implementation`
+/// - `configured_flag` - the flag of the record in `fnmock::threads`, or None until the mock is configured for the first time
+pub(crate) struct MockState {
+    pub(crate) name: &'static str,
+    pub(crate) module_path: Option<&'static str>,
+    pub(crate) expected_times: Option<u32>,
+    pub(crate) call_budget: Option<u32>,
+    pub(crate) setup_location: Option<&'static Location<'static>>,
+    pub(crate) calls_before_checkpoint: usize,
+    pub(crate) generation: u64,
+    pub(crate) setup_example: Option<&'static str>,
+    pub(crate) definition: Option<&'static str>,
+    pub(crate) real_call_guard: RealCallGuard,
+    configured_flag: Option<Arc<AtomicBool>>,
+}
+
+impl MockState {
+    pub(crate) const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            module_path: None,
+            expected_times: None,
+            call_budget: None,
+            setup_location: None,
+            calls_before_checkpoint: 0,
+            generation: 0,
+            setup_example: None,
+            definition: None,
+            real_call_guard: RealCallGuard::new(),
+            configured_flag: None,
+        }
+    }
+
+    fn message(&self, message: String) -> String {
+        with_definition(self.definition, with_prefix(self.module_path, message))
+    }
+
+    pub(crate) fn record_configured(&mut self) {
+        self.generation = registry::generation();
+        self.real_call_guard.record_configured();
+        // Only the first configuration on the thread locks the records, later ones set the flag
+        match &self.configured_flag {
+            Some(configured) => configured.store(true, Ordering::Relaxed),
+            None => self.configured_flag = Some(threads::record_configured(self.module_path, self.name)),
+        }
+    }
+
+    /// Panics if the mock was configured in a previous generation of the thread, i.e. a previous test.
+    #[inline]
+    pub(crate) fn check_generation(&self) {
+        let current_generation = registry::generation();
+        if self.generation != current_generation {
+            self.panic_stale(current_generation)
+        }
+    }
+
+    pub(crate) fn remove_configured(&self) {
+        if let Some(configured) = &self.configured_flag {
+            configured.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Checks that the real implementation of the mocked function may run, see `RealCallGuard::check`.
+    pub(crate) fn check_real_call(&self) {
+        self.real_call_guard.check(self.name, "mock", self.module_path, self.definition);
+    }
+
+    pub(crate) fn record_setup(&mut self, location: &'static Location<'static>, num_of_calls: usize) {
+        strict::check_setup(self.name, "mock", self.module_path, self.definition, self.setup_location, location);
+        strict::check_setup_after_call(self.name, self.module_path, self.definition, num_of_calls - self.calls_before_checkpoint, location);
+        self.setup_location = Some(location);
+    }
+
+    // Kept out of line, so the hot path of `call` stays small
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_not_initialized(&self) -> ! {
+        panic!("{}", self.not_initialized_message())
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn panic_stale(&self, current_generation: u64) -> ! {
+        panic!("{}", self.message(format!(
+            "{} mock has a stale mock configuration leaked from a previous test \
+             (configured in generation {}, the current generation is {}), \
+             clear the doubles at the end of every test, e.g. with fnmock::clear_all_mocks!()",
+            self.name, self.generation, current_generation
+        )))
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_forbidden(&self, params: &dyn Debug) -> ! {
+        panic!("{}", self.message(format!("{} mock is forbidden, but was called with {:?}", self.name, params)))
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_call_budget_exceeded(&self, call_budget: u32, params: &dyn Debug) -> ! {
+        panic!("{}", self.message(format!(
+            "{} mock exceeded its call budget of {} calls, call {} was made with {:?}",
+            self.name, call_budget, call_budget as usize + 1, params
+        )))
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_precondition_violated(&self, location: &Location<'_>, params: &dyn Debug) -> ! {
+        panic!("{}", self.message(format!(
+            "{} mock precondition added at {} was violated by the call with {:?}",
+            self.name, location, params
+        )))
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_postcondition_violated(&self, location: &Location<'_>, params: &dyn Debug, result: &str) -> ! {
+        panic!("{}", self.message(format!(
+            "{} mock postcondition added at {} was violated by the call with {:?} returning {}",
+            self.name, location, params, result
+        )))
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_pending(&self) -> ! {
+        panic!("{}", self.message(format!("{} mock is pending, which is only supported for async functions", self.name)))
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_not_exportable(&self) -> ! {
+        panic!("{}", self.message(format!(
+            "{} mock is set up with canned items, which can't be exported, set it up on the other thread instead",
+            self.name
+        )))
+    }
+
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn panic_timestamps_not_recorded(&self) -> ! {
+        panic!("{}", self.message(format!(
+            "{} mock doesn't record timestamps, call record_timestamps() before the calls",
+            self.name
+        )))
+    }
+
+    fn not_initialized_message(&self) -> String {
+        let mut message = format!("{} mock not initialized on thread {}", self.name, threads::describe_current_thread());
+
+        let other_threads = threads::configured_on_other_threads(self.module_path, self.name);
+        if !other_threads.is_empty() {
+            message.push_str(&format!(
+                ", but it is configured on thread {}. \
+                 Mocks are thread-local: if the code under test spawned this thread, \
+                 configure the mock on the spawned thread or call the function on the test thread \
+                 (see the Thread Safety section of the fnmock README)",
+                other_threads.join(", ")
+            ));
+        }
+
+        let configured = threads::configured_on_current_thread();
+        if configured.is_empty() {
+            message.push_str("\nNo mocks are configured on this thread");
+        } else {
+            message.push_str(&format!("\nMocks configured on this thread: {}", configured.join(", ")));
+        }
+
+        if let Some(setup_example) = self.setup_example {
+            message.push_str(&format!("\nSet up the mock before the call, e.g.:\n    {}", setup_example));
+        }
+
+        self.message(message)
+    }
+
+    // --- Check ---
+
+    pub(crate) fn check_times(&self, num_of_calls: usize, expected_num_of_calls: u32) -> Result<(), String> {
+        if num_of_calls == expected_num_of_calls as usize {
+            Ok(())
+        } else {
+            Err(self.message(format!("Expected {} mock to be called {} times, received {}",
+                                     self.name, expected_num_of_calls, num_of_calls)))
+        }
+    }
+
+    pub(crate) fn check_times_in_context(&self, context: &str, num_of_calls: usize, expected_num_of_calls: u32) -> Result<(), String> {
+        if num_of_calls == expected_num_of_calls as usize {
+            Ok(())
+        } else {
+            Err(self.message(format!("Expected {} mock to be called {} times in context {:?}, received {}",
+                                     self.name, expected_num_of_calls, context, num_of_calls)))
+        }
+    }
+
+    /// Checks the busiest window, given as the position of its first call and its number of calls.
+    pub(crate) fn check_called_at_most_per(&self, max_calls: u32, window: Duration, busiest_window: Option<(usize, usize)>) -> Result<(), String> {
+        match busiest_window {
+            Some((first_call, num_of_calls)) if num_of_calls > max_calls as usize => {
+                Err(self.message(format!("Expected {} mock to be called at most {} times per {:?}, received {} calls within {:?} starting at call {}",
+                                         self.name, max_calls, window, num_of_calls, window, first_call + 1)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_called_with(&self, was_called_with: bool, params: &dyn Debug) -> Result<(), String> {
+        if was_called_with {
+            Ok(())
+        } else {
+            Err(self.message(format!("Expected {} mock to be called with {:?}", self.name, params)))
+        }
+    }
+
+    pub(crate) fn check_calls_exact(&self, expected: &[String], actual: &[String]) -> Result<(), String> {
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(self.message(format!("Expected {} mock calls to match exactly (- expected, + actual):\n{}",
+                                     self.name, format_call_diff(expected, actual))))
+        }
+    }
+
+    pub(crate) fn check_called_matching(&self, was_called_matching: bool, description: &str) -> Result<(), String> {
+        if was_called_matching {
+            Ok(())
+        } else {
+            Err(self.message(format!("Expected {} mock to be called with params matching {}", self.name, description)))
+        }
+    }
+
+    pub(crate) fn check_item_passed(&self, was_passed: bool, item: &dyn Debug) -> Result<(), String> {
+        if was_passed {
+            Ok(())
+        } else {
+            Err(self.message(format!("Expected {} mock to be passed the item {:?} in any call", self.name, item)))
+        }
+    }
+
+    pub(crate) fn check_no_more_interactions(&self, unverified_calls: &[&dyn Debug]) -> Result<(), String> {
+        if unverified_calls.is_empty() {
+            return Ok(());
+        }
+
+        let calls: Vec<String> = unverified_calls.iter().map(|params| format!("{:?}", params)).collect();
+        let noun = if calls.len() == 1 { "call" } else { "calls" };
+        Err(self.message(format!("Expected no more interactions with {} mock, but found {} unverified {}: {}",
+                                 self.name, calls.len(), noun, calls.join(", "))))
+    }
+
+    /// Checks the expectations against the calls since the last `checkpoint`.
+    pub(crate) fn check_expectations(&self, num_of_calls: usize) -> Result<(), String> {
+        match self.expected_times {
+            Some(expected_num_of_calls) => self.check_times(num_of_calls - self.calls_before_checkpoint, expected_num_of_calls),
+            None => Ok(()),
+        }
+    }
+}
+
+// The thread-local mocks are dropped when their thread exits, which removes their record
+impl Drop for MockState {
+    fn drop(&mut self) {
+        if let Some(configured) = &self.configured_flag {
+            threads::remove_configured(configured);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_configured_only_records_once_per_thread() {
+        let mut state = MockState::new("record_once");
+        state.record_configured();
+        state.remove_configured();
+        state.record_configured();
+
+        assert_eq!(threads::configured_on_current_thread().iter().filter(|mock| *mock == "record_once").count(), 1);
+    }
+
+    #[test]
+    fn test_dropped_state_removes_record() {
+        std::thread::spawn(|| MockState::new("dropped").record_configured()).join().unwrap();
+
+        assert!(threads::configured_on_other_threads(None, "dropped").is_empty());
+    }
+
+    #[test]
+    fn test_check_times_with_matching_count() {
+        let state = MockState::new("add");
+        assert_eq!(state.check_times(2, 2), Ok(()));
+    }
+
+    #[test]
+    fn test_check_times_reports_expected_before_received() {
+        let state = MockState::new("add");
+        assert_eq!(state.check_times(1, 3), Err("Expected add mock to be called 3 times, received 1".to_string()));
+    }
+
+    #[test]
+    fn test_check_called_with_formats_params() {
+        let state = MockState::new("add");
+        assert_eq!(
+            state.check_called_with(false, &(1, "a")),
+            Err("Expected add mock to be called with (1, \"a\")".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_expectations_without_expectation() {
+        let state = MockState::new("add");
+        assert_eq!(state.check_expectations(5), Ok(()));
+    }
+}
@@ -0,0 +1,306 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread::ThreadId;
+use crate::function_mock::MockExport;
+
+/// Configuration of a mock copied for another thread, see `MockStates`
+pub struct ExportedMock {
+    install: Box<dyn Fn() + Send>,
+}
+
+impl ExportedMock {
+    /// Wraps the exported configuration with the `import_state` proxy of the mock.
+    ///
+    /// # Arguments
+    ///
+    /// - `export` - the configuration exported with `FunctionMock::export`
+    /// - `import` - imports a copy of the configuration into the mock of the current thread
+    pub fn new<Params, Result>(export: MockExport<Params, Result>, import: fn(MockExport<Params, Result>)) -> Self
+    where
+        MockExport<Params, Result>: Send + 'static,
+    {
+        Self { install: Box::new(move || import(export.clone())) }
+    }
+}
+
+/// Selects how the generated mocks export themselves for `MockStates::capture`.
+///
+/// Normally you don't need this type. The generated mocks call `(&ExportProbe::new()).export_mock(..)`,
+/// which resolves to `ExportSend` if the configuration of the mock can be sent to another thread,
+/// and to `ExportLocal` otherwise, e.g. for mocks returning an `Rc`.
+#[doc(hidden)]
+pub struct ExportProbe<Params, Result>(PhantomData<fn() -> (Params, Result)>);
+
+#[doc(hidden)]
+#[allow(clippy::new_without_default)]
+impl<Params, Result> ExportProbe<Params, Result> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Exports the configuration of a mock, whose configuration can be sent to another thread, see `ExportProbe`
+#[doc(hidden)]
+pub trait ExportSend<Params, Result> {
+    fn export_mock(&self, export: fn() -> Option<MockExport<Params, Result>>, import: fn(MockExport<Params, Result>)) -> Option<ExportedMock>;
+}
+
+impl<Params, Result> ExportSend<Params, Result> for ExportProbe<Params, Result>
+where
+    MockExport<Params, Result>: Send + 'static,
+{
+    fn export_mock(&self, export: fn() -> Option<MockExport<Params, Result>>, import: fn(MockExport<Params, Result>)) -> Option<ExportedMock> {
+        Some(ExportedMock::new(export()?, import))
+    }
+}
+
+/// Skips a mock, whose configuration can't be sent to another thread, see `ExportProbe`
+#[doc(hidden)]
+pub trait ExportLocal<Params, Result> {
+    fn export_mock(&self, export: fn() -> Option<MockExport<Params, Result>>, import: fn(MockExport<Params, Result>)) -> Option<ExportedMock>;
+}
+
+impl<Params, Result> ExportLocal<Params, Result> for &ExportProbe<Params, Result> {
+    fn export_mock(&self, _: fn() -> Option<MockExport<Params, Result>>, _: fn(MockExport<Params, Result>)) -> Option<ExportedMock> {
+        None
+    }
+}
+
+/// Configurations of all mocks of a thread, which can be installed on other threads
+///
+/// The mocks are thread-local, so a spawned thread or a task of a multi-threaded runtime
+/// starts with unconfigured mocks. `spawn` and `scope` capture the configurations and install them
+/// before the spawned code runs. Use `MockStates` directly for other executors.
+///
+/// Only the configurations are copied: the calls made on the other thread are recorded there,
+/// so assert on the results of the spawned code instead. Mocks returning types that aren't `Send`
+/// and mocks set up with canned items, e.g. with `setup_iter`, stay unconfigured on the other thread.
+pub struct MockStates {
+    mocks: Vec<ExportedMock>,
+    captured_on: ThreadId,
+}
+
+impl MockStates {
+    /// Copies the configurations of the mocks configured on the current thread.
+    ///
+    /// The mocks whose configuration can't be exported are skipped, so only calling them on the other thread panics.
+    pub fn capture() -> Self {
+        Self { mocks: crate::registry::export_mocks(), captured_on: std::thread::current().id() }
+    }
+
+    /// Replaces the configurations of the captured mocks on the current thread, clearing their call histories.
+    ///
+    /// Does nothing on the thread the mocks were captured on, e.g. for a task of a current-thread runtime,
+    /// so its calls and the configurations changed after the capture are kept.
+    pub fn install(&self) {
+        if std::thread::current().id() == self.captured_on {
+            return;
+        }
+        for mock in &self.mocks {
+            (mock.install)()
+        }
+    }
+
+    /// Wraps the future, so the mocks are installed on every thread it is polled on.
+    ///
+    /// The mocks are installed once per thread, so the calls of the future aren't cleared between polls.
+    pub fn instrument<F: Future>(self, future: F) -> WithMocks<F> {
+        WithMocks { states: self, installed_on: Vec::new(), future: Box::pin(future) }
+    }
+}
+
+/// Future installing captured mocks before it is polled, see `MockStates::instrument`
+pub struct WithMocks<F> {
+    states: MockStates,
+    installed_on: Vec<ThreadId>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithMocks<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = self.get_mut();
+
+        // A multi-threaded runtime may move the task to another worker thread between polls
+        let current = std::thread::current().id();
+        if !this.installed_on.contains(&current) {
+            this.states.install();
+            this.installed_on.push(current);
+        }
+        this.future.as_mut().poll(cx)
+    }
+}
+
+/// Spawns a tokio task like `tokio::spawn`, with the mocks configured on the current thread.
+///
+/// `tokio::spawn` runs the task on a worker thread of multi-threaded runtimes, whose mocks aren't
+/// configured, so the real implementations would be called. Only available with the `tokio` feature.
+///
+/// # Example
+///
+/// ```ignore
+/// #[tokio::test(flavor = "multi_thread")]
+/// async fn test_background_sync() {
+///     fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+///
+///     let user = fnmock::spawn(async { fetch_user(1) }).await.unwrap();
+///
+///     assert_eq!(user, Ok("mock user".to_string()));
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics like `MockStates::capture` if a mock can't be exported
+#[cfg(feature = "tokio")]
+pub fn spawn<F>(future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(MockStates::capture().instrument(future))
+}
+
+/// Scope of `scope`, whose spawned threads start with the mocks configured on the spawning thread
+#[derive(Clone, Copy)]
+pub struct Scope<'scope, 'env: 'scope> {
+    scope: &'scope std::thread::Scope<'scope, 'env>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Spawns a scoped thread like `std::thread::Scope::spawn`, installing the mocks configured on the current thread first.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `MockStates::capture` if a mock can't be exported
+    pub fn spawn<F, T>(&self, f: F) -> std::thread::ScopedJoinHandle<'scope, T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let states = MockStates::capture();
+        self.scope.spawn(move || {
+            states.install();
+            f()
+        })
+    }
+}
+
+/// Creates a scope for spawning threads like `std::thread::scope`, which start with the mocks configured on the current thread.
+///
+/// # Example
+///
+/// ```ignore
+/// fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+///
+/// let users = fnmock::scope(|scope| {
+///     let handles: Vec<_> = (1..=2).map(|id| scope.spawn(move || fetch_user(id))).collect();
+///     handles.into_iter().map(|handle| handle.join().unwrap()).collect::<Vec<_>>()
+/// });
+/// ```
+pub fn scope<'env, F, T>(f: F) -> T
+where
+    F: for<'scope> FnOnce(Scope<'scope, 'env>) -> T,
+{
+    std::thread::scope(|scope| f(Scope { scope }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    thread_local! {
+        static IMPORTED: Cell<u32> = const { Cell::new(0) };
+    }
+
+    fn mock_export() -> Option<MockExport<u32, u32>> {
+        let mut mock = crate::function_mock::FunctionMock::new("propagation_test");
+        mock.setup(|id| id * 2);
+        mock.try_export()
+    }
+
+    fn record_import(_: MockExport<u32, u32>) {
+        IMPORTED.with(|imported| imported.set(imported.get() + 1));
+    }
+
+    // The borrow selects `ExportLocal` if `ExportSend` doesn't apply, like in the generated mocks
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn test_export_probe_skips_configurations_that_are_not_send() {
+        let sendable = (&ExportProbe::<u32, u32>::new()).export_mock(mock_export, record_import);
+        let local = (&ExportProbe::<u32, Rc<u32>>::new()).export_mock(
+            || crate::function_mock::FunctionMock::new("local_test").try_export(),
+            |_| {},
+        );
+
+        assert!(sendable.is_some());
+        assert!(local.is_none());
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn test_export_probe_skips_configurations_with_canned_items() {
+        let canned = (&ExportProbe::<(), u32>::new()).export_mock(
+            || {
+                let mut mock = crate::function_mock::FunctionMock::new("canned_test");
+                mock.setup_producing(Box::new(std::cell::Cell::new(1u32)), |_| 1);
+                mock.try_export()
+            },
+            |_| {},
+        );
+
+        assert!(canned.is_none());
+    }
+
+    // States captured on another thread, so they are installed on the current one
+    fn states_of_other_thread() -> MockStates {
+        let captured_on = std::thread::spawn(|| std::thread::current().id()).join().unwrap();
+        MockStates { mocks: vec![ExportedMock::new(mock_export().unwrap(), record_import)], captured_on }
+    }
+
+    #[test]
+    fn test_with_mocks_installs_once_per_thread() {
+        let states = states_of_other_thread();
+        let mut future = states.instrument(async {
+            std::future::ready(()).await;
+            IMPORTED.with(Cell::get)
+        });
+
+        let imported = Pin::new(&mut future).poll(&mut Context::from_waker(std::task::Waker::noop()));
+
+        assert_eq!(imported, Poll::Ready(1));
+    }
+
+    #[test]
+    fn test_install_skips_capturing_thread() {
+        let states = MockStates {
+            mocks: vec![ExportedMock::new(mock_export().unwrap(), record_import)],
+            captured_on: std::thread::current().id(),
+        };
+
+        states.install();
+
+        assert_eq!(IMPORTED.with(Cell::get), 0);
+    }
+
+    #[test]
+    fn test_install_on_other_thread_leaves_current_thread_unchanged() {
+        let states = MockStates {
+            mocks: vec![ExportedMock::new(mock_export().unwrap(), record_import)],
+            captured_on: std::thread::current().id(),
+        };
+
+        let imported = std::thread::spawn(move || {
+            states.install();
+            IMPORTED.with(Cell::get)
+        }).join().unwrap();
+
+        assert_eq!(imported, 1);
+        assert_eq!(IMPORTED.with(Cell::get), 0);
+    }
+}
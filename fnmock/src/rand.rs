@@ -0,0 +1,139 @@
+//! Stubbable sources of randomness
+//!
+//! Code drawing its randomness from these functions can be made deterministic in tests
+//! with a single `setup` call on the stub module of the function. The functions are provided
+//! by fnmock, so the stub modules are available in every build, unlike the generated doubles.
+//! Only available with the `rand` feature, so other crates don't check the stubs on every call.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // In the code under test, e.g. seeding a `rand` generator
+//! let mut rng = StdRng::seed_from_u64(fnmock::rand::shuffle_seed());
+//! items.shuffle(&mut rng);
+//!
+//! // In the test
+//! fnmock::rand::shuffle_seed_stub::setup(42);
+//! fnmock::rand::random_u64_stub::setup_from_iter([1, 2, 3]);
+//! ```
+
+use std::hash::{BuildHasher, RandomState};
+
+/// Returns a random `u64` from the randomly keyed hasher of the standard library.
+///
+/// Good enough for ids and seeds, but not cryptographically secure.
+fn real_random_u64() -> u64 {
+    RandomState::new().hash_one(std::time::SystemTime::now())
+}
+
+/// Creates a stubbable function and its stub module with the API of the generated stubs
+macro_rules! stubbable_random {
+    ($(#[$attr:meta])* $name:ident, $stub:ident) => {
+        $(#[$attr])*
+        pub fn $name() -> u64 {
+            if $stub::is_set() {
+                return $stub::get_return_value();
+            }
+            real_random_u64()
+        }
+
+        #[doc = concat!("Stub of `", stringify!($name), "()`, cleared by `fnmock::clear_all_mocks!()` like the generated stubs")]
+        pub mod $stub {
+            use crate::copy_function_stub::CopyFunctionStub;
+
+            thread_local! {
+                static STUB: CopyFunctionStub<u64> = const { CopyFunctionStub::new(stringify!($stub)) };
+            }
+
+            fn register() {
+                crate::registry::register(module_path!(), clear, || STUB.with(|stub| stub.to_string()));
+            }
+
+            #[track_caller]
+            fn record_setup() {
+                let location = std::panic::Location::caller();
+                STUB.with(|stub| stub.record_setup(location))
+            }
+
+            /// Sets the value returned on every call.
+            #[track_caller]
+            pub fn setup(return_value: u64) {
+                register();
+                record_setup();
+                STUB.with(|stub| stub.setup(return_value))
+            }
+
+            /// Returns the next value of the iterator on every call.
+            #[track_caller]
+            pub fn setup_from_iter<I>(values: I)
+            where
+                I: IntoIterator<Item = u64>,
+                I::IntoIter: 'static,
+            {
+                register();
+                record_setup();
+                STUB.with(|stub| stub.setup_from_iter(values))
+            }
+
+            /// Resets the stub, so the function returns random values again.
+            pub fn clear() {
+                STUB.with(|stub| stub.clear())
+            }
+
+            /// Checks if the stub has been configured.
+            pub fn is_set() -> bool {
+                STUB.with(|stub| stub.is_set())
+            }
+
+            /// Gets the configured return value.
+            pub fn get_return_value() -> u64 {
+                STUB.with(|stub| stub.get_return_value())
+            }
+        }
+    };
+}
+
+stubbable_random!(
+    /// Returns a random `u64`, or the value configured with `random_u64_stub` on the current thread.
+    random_u64,
+    random_u64_stub
+);
+
+stubbable_random!(
+    /// Returns a random seed for shuffling or seeding a random number generator,
+    /// or the value configured with `shuffle_seed_stub` on the current thread.
+    shuffle_seed,
+    shuffle_seed_stub
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_u64_returns_different_values() {
+        let values: Vec<u64> = (0..10).map(|_| random_u64()).collect();
+
+        assert!(values.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn test_stubbed_random_u64_is_deterministic() {
+        random_u64_stub::setup(4);
+        assert_eq!(random_u64(), 4);
+
+        random_u64_stub::setup_from_iter([1, 2]);
+        assert_eq!(random_u64(), 1);
+        assert_eq!(random_u64(), 2);
+    }
+
+    #[test]
+    fn test_clear_all_mocks_clears_random_stubs() {
+        shuffle_seed_stub::setup(42);
+        assert_eq!(shuffle_seed(), 42);
+
+        crate::clear_all_mocks!();
+
+        assert!(!shuffle_seed_stub::is_set());
+    }
+}
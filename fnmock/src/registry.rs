@@ -0,0 +1,467 @@
+use std::cell::{Cell, RefCell};
+use crate::propagation::ExportedMock;
+use crate::verify::{report_failure, MockReport, MockStatus, VerificationReport};
+
+/// Entry of a double (mock, fake or stub) in the registry
+///
+/// # Fields
+///
+/// - `name` - the unique name of the double's module (including the module path)
+/// - `clear` - the `clear` proxy function of the double's module
+/// - `describe` - describes the configuration status and call count of the double for `dump_state`
+/// - `group` - the group of the double, e.g. `#[mock_function(group = "db")]`, or None
+/// - `check_expectations` - checks the expectations of a mock for `Group::verify_all` / `verify_module`
+/// - `status` - returns the verification status of a mock for `try_verify_all`, or None for fakes and stubs
+/// - `export` - exports the configuration of a mock for `MockStates::capture`, or None for fakes and stubs
+#[derive(Clone, Copy)]
+struct RegisteredDouble {
+    name: &'static str,
+    clear: fn(),
+    describe: fn() -> String,
+    group: Option<&'static str>,
+    check_expectations: fn() -> Result<(), String>,
+    status: Option<fn() -> MockStatus>,
+    export: Option<fn() -> Option<ExportedMock>>,
+}
+
+// Like the doubles themselves, the registry is thread-local,
+// so it only contains the doubles used by the current test.
+thread_local! {
+    static REGISTRY: RefCell<Vec<RegisteredDouble>> = const { RefCell::new(Vec::new()) };
+    static GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Returns the generation of the current thread, which is bumped by `begin_test` and `clear_all`.
+///
+/// Mocks remember the generation they were configured in, so a configuration leaking
+/// into the next test on a reused thread is detected when the mock is called.
+pub fn generation() -> u64 {
+    GENERATION.with(Cell::get)
+}
+
+fn bump_generation() {
+    GENERATION.with(|generation| generation.set(generation.get() + 1));
+}
+
+/// Starts a new test on the current thread by bumping its generation.
+///
+/// Normally you don't need to call this function, `#[fnmock::test]` calls it before the test body.
+/// Mocks configured before, e.g. by a previous test that ran on the same thread without clearing them,
+/// panic when they are called.
+pub fn begin_test() {
+    bump_generation();
+}
+
+/// Registers a double on the current thread.
+///
+/// Normally you don't need to call this function.
+/// The generated modules register themselves the first time they are configured on a thread,
+/// so doubles that are never configured don't cost anything. Registering a double again has no effect.
+///
+/// # Arguments
+///
+/// - `name` - the unique name of the double's module (including the module path)
+/// - `clear` - the `clear` proxy function of the double's module
+/// - `describe` - describes the double, e.g. with its `Display` implementation
+pub fn register(name: &'static str, clear: fn(), describe: fn() -> String) {
+    push(RegisteredDouble { name, clear, describe, group: None, check_expectations: || Ok(()), status: None, export: None });
+}
+
+/// Registers a mock on the current thread like `register`, including its expectations and group.
+///
+/// Normally you don't need to call this function.
+/// The generated mock modules register themselves with it the first time they are configured on a thread.
+///
+/// # Arguments
+///
+/// - `name` - the unique name of the mock's module (including the module path)
+/// - `clear` - the `clear` proxy function of the mock's module
+/// - `describe` - describes the mock, e.g. with its `Display` implementation
+/// - `check_expectations` - checks the expectations of the mock like its `verify` proxy function
+/// - `status` - returns the verification status of the mock, e.g. with `FunctionMock::status`
+/// - `export` - exports the configuration of the mock for another thread, or None if it can't be sent there
+/// - `group` - the name of the group, see `fnmock::group`, or None
+pub fn register_mock(
+    name: &'static str,
+    clear: fn(),
+    describe: fn() -> String,
+    check_expectations: fn() -> Result<(), String>,
+    status: fn() -> MockStatus,
+    export: fn() -> Option<ExportedMock>,
+    group: Option<&'static str>,
+) {
+    push(RegisteredDouble { name, clear, describe, group, check_expectations, status: Some(status), export: Some(export) });
+}
+
+fn push(double: RegisteredDouble) {
+    REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if !registry.iter().any(|registered| registered.name == double.name) {
+            registry.push(double);
+        }
+    })
+}
+
+/// Returns the names of all doubles registered on the current thread.
+pub fn registered_names() -> Vec<&'static str> {
+    REGISTRY.with(|registry| {
+        registry.borrow().iter().map(|double| double.name).collect()
+    })
+}
+
+/// Clears all doubles registered on the current thread.
+///
+/// Prefer the `fnmock::clear_all_mocks!()` macro in tests.
+pub fn clear_all() {
+    bump_generation();
+
+    // Collect the clear functions first, so the registry isn't borrowed while they run
+    let clear_functions: Vec<fn()> = REGISTRY.with(|registry| {
+        registry.borrow().iter().map(|double| double.clear).collect()
+    });
+
+    for clear in clear_functions {
+        clear();
+    }
+}
+
+/// Describes every double registered on the current thread with its configuration status and call count.
+///
+/// See `dump_state`, which prints the description.
+pub fn format_state() -> String {
+    // Collect the entries first, so the registry isn't borrowed while the doubles describe themselves
+    let doubles: Vec<RegisteredDouble> = REGISTRY.with(|registry| registry.borrow().clone());
+
+    let thread = std::thread::current();
+    let mut state = format!("fnmock state of thread '{}':", thread.name().unwrap_or("<unnamed>"));
+    if doubles.is_empty() {
+        state.push_str("\n  no doubles configured");
+    }
+    for double in doubles {
+        state.push_str(&format!("\n  {}: {}", double.name, (double.describe)()));
+        if let Some(group) = double.group {
+            state.push_str(&format!(" [group {}]", group));
+        }
+    }
+    state
+}
+
+/// Prints every double used on the current thread with its configuration status and call count.
+///
+/// Replaces ad-hoc `println!` forensics when a large test fails. Only doubles that were configured
+/// on the current thread are known, since the doubles register themselves on their first configuration.
+///
+/// # Example
+///
+/// ```ignore
+/// fnmock::dump_state();
+/// // fnmock state of thread 'tests::test_checkout':
+/// //   my_crate::db::fetch_user_mock: fetch_user_mock mock (set, called 2 times)
+/// //   my_crate::config::get_config_stub: get_config_stub stub (not set)
+/// ```
+pub fn dump_state() {
+    println!("{}", format_state())
+}
+
+/// Runs the clear functions of the registered doubles matching the filter.
+fn clear_matching(filter: impl Fn(&RegisteredDouble) -> bool) {
+    // Collect the clear functions first, so the registry isn't borrowed while they run
+    let clear_functions: Vec<fn()> = REGISTRY.with(|registry| {
+        registry.borrow().iter().filter(|double| filter(double)).map(|double| double.clear).collect()
+    });
+
+    for clear in clear_functions {
+        clear();
+    }
+}
+
+/// Checks the expectations of the registered doubles matching the filter and reports all failures together.
+#[track_caller]
+fn verify_matching(filter: impl Fn(&RegisteredDouble) -> bool) {
+    let checks: Vec<fn() -> Result<(), String>> = REGISTRY.with(|registry| {
+        registry.borrow().iter().filter(|double| filter(double)).map(|double| double.check_expectations).collect()
+    });
+
+    let failures: Vec<String> = checks.into_iter().filter_map(|check| check().err()).collect();
+    if !failures.is_empty() {
+        report_failure(failures.join("\n"))
+    }
+}
+
+/// Verifies the expectations of all mocks registered on the current thread without panicking.
+///
+/// Unlike `verify()` and `Group::verify_all`, the result is returned as a `VerificationReport`
+/// with the status of every mock, which custom harnesses can render, aggregate across
+/// parameterized cases or attach to CI artifacts. Fakes and stubs have no expectations, so they aren't listed.
+///
+/// # Example
+///
+/// ```ignore
+/// let report = fnmock::try_verify_all();
+/// std::fs::write("target/mock-report.txt", report.to_string()).unwrap();
+/// assert!(report.is_success(), "{}", report);
+/// ```
+pub fn try_verify_all() -> VerificationReport {
+    // Collect the entries first, so the registry isn't borrowed while the mocks check their expectations
+    let doubles: Vec<RegisteredDouble> = REGISTRY.with(|registry| registry.borrow().clone());
+
+    let mocks = doubles
+        .into_iter()
+        .filter_map(|double| {
+            let status = double.status?;
+            Some(MockReport { name: double.name, group: double.group, status: status() })
+        })
+        .collect();
+    VerificationReport::new(mocks)
+}
+
+/// Exports the configurations of the mocks registered on the current thread, see `MockStates::capture`.
+pub(crate) fn export_mocks() -> Vec<ExportedMock> {
+    // Collect the entries first, so the registry isn't borrowed while the mocks export themselves
+    let doubles: Vec<RegisteredDouble> = REGISTRY.with(|registry| registry.borrow().clone());
+
+    doubles.into_iter().filter_map(|double| (double.export?)()).collect()
+}
+
+/// Checks if the double's module is defined directly in the module, e.g. `crate::db::fetch_user_mock` in `crate::db`.
+fn is_in_module(double: &RegisteredDouble, module_path: &str) -> bool {
+    double.name.rsplit_once("::").is_some_and(|(parent, _)| parent == module_path)
+}
+
+/// Clears the doubles defined directly in the module, which were used on the current thread.
+///
+/// Prefer the `mocks::clear_all()` function generated by `#[mock_module]` in tests.
+///
+/// # Arguments
+///
+/// - `module_path` - the module path of the module, e.g. `my_crate::db`
+pub fn clear_module(module_path: &str) {
+    clear_matching(|double| is_in_module(double, module_path))
+}
+
+/// Verifies the expectations of the mocks defined directly in the module, which were used on the current thread.
+///
+/// Prefer the `mocks::verify_all()` function generated by `#[mock_module]` in tests.
+///
+/// # Arguments
+///
+/// - `module_path` - the module path of the module, e.g. `my_crate::db`
+///
+/// # Panics
+///
+/// Panics with the messages of all unmet expectations if any mock of the module has one
+#[track_caller]
+pub fn verify_module(module_path: &str) {
+    verify_matching(|double| is_in_module(double, module_path))
+}
+
+/// Named group of doubles, see `fnmock::group`
+#[derive(Clone, Copy, Debug)]
+pub struct Group<'a> {
+    name: &'a str,
+}
+
+impl Group<'_> {
+    /// Returns the names of the doubles of the group registered on the current thread.
+    pub fn names(&self) -> Vec<&'static str> {
+        REGISTRY.with(|registry| {
+            registry.borrow().iter()
+                .filter(|double| double.group == Some(self.name))
+                .map(|double| double.name)
+                .collect()
+        })
+    }
+
+    /// Clears the doubles of the group used on the current thread.
+    pub fn clear(&self) {
+        clear_matching(|double| double.group == Some(self.name))
+    }
+
+    /// Verifies the expectations of all mocks of the group, like calling `verify()` on each of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the messages of all unmet expectations if any mock of the group has one
+    #[track_caller]
+    pub fn verify_all(&self) {
+        verify_matching(|double| double.group == Some(self.name))
+    }
+}
+
+/// Returns the group of doubles with the name, e.g. the mocks generated with `#[mock_function(group = "db")]`.
+///
+/// Like the registry, a group only contains the doubles configured on the current thread.
+///
+/// # Example
+///
+/// ```ignore
+/// fetch_user_mock::configure(|m| { m.ok("mock user".to_string()).expect_times(1); });
+/// save_user_mock::setup(|_| Ok(()));
+///
+/// // ...
+///
+/// fnmock::group("db").verify_all();
+/// fnmock::group("db").clear();
+/// ```
+pub fn group(name: &str) -> Group<'_> {
+    Group { name }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    thread_local! {
+        static FIRST_CLEARED: Cell<bool> = const { Cell::new(false) };
+        static SECOND_CLEARED: Cell<bool> = const { Cell::new(false) };
+    }
+
+    fn clear_first() {
+        FIRST_CLEARED.with(|cleared| cleared.set(true));
+    }
+
+    fn clear_second() {
+        SECOND_CLEARED.with(|cleared| cleared.set(true));
+    }
+
+    fn describe() -> String {
+        "double (set)".to_string()
+    }
+
+    fn status() -> MockStatus {
+        MockStatus { is_set: true, call_count: 1, expected_calls: None, unmet_expectation: None }
+    }
+
+    #[test]
+    fn test_register_adds_double() {
+        register("module::first_mock", clear_first, describe);
+        register("module::second_stub", clear_second, describe);
+
+        assert_eq!(registered_names(), vec!["module::first_mock", "module::second_stub"]);
+    }
+
+    #[test]
+    fn test_register_ignores_registered_double() {
+        register("module::first_mock", clear_first, describe);
+        register("module::first_mock", clear_first, describe);
+
+        assert_eq!(registered_names(), vec!["module::first_mock"]);
+    }
+
+    #[test]
+    fn test_begin_test_and_clear_all_bump_generation() {
+        let before = generation();
+
+        begin_test();
+        clear_all();
+
+        assert_eq!(generation(), before + 2);
+    }
+
+    #[test]
+    fn test_registry_starts_empty() {
+        assert!(registered_names().is_empty());
+    }
+
+    #[test]
+    fn test_clear_all_calls_every_clear_function() {
+        register("module::first_mock", clear_first, describe);
+        register("module::second_stub", clear_second, describe);
+
+        clear_all();
+
+        assert!(FIRST_CLEARED.with(|cleared| cleared.get()));
+        assert!(SECOND_CLEARED.with(|cleared| cleared.get()));
+    }
+
+    #[test]
+    fn test_clear_all_without_doubles() {
+        clear_all();
+    }
+
+    #[test]
+    fn test_group_only_clears_its_doubles() {
+        register_mock("module::first_mock", clear_first, describe, || Ok(()), status, || None, Some("db"));
+        register("module::second_stub", clear_second, describe);
+
+        group("db").clear();
+
+        assert_eq!(group("db").names(), vec!["module::first_mock"]);
+        assert!(FIRST_CLEARED.with(|cleared| cleared.get()));
+        assert!(!SECOND_CLEARED.with(|cleared| cleared.get()));
+    }
+
+    #[test]
+    #[should_panic(expected = "first failed\nsecond failed")]
+    fn test_group_verify_all_reports_all_failures() {
+        register_mock("module::first_mock", clear_first, describe, || Err("first failed".to_string()), status, || None, Some("db"));
+        register_mock("module::ok_mock", clear_first, describe, || Ok(()), status, || None, Some("db"));
+        register_mock("module::second_mock", clear_second, describe, || Err("second failed".to_string()), status, || None, Some("db"));
+        register_mock("module::other_mock", clear_second, describe, || Err("other failed".to_string()), status, || None, Some("http"));
+
+        group("db").verify_all();
+    }
+
+    #[test]
+    fn test_clear_module_only_clears_doubles_defined_in_module() {
+        register("crate::db::first_mock", clear_first, describe);
+        register("crate::db::nested::second_stub", clear_second, describe);
+
+        clear_module("crate::db");
+
+        assert!(FIRST_CLEARED.with(|cleared| cleared.get()));
+        assert!(!SECOND_CLEARED.with(|cleared| cleared.get()));
+    }
+
+    #[test]
+    #[should_panic(expected = "db failed")]
+    fn test_verify_module_checks_mocks_defined_in_module() {
+        register_mock("crate::db::first_mock", clear_first, describe, || Err("db failed".to_string()), status, || None, None);
+        register("crate::db::second_stub", clear_second, describe);
+
+        verify_module("crate::http");
+        verify_module("crate::db");
+    }
+
+    #[test]
+    fn test_format_state_describes_every_double() {
+        register("module::first_mock", clear_first, describe);
+        register_mock("module::second_mock", clear_second, describe, || Ok(()), status, || None, Some("db"));
+
+        let state = format_state();
+
+        assert!(state.starts_with("fnmock state of thread 'registry::tests::test_format_state_describes_every_double':"));
+        assert!(state.ends_with("\n  module::first_mock: double (set)\n  module::second_mock: double (set) [group db]"));
+    }
+
+    #[test]
+    fn test_format_state_without_doubles() {
+        assert!(format_state().ends_with("\n  no doubles configured"));
+    }
+
+    #[test]
+    fn test_clear_all_keeps_doubles_registered() {
+        register("module::first_mock", clear_first, describe);
+
+        clear_all();
+
+        assert_eq!(registered_names(), vec!["module::first_mock"]);
+    }
+
+    #[test]
+    fn test_try_verify_all_reports_every_mock_without_panicking() {
+        fn failed_status() -> MockStatus {
+            MockStatus { is_set: true, call_count: 0, expected_calls: Some(1), unmet_expectation: Some("second failed".to_string()) }
+        }
+        register_mock("module::first_mock", clear_first, describe, || Ok(()), status, || None, Some("db"));
+        register("module::first_stub", clear_first, describe);
+        register_mock("module::second_mock", clear_second, describe, || Err("second failed".to_string()), failed_status, || None, None);
+
+        let report = try_verify_all();
+
+        assert!(!report.is_success());
+        assert_eq!(report.mocks().iter().map(|mock| mock.name).collect::<Vec<_>>(), vec!["module::first_mock", "module::second_mock"]);
+        assert_eq!(report.failures().map(|mock| mock.name).collect::<Vec<_>>(), vec!["module::second_mock"]);
+    }
+}
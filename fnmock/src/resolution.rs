@@ -0,0 +1,82 @@
+//! Resolution order of functions with a mock and a fake
+//!
+//! `#[mock_function(fake)]` generates a mock and a fake for the same function, so a fake can be set up
+//! for a whole module while single tests override it with a mock. At call time the function uses
+//!
+//! 1. the mock, if it is configured
+//! 2. the fake, if it is configured
+//! 3. the real implementation
+//!
+//! The order of the mock and the fake is configured per thread with [`set_order`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! fetch_user_fake::setup(|id| Ok(format!("fake_user_{}", id)));
+//! fetch_user_mock::setup(|_| Err("not found".to_string()));
+//!
+//! // The mock overrides the fake
+//! assert_eq!(fetch_user(1), Err("not found".to_string()));
+//!
+//! fnmock::resolution::set_order(fnmock::resolution::Order::FakeFirst);
+//! assert_eq!(fetch_user(1), Ok("fake_user_1".to_string()));
+//! ```
+
+use std::cell::Cell;
+
+/// Which double a function with a mock and a fake uses, if both are configured
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    /// The mock overrides the fake, which is the default
+    #[default]
+    MockFirst,
+    /// The fake overrides the mock
+    FakeFirst,
+}
+
+thread_local! {
+    static ORDER: Cell<Order> = const { Cell::new(Order::MockFirst) };
+}
+
+/// Sets the resolution order of the functions with a mock and a fake on the current thread.
+pub fn set_order(order: Order) {
+    ORDER.with(|setting| setting.set(order));
+}
+
+/// Returns the resolution order of the functions with a mock and a fake on the current thread.
+pub fn order() -> Order {
+    ORDER.with(Cell::get)
+}
+
+/// Checks if a function with a mock and a fake calls the fake.
+///
+/// Normally you don't need to call this function, it backs the functions generated by `#[mock_function(fake)]`.
+///
+/// # Arguments
+///
+/// - `mock_is_set` - whether the mock of the function is configured
+/// - `fake_is_set` - whether the fake of the function is configured
+#[inline]
+pub fn uses_fake(mock_is_set: bool, fake_is_set: bool) -> bool {
+    fake_is_set && (!mock_is_set || order() == Order::FakeFirst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uses_fake_only_without_mock_by_default() {
+        assert!(uses_fake(false, true));
+        assert!(!uses_fake(true, true));
+        assert!(!uses_fake(false, false));
+    }
+
+    #[test]
+    fn test_uses_fake_over_mock_with_fake_first() {
+        set_order(Order::FakeFirst);
+
+        assert!(uses_fake(true, true));
+        assert!(!uses_fake(true, false));
+    }
+}
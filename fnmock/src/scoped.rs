@@ -0,0 +1,73 @@
+/// Guard calling the `clear` function of a double when dropped
+struct ClearGuard {
+    clear: fn(),
+}
+
+impl Drop for ClearGuard {
+    fn drop(&mut self) {
+        (self.clear)()
+    }
+}
+
+/// Runs `body` and calls `clear` afterward.
+///
+/// Normally you don't need to call this function.
+/// It backs the generated `with` proxies, e.g. `fetch_user_mock::with(implementation, body)`.
+///
+/// `clear` is called even if `body` panics, so a failing test doesn't leave the double configured.
+///
+/// # Arguments
+///
+/// - `clear` - the `clear` proxy function of the double's module
+/// - `body` - the code to run while the double is configured
+///
+/// # Returns
+///
+/// The return value of `body`
+pub fn run_scoped<T>(clear: fn(), body: impl FnOnce() -> T) -> T {
+    let _guard = ClearGuard { clear };
+    body()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    thread_local! {
+        static CLEARED: Cell<bool> = const { Cell::new(false) };
+    }
+
+    fn clear() {
+        CLEARED.with(|cleared| cleared.set(true));
+    }
+
+    fn is_cleared() -> bool {
+        CLEARED.with(|cleared| cleared.get())
+    }
+
+    #[test]
+    fn test_run_scoped_returns_body_result() {
+        let result = run_scoped(clear, || 42);
+        assert_eq!(result, 42);
+    }
+
+    #[test]
+    fn test_run_scoped_clears_after_body() {
+        run_scoped(clear, || {
+            assert!(!is_cleared());
+        });
+
+        assert!(is_cleared());
+    }
+
+    #[test]
+    fn test_run_scoped_clears_when_body_panics() {
+        let result = std::panic::catch_unwind(|| {
+            run_scoped(clear, || panic!("test failed"))
+        });
+
+        assert!(result.is_err());
+        assert!(is_cleared());
+    }
+}
@@ -0,0 +1,328 @@
+//! Opt-in checks for accidental reconfiguration of doubles
+//!
+//! A test helper setting up a double silently overrides the setup of another helper or of the
+//! test itself, so the test may pass without ever exercising the behavior it was written for.
+//! The checks report such setups with their call sites:
+//!
+//! - repeated setup - a double is set up twice without being cleared in between
+//! - setup after call - a mock is set up after it was called, without `checkpoint()` or `clear()` in between
+//! - real implementation - the real implementation of a function runs, although its double was configured
+//!   in the current test, e.g. before it was cleared or by `setup_when()` not matching the call
+//!
+//! Each check is configured per thread, so every test opts in on its own,
+//! or for the whole test suite with an environment variable (`allow`, `warn` or `panic`):
+//! `FNMOCK_REPEATED_SETUP`, `FNMOCK_SETUP_AFTER_CALL` and `FNMOCK_REAL_IMPLEMENTATION`.
+//! Declaring a double required with its `require()` proxy always panics if the real implementation runs.
+//!
+//! # Example
+//!
+//! ```ignore
+//! fnmock::strict::set_repeated_setup(fnmock::strict::Strictness::Panic);
+//!
+//! fetch_user_mock::setup(|_| Ok("mock user".to_string()));
+//!
+//! // Panics with "fetch_user_mock mock was set up again without being cleared ..."
+//! fetch_user_mock::setup(|_| Err("not found".to_string()));
+//! ```
+
+use std::cell::Cell;
+use std::panic::Location;
+use std::sync::OnceLock;
+use std::thread::LocalKey;
+use crate::messages::{with_definition, with_prefix};
+use crate::registry;
+
+/// Environment variable setting the default of the repeated setup check of every thread
+pub const REPEATED_SETUP_ENV_VAR: &str = "FNMOCK_REPEATED_SETUP";
+
+/// Environment variable setting the default of the setup after call check of every thread
+pub const SETUP_AFTER_CALL_ENV_VAR: &str = "FNMOCK_SETUP_AFTER_CALL";
+
+/// Environment variable setting the default of the real implementation check of every thread
+pub const REAL_IMPLEMENTATION_ENV_VAR: &str = "FNMOCK_REAL_IMPLEMENTATION";
+
+/// How a double reacts to a reconfiguration detected by one of the checks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// The new setup replaces the previous one, which is the default
+    #[default]
+    Allow,
+    /// The new setup replaces the previous one and the call sites are printed to stderr
+    Warn,
+    /// The new setup panics with the call sites
+    Panic,
+}
+
+impl Strictness {
+    fn from_env(name: &str) -> Self {
+        match std::env::var(name).as_deref() {
+            Ok("warn") => Strictness::Warn,
+            Ok("panic") => Strictness::Panic,
+            _ => Strictness::Allow,
+        }
+    }
+
+    fn report(self, message: impl FnOnce() -> String) {
+        match self {
+            Strictness::Allow => {}
+            Strictness::Warn => eprintln!("{}", message()),
+            Strictness::Panic => panic_reconfigured(message()),
+        }
+    }
+}
+
+// The environment variables are only read once for the whole test suite
+static REPEATED_SETUP_DEFAULT: OnceLock<Strictness> = OnceLock::new();
+static SETUP_AFTER_CALL_DEFAULT: OnceLock<Strictness> = OnceLock::new();
+static REAL_IMPLEMENTATION_DEFAULT: OnceLock<Strictness> = OnceLock::new();
+
+thread_local! {
+    static REPEATED_SETUP: Cell<Option<Strictness>> = const { Cell::new(None) };
+    static SETUP_AFTER_CALL: Cell<Option<Strictness>> = const { Cell::new(None) };
+    static REAL_IMPLEMENTATION: Cell<Option<Strictness>> = const { Cell::new(None) };
+}
+
+fn current(
+    setting: &'static LocalKey<Cell<Option<Strictness>>>,
+    default: &OnceLock<Strictness>,
+    env_var: &str,
+) -> Strictness {
+    setting.with(Cell::get).unwrap_or_else(|| *default.get_or_init(|| Strictness::from_env(env_var)))
+}
+
+/// Sets how doubles react to being set up twice without `clear()` in between on the current thread.
+///
+/// Overrides the `FNMOCK_REPEATED_SETUP` environment variable for the current thread.
+pub fn set_repeated_setup(strictness: Strictness) {
+    REPEATED_SETUP.with(|setting| setting.set(Some(strictness)));
+}
+
+/// Returns how doubles react to being set up twice without `clear()` in between on the current thread.
+pub fn repeated_setup() -> Strictness {
+    current(&REPEATED_SETUP, &REPEATED_SETUP_DEFAULT, REPEATED_SETUP_ENV_VAR)
+}
+
+/// Sets how mocks react to being set up after they were called on the current thread.
+///
+/// `checkpoint()` and `clear()` start a new phase of the test, in which the mock may be set up again.
+/// Overrides the `FNMOCK_SETUP_AFTER_CALL` environment variable for the current thread.
+pub fn set_setup_after_call(strictness: Strictness) {
+    SETUP_AFTER_CALL.with(|setting| setting.set(Some(strictness)));
+}
+
+/// Returns how mocks react to being set up after they were called on the current thread.
+pub fn setup_after_call() -> Strictness {
+    current(&SETUP_AFTER_CALL, &SETUP_AFTER_CALL_DEFAULT, SETUP_AFTER_CALL_ENV_VAR)
+}
+
+/// Sets how doubled functions react to running their real implementation after their double
+/// was configured in the current test on the current thread.
+///
+/// Overrides the `FNMOCK_REAL_IMPLEMENTATION` environment variable for the current thread.
+pub fn set_real_implementation(strictness: Strictness) {
+    REAL_IMPLEMENTATION.with(|setting| setting.set(Some(strictness)));
+}
+
+/// Returns how doubled functions react to running their real implementation after their double was configured.
+pub fn real_implementation() -> Strictness {
+    current(&REAL_IMPLEMENTATION, &REAL_IMPLEMENTATION_DEFAULT, REAL_IMPLEMENTATION_ENV_VAR)
+}
+
+/// Tracks whether the real implementation of a doubled function may run, see `check_real_call`
+///
+/// # Fields
+///
+/// - `required` - whether the double was declared required with `require()` since it was last cleared
+/// - `configured_in` - the generation of the thread the double was last configured in, or None if it never was
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RealCallGuard {
+    required: bool,
+    configured_in: Option<u64>,
+}
+
+impl RealCallGuard {
+    pub(crate) const fn new() -> Self {
+        Self { required: false, configured_in: None }
+    }
+
+    /// Records that the double is configured in the current generation of the thread, i.e. the current test.
+    pub(crate) fn record_configured(&mut self) {
+        self.configured_in = Some(registry::generation());
+    }
+
+    pub(crate) fn require(&mut self) {
+        self.required = true;
+        self.record_configured();
+    }
+
+    /// Resets the requirement, but keeps that the double was configured in the current test.
+    pub(crate) fn clear(&mut self) {
+        self.required = false;
+    }
+
+    /// Checks that the real implementation of the doubled function may run.
+    ///
+    /// # Arguments
+    ///
+    /// - `name` - the name of the double
+    /// - `kind` - the kind of the double, e.g. `mock`
+    /// - `module_path` - the module path of the double for the message prefix, if known
+    /// - `definition` - the location of the function the double was generated for, if known
+    ///
+    /// # Panics
+    ///
+    /// Panics if the double is required, or reports as configured by `set_real_implementation`
+    /// if the double was configured in the current test
+    pub(crate) fn check(&self, name: &str, kind: &str, module_path: Option<&str>, definition: Option<&str>) {
+        if self.required {
+            panic_reconfigured(with_definition(definition, with_prefix(module_path, format!(
+                "{} {} is required, but the real implementation was called",
+                name, kind
+            ))));
+        }
+
+        if self.configured_in == Some(registry::generation()) {
+            real_implementation().report(|| with_definition(definition, with_prefix(module_path, format!(
+                "the real implementation was called, although {} {} was configured in this test",
+                name, kind
+            ))));
+        }
+    }
+}
+
+/// Checks a setup of a double against its previous setup, if it wasn't cleared since.
+///
+/// # Arguments
+///
+/// - `name` - the name of the double
+/// - `kind` - the kind of the double, e.g. `mock`
+/// - `module_path` - the module path of the double for the message prefix, if known
+/// - `definition` - the location of the function the double was generated for, if known
+/// - `previous` - where the double was set up before, or None if it was cleared since
+/// - `current` - where the double is set up now
+pub(crate) fn check_setup(
+    name: &str,
+    kind: &str,
+    module_path: Option<&str>,
+    definition: Option<&str>,
+    previous: Option<&'static Location<'static>>,
+    current: &'static Location<'static>,
+) {
+    let Some(previous) = previous else {
+        return;
+    };
+
+    repeated_setup().report(|| with_definition(definition, with_prefix(module_path, format!(
+        "{} {} was set up again without being cleared, the setup at {} is overridden by the setup at {}",
+        name, kind, previous, current
+    ))));
+}
+
+/// Checks a setup of a mock against the calls since the last checkpoint.
+///
+/// # Arguments
+///
+/// - `name` - the name of the mock
+/// - `module_path` - the module path of the mock for the message prefix, if known
+/// - `definition` - the location of the mocked function, if known
+/// - `num_of_calls` - the number of calls since the last checkpoint
+/// - `current` - where the mock is set up now
+pub(crate) fn check_setup_after_call(
+    name: &str,
+    module_path: Option<&str>,
+    definition: Option<&str>,
+    num_of_calls: usize,
+    current: &'static Location<'static>,
+) {
+    if num_of_calls == 0 {
+        return;
+    }
+
+    setup_after_call().report(|| with_definition(definition, with_prefix(module_path, format!(
+        "{} mock was set up at {} after it was called {} times, call checkpoint() or clear() before reconfiguring it",
+        name, current, num_of_calls
+    ))));
+}
+
+#[cold]
+#[inline(never)]
+fn panic_reconfigured(message: String) -> ! {
+    panic!("{}", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_setup_allows_repeated_setup_by_default() {
+        check_setup("add", "mock", None, None, Some(Location::caller()), Location::caller());
+    }
+
+    #[test]
+    fn test_check_setup_without_previous_setup() {
+        set_repeated_setup(Strictness::Panic);
+
+        check_setup("add", "mock", None, None, None, Location::caller());
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock was set up again without being cleared, the setup at ")]
+    fn test_check_setup_panics_on_repeated_setup() {
+        set_repeated_setup(Strictness::Panic);
+
+        check_setup("add", "mock", None, None, Some(Location::caller()), Location::caller());
+    }
+
+    #[test]
+    fn test_real_call_guard_allows_real_call_by_default() {
+        let mut guard = RealCallGuard::new();
+        guard.record_configured();
+
+        guard.check("add", "mock", None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "the real implementation was called, although add stub was configured in this test")]
+    fn test_real_call_guard_panics_after_configuration() {
+        set_real_implementation(Strictness::Panic);
+        let mut guard = RealCallGuard::new();
+
+        guard.check("add", "stub", None, None);
+        guard.record_configured();
+        guard.check("add", "stub", None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "add mock is required, but the real implementation was called")]
+    fn test_real_call_guard_panics_if_required() {
+        let mut guard = RealCallGuard::new();
+        guard.require();
+
+        guard.check("add", "mock", None, None);
+    }
+
+    #[test]
+    fn test_real_call_guard_clear_resets_requirement() {
+        let mut guard = RealCallGuard::new();
+        guard.require();
+        guard.clear();
+
+        guard.check("add", "mock", None, None);
+    }
+
+    #[test]
+    fn test_check_setup_after_call_without_calls() {
+        set_setup_after_call(Strictness::Panic);
+
+        check_setup_after_call("add", None, None, 0, Location::caller());
+    }
+
+    #[test]
+    #[should_panic(expected = "after it was called 2 times, call checkpoint() or clear() before reconfiguring it")]
+    fn test_check_setup_after_call_panics_after_calls() {
+        set_setup_after_call(Strictness::Panic);
+
+        check_setup_after_call("add", None, None, 2, Location::caller());
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+
+/// Thread on which a mock was configured
+///
+/// # Fields
+///
+/// - `module_path` - the module path of the mock if known
+/// - `mock` - the name of the mock
+/// - `thread_id` - the id of the configuring thread
+/// - `thread_name` - the name of the configuring thread, e.g. the test name
+/// - `configured` - whether the mock is currently configured on the thread, shared with the mock
+struct ConfiguringThread {
+    module_path: Option<&'static str>,
+    mock: &'static str,
+    thread_id: ThreadId,
+    thread_name: Option<String>,
+    configured: Arc<AtomicBool>,
+}
+
+impl ConfiguringThread {
+    fn is_configured(&self) -> bool {
+        self.configured.load(Ordering::Relaxed)
+    }
+
+    fn is_mock(&self, module_path: Option<&str>, mock: &str) -> bool {
+        self.module_path == module_path && self.mock == mock
+    }
+}
+
+// The mocks themselves are thread-local, so this is the only place where a thread
+// can find out that a mock was configured on another thread.
+// Every mock adds its entry once per thread and then only updates the flag of the entry,
+// so configuring the mocks of parallel tests doesn't contend on the lock.
+static CONFIGURING_THREADS: Mutex<Vec<ConfiguringThread>> = Mutex::new(Vec::new());
+
+fn configuring_threads() -> std::sync::MutexGuard<'static, Vec<ConfiguringThread>> {
+    CONFIGURING_THREADS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Records that the mock was configured on the current thread for the first time.
+///
+/// # Returns
+///
+/// The flag of the record, which the mock sets and resets when it is configured and cleared
+pub(crate) fn record_configured(module_path: Option<&'static str>, mock: &'static str) -> Arc<AtomicBool> {
+    let current = std::thread::current();
+    let configured = Arc::new(AtomicBool::new(true));
+
+    configuring_threads().push(ConfiguringThread {
+        module_path,
+        mock,
+        thread_id: current.id(),
+        thread_name: current.name().map(str::to_string),
+        configured: Arc::clone(&configured),
+    });
+    configured
+}
+
+/// Removes the record with the flag, e.g. when the mock is dropped at the exit of its thread.
+pub(crate) fn remove_configured(configured: &Arc<AtomicBool>) {
+    configuring_threads().retain(|thread| !Arc::ptr_eq(&thread.configured, configured));
+}
+
+/// Describes the other threads the mock is currently configured on.
+///
+/// # Returns
+///
+/// A description like `'test_name' (ThreadId(2))` for every other thread
+pub(crate) fn configured_on_other_threads(module_path: Option<&str>, mock: &str) -> Vec<String> {
+    let current_id = std::thread::current().id();
+
+    configuring_threads()
+        .iter()
+        .filter(|thread| thread.is_mock(module_path, mock) && thread.thread_id != current_id && thread.is_configured())
+        .map(|thread| format!(
+            "'{}' ({:?})",
+            thread.thread_name.as_deref().unwrap_or("<unnamed>"),
+            thread.thread_id
+        ))
+        .collect()
+}
+
+/// Returns the names of the mocks currently configured on the current thread.
+pub(crate) fn configured_on_current_thread() -> Vec<String> {
+    let current_id = std::thread::current().id();
+
+    configuring_threads()
+        .iter()
+        .filter(|thread| thread.thread_id == current_id && thread.is_configured())
+        .map(|thread| match thread.module_path {
+            // The generated mock modules are named after the mock, which doesn't need to be repeated
+            Some(module_path) if module_path.rsplit("::").next() == Some(thread.mock) => module_path.to_string(),
+            Some(module_path) => format!("{}::{}", module_path, thread.mock),
+            None => thread.mock.to_string(),
+        })
+        .collect()
+}
+
+/// Describes the current thread like `configured_on_other_threads`.
+pub(crate) fn describe_current_thread() -> String {
+    let current = std::thread::current();
+    format!("'{}' ({:?})", current.name().unwrap_or("<unnamed>"), current.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_on_other_threads_finds_other_thread() {
+        let configured = std::thread::spawn(|| record_configured(Some("threads_test"), "other_thread_mock")).join().unwrap();
+
+        let threads = configured_on_other_threads(Some("threads_test"), "other_thread_mock");
+        assert_eq!(threads.len(), 1);
+        assert!(threads[0].contains("<unnamed>"));
+        remove_configured(&configured);
+    }
+
+    #[test]
+    fn test_configured_on_other_threads_ignores_current_thread() {
+        let configured = record_configured(Some("threads_test"), "current_thread_mock");
+
+        assert!(configured_on_other_threads(Some("threads_test"), "current_thread_mock").is_empty());
+        remove_configured(&configured);
+    }
+
+    #[test]
+    fn test_cleared_flag_hides_record() {
+        let configured = std::thread::spawn(|| record_configured(Some("threads_test"), "cleared_mock")).join().unwrap();
+
+        configured.store(false, Ordering::Relaxed);
+
+        assert!(configured_on_other_threads(Some("threads_test"), "cleared_mock").is_empty());
+        remove_configured(&configured);
+    }
+
+    #[test]
+    fn test_remove_configured_removes_record() {
+        let configured = std::thread::spawn(|| record_configured(Some("threads_test"), "removed_mock")).join().unwrap();
+
+        remove_configured(&configured);
+
+        assert!(configuring_threads().iter().all(|thread| !thread.is_mock(Some("threads_test"), "removed_mock")));
+    }
+
+    #[test]
+    fn test_configured_on_current_thread_omits_repeated_name() {
+        let configured = record_configured(Some("threads_test::fetch_user_mock"), "fetch_user_mock");
+
+        assert!(configured_on_current_thread().contains(&"threads_test::fetch_user_mock".to_string()));
+        remove_configured(&configured);
+    }
+
+    #[test]
+    fn test_describe_current_thread_contains_test_name() {
+        assert!(describe_current_thread().contains("test_describe_current_thread_contains_test_name"));
+    }
+}
@@ -0,0 +1,349 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::panic::Location;
+use crate::MockControl;
+
+// The failures of the current `verify_all_collecting` call or None if assertions panic immediately
+thread_local! {
+    static COLLECTED_FAILURES: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Guard resetting the collection when `verify_all_collecting` returns or its body panics
+struct CollectingGuard;
+
+impl Drop for CollectingGuard {
+    fn drop(&mut self) {
+        COLLECTED_FAILURES.with(|failures| failures.borrow_mut().take());
+    }
+}
+
+/// Reports a failed mock assertion.
+///
+/// Normally you don't need to call this function.
+/// It backs the generated `assert_*` and `verify` proxies: it panics with the message,
+/// or records it with the caller's location inside `verify_all_collecting`.
+///
+/// # Arguments
+///
+/// - `message` - the failure message
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub fn report_failure(message: String) {
+    let location = Location::caller();
+    let collected = COLLECTED_FAILURES.with(|failures| match failures.borrow_mut().as_mut() {
+        Some(failures) => {
+            failures.push(format!("{} (at {})", message, location));
+            true
+        }
+        None => false,
+    });
+
+    if !collected {
+        panic!("{}", message)
+    }
+}
+
+/// Labels a failure message with the context message of an `assert_*_msg` assertion.
+///
+/// Normally you don't need to call this function, it backs the generated `assert_*_msg` proxies.
+///
+/// # Arguments
+///
+/// - `context` - the message given to the assertion, e.g. `after retry loop`
+/// - `message` - the failure message
+pub fn with_context_message(context: &str, message: String) -> String {
+    format!("{}: {}", context, message)
+}
+
+/// Runs `body` and reports all failed mock assertions inside it together.
+///
+/// Instead of panicking on the first failed assertion, the failures of all mock assertions
+/// (`assert_times`, `assert_with`, `verify`, ...) in `body` are collected and reported in a single
+/// panic after `body` finished. Other panics in `body` are not collected.
+///
+/// Nested calls add their failures to the outermost call.
+///
+/// # Example
+///
+/// ```ignore
+/// fnmock::verify::verify_all_collecting(|| {
+///     fetch_user_mock::assert_times(1);
+///     send_email_mock::assert_with("alice".to_string());
+/// });
+/// ```
+///
+/// # Returns
+///
+/// The return value of `body`
+///
+/// # Panics
+///
+/// Panics with all failure messages and their locations if any mock assertion in `body` failed
+#[track_caller]
+pub fn verify_all_collecting<T>(body: impl FnOnce() -> T) -> T {
+    let is_nested = COLLECTED_FAILURES.with(|failures| failures.borrow().is_some());
+    if is_nested {
+        return body();
+    }
+
+    COLLECTED_FAILURES.with(|failures| *failures.borrow_mut() = Some(Vec::new()));
+    let guard = CollectingGuard;
+
+    let result = body();
+
+    let failures = COLLECTED_FAILURES.with(|failures| failures.borrow_mut().take()).unwrap_or_default();
+    drop(guard);
+
+    if !failures.is_empty() {
+        panic!("{} mock assertions failed:\n{}", failures.len(), failures.join("\n"));
+    }
+
+    result
+}
+
+/// Asserts that the mocks weren't called beyond the calls covered by assertions.
+///
+/// A call is covered once an `assert_with*()` assertion matched it or `assert_calls_exact()` succeeded.
+/// This catches code under test silently doing extra work, like a second unexpected request.
+/// Calls made after the assertions aren't covered.
+///
+/// # Example
+///
+/// ```ignore
+/// fetch_user_mock::assert_with(1);
+/// send_email_mock::assert_with("alice".to_string());
+///
+/// fnmock::verify::verify_no_more_interactions(&[&fetch_user_mock::MockHandle, &send_email_mock::MockHandle]);
+/// ```
+///
+/// # Panics
+///
+/// Panics with the uncovered calls of every mock if any mock has uncovered calls
+#[track_caller]
+pub fn verify_no_more_interactions(mocks: &[&dyn MockControl]) {
+    let failures: Vec<String> = mocks
+        .iter()
+        .filter_map(|mock| mock.check_no_more_interactions().err())
+        .collect();
+
+    if !failures.is_empty() {
+        report_failure(failures.join("\n"))
+    }
+}
+
+/// Verification status of a mock, see `VerificationReport`
+///
+/// # Fields
+///
+/// - `is_set` - whether the mock is configured
+/// - `call_count` - the number of calls since the call history was last cleared
+/// - `expected_calls` - the number of calls expected with `expect_times`, or None
+/// - `unmet_expectation` - the failure message of `verify`, or None if the expectations are met
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockStatus {
+    pub is_set: bool,
+    pub call_count: usize,
+    pub expected_calls: Option<u32>,
+    pub unmet_expectation: Option<String>,
+}
+
+/// Entry of a mock in a `VerificationReport`
+///
+/// # Fields
+///
+/// - `name` - the unique name of the mock's module (including the module path)
+/// - `group` - the group of the mock, e.g. `#[mock_function(group = "db")]`, or None
+/// - `status` - the verification status of the mock
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockReport {
+    pub name: &'static str,
+    pub group: Option<&'static str>,
+    pub status: MockStatus,
+}
+
+impl MockReport {
+    pub fn is_success(&self) -> bool {
+        self.status.unmet_expectation.is_none()
+    }
+}
+
+/// Result of verifying the expectations of the mocks without panicking, see `fnmock::registry::try_verify_all`
+///
+/// Lets custom harnesses render the results, aggregate them across parameterized cases with `extend`,
+/// or attach them to CI artifacts. The `Display` implementation lists every mock with its status.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+    mocks: Vec<MockReport>,
+}
+
+impl VerificationReport {
+    pub fn new(mocks: Vec<MockReport>) -> Self {
+        Self { mocks }
+    }
+
+    /// Returns the entries of all verified mocks.
+    pub fn mocks(&self) -> &[MockReport] {
+        &self.mocks
+    }
+
+    /// Returns the entries of the mocks with unmet expectations.
+    pub fn failures(&self) -> impl Iterator<Item = &MockReport> {
+        self.mocks.iter().filter(|mock| !mock.is_success())
+    }
+
+    /// Checks if the expectations of all mocks are met.
+    pub fn is_success(&self) -> bool {
+        self.mocks.iter().all(MockReport::is_success)
+    }
+
+    /// Adds the entries of another report, e.g. of the next case of a parameterized test.
+    pub fn extend(&mut self, other: VerificationReport) {
+        self.mocks.extend(other.mocks);
+    }
+}
+
+impl Display for VerificationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let failed = self.failures().count();
+        write!(f, "fnmock verification: {} of {} mocks failed", failed, self.mocks.len())?;
+        for mock in &self.mocks {
+            let result = if mock.is_success() { "ok" } else { "FAILED" };
+            let state = if mock.status.is_set { "set" } else { "not set" };
+            write!(f, "\n  {} {} ({}, called {} times", result, mock.name, state, mock.status.call_count)?;
+            if let Some(expected_calls) = mock.status.expected_calls {
+                write!(f, ", expected {}", expected_calls)?;
+            }
+            write!(f, ")")?;
+            if let Some(group) = mock.group {
+                write!(f, " [group {}]", group)?;
+            }
+            if let Some(unmet_expectation) = &mock.status.unmet_expectation {
+                write!(f, ": {}", unmet_expectation)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_context_message_prepends_context() {
+        let message = with_context_message("after retry loop", "Expected add mock to be called 2 times".to_string());
+        assert_eq!(message, "after retry loop: Expected add mock to be called 2 times");
+    }
+
+    #[test]
+    #[should_panic(expected = "first failure")]
+    fn test_report_failure_panics_outside_of_collection() {
+        report_failure("first failure".to_string());
+    }
+
+    #[test]
+    fn test_verify_all_collecting_returns_body_result() {
+        let result = verify_all_collecting(|| 42);
+        assert_eq!(result, 42);
+    }
+
+    #[test]
+    fn test_verify_all_collecting_reports_all_failures() {
+        let panic = std::panic::catch_unwind(|| {
+            verify_all_collecting(|| {
+                report_failure("first failure".to_string());
+                report_failure("second failure".to_string());
+            })
+        }).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("2 mock assertions failed:\nfirst failure (at fnmock/src/verify.rs:"));
+        assert!(message.contains("\nsecond failure (at fnmock/src/verify.rs:"));
+    }
+
+    #[test]
+    fn test_verify_all_collecting_collects_nested_failures_in_outer_call() {
+        let panic = std::panic::catch_unwind(|| {
+            verify_all_collecting(|| {
+                verify_all_collecting(|| report_failure("inner failure".to_string()));
+                report_failure("outer failure".to_string());
+            })
+        }).unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+
+        assert!(message.starts_with("2 mock assertions failed:\ninner failure"));
+    }
+
+    struct FakeMock(Result<(), String>);
+
+    impl MockControl for FakeMock {
+        fn name(&self) -> &'static str {
+            "fake_mock"
+        }
+
+        fn clear(&self) {}
+
+        fn assert_times(&self, _expected_num_of_calls: u32) {}
+
+        fn check_no_more_interactions(&self) -> Result<(), String> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_verify_no_more_interactions_reports_every_mock() {
+        let verified = FakeMock(Ok(()));
+        let first = FakeMock(Err("first unverified".to_string()));
+        let second = FakeMock(Err("second unverified".to_string()));
+
+        let panic = std::panic::catch_unwind(|| {
+            verify_no_more_interactions(&[&first, &verified, &second])
+        }).unwrap_err();
+
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "first unverified\nsecond unverified");
+    }
+
+    #[test]
+    fn test_verify_all_collecting_stops_collecting_when_body_panics() {
+        let result = std::panic::catch_unwind(|| {
+            verify_all_collecting(|| panic!("unrelated panic"))
+        });
+        assert!(result.is_err());
+
+        let result = std::panic::catch_unwind(|| report_failure("failure".to_string()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verification_report_lists_every_mock() {
+        let passed = MockReport {
+            name: "crate::db::fetch_user_mock",
+            group: Some("db"),
+            status: MockStatus { is_set: true, call_count: 2, expected_calls: Some(2), unmet_expectation: None },
+        };
+        let failed = MockReport {
+            name: "crate::mail::send_email_mock",
+            group: None,
+            status: MockStatus {
+                is_set: true,
+                call_count: 0,
+                expected_calls: None,
+                unmet_expectation: Some("send_email_mock failed".to_string()),
+            },
+        };
+        let mut report = VerificationReport::new(vec![passed]);
+        assert!(report.is_success());
+
+        report.extend(VerificationReport::new(vec![failed.clone()]));
+
+        assert!(!report.is_success());
+        assert_eq!(report.failures().collect::<Vec<_>>(), vec![&failed]);
+        assert_eq!(
+            report.to_string(),
+            "fnmock verification: 1 of 2 mocks failed\n  \
+             ok crate::db::fetch_user_mock (set, called 2 times, expected 2) [group db]\n  \
+             FAILED crate::mail::send_email_mock (set, called 0 times): send_email_mock failed"
+        );
+    }
+}